@@ -0,0 +1,170 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::models::*;
+use super::game::Game;
+use super::tablebase::{EndgameTablebase, Wdl};
+
+// A side loses once its score has been at least `score` centipawns below zero for `moves` of its moves in a
+// row. With `two_sided`, the opponent has to agree by being at least as far ahead over its own moves.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ResignRule {
+    pub moves: usize,
+    pub score: i32,
+    pub two_sided: bool
+}
+
+// From move number `move_number` on, the game is a draw once the scores of both sides have stayed within
+// `score` centipawns of zero for `moves` moves of each side
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DrawRule {
+    pub move_number: i64,
+    pub moves: usize,
+    pub score: i32
+}
+
+#[derive(Clone, Default)]
+pub struct AdjudicationRules {
+    pub resign: Option<ResignRule>,
+    pub draw: Option<DrawRule>,
+
+    // Positions covered by the tablebase end with its result
+    pub tablebase: Option<Arc<dyn EndgameTablebase>>,
+
+    // Games reaching this many half-moves, counted from the start of the game, are drawn
+    pub max_plies: Option<usize>
+}
+
+impl fmt::Debug for AdjudicationRules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AdjudicationRules")
+            .field("resign", &self.resign)
+            .field("draw", &self.draw)
+            .field("tablebase", &self.tablebase.is_some())
+            .field("max_plies", &self.max_plies)
+            .finish()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AdjudicationReason {
+    Resign,
+    Draw,
+    Tablebase,
+    MaxLength
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Adjudication {
+    pub result: GameResult,
+    pub reason: AdjudicationReason
+}
+
+impl Adjudication {
+    // The game ended with the result, with "adjudication" as its termination
+    pub fn apply(&self, game: &Game) -> Game {
+        game.adjudicate(self.result, "adjudication")
+    }
+}
+
+// Decides games which don't need to be played out, from the scores reported after every move. Used by the
+// match runner, or on its own, e.g. by a server ending engine games early.
+#[derive(Debug, Clone)]
+pub struct Adjudicator {
+    rules: AdjudicationRules,
+
+    // The color which moved and its score, for every recorded move
+    scores: Vec<(Color, Option<i32>)>
+}
+
+impl Adjudicator {
+    pub fn new(rules: AdjudicationRules) -> Self {
+        Adjudicator { rules, scores: Vec::new() }
+    }
+
+    pub fn rules(&self) -> &AdjudicationRules {
+        &self.rules
+    }
+
+    // Forgets the scores, e.g. before another game
+    pub fn reset(&mut self) {
+        self.scores.clear();
+    }
+
+    // To be called after every move with the game after it and the score of the side which moved, in
+    // centipawns from its point of view. Moves without a score break the resign and draw rules' streaks.
+    pub fn record(&mut self, game: &Game, score: Option<i32>) -> Option<Adjudication> {
+        if let Some(valid_move) = game.last_move() {
+            self.scores.push((valid_move.color, score));
+        }
+
+        self.check(game)
+    }
+
+    pub fn check(&self, game: &Game) -> Option<Adjudication> {
+        if game.is_over() {
+            return None;
+        }
+
+        let adjudication = |result, reason| Some(Adjudication { result, reason });
+
+        if let Some(probe) = self.rules.tablebase.as_ref().and_then( |tablebase| tablebase.probe(game) ) {
+            let next_to_move = game.position().next_to_move;
+
+            let result = match probe.wdl {
+                Wdl::Win  => win_for(next_to_move),
+                Wdl::Loss => win_for(next_to_move.opposite()),
+                Wdl::Draw => GameResult::Draw
+            };
+
+            return adjudication(result, AdjudicationReason::Tablebase);
+        }
+
+        if let Some(rule) = self.rules.resign {
+            for color in [Color::White, Color::Black] {
+                let losing = self.last_scores(color, rule.moves).is_some_and( |scores| scores.iter().all( |score| *score <= -rule.score ) );
+                let agreed = !rule.two_sided || self.last_scores(color.opposite(), rule.moves).is_some_and( |scores| scores.iter().all( |score| *score >= rule.score ) );
+
+                if losing && agreed {
+                    return adjudication(win_for(color.opposite()), AdjudicationReason::Resign);
+                }
+            }
+        }
+
+        if let Some(rule) = self.rules.draw {
+            let level = |color| self.last_scores(color, rule.moves).is_some_and( |scores| scores.iter().all( |score| score.abs() <= rule.score ) );
+
+            if game.position().full_move_counter >= rule.move_number && level(Color::White) && level(Color::Black) {
+                return adjudication(GameResult::Draw, AdjudicationReason::Draw);
+            }
+        }
+
+        if self.rules.max_plies.is_some_and( |max_plies| game.ply_count() >= max_plies ) {
+            return adjudication(GameResult::Draw, AdjudicationReason::MaxLength);
+        }
+
+        None
+    }
+
+    // The last `count` scores of the color, None if it doesn't have that many in a row
+    fn last_scores(&self, color: Color, count: usize) -> Option<Vec<i32>> {
+        if count == 0 {
+            return None;
+        }
+
+        self.scores.iter()
+            .rev()
+            .filter( |(mover, _)| *mover == color )
+            .take(count)
+            .map( |(_, score)| *score )
+            .collect::<Option<Vec<i32>>>()
+            .filter( |scores| scores.len() == count )
+    }
+}
+
+fn win_for(color: Color) -> GameResult {
+    match color {
+        Color::White => GameResult::WhiteWins,
+        Color::Black => GameResult::BlackWins
+    }
+}
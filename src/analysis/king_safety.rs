@@ -0,0 +1,149 @@
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KingSafety {
+    pub king: Square,
+
+    // Files next to and including the king's file which have a pawn of the king's color one or two ranks ahead
+    pub pawn_shield: usize,
+    pub missing_shield_pawns: usize,
+
+    // Files next to and including the king's file, without any pawns / with only enemy pawns
+    pub open_files: Vec<i8>,
+    pub half_open_files: Vec<i8>,
+
+    // Enemy pieces attacking the king or the squares around it
+    pub king_zone_attackers: Vec<(Square, Piece)>
+}
+
+impl KingSafety {
+    pub fn is_exposed(&self) -> bool {
+        self.missing_shield_pawns >= 2 || !self.open_files.is_empty() || self.king_zone_attackers.len() >= 3
+    }
+}
+
+pub fn king_safety(game: &Game, color: Color) -> Option<KingSafety> {
+    board_king_safety(game.board(), color)
+}
+
+// Only needs the board, e.g. for evaluating a position without creating a game for it
+pub fn board_king_safety(board: &Board, color: Color) -> Option<KingSafety> {
+    let king = find_king(board, color)?;
+
+    let forward = match color {
+        Color::White => 1,
+        Color::Black => -1
+    };
+
+    let files: Vec<i8> = (king.file - 1..=king.file + 1).filter( |file| (0..BOARD_SIZE).contains(file) ).collect();
+
+    let pawn_shield = files.iter()
+        .filter( |file| [1, 2].iter().any( |distance| {
+            let square = Square { rank: king.rank + forward * distance, file: **file };
+
+            (0..BOARD_SIZE).contains(&square.rank) &&
+                piece_at(board, square) == Some(&OccupiedSquare { piece: Piece::Pawn, color })
+        }))
+        .count();
+
+    let mut open_files = Vec::new();
+    let mut half_open_files = Vec::new();
+
+    for file in files.iter() {
+        let has_pawn = |pawn_color: Color| (0..BOARD_SIZE).any( |rank|
+            piece_at(board, Square { rank, file: *file }) == Some(&OccupiedSquare { piece: Piece::Pawn, color: pawn_color })
+        );
+
+        match (has_pawn(color), has_pawn(color.opposite())) {
+            (false, false) => open_files.push(*file),
+            (false, true)  => half_open_files.push(*file),
+            _ => ()
+        }
+    }
+
+    let mut king_zone_attackers: Vec<(Square, Piece)> = Vec::new();
+
+    for rank in king.rank - 1..=king.rank + 1 {
+        for file in king.file - 1..=king.file + 1 {
+            if !(0..BOARD_SIZE).contains(&rank) || !(0..BOARD_SIZE).contains(&file) {
+                continue;
+            }
+
+            for attacker in attackers_of(board, Square { rank, file }, color.opposite()) {
+                if !king_zone_attackers.contains(&attacker) {
+                    king_zone_attackers.push(attacker);
+                }
+            }
+        }
+    }
+
+    Some(KingSafety {
+        king,
+        pawn_shield,
+        missing_shield_pawns: files.len() - pawn_shield,
+        open_files,
+        half_open_files,
+        king_zone_attackers
+    })
+}
+
+fn find_king(board: &Board, color: Color) -> Option<Square> {
+    board.squares.iter()
+        .position( |occupancy| *occupancy == Some(OccupiedSquare { piece: Piece::King, color }) )
+        .map( Board::square )
+}
+
+// The same as Game::attackers_of, cheapest attackers first
+fn attackers_of(board: &Board, square: Square, by_color: Color) -> Vec<(Square, Piece)> {
+    let mut attackers: Vec<(Square, Piece)> = board.squares.iter().enumerate()
+        .filter_map( |(i, occupancy)| match occupancy {
+            Some(OccupiedSquare { piece, color }) if *color == by_color => Some((Board::square(i), *piece)),
+            _ => None
+        })
+        .filter( |(from, piece)| attacks(board, *piece, *from, by_color, square) )
+        .collect();
+
+    attackers.sort_by_key( |(_, piece)| if *piece == Piece::King { i32::MAX } else { piece.value() } );
+    attackers
+}
+
+fn attacks(board: &Board, piece: Piece, from: Square, color: Color, target: Square) -> bool {
+    let rank_delta = target.rank - from.rank;
+    let file_delta = target.file - from.file;
+
+    let forward = match color {
+        Color::White => 1,
+        Color::Black => -1
+    };
+
+    match piece {
+        Piece::Pawn   => rank_delta == forward && file_delta.abs() == 1,
+        Piece::Knight => (rank_delta.abs(), file_delta.abs()) == (1, 2) || (rank_delta.abs(), file_delta.abs()) == (2, 1),
+        Piece::King   => from != target && rank_delta.abs() <= 1 && file_delta.abs() <= 1,
+        Piece::Rook   => (rank_delta == 0 || file_delta == 0) && line_is_clear(board, from, target),
+        Piece::Bishop => rank_delta.abs() == file_delta.abs() && line_is_clear(board, from, target),
+        Piece::Queen  => (rank_delta == 0 || file_delta == 0 || rank_delta.abs() == file_delta.abs()) && line_is_clear(board, from, target)
+    }
+}
+
+// Whether the squares between the two (on one line) are empty
+fn line_is_clear(board: &Board, from: Square, to: Square) -> bool {
+    if from == to {
+        return false;
+    }
+
+    let rank_step = (to.rank - from.rank).signum();
+    let file_step = (to.file - from.file).signum();
+
+    let mut square = Square { rank: from.rank + rank_step, file: from.file + file_step };
+
+    while square != to {
+        if piece_at(board, square).is_some() {
+            return false;
+        }
+
+        square = Square { rank: square.rank + rank_step, file: square.file + file_step };
+    }
+
+    true
+}
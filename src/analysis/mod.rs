@@ -0,0 +1,120 @@
+use super::models::*;
+use super::game::Game;
+
+mod king_safety;
+mod proof_game;
+
+pub use king_safety::{KingSafety, king_safety, board_king_safety};
+pub use proof_game::reachable_from_startpos;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Metrics {
+    pub white: SideMetrics,
+    pub black: SideMetrics
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SideMetrics {
+    pub mobility: Mobility,
+    pub developed_minor_pieces: usize,
+    pub castled: bool,
+
+    // Number of attacks on d4, e4, d5 and e5
+    pub center_control: usize
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Mobility {
+    pub pawn: usize,
+    pub knight: usize,
+    pub bishop: usize,
+    pub rook: usize,
+    pub queen: usize,
+    pub king: usize
+}
+
+impl Mobility {
+    pub fn total(&self) -> usize {
+        self.pawn + self.knight + self.bishop + self.rook + self.queen + self.king
+    }
+
+    fn count(&mut self, piece: Piece) {
+        match piece {
+            Piece::Pawn   => self.pawn += 1,
+            Piece::Knight => self.knight += 1,
+            Piece::Bishop => self.bishop += 1,
+            Piece::Rook   => self.rook += 1,
+            Piece::Queen  => self.queen += 1,
+            Piece::King   => self.king += 1
+        }
+    }
+}
+
+static CENTER_SQUARES: [Square; 4] = [
+    Square { rank: 3, file: 3 },
+    Square { rank: 3, file: 4 },
+    Square { rank: 4, file: 3 },
+    Square { rank: 4, file: 4 }
+];
+
+pub fn metrics(game: &Game) -> Metrics {
+    Metrics {
+        white: side_metrics(game, Color::White),
+        black: side_metrics(game, Color::Black)
+    }
+}
+
+fn side_metrics(game: &Game, color: Color) -> SideMetrics {
+    let mut mobility = Mobility::default();
+
+    for valid_move in game.valid_moves_for(color) {
+        mobility.count(valid_move.piece);
+    }
+
+    let center_control = CENTER_SQUARES.iter()
+        .map( |square| game.attackers_of(*square, color).len() )
+        .sum();
+
+    SideMetrics {
+        mobility,
+        developed_minor_pieces: developed_minor_pieces(game.board(), color),
+        castled: castled(game.board(), color),
+        center_control
+    }
+}
+
+fn home_rank(color: Color) -> i8 {
+    match color {
+        Color::White => 0,
+        Color::Black => 7
+    }
+}
+
+fn piece_at(board: &Board, square: Square) -> Option<&OccupiedSquare> {
+    board.squares[Board::index(square)].as_ref()
+}
+
+fn developed_minor_pieces(board: &Board, color: Color) -> usize {
+    board.squares.iter()
+        .enumerate()
+        .filter( |(i, occupancy)| match occupancy {
+            Some(OccupiedSquare { piece: Piece::Knight, color: piece_color }) => {
+                *piece_color == color && !(Board::square(*i).rank == home_rank(color) && [1, 6].contains(&Board::square(*i).file))
+            },
+            Some(OccupiedSquare { piece: Piece::Bishop, color: piece_color }) => {
+                *piece_color == color && !(Board::square(*i).rank == home_rank(color) && [2, 5].contains(&Board::square(*i).file))
+            },
+            _ => false
+        })
+        .count()
+}
+
+// There is no castling history, so judge by the king and rook placement
+fn castled(board: &Board, color: Color) -> bool {
+    let rank = home_rank(color);
+    let is = |file: i8, piece: Piece| piece_at(board, Square { rank, file }) == Some(&OccupiedSquare { piece, color });
+
+    (is(6, Piece::King) && is(5, Piece::Rook)) ||
+    (is(2, Piece::King) && is(3, Piece::Rook)) ||
+    (is(1, Piece::King) && (is(2, Piece::Rook) || is(3, Piece::Rook)))
+}
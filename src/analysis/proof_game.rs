@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+use super::super::models::*;
+use super::super::game::{Game, ValidMove};
+
+// Finds a shortest sequence of legal moves leading from the standard starting position to `position`, trying
+// games of up to `max_plies` half-moves. The move counters and castling rights are ignored. An en passant
+// square is only compared if `position` has one.
+pub fn reachable_from_startpos(position: &Position, max_plies: usize) -> Option<Vec<ValidMove>> {
+    let start = Game::new(Game::standard_position());
+
+    // White to move needs an even number of half-moves
+    let first_depth = match position.next_to_move {
+        Color::White => 0,
+        Color::Black => 1
+    };
+
+    let mut search = ProofGameSearch { target: position, failed: HashSet::new() };
+    let mut moves = Vec::new();
+
+    for depth in (first_depth..=max_plies).step_by(2) {
+        if search.search(&start, depth, &mut moves) {
+            return Some(moves);
+        }
+    }
+
+    None
+}
+
+struct ProofGameSearch<'a> {
+    target: &'a Position,
+
+    // Positions (by hash) and remaining depths which are known not to lead to the target
+    failed: HashSet<(u64, usize)>
+}
+
+impl<'a> ProofGameSearch<'a> {
+    fn search(&mut self, game: &Game, depth: usize, moves: &mut Vec<ValidMove>) -> bool {
+        if depth == 0 {
+            return self.matches(game.position());
+        }
+
+        if self.failed.contains(&(game.hash(), depth)) || minimum_plies(game.position(), self.target) > depth {
+            return false;
+        }
+
+        for valid_move in game.valid_moves() {
+            let next = game.make_valid_move(&valid_move);
+
+            moves.push(valid_move);
+
+            if self.search(&next, depth - 1, moves) {
+                return true;
+            }
+
+            moves.pop();
+        }
+
+        self.failed.insert((game.hash(), depth));
+
+        false
+    }
+
+    fn matches(&self, position: &Position) -> bool {
+        position.board == self.target.board &&
+            position.next_to_move == self.target.next_to_move &&
+            (self.target.en_passant_square.is_none() || position.en_passant_square == self.target.en_passant_square)
+    }
+}
+
+// A lower bound of the half-moves needed to get from `position` to `target`, or usize::MAX if it can't be done
+fn minimum_plies(position: &Position, target: &Position) -> usize {
+    let moves_needed = |color: Color| -> Option<usize> {
+        let count = |position: &Position, color: Color, pawns: bool| position.board.squares.iter()
+            .flatten()
+            .filter( |occupancy| occupancy.color == color && (!pawns || occupancy.piece == Piece::Pawn) )
+            .count();
+
+        // Pieces can only disappear and pawns can't come back
+        if count(position, color, false) < count(target, color, false) || count(position, color, true) < count(target, color, true) {
+            return None;
+        }
+
+        // Every move puts at most one piece on its target square...
+        let misplaced = target.board.squares.iter().zip(position.board.squares.iter())
+            .filter( |(wanted, current)| matches!(wanted, Some(occupancy) if occupancy.color == color) && wanted != current )
+            .count();
+
+        // ...and captures at most one of the opponent's pieces
+        let captures = count(position, color.opposite(), false).saturating_sub(count(target, color.opposite(), false));
+
+        Some(misplaced.max(captures))
+    };
+
+    let (to_move, other) = match (moves_needed(position.next_to_move), moves_needed(position.next_to_move.opposite())) {
+        (Some(to_move), Some(other)) => (to_move, other),
+        _ => return usize::MAX
+    };
+
+    (2 * to_move).saturating_sub(1).max(2 * other)
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+// Fitted by lichess on its games, see https://lichess.org/page/accuracy
+const WIN_CURVE: f64 = -0.003_682_08;
+
+// Expected score for the side the centipawns are counted for, from 0 (lost) to 1 (won), with draws as halves
+pub fn expected_score(centipawns: i32) -> f64 {
+    1.0 / (1.0 + (WIN_CURVE * centipawns as f64).exp())
+}
+
+// Percent, 100 for moves which keep the expected score and close to 0 for ones which throw the game away
+pub fn move_accuracy(score_before: i32, score_after: i32) -> f64 {
+    let lost = 100.0 * (expected_score(score_before) - expected_score(score_after)).max(0.0);
+
+    (103.1668 * (-0.04354 * lost).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GameAccuracy {
+    // None for a side without any moves
+    pub white: Option<f64>,
+    pub black: Option<f64>
+}
+
+impl GameAccuracy {
+    pub fn of(&self, color: Color) -> Option<f64> {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black
+        }
+    }
+}
+
+// The average of the move accuracies and their harmonic mean, so that a few blunders pull it down more than
+// a plain average would
+pub fn game_accuracy(assessments: &[MoveAssessment]) -> GameAccuracy {
+    let side = |color: Color| {
+        let accuracies: Vec<f64> = assessments.iter()
+            .filter( |assessment| assessment.valid_move.color == color )
+            .map( |assessment| assessment.accuracy() )
+            .collect();
+
+        if accuracies.is_empty() {
+            return None;
+        }
+
+        let count = accuracies.len() as f64;
+        let mean = accuracies.iter().sum::<f64>() / count;
+        let harmonic = count / accuracies.iter().map( |accuracy| 1.0 / accuracy.max(1.0) ).sum::<f64>();
+
+        Some((mean + harmonic) / 2.0)
+    };
+
+    GameAccuracy { white: side(Color::White), black: side(Color::Black) }
+}
+
+impl MoveAssessment {
+    pub fn expected_score_before(&self) -> f64 {
+        expected_score(self.score_before)
+    }
+
+    pub fn expected_score_after(&self) -> f64 {
+        expected_score(self.score_after)
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        move_accuracy(self.score_before, self.score_after)
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+use super::super::game::{Game, ValidMove};
+use super::super::search::{Searcher, SearchLimits};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CachedAnalysis {
+    pub depth: u32,
+
+    // Centipawns from the point of view of the side to move
+    pub score: i32,
+
+    // In UCI notation, so that it can be stored without the position
+    pub best_move: Option<String>
+}
+
+// Engine results by position, so that reviewing a game again, or another game reaching the same positions,
+// doesn't search them again. Keyed by `Position::stable_key`, which doesn't change between versions, so the cache
+// can be saved and loaded.
+#[derive(Debug, Default, Clone)]
+pub struct AnalysisCache {
+    entries: HashMap<u64, CachedAnalysis>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Option<std::path::PathBuf>
+}
+
+impl AnalysisCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Starts empty if the file doesn't exist yet. `save` writes back to the same file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+
+        let mut cache = if path.exists() {
+            let text = std::fs::read_to_string(path).map_err( |error| error.to_string() )?;
+
+            Self::from_text(&text)?
+        } else {
+            Self::new()
+        };
+
+        cache.path = Some(path.to_path_buf());
+
+        Ok(cache)
+    }
+
+    // Does nothing for caches which weren't opened from a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Result<(), String> {
+        match &self.path {
+            Some(path) => std::fs::write(path, self.to_text()).map_err( |error| error.to_string() ),
+            None => Ok(())
+        }
+    }
+
+    // Only results searched at least as deep as asked for
+    pub fn get(&self, key: u64, depth: u32) -> Option<&CachedAnalysis> {
+        self.entries.get(&key).filter( |entry| entry.depth >= depth )
+    }
+
+    // Keeps the deeper result when the position is already there
+    pub fn insert(&mut self, key: u64, analysis: CachedAnalysis) {
+        match self.entries.get(&key) {
+            Some(existing) if existing.depth > analysis.depth => (),
+            _ => { self.entries.insert(key, analysis); }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Searches the position unless a deep enough result is already there
+    pub fn analyse(&mut self, searcher: &mut Searcher, game: &Game, depth: u32) -> (i32, Option<ValidMove>) {
+        let key = game.position().stable_key();
+
+        if let Some(entry) = self.get(key, depth) {
+            let best_move = entry.best_move.as_ref().and_then( |notation| ValidMove::from_uci(game, notation).ok() );
+
+            // A colliding key can point to a move which isn't legal here, in which case search again
+            if best_move.is_some() || entry.best_move.is_none() {
+                return (entry.score, best_move);
+            }
+        }
+
+        let result = searcher.search(game, &SearchLimits::depth(depth));
+
+        self.insert(key, CachedAnalysis {
+            depth,
+            score: result.score,
+            best_move: result.best_move.as_ref().map( |best_move| best_move.uci() )
+        });
+
+        (result.score, result.best_move)
+    }
+
+    // One position per line: "<stable key in hex> <depth> <score> <best move or ->"
+    pub fn to_text(&self) -> String {
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+
+        keys.into_iter().map( |key| {
+            let entry = &self.entries[key];
+
+            format!("{:016x} {} {} {}\n", key, entry.depth, entry.score, entry.best_move.as_deref().unwrap_or("-"))
+        }).collect()
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut cache = Self::new();
+
+        for (index, line) in text.lines().enumerate().filter( |(_, line)| !line.trim().is_empty() ) {
+            let invalid = || format!("Invalid analysis cache entry on line {}", index + 1);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            if fields.len() != 4 {
+                return Err(invalid());
+            }
+
+            let key = u64::from_str_radix(fields[0], 16).map_err( |_| invalid() )?;
+            let depth = fields[1].parse().map_err( |_| invalid() )?;
+            let score = fields[2].parse().map_err( |_| invalid() )?;
+            let best_move = if fields[3] == "-" { None } else { Some(String::from(fields[3])) };
+
+            cache.insert(key, CachedAnalysis { depth, score, best_move });
+        }
+
+        Ok(cache)
+    }
+}
+
+// Serialized as the text of to_text, e.g. to be saved as part of an AnalysisJob
+impl Serialize for AnalysisCache {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_text())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnalysisCache {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+
+        Self::from_text(&text).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,119 @@
+use super::*;
+use super::super::parser::ParsedGame;
+use std::time::Duration;
+
+// A side is in time trouble once its clock drops below this part of the starting time
+const TIME_TROUBLE_FRACTION: f64 = 0.1;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MoveTime {
+    pub ply: usize,
+    pub color: Color,
+
+    // Remaining time after the move
+    pub clock: Duration,
+
+    // None for the first move of a side when the time control is unknown
+    pub spent: Option<Duration>,
+    pub time_trouble: bool
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TimeReport {
+    pub moves: Vec<MoveTime>,
+
+    // First ply where each side got into time trouble
+    pub white_time_trouble_from: Option<usize>,
+    pub black_time_trouble_from: Option<usize>,
+
+    // Mistakes and blunders made in time trouble
+    pub blunders_in_time_trouble: Vec<MoveAssessment>
+}
+
+impl TimeReport {
+    pub fn total_spent(&self, color: Color) -> Duration {
+        self.moves.iter()
+            .filter( |move_time| move_time.color == color )
+            .filter_map( |move_time| move_time.spent )
+            .sum()
+    }
+}
+
+// Only moves with a %clk comment are included
+pub fn time_usage(pgn_game: &ParsedGame) -> Vec<MoveTime> {
+    let color_to_start = match &pgn_game.fen {
+        Some(fen) => Position::from_fen(fen).map( |position| position.next_to_move ).unwrap_or(Color::White),
+        None => Color::White
+    };
+
+    let (base, increment) = match pgn_game.headers().time_control.as_deref().and_then(parse_time_control) {
+        Some((base, increment)) => (Some(base), increment),
+        None => (None, Duration::from_secs(0))
+    };
+
+    let mut previous_clock = [base, base];
+    let mut starting_clock = [base, base];
+    let mut moves = Vec::new();
+
+    for (ply, clock) in pgn_game.clocks().into_iter().enumerate() {
+        let color = if ply % 2 == 0 { color_to_start } else { color_to_start.opposite() };
+        let side = match color {
+            Color::White => 0,
+            Color::Black => 1
+        };
+
+        let clock = match clock {
+            Some(clock) => clock,
+            None => continue
+        };
+
+        let spent = previous_clock[side].map( |previous| (previous + increment).saturating_sub(clock) );
+        let starting = *starting_clock[side].get_or_insert(clock);
+
+        moves.push(MoveTime {
+            ply,
+            color,
+            clock,
+            spent,
+            time_trouble: clock.as_secs_f64() < starting.as_secs_f64() * TIME_TROUBLE_FRACTION
+        });
+
+        previous_clock[side] = Some(clock);
+    }
+
+    moves
+}
+
+pub fn time_report(pgn_game: &ParsedGame, assessments: &[MoveAssessment]) -> TimeReport {
+    let moves = time_usage(pgn_game);
+
+    let time_trouble_from = |color: Color| moves.iter()
+        .find( |move_time| move_time.color == color && move_time.time_trouble )
+        .map( |move_time| move_time.ply );
+
+    let blunders_in_time_trouble = assessments.iter()
+        .filter( |assessment| matches!(assessment.verdict, Some(Verdict::Mistake) | Some(Verdict::Blunder)) )
+        .filter( |assessment| moves.iter().any( |move_time| move_time.ply == assessment.ply && move_time.time_trouble ) )
+        .cloned()
+        .collect();
+
+    TimeReport {
+        white_time_trouble_from: time_trouble_from(Color::White),
+        black_time_trouble_from: time_trouble_from(Color::Black),
+        blunders_in_time_trouble,
+        moves
+    }
+}
+
+// "300+2" is five minutes with a two second increment
+fn parse_time_control(time_control: &str) -> Option<(Duration, Duration)> {
+    let mut parts = time_control.split('+');
+
+    let base: u64 = parts.next()?.parse().ok()?;
+    let increment: u64 = match parts.next() {
+        Some(increment) => increment.parse().ok()?,
+        None => 0
+    };
+
+    Some((Duration::from_secs(base), Duration::from_secs(increment)))
+}
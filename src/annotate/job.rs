@@ -0,0 +1,239 @@
+use std::collections::VecDeque;
+
+use serde::{Serialize, Deserialize};
+
+use super::*;
+use super::super::pgn_file::PgnChunks;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GameReview {
+    pub tags: Vec<(String, String)>,
+
+    // One comment per move, see commentary()
+    pub commentary: Vec<String>,
+
+    pub white_accuracy: Option<f64>,
+    pub black_accuracy: Option<f64>
+}
+
+// Jobs opened from a file are saved after this many reviewed games, and when `run` stops
+const CHECKPOINT_GAMES: usize = 10;
+
+// Reviews every game of a PGN database, one game at a time. In between games the job can be saved, e.g. as
+// JSON, and resumed later with the same PGN. Only the offset of the input is stored, not the input itself,
+// along with its length and checksum so that the job refuses to continue with another input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisJob {
+    pub options: CommentaryOptions,
+
+    // Set by the first step
+    pub input: Option<InputFingerprint>,
+
+    // Where reading the input continues, see PgnChunks::bytes_consumed
+    pub offset: usize,
+
+    // Games read from the input but not reviewed yet, as PGN
+    pub queue: VecDeque<String>,
+
+    pub reviews: Vec<GameReview>,
+
+    // Games which couldn't be read
+    pub errors: Vec<String>,
+
+    pub cache: AnalysisCache,
+
+    // Whether the checksum was compared in this process already, the length is compared on every step
+    #[serde(skip)]
+    input_checked: bool,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    path: Option<std::path::PathBuf>
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct InputFingerprint {
+    pub length: usize,
+
+    // FNV-1a of the input bytes
+    pub checksum: u64
+}
+
+impl InputFingerprint {
+    pub fn of(input: &str) -> Self {
+        let checksum = input.bytes().fold(0xcbf29ce484222325, |hash: u64, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3) );
+
+        InputFingerprint { length: input.len(), checksum }
+    }
+}
+
+impl AnalysisJob {
+    pub fn new(options: CommentaryOptions) -> Self {
+        AnalysisJob {
+            options,
+            input: None,
+            offset: 0,
+            queue: VecDeque::new(),
+            reviews: Vec::new(),
+            errors: Vec::new(),
+            cache: AnalysisCache::new(),
+            input_checked: false,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            path: None
+        }
+    }
+
+    // Resumes the job saved in the file, or starts a new one if it doesn't exist yet. `save` writes back to
+    // the same file. A saved job is only resumed with the options it was started with, as the reviews so far
+    // would be mixed with ones made differently.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open<P: AsRef<std::path::Path>>(path: P, options: CommentaryOptions) -> Result<Self, String> {
+        let path = path.as_ref();
+
+        let mut job = if path.exists() {
+            let text = std::fs::read_to_string(path).map_err( |error| error.to_string() )?;
+            let job = Self::from_json(&text)?;
+
+            if job.options != options {
+                return Err(String::from("The analysis job was started with other options"));
+            }
+
+            job
+        } else {
+            Self::new(options)
+        };
+
+        job.path = Some(path.to_path_buf());
+
+        Ok(job)
+    }
+
+    // Does nothing for jobs which weren't opened from a file. The file is replaced only once the new one is
+    // written, so that stopping in the middle of saving doesn't lose the job.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(())
+        };
+
+        let temporary = path.with_extension("tmp");
+
+        std::fs::write(&temporary, self.to_json()).map_err( |error| error.to_string() )?;
+        std::fs::rename(&temporary, path).map_err( |error| error.to_string() )
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Cannot serialize the analysis job")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err( |error| format!("Invalid analysis job: {}", error) )
+    }
+
+    pub fn is_done(&self, pgn: &str) -> bool {
+        self.queue.is_empty() && self.offset >= pgn.len()
+    }
+
+    // Reviews the next game, reading more of the input when none is queued. Returns whether there is more
+    // to do after it, or an error if the input isn't the one the job was started with.
+    pub fn step(&mut self, pgn: &str) -> Result<bool, String> {
+        self.check_input(pgn)?;
+
+        if self.queue.is_empty() && !self.is_done(pgn) {
+            let mut chunks = PgnChunks::new(pgn).starting_at(self.offset);
+
+            for tagged_game in chunks.next_chunk(1) {
+                match tagged_game {
+                    Ok((tags, game)) => self.queue.push_back(game.to_pgn_with_tags(&tags)),
+                    Err(error) => self.errors.push(error)
+                }
+            }
+
+            self.offset = chunks.bytes_consumed();
+        }
+
+        if let Some(queued) = self.queue.pop_front() {
+            match PgnChunks::new(&queued).next() {
+                Some(Ok((tags, game))) => {
+                    let review = self.review(tags, &game);
+                    self.reviews.push(review);
+                },
+
+                Some(Err(error)) => self.errors.push(error),
+                None => ()
+            }
+        }
+
+        Ok(!self.is_done(pgn))
+    }
+
+    // Reviews at most `max_games` games, saving the job after every CHECKPOINT_GAMES reviewed games and before
+    // returning. Returns the number of games reviewed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run(&mut self, pgn: &str, max_games: usize) -> Result<usize, String> {
+        self.run_with_progress(pgn, max_games, |_| ())
+    }
+
+    // Same as `run`, calling `on_save` after each time the job is saved
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run_with_progress<F>(&mut self, pgn: &str, max_games: usize, mut on_save: F) -> Result<usize, String>
+        where F: FnMut(&AnalysisJob)
+    {
+        let reviewed = self.reviews.len();
+
+        while self.reviews.len() - reviewed < max_games && !self.is_done(pgn) {
+            let before = self.reviews.len();
+            self.step(pgn)?;
+
+            // Steps which only read the input or hit an unreadable game don't count
+            if self.reviews.len() > before && (self.reviews.len() - reviewed).is_multiple_of(CHECKPOINT_GAMES) {
+                self.save()?;
+                on_save(self);
+            }
+        }
+
+        self.save()?;
+        on_save(self);
+
+        Ok(self.reviews.len() - reviewed)
+    }
+
+    fn check_input(&mut self, pgn: &str) -> Result<(), String> {
+        let mismatch = || String::from("The PGN is not the one the analysis job was started with");
+
+        match self.input {
+            None => {
+                self.input = Some(InputFingerprint::of(pgn));
+                self.input_checked = true;
+            },
+
+            Some(input) if input.length != pgn.len() => return Err(mismatch()),
+
+            Some(input) if !self.input_checked => {
+                if InputFingerprint::of(pgn) != input {
+                    return Err(mismatch());
+                }
+
+                self.input_checked = true;
+            },
+
+            Some(_) => ()
+        }
+
+        Ok(())
+    }
+
+    fn review(&mut self, tags: Vec<(String, String)>, game: &Game) -> GameReview {
+        let assessments = blunder_check_cached(game, &self.options, &mut self.cache);
+        let accuracy = game_accuracy(&assessments);
+
+        GameReview {
+            tags,
+            commentary: commentary_cached(game, &self.options, &mut self.cache),
+            white_accuracy: accuracy.white,
+            black_accuracy: accuracy.black
+        }
+    }
+}
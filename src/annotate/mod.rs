@@ -0,0 +1,265 @@
+use serde::{Serialize, Deserialize};
+
+use super::models::*;
+use super::game::{Game, ValidMove};
+use super::analysis;
+use super::search::Searcher;
+
+// Evaluation loss (in centipawns, for the side that moved) from which a move gets flagged
+const INACCURACY_LOSS: i32 = 70;
+const MISTAKE_LOSS: i32 = 150;
+const BLUNDER_LOSS: i32 = 300;
+
+// Mate scores are capped so that missing a mate does not dwarf everything else
+const MAX_SCORE: i32 = 2000;
+
+mod clock;
+mod cache;
+mod accuracy;
+mod screening;
+mod job;
+
+pub use clock::{MoveTime, TimeReport, time_usage, time_report};
+pub use cache::{AnalysisCache, CachedAnalysis};
+pub use accuracy::{GameAccuracy, expected_score, move_accuracy, game_accuracy};
+pub use screening::{GamePhase, PhaseStats, ScreeningReport, LOSS_BUCKETS, screen_player};
+pub use job::{AnalysisJob, GameReview, InputFingerprint};
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CommentaryOptions {
+    pub depth: u32
+}
+
+impl Default for CommentaryOptions {
+    fn default() -> Self {
+        CommentaryOptions { depth: 2 }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Verdict {
+    Inaccuracy,
+    Mistake,
+    Blunder
+}
+
+impl Verdict {
+    fn from_loss(loss: i32) -> Option<Verdict> {
+        if loss >= BLUNDER_LOSS {
+            Some(Verdict::Blunder)
+        } else if loss >= MISTAKE_LOSS {
+            Some(Verdict::Mistake)
+        } else if loss >= INACCURACY_LOSS {
+            Some(Verdict::Inaccuracy)
+        } else {
+            None
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Verdict::Inaccuracy => "an inaccuracy",
+            Verdict::Mistake    => "a mistake",
+            Verdict::Blunder    => "a blunder"
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MoveAssessment {
+    // Index into Game::moves()
+    pub ply: usize,
+    pub valid_move: ValidMove,
+    pub san: String,
+
+    // Centipawns for the side which played the move, before and after it, capped for mates
+    pub score_before: i32,
+    pub score_after: i32,
+
+    // Centipawns lost for the side which played the move
+    pub loss: i32,
+    pub verdict: Option<Verdict>,
+
+    // What the engine would have played instead, in SAN
+    pub best_move: Option<String>,
+
+    // The opponent's best answer to the move
+    pub best_reply: Option<String>
+}
+
+pub fn blunder_check(game: &Game, options: &CommentaryOptions) -> Vec<MoveAssessment> {
+    blunder_check_cached(game, options, &mut AnalysisCache::new())
+}
+
+// Takes the positions already in the cache from there and adds the ones it had to search
+pub fn blunder_check_cached(game: &Game, options: &CommentaryOptions, cache: &mut AnalysisCache) -> Vec<MoveAssessment> {
+    let history = game.history();
+    let mut searcher = Searcher::new();
+
+    // Scores from the point of view of the side to move, for every position including the final one
+    let mut games: Vec<&Game> = history.iter().map( |(before, _)| before ).collect();
+    games.push(game);
+
+    let results: Vec<_> = games.iter().map( |position| cache.analyse(&mut searcher, position, options.depth) ).collect();
+
+    history.iter().enumerate().map( |(ply, (before, valid_move))| {
+        let after = games[ply + 1];
+        let score_before = results[ply].0.clamp(-MAX_SCORE, MAX_SCORE);
+        let score_after = -results[ply + 1].0.clamp(-MAX_SCORE, MAX_SCORE);
+        let loss = score_before - score_after;
+
+        MoveAssessment {
+            ply,
+            valid_move: valid_move.clone(),
+            san: before.san(valid_move),
+            score_before,
+            score_after,
+            loss,
+            verdict: Verdict::from_loss(loss),
+            best_move: results[ply].1.as_ref().map( |best_move| before.san(best_move) ),
+            best_reply: results[ply + 1].1.as_ref().map( |reply| after.san(reply) )
+        }
+    }).collect()
+}
+
+// One comment per move of the game, e.g. "3. Bb5: develops the bishop and attacks c6"
+pub fn commentary(game: &Game) -> Vec<String> {
+    commentary_with(game, &CommentaryOptions::default())
+}
+
+pub fn commentary_with(game: &Game, options: &CommentaryOptions) -> Vec<String> {
+    commentary_cached(game, options, &mut AnalysisCache::new())
+}
+
+pub fn commentary_cached(game: &Game, options: &CommentaryOptions, cache: &mut AnalysisCache) -> Vec<String> {
+    let history = game.history();
+    let assessments = blunder_check_cached(game, options, cache);
+
+    history.iter().zip(assessments.iter()).enumerate().map( |(ply, ((before, valid_move), assessment))| {
+        let after = match history.get(ply + 1) {
+            Some((after, _)) => after,
+            None => game
+        };
+
+        let remarks = describe_move(before, after, valid_move);
+
+        let verdict = assessment.verdict.map( |verdict| match &assessment.best_reply {
+            Some(reply) if verdict != Verdict::Inaccuracy => format!("{}, allowing {}", verdict.description(), reply),
+            _ => String::from(verdict.description())
+        });
+
+        let number = match valid_move.color {
+            Color::White => format!("{}.", before.position().full_move_counter),
+            Color::Black => format!("{}...", before.position().full_move_counter)
+        };
+
+        let comment = match (remarks.is_empty(), verdict) {
+            (true, None)           => String::new(),
+            (true, Some(verdict))  => verdict,
+            (false, None)          => join_remarks(&remarks),
+            (false, Some(verdict)) => format!("{}; {}", join_remarks(&remarks), verdict)
+        };
+
+        if comment.is_empty() {
+            format!("{} {}", number, assessment.san)
+        } else {
+            format!("{} {}: {}", number, assessment.san, comment)
+        }
+    }).collect()
+}
+
+fn describe_move(before: &Game, after: &Game, valid_move: &ValidMove) -> Vec<String> {
+    let mut remarks = Vec::new();
+    let color = valid_move.color;
+
+    if valid_move.is_castle() {
+        remarks.push(String::from("castles"));
+    } else if is_development(valid_move) {
+        remarks.push(format!("develops the {}", piece_name(valid_move.piece)));
+    }
+
+    if let Some(taken) = valid_move.takes {
+        remarks.push(format!("takes the {}", piece_name(taken)));
+    }
+
+    if let Some(promotion) = valid_move.promotion {
+        remarks.push(format!("promotes to a {}", piece_name(promotion)));
+    }
+
+    if after.in_mate() {
+        remarks.push(String::from("delivers mate"));
+        return remarks;
+    }
+
+    if after.in_check(color.opposite()) {
+        remarks.push(String::from("gives check"));
+    }
+
+    let newly_attacked: Vec<String> = after.attacked_squares(valid_move.promotion.unwrap_or(valid_move.piece), valid_move.to, color)
+        .into_iter()
+        .filter( |square| match after.square_occupied(*square) {
+            // Defended pawns are not worth mentioning
+            Some(occupancy) if occupancy.color != color => match occupancy.piece {
+                Piece::King => false,
+                Piece::Pawn => after.defenders_of(*square, occupancy.color).is_empty(),
+                _ => true
+            },
+            _ => false
+        })
+        .filter( |square| !before.attackers_of(*square, color).iter().any( |(from, _)| *from == valid_move.from ) )
+        .map( |square| format!("{:?}", square) )
+        .collect();
+
+    if !newly_attacked.is_empty() {
+        remarks.push(format!("attacks {}", join_remarks(&newly_attacked)));
+    }
+
+    if after.square_safety(valid_move.to).is_hanging() {
+        remarks.push(format!("leaves the {} on {:?} hanging", piece_name(valid_move.promotion.unwrap_or(valid_move.piece)), valid_move.to));
+    }
+
+    let king_was_exposed = analysis::king_safety(before, color).is_some_and( |safety| safety.is_exposed() );
+    let king_is_exposed = analysis::king_safety(after, color).is_some_and( |safety| safety.is_exposed() );
+
+    if king_is_exposed && !king_was_exposed {
+        remarks.push(String::from("exposes the king"));
+    }
+
+    remarks
+}
+
+// A knight or bishop leaving its starting square
+fn is_development(valid_move: &ValidMove) -> bool {
+    let home_rank = match valid_move.color {
+        Color::White => 0,
+        Color::Black => 7
+    };
+
+    let home_files: &[i8] = match valid_move.piece {
+        Piece::Knight => &[1, 6],
+        Piece::Bishop => &[2, 5],
+        _ => return false
+    };
+
+    valid_move.from.rank == home_rank && home_files.contains(&valid_move.from.file)
+}
+
+fn piece_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn   => "pawn",
+        Piece::Knight => "knight",
+        Piece::Bishop => "bishop",
+        Piece::Rook   => "rook",
+        Piece::Queen  => "queen",
+        Piece::King   => "king"
+    }
+}
+
+// "a, b and c"
+fn join_remarks(remarks: &[String]) -> String {
+    match remarks.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new()
+    }
+}
@@ -0,0 +1,156 @@
+use super::*;
+use super::super::pgn_file::PgnChunks;
+
+// Upper bounds (exclusive) of the centipawn loss buckets, the last bucket has everything from 300 on
+pub const LOSS_BUCKETS: [i32; 7] = [1, 10, 25, 50, 100, 200, 300];
+
+// Moves up to this half-move count as the opening
+const OPENING_PLIES: usize = 20;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+
+    // Once the position is classified as an endgame, see Position::endgame_type
+    Endgame
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PhaseStats {
+    pub moves: usize,
+
+    // Moves which were the engine's first choice. Moves without an alternative aren't counted.
+    pub engine_matches: usize,
+    pub forced_moves: usize,
+
+    pub total_loss: i64,
+    pub total_accuracy: f64
+}
+
+impl PhaseStats {
+    // Percent of the moves which had an alternative
+    pub fn engine_match_percentage(&self) -> Option<f64> {
+        let counted = self.moves - self.forced_moves;
+
+        if counted == 0 {
+            None
+        } else {
+            Some(100.0 * self.engine_matches as f64 / counted as f64)
+        }
+    }
+
+    pub fn average_loss(&self) -> Option<f64> {
+        if self.moves == 0 { None } else { Some(self.total_loss as f64 / self.moves as f64) }
+    }
+
+    // The plain average of the move accuracies
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.moves == 0 { None } else { Some(self.total_accuracy / self.moves as f64) }
+    }
+
+    fn add(&mut self, assessment: &MoveAssessment, forced: bool) {
+        self.moves += 1;
+        self.total_loss += assessment.loss.max(0) as i64;
+        self.total_accuracy += assessment.accuracy();
+
+        if forced {
+            self.forced_moves += 1;
+        } else if assessment.best_move.as_ref() == Some(&assessment.san) {
+            self.engine_matches += 1;
+        }
+    }
+}
+
+// Statistics for screening the moves of one player, e.g. for an arbiter looking for engine assistance.
+// A high match percentage alone proves nothing, it has to be compared with the player's usual numbers.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ScreeningReport {
+    pub player: String,
+    pub games: usize,
+
+    pub overall: PhaseStats,
+    pub opening: PhaseStats,
+    pub middlegame: PhaseStats,
+    pub endgame: PhaseStats,
+
+    // Number of moves per loss bucket, see LOSS_BUCKETS
+    pub loss_distribution: [usize; 8],
+
+    // Games which couldn't be read
+    pub errors: Vec<String>
+}
+
+impl ScreeningReport {
+    pub fn phase(&self, phase: GamePhase) -> &PhaseStats {
+        match phase {
+            GamePhase::Opening    => &self.opening,
+            GamePhase::Middlegame => &self.middlegame,
+            GamePhase::Endgame    => &self.endgame
+        }
+    }
+
+    fn phase_mut(&mut self, phase: GamePhase) -> &mut PhaseStats {
+        match phase {
+            GamePhase::Opening    => &mut self.opening,
+            GamePhase::Middlegame => &mut self.middlegame,
+            GamePhase::Endgame    => &mut self.endgame
+        }
+    }
+}
+
+// Analyses every game of the PGNs in which the player, matched by the White and Black tags, took part
+pub fn screen_player(pgns: &[&str], player: &str, options: &CommentaryOptions, cache: &mut AnalysisCache) -> ScreeningReport {
+    let mut report = ScreeningReport { player: String::from(player), ..ScreeningReport::default() };
+
+    for pgn in pgns {
+        for tagged_game in PgnChunks::new(pgn) {
+            let (tags, game) = match tagged_game {
+                Ok(tagged_game) => tagged_game,
+                Err(error) => {
+                    report.errors.push(error);
+                    continue;
+                }
+            };
+
+            let tag = |name: &str| tags.iter().find( |(tag, _)| tag == name ).map( |(_, value)| value.as_str() );
+
+            let color = if tag("White") == Some(player) {
+                Color::White
+            } else if tag("Black") == Some(player) {
+                Color::Black
+            } else {
+                continue;
+            };
+
+            report.games += 1;
+
+            let history = game.history();
+
+            for assessment in blunder_check_cached(&game, options, cache) {
+                if assessment.valid_move.color != color {
+                    continue;
+                }
+
+                let before = &history[assessment.ply].0;
+                let forced = before.count_legal_moves() == 1;
+
+                let phase = if before.position().endgame_type().is_some() {
+                    GamePhase::Endgame
+                } else if assessment.ply < OPENING_PLIES {
+                    GamePhase::Opening
+                } else {
+                    GamePhase::Middlegame
+                };
+
+                report.overall.add(&assessment, forced);
+                report.phase_mut(phase).add(&assessment, forced);
+
+                let bucket = LOSS_BUCKETS.iter().position( |bound| assessment.loss < *bound ).unwrap_or(LOSS_BUCKETS.len());
+                report.loss_distribution[bucket] += 1;
+            }
+        }
+    }
+
+    report
+}
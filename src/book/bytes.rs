@@ -0,0 +1,137 @@
+use std::convert::TryInto;
+
+use super::*;
+use super::super::models::*;
+
+// A serialized opening tree is little endian:
+//
+//   "PGOT", version (u32, 1), number of moves (u32)
+//   every move: key of the position it is played from (u64), key of the position after it (u64),
+//               weight (u32), the move (8 bytes, see write_move)
+//
+// The keys are `Position::stable_key`, which is fixed as part of the format. Moves are sorted, so the same tree always gives the same bytes.
+const MAGIC: &[u8; 4] = b"PGOT";
+const VERSION: u32 = 1;
+
+const NO_SQUARE: u8 = 255;
+const NO_PIECE: u8 = 255;
+
+impl OpeningTree {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut edges: Vec<(u64, u64, u32, &ValidMove)> = self.incoming.iter()
+            .flat_map( |(next_hash, incoming)| incoming.iter().map( move |(hash, valid_move)| (*hash, *next_hash, valid_move) ) )
+            .map( |(hash, next_hash, valid_move)| (hash, next_hash, self.weight(hash, valid_move), valid_move) )
+            .collect();
+
+        edges.sort_by_key( |(hash, next_hash, _, valid_move)| (*hash, *next_hash, valid_move.to_u16()) );
+
+        let mut bytes = MAGIC.to_vec();
+
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(edges.len() as u32).to_le_bytes());
+
+        for (hash, next_hash, weight, valid_move) in edges {
+            bytes.extend_from_slice(&hash.to_le_bytes());
+            bytes.extend_from_slice(&next_hash.to_le_bytes());
+            bytes.extend_from_slice(&weight.to_le_bytes());
+
+            write_move(&mut bytes, valid_move);
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = bytes;
+
+        if take(&mut reader, 4)? != MAGIC {
+            return Err(String::from("Not an opening tree"));
+        }
+
+        let version = u32::from_le_bytes(take(&mut reader, 4)?.try_into().unwrap());
+        if version != VERSION {
+            return Err(format!("Unsupported opening tree version {}", version));
+        }
+
+        let count = u32::from_le_bytes(take(&mut reader, 4)?.try_into().unwrap()) as usize;
+        let mut tree = OpeningTree::new();
+
+        for _ in 0..count {
+            let hash = u64::from_le_bytes(take(&mut reader, 8)?.try_into().unwrap());
+            let next_hash = u64::from_le_bytes(take(&mut reader, 8)?.try_into().unwrap());
+            let weight = u32::from_le_bytes(take(&mut reader, 4)?.try_into().unwrap());
+            let valid_move = read_move(take(&mut reader, 8)?)?;
+
+            tree.add_edge(hash, next_hash, valid_move, weight);
+        }
+
+        if !reader.is_empty() {
+            return Err(String::from("Unexpected data after the opening tree"));
+        }
+
+        Ok(tree)
+    }
+}
+
+// Color, from, to, piece, captured piece, en passant capture, promotion, en passant square
+fn write_move(bytes: &mut Vec<u8>, valid_move: &ValidMove) {
+    let square = |square: Option<Square>| square.map( |square| square.to_a1_index() as u8 ).unwrap_or(NO_SQUARE);
+    let piece = |piece: Option<Piece>| piece.map( |piece| piece.index() as u8 ).unwrap_or(NO_PIECE);
+
+    bytes.extend_from_slice(&[
+        if valid_move.color == Color::White { 0 } else { 1 },
+        square(Some(valid_move.from)),
+        square(Some(valid_move.to)),
+        piece(Some(valid_move.piece)),
+        piece(valid_move.takes),
+        valid_move.takes_en_passant as u8,
+        piece(valid_move.promotion),
+        square(valid_move.en_passant_square)
+    ]);
+}
+
+fn read_move(bytes: &[u8]) -> Result<ValidMove, String> {
+    let invalid = || String::from("Invalid move in the opening tree");
+
+    let square = |value: u8| match value {
+        NO_SQUARE => Ok(None),
+        _ => Square::from_a1_index(value as usize).map(Some).ok_or_else(invalid)
+    };
+
+    let piece = |value: u8| match value {
+        NO_PIECE => Ok(None),
+        0..=5 => Ok(Some(PIECES[value as usize])),
+        _ => Err(invalid())
+    };
+
+    Ok(ValidMove {
+        color: match bytes[0] {
+            0 => Color::White,
+            1 => Color::Black,
+            _ => return Err(invalid())
+        },
+
+        from: square(bytes[1])?.ok_or_else(invalid)?,
+        to: square(bytes[2])?.ok_or_else(invalid)?,
+
+        piece: piece(bytes[3])?.ok_or_else(invalid)?,
+
+        takes: piece(bytes[4])?,
+        takes_en_passant: bytes[5] != 0,
+
+        promotion: piece(bytes[6])?,
+
+        en_passant_square: square(bytes[7])?
+    })
+}
+
+fn take<'a>(reader: &mut &'a [u8], length: usize) -> Result<&'a [u8], String> {
+    if reader.len() < length {
+        return Err(String::from("Truncated opening tree"));
+    }
+
+    let (taken, rest) = reader.split_at(length);
+    *reader = rest;
+
+    Ok(taken)
+}
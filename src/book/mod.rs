@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use super::game::{Game, ValidMove, Replay, ReplayError};
+use super::rng::{Rng, mix};
+
+mod bytes;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BookMove {
+    pub valid_move: ValidMove,
+    pub weight: u32
+}
+
+// Anything that can suggest moves for a position, e.g. an opening tree built from PGN games
+pub trait OpeningBook {
+    fn moves(&self, game: &Game) -> Vec<BookMove>;
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BookSelection {
+    MostPlayed,
+
+    // Picks proportionally to the weights. The same seed always picks the same move in a given position,
+    // unless the moves are chosen with a generator, see choose_move_with_rng.
+    Weighted { seed: u64 }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BookOptions {
+    // The book is only consulted up to (and including) this full move number
+    pub max_full_moves: i64,
+    pub selection: BookSelection
+}
+
+impl Default for BookOptions {
+    fn default() -> Self {
+        BookOptions {
+            max_full_moves: 10,
+            selection: BookSelection::MostPlayed
+        }
+    }
+}
+
+// Positions are keyed by `Position::stable_key`, so move orders that transpose into each other share their moves
+// and the serialized tree stays readable by later versions
+#[derive(Debug, Clone, Default)]
+pub struct OpeningTree {
+    positions: HashMap<u64, Vec<BookMove>>,
+
+    // Moves leading into each position, with the hash of the position they were played from
+    incoming: HashMap<u64, Vec<(u64, ValidMove)>>
+}
+
+impl OpeningTree {
+    pub fn new() -> Self {
+        OpeningTree { positions: HashMap::new(), incoming: HashMap::new() }
+    }
+
+    pub fn from_pgn(pgn: &str) -> Result<Self, String> {
+        let mut tree = Self::new();
+
+        for pgn_game in Game::parse_pgn(pgn)? {
+            tree.add_replay(Game::replay(pgn_game))?;
+        }
+
+        Ok(tree)
+    }
+
+    pub fn add_replay(&mut self, replay: Replay) -> Result<(), ReplayError> {
+        let mut previous = replay.game().clone();
+
+        for step in replay {
+            let (valid_move, game) = step?;
+
+            self.add_move(&previous, valid_move, 1);
+            previous = game;
+        }
+
+        Ok(())
+    }
+
+    pub fn add_move(&mut self, game: &Game, valid_move: ValidMove, weight: u32) {
+        let next_hash = game.make_valid_move(&valid_move).position().stable_key();
+
+        self.add_edge(game.position().stable_key(), next_hash, valid_move, weight);
+    }
+
+    fn add_edge(&mut self, hash: u64, next_hash: u64, valid_move: ValidMove, weight: u32) {
+        let incoming = self.incoming.entry(next_hash).or_default();
+
+        if !incoming.iter().any( |(from, incoming_move)| *from == hash && *incoming_move == valid_move ) {
+            incoming.push((hash, valid_move.clone()));
+        }
+
+        let moves = self.positions.entry(hash).or_default();
+
+        match moves.iter_mut().find( |book_move| book_move.valid_move == valid_move ) {
+            Some(book_move) => book_move.weight += weight,
+            None => moves.push(BookMove { valid_move, weight })
+        }
+    }
+
+    // Adds all moves of the other tree, summing the weights. Both trees key positions the same way, so lines
+    // of the two trees which transpose into each other end up sharing the same node.
+    pub fn merge_with_transpositions(&mut self, other: &OpeningTree) {
+        for (next_hash, incoming) in other.incoming.iter() {
+            for (hash, valid_move) in incoming {
+                self.add_edge(*hash, *next_hash, valid_move.clone(), other.weight(*hash, valid_move));
+            }
+        }
+    }
+
+    // Number of different positions of the tree from which the position was reached
+    pub fn transpositions(&self, game: &Game) -> usize {
+        self.incoming.get(&game.position().stable_key()).map( |incoming| incoming.len() ).unwrap_or(0)
+    }
+
+    // Every move order inside the tree which leads to the position, starting from a position without any
+    // incoming moves (usually the starting position)
+    pub fn move_orders(&self, game: &Game) -> Vec<Vec<ValidMove>> {
+        let mut move_orders = Vec::new();
+        let mut line = Vec::new();
+        let mut visited = Vec::new();
+
+        self.collect_move_orders(game.position().stable_key(), &mut line, &mut visited, &mut move_orders);
+
+        move_orders
+    }
+
+    fn collect_move_orders(&self, hash: u64, line: &mut Vec<ValidMove>, visited: &mut Vec<u64>, move_orders: &mut Vec<Vec<ValidMove>>) {
+        let incoming = match self.incoming.get(&hash) {
+            Some(incoming) if !incoming.is_empty() => incoming,
+            _ => {
+                if !line.is_empty() {
+                    move_orders.push(line.iter().rev().cloned().collect());
+                }
+
+                return;
+            }
+        };
+
+        // Repetitions create cycles
+        if visited.contains(&hash) {
+            return;
+        }
+
+        visited.push(hash);
+
+        for (from, valid_move) in incoming {
+            line.push(valid_move.clone());
+            self.collect_move_orders(*from, line, visited, move_orders);
+            line.pop();
+        }
+
+        visited.pop();
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    fn weight(&self, hash: u64, valid_move: &ValidMove) -> u32 {
+        self.positions.get(&hash)
+            .and_then( |moves| moves.iter().find( |book_move| book_move.valid_move == *valid_move ) )
+            .map( |book_move| book_move.weight )
+            .unwrap_or(0)
+    }
+}
+
+impl OpeningBook for OpeningTree {
+    fn moves(&self, game: &Game) -> Vec<BookMove> {
+        let valid_moves = game.valid_moves();
+
+        // Hash collisions could suggest moves from another position
+        self.positions.get(&game.position().stable_key())
+            .map( |moves| moves.iter().filter( |book_move| valid_moves.contains(&book_move.valid_move) ).cloned().collect() )
+            .unwrap_or_default()
+    }
+}
+
+pub fn choose_move(book: &dyn OpeningBook, game: &Game, options: &BookOptions) -> Option<ValidMove> {
+    choose(book, game, options, None)
+}
+
+// Weighted picks are drawn from the generator instead of the seed, e.g. so that one seed replays a whole game
+// of a bot without it repeating its choices every time a position comes up again
+pub fn choose_move_with_rng(book: &dyn OpeningBook, game: &Game, options: &BookOptions, rng: &mut dyn Rng) -> Option<ValidMove> {
+    choose(book, game, options, Some(rng))
+}
+
+fn choose(book: &dyn OpeningBook, game: &Game, options: &BookOptions, rng: Option<&mut dyn Rng>) -> Option<ValidMove> {
+    if game.position().full_move_counter > options.max_full_moves {
+        return None;
+    }
+
+    let mut moves: Vec<BookMove> = book.moves(game).into_iter()
+        .filter( |book_move| book_move.weight > 0 )
+        .collect();
+
+    // Deterministic order regardless of how the book stores its moves
+    moves.sort_by( |a, b| b.weight.cmp(&a.weight).then_with( || a.valid_move.notation().cmp(&b.valid_move.notation()) ) );
+
+    match options.selection {
+        BookSelection::MostPlayed => moves.into_iter().next().map( |book_move| book_move.valid_move ),
+
+        BookSelection::Weighted { seed } => {
+            let total: u64 = moves.iter().map( |book_move| book_move.weight as u64 ).sum();
+
+            if total == 0 {
+                return None;
+            }
+
+            let mut pick = match rng {
+                Some(rng) => rng.below(total),
+                None => mix(seed ^ game.hash()) % total
+            };
+
+            for book_move in moves {
+                if pick < book_move.weight as u64 {
+                    return Some(book_move.valid_move);
+                }
+
+                pick -= book_move.weight as u64;
+            }
+
+            None
+        }
+    }
+}
+
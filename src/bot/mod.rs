@@ -0,0 +1,175 @@
+use super::game::{Game, ValidMove};
+use super::search::{Searcher, SearchLimits};
+use super::rng::{Rng, SplitMix64};
+
+pub const MIN_ELO: u32 = 400;
+pub const MAX_ELO: u32 = 2400;
+
+// How a bot of a given rating plays. The numbers are rough guesses, tuned by playing the levels against each other.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BotStrength {
+    pub depth: u32,
+    pub nodes: u64,
+
+    // Chance in percent to play a weaker move than the best one found
+    pub mistake_chance: u32,
+
+    // Centipawns a mistake can lose at most, so that weak bots hang pieces and stronger ones only drift
+    pub max_mistake: i32
+}
+
+impl BotStrength {
+    pub fn for_elo(elo: u32) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        let steps = elo - MIN_ELO;
+
+        BotStrength {
+            depth: 1 + steps / 400,
+            nodes: 200 << (steps / 200),
+            mistake_chance: (MAX_ELO - elo) / 50,
+            max_mistake: ((MAX_ELO - elo) / 4) as i32
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BotMove {
+    pub valid_move: ValidMove,
+
+    // What the search found, the same as `valid_move` unless the bot made a mistake on purpose
+    pub best_move: ValidMove,
+    pub mistake: bool
+}
+
+// The built-in engine playing at a target rating, e.g. for "play against a 1200 bot" levels.
+// The search is capped in depth and nodes, and every now and then a weaker move is played on purpose.
+// Mistakes are picked among moves which look reasonable at a glance, so that they are the kind people make.
+pub struct Bot {
+    elo: u32,
+    strength: BotStrength,
+    searcher: Searcher,
+
+    // With a seed, the generator starts over from it in every game
+    seed: Option<u64>,
+    rng: Box<dyn Rng + Send>
+}
+
+impl Bot {
+    pub fn new(elo: u32) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+
+        Bot {
+            elo,
+            strength: BotStrength::for_elo(elo),
+            searcher: Searcher::new(),
+
+            seed: Some(0),
+            rng: Box::new(SplitMix64::new(0))
+        }
+    }
+
+    // The same seed plays the same moves in the same games
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self.rng = Box::new(SplitMix64::new(seed));
+        self
+    }
+
+    // Draws from the generator for the whole life of the bot, e.g. one shared with other parts of an
+    // experiment which is replayed from a single seed
+    pub fn with_rng<R: Rng + Send + 'static>(mut self, rng: R) -> Self {
+        self.seed = None;
+        self.rng = Box::new(rng);
+        self
+    }
+
+    // E.g. a searcher with an opening book, so that the bot doesn't play the same openings every game
+    pub fn with_searcher(mut self, searcher: Searcher) -> Self {
+        self.searcher = searcher;
+        self
+    }
+
+    pub fn with_strength(mut self, strength: BotStrength) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn elo(&self) -> u32 {
+        self.elo
+    }
+
+    pub fn strength(&self) -> BotStrength {
+        self.strength
+    }
+
+    pub fn new_game(&mut self) {
+        self.searcher.clear();
+
+        if let Some(seed) = self.seed {
+            self.rng = Box::new(SplitMix64::new(seed));
+        }
+    }
+
+    // None when there are no legal moves
+    pub fn choose_move(&mut self, game: &Game) -> Option<BotMove> {
+        let limits = SearchLimits {
+            depth: Some(self.strength.depth),
+            nodes: Some(self.strength.nodes),
+            ..Default::default()
+        };
+
+        let result = self.searcher.search(game, &limits);
+        let best_move = result.best_move?;
+
+        let roll = self.rng.next_u64();
+
+        if result.from_book || roll % 100 >= self.strength.mistake_chance as u64 {
+            return Some(BotMove { valid_move: best_move.clone(), best_move, mistake: false });
+        }
+
+        let mistake = self.choose_mistake(game, &best_move, roll / 100);
+
+        Some(match mistake {
+            Some(valid_move) => BotMove { valid_move, best_move, mistake: true },
+            None             => BotMove { valid_move: best_move.clone(), best_move, mistake: false }
+        })
+    }
+
+    // Scores every move with a quick look at the captures that follow, which is how a mistake tends to look fine.
+    // Moves closer to the best one are picked more often.
+    fn choose_mistake(&mut self, game: &Game, best_move: &ValidMove, roll: u64) -> Option<ValidMove> {
+        let scored: Vec<(ValidMove, i32)> = game.valid_moves().into_iter()
+            .map( |valid_move| {
+                let child = game.make_valid_move(&valid_move);
+                let score = -self.searcher.quiescence_score(&child);
+
+                (valid_move, score)
+            })
+            .collect();
+
+        let best_score = scored.iter().map( |(_, score)| *score ).max()?;
+
+        let candidates: Vec<(ValidMove, u64)> = scored.into_iter()
+            .filter( |(valid_move, score)| valid_move != best_move && best_score - score <= self.strength.max_mistake )
+            .map( |(valid_move, score)| (valid_move, (self.strength.max_mistake - (best_score - score) + 1) as u64) )
+            .collect();
+
+        let total: u64 = candidates.iter().map( |(_, weight)| weight ).sum();
+
+        if total == 0 {
+            return None;
+        }
+
+        let mut pick = roll % total;
+
+        for (valid_move, weight) in candidates {
+            if pick < weight {
+                return Some(valid_move);
+            }
+
+            pick -= weight;
+        }
+
+        None
+    }
+}
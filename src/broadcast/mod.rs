@@ -0,0 +1,125 @@
+use super::models::*;
+use super::game::Game;
+use super::manager::{GameId, GameManager};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BroadcastUpdate {
+    pub id: GameId,
+
+    // Numbered move text of the moves played since the previous update, to be appended to what was sent before
+    pub moves: String,
+    pub result: GameResult,
+
+    // Set when moves which were already sent got taken back, so the game has to be sent again as a whole
+    pub replaced: bool,
+
+    // The complete PGN of the game
+    pub pgn: String
+}
+
+#[derive(Debug)]
+struct BroadcastGame {
+    id: GameId,
+    tags: Vec<(String, String)>,
+    game: Game,
+
+    sent_plies: usize,
+    sent_result: GameResult,
+    sent_moves: Vec<String>
+}
+
+// The games of a live broadcast, e.g. one round of a tournament, in the order they were added. Each call to
+// `updates` reports what changed since the previous one, the way the lichess broadcast API takes them.
+#[derive(Debug, Default)]
+pub struct Broadcast {
+    games: Vec<BroadcastGame>
+}
+
+impl Broadcast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The tags usually include Event, Round, White and Black. Adding a game which is already there replaces its tags.
+    pub fn add_game(&mut self, id: GameId, tags: Vec<(String, String)>, game: Game) {
+        match self.games.iter_mut().find( |broadcast_game| broadcast_game.id == id ) {
+            Some(broadcast_game) => {
+                broadcast_game.tags = tags;
+                broadcast_game.game = game;
+            },
+            None => self.games.push(BroadcastGame {
+                id,
+                tags,
+                game,
+
+                sent_plies: 0,
+                sent_result: GameResult::Unknown,
+                sent_moves: Vec::new()
+            })
+        }
+    }
+
+    pub fn remove_game(&mut self, id: GameId) {
+        self.games.retain( |broadcast_game| broadcast_game.id != id );
+    }
+
+    pub fn update_game(&mut self, id: GameId, game: Game) -> Result<(), String> {
+        let broadcast_game = self.games.iter_mut()
+            .find( |broadcast_game| broadcast_game.id == id )
+            .ok_or(format!("Game {} is not part of the broadcast", id))?;
+
+        broadcast_game.game = game;
+
+        Ok(())
+    }
+
+    // Takes the current state of every broadcast game which is also in the manager
+    pub fn sync(&mut self, manager: &GameManager) {
+        for broadcast_game in self.games.iter_mut() {
+            if let Some(game) = manager.game(broadcast_game.id) {
+                broadcast_game.game = game;
+            }
+        }
+    }
+
+    // Games with new moves or a new result since the previous call
+    pub fn updates(&mut self) -> Vec<BroadcastUpdate> {
+        let mut updates = Vec::new();
+
+        for broadcast_game in self.games.iter_mut() {
+            let moves: Vec<String> = broadcast_game.game.moves().iter().map( |valid_move| valid_move.uci() ).collect();
+            let result = broadcast_game.game.result();
+
+            let replaced = moves.len() < broadcast_game.sent_plies ||
+                moves[..broadcast_game.sent_plies] != broadcast_game.sent_moves[..];
+
+            if !replaced && moves.len() == broadcast_game.sent_plies && result == broadcast_game.sent_result {
+                continue;
+            }
+
+            let from_ply = if replaced { 0 } else { broadcast_game.sent_plies };
+
+            updates.push(BroadcastUpdate {
+                id: broadcast_game.id,
+                moves: broadcast_game.game.movetext_since(from_ply),
+                result,
+                replaced,
+                pgn: broadcast_game.game.to_pgn_with_tags(&broadcast_game.tags)
+            });
+
+            broadcast_game.sent_plies = moves.len();
+            broadcast_game.sent_result = result;
+            broadcast_game.sent_moves = moves;
+        }
+
+        updates
+    }
+
+    // All games as one PGN, as pushed to a broadcast round
+    pub fn pgn(&self) -> String {
+        self.games.iter()
+            .map( |broadcast_game| broadcast_game.game.to_pgn_with_tags(&broadcast_game.tags) )
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
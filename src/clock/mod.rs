@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use super::models::*;
+
+// A chess clock. It doesn't read the time itself, every call gets the current time as a duration since any
+// fixed point (e.g. performance.now() in a browser, where std::time::Instant isn't available).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Clock {
+    increment: Duration,
+
+    // A simple delay: the time only starts counting down this long after the turn started
+    delay: Duration,
+
+    white: Duration,
+    black: Duration,
+
+    // The side whose time is running and since when
+    running: Option<(Color, Duration)>,
+    flagged: Option<Color>
+}
+
+impl Clock {
+    pub fn new(base: Duration, increment: Duration) -> Self {
+        Self::with_delay(base, increment, Duration::ZERO)
+    }
+
+    pub fn with_delay(base: Duration, increment: Duration, delay: Duration) -> Self {
+        Clock { increment, delay, white: base, black: base, running: None, flagged: None }
+    }
+
+    pub fn start(&mut self, color: Color, now: Duration) {
+        if self.flagged.is_none() {
+            self.running = Some((color, now));
+        }
+    }
+
+    // Stops the time of the side to move, keeping what it has used so far
+    pub fn pause(&mut self, now: Duration) {
+        if let Some((color, _)) = self.running {
+            let remaining = self.remaining(color, now);
+
+            *self.time_mut(color) = remaining;
+            self.running = None;
+        }
+    }
+
+    // Ends the turn of the side whose time is running: it gets the increment and the other side's time starts.
+    // Returns the color which ran out of time instead, if it did before pressing.
+    pub fn press(&mut self, now: Duration) -> Option<Color> {
+        if let Some(flagged) = self.check_flag(now) {
+            return Some(flagged);
+        }
+
+        if let Some((color, _)) = self.running {
+            let remaining = self.remaining(color, now) + self.increment;
+
+            *self.time_mut(color) = remaining;
+            self.running = Some((color.opposite(), now));
+        }
+
+        None
+    }
+
+    pub fn remaining(&self, color: Color, now: Duration) -> Duration {
+        let time = match color {
+            Color::White => self.white,
+            Color::Black => self.black
+        };
+
+        match self.running {
+            Some((running, since)) if running == color => time.saturating_sub(now.saturating_sub(since).saturating_sub(self.delay)),
+            _ => time
+        }
+    }
+
+    // The color which ran out of time, if any. The clock stops when a flag falls.
+    pub fn check_flag(&mut self, now: Duration) -> Option<Color> {
+        if let Some((color, _)) = self.running {
+            if self.remaining(color, now) == Duration::from_secs(0) {
+                *self.time_mut(color) = Duration::from_secs(0);
+
+                self.running = None;
+                self.flagged = Some(color);
+            }
+        }
+
+        self.flagged
+    }
+
+    pub fn running(&self) -> Option<Color> {
+        self.running.map( |(color, _)| color )
+    }
+
+    pub fn flagged(&self) -> Option<Color> {
+        self.flagged
+    }
+
+    fn time_mut(&mut self, color: Color) -> &mut Duration {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::convert::TryFrom;
+
+use serde::{Serialize, Deserialize};
+
+use super::models::*;
+use super::game::{Game, ValidMove};
+
+// Moves prepared in advance by the player who just moved, e.g. "if 12... Nf6 then 13. e5, and if 13... Nd5
+// then 14. c4". Every line alternates between a move of the opponent and the answer to it, starting with the
+// opponent's move from the position after `ply` half-moves, which has the stable key `position_key`.
+//
+// As JSON, the lines are arrays of moves in the ValidMove format:
+//
+//   {"ply": 22, "position_key": 1234, "lines": [[{"color": "black", "from": "g8", "to": "f6", ...}, {"color": "white", ...}]]}
+//
+// Reading the JSON checks that every line is made of pairs of moves, the moves themselves are checked against
+// the game by `validate`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedConditionalMoves")]
+pub struct ConditionalMoves {
+    pub ply: usize,
+    pub position_key: u64,
+    pub lines: Vec<Vec<ValidMove>>
+}
+
+#[derive(Deserialize)]
+struct UncheckedConditionalMoves {
+    ply: usize,
+    position_key: u64,
+    lines: Vec<Vec<ValidMove>>
+}
+
+impl TryFrom<UncheckedConditionalMoves> for ConditionalMoves {
+    type Error = String;
+
+    fn try_from(unchecked: UncheckedConditionalMoves) -> Result<Self, String> {
+        if !unchecked.lines.iter().all( |line| is_paired(line) ) {
+            return Err(String::from("Every move of the opponent needs an answer"));
+        }
+
+        Ok(ConditionalMoves { ply: unchecked.ply, position_key: unchecked.position_key, lines: unchecked.lines })
+    }
+}
+
+impl ConditionalMoves {
+    pub fn new(game: &Game) -> Self {
+        ConditionalMoves { ply: game.ply_count(), position_key: game.position().stable_key(), lines: Vec::new() }
+    }
+
+    // One line per row of numbered move text, e.g. "12... Nf6 13. e5 13... Nd5 14. c4"
+    pub fn from_pgn(game: &Game, pgn: &str) -> Result<Self, String> {
+        let mut conditional_moves = Self::new(game);
+
+        for line in pgn.lines().filter( |line| !line.trim().is_empty() ) {
+            conditional_moves.add_line(game, line)?;
+        }
+
+        Ok(conditional_moves)
+    }
+
+    pub fn to_pgn(&self, game: &Game) -> String {
+        self.lines.iter()
+            .map( |line| line.iter().fold(game.clone(), |game, valid_move| game.make_valid_move(valid_move) ).movetext_since(self.ply) )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // The player who prepared the moves
+    pub fn owner(&self, game: &Game) -> Color {
+        let next_to_move = game.initial_position().next_to_move;
+
+        if self.ply.is_multiple_of(2) { next_to_move.opposite() } else { next_to_move }
+    }
+
+    // Move numbers are optional, e.g. "Nf6 e5" works too
+    pub fn add_line(&mut self, game: &Game, notation: &str) -> Result<(), String> {
+        let mut current = game.clone();
+        let mut line = Vec::new();
+
+        let moves = notation.split_whitespace()
+            .map( |token| token.trim_start_matches( |c: char| c.is_ascii_digit() || c == '.' ) )
+            .filter( |token| !token.is_empty() );
+
+        for san in moves {
+            let valid_move = ValidMove::from_notation(&current, san)
+                .map_err( |_| format!("Invalid or illegal move '{}' in the conditional moves", san) )?;
+
+            current = current.make_valid_move(&valid_move);
+            line.push(valid_move);
+        }
+
+        self.add(game, line)
+    }
+
+    pub fn add(&mut self, game: &Game, line: Vec<ValidMove>) -> Result<(), String> {
+        self.validate_line(game, &line)?;
+
+        for existing in &self.lines {
+            let common = existing.iter().zip(line.iter()).take_while( |(a, b)| a == b ).count();
+
+            // Both lines expect the same moves of the opponent but answer them differently
+            if common < existing.len().min(line.len()) && common % 2 == 1 {
+                return Err(format!("The line answers move {} differently than another one", common));
+            }
+        }
+
+        self.lines.push(line);
+        Ok(())
+    }
+
+    // The game has to be at the position the lines start from, with every line legal and made of
+    // (opponent move, answer) pairs
+    pub fn validate(&self, game: &Game) -> Result<(), String> {
+        self.lines.iter().try_for_each( |line| self.validate_line(game, line) )
+    }
+
+    // To be called after the opponent moved. Returns the prepared answer and drops the lines which didn't
+    // expect the move. Without an answer every line is dropped.
+    pub fn answer(&mut self, game: &Game) -> Option<ValidMove> {
+        let last_move = match game.last_move() {
+            Some(last_move) if game.ply_count() == self.ply + 1 => last_move.clone(),
+            _ => return None
+        };
+
+        // The lines can have been changed after they were checked, so they are only read with get
+        let answer = self.lines.iter()
+            .find( |line| line.first() == Some(&last_move) )
+            .and_then( |line| line.get(1).cloned() );
+
+        match &answer {
+            Some(answer) => {
+                self.lines.retain( |line| line.len() > 2 && line[0] == last_move && line[1] == *answer );
+
+                for line in self.lines.iter_mut() {
+                    line.drain(..2);
+                }
+
+                self.ply += 2;
+                self.position_key = game.make_valid_move(answer).position().stable_key();
+            },
+
+            None => self.lines.clear()
+        }
+
+        answer
+    }
+
+    // The game with the prepared answer made, if there is one
+    pub fn execute(&mut self, game: &Game) -> Option<Game> {
+        self.answer(game).map( |answer| game.make_valid_move(&answer) )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    fn validate_line(&self, game: &Game, line: &[ValidMove]) -> Result<(), String> {
+        if game.ply_count() != self.ply {
+            return Err(format!("The conditional moves start after half-move {}, but the game has {}", self.ply, game.ply_count()));
+        }
+
+        if game.position().stable_key() != self.position_key {
+            return Err(String::from("The conditional moves were prepared for another position"));
+        }
+
+        if !is_paired(line) {
+            return Err(String::from("Every move of the opponent needs an answer"));
+        }
+
+        line.iter()
+            .try_fold(game.clone(), |game, valid_move| game.try_make_valid_move(valid_move) )
+            .map_err( |_| String::from("The line has an illegal move") )?;
+
+        Ok(())
+    }
+}
+
+fn is_paired(line: &[ValidMove]) -> bool {
+    !line.is_empty() && line.len().is_multiple_of(2)
+}
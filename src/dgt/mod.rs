@@ -0,0 +1,143 @@
+use super::models::*;
+use super::game::{Game, ValidMove};
+
+// Piece codes of the DGT board protocol, in the order they are numbered from 1 (0 is an empty square)
+static PIECE_CODES: [(Piece, Color); 12] = [
+    (Piece::Pawn,   Color::White),
+    (Piece::Rook,   Color::White),
+    (Piece::Knight, Color::White),
+    (Piece::Bishop, Color::White),
+    (Piece::King,   Color::White),
+    (Piece::Queen,  Color::White),
+    (Piece::Pawn,   Color::Black),
+    (Piece::Rook,   Color::Black),
+    (Piece::Knight, Color::Black),
+    (Piece::Bishop, Color::Black),
+    (Piece::King,   Color::Black),
+    (Piece::Queen,  Color::Black)
+];
+
+// Moves that may have been played between two frames, e.g. when the opponent replies before the board is read
+const MAX_MOVES_PER_FRAME: usize = 2;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BoardUpdate {
+    Unchanged,
+
+    // Moves which lead from the previous position to the frame, in order
+    Moves(Vec<ValidMove>),
+
+    // The frame shows a position from before the last `n` half-moves, which were taken back
+    TakeBack(usize),
+
+    // Usually a move in progress, with a piece lifted or only one of the pieces of a capture moved. The game
+    // stays the same until a frame shows a position which can be reached.
+    Unrecognized
+}
+
+// Frames are 64 bytes of DGT piece codes, from a8 to h8 and down to h1
+pub fn decode_frame(frame: &[u8]) -> Result<Board, String> {
+    if frame.len() != 64 {
+        return Err(format!("A frame has 64 squares, got {}", frame.len()));
+    }
+
+    let squares = frame.iter()
+        .map( |code| match *code {
+            0 => Ok(None),
+            code if (code as usize) <= PIECE_CODES.len() => {
+                let (piece, color) = PIECE_CODES[code as usize - 1];
+
+                Ok(Some(OccupiedSquare { piece, color }))
+            },
+            code => Err(format!("Invalid piece code {}", code))
+        })
+        .collect::<Result<Vec<Option<OccupiedSquare>>, String>>()?;
+
+    Ok(Board { squares })
+}
+
+pub fn encode_frame(board: &Board) -> Vec<u8> {
+    board.squares.iter()
+        .map( |occupancy| match occupancy {
+            Some(occupancy) => PIECE_CODES.iter()
+                .position( |(piece, color)| *piece == occupancy.piece && *color == occupancy.color )
+                .map( |index| index as u8 + 1 )
+                .unwrap_or(0),
+            None => 0
+        })
+        .collect()
+}
+
+// Follows a game played on a physical board from snapshots of its squares
+pub struct BoardTracker {
+    game: Game
+}
+
+impl BoardTracker {
+    pub fn new(game: Game) -> Self {
+        BoardTracker { game }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn feed(&mut self, frame: &[u8]) -> Result<BoardUpdate, String> {
+        let board = decode_frame(frame)?;
+
+        if board == self.game.position().board {
+            return Ok(BoardUpdate::Unchanged);
+        }
+
+        if let Some(moves) = moves_to(&self.game, &board, MAX_MOVES_PER_FRAME) {
+            for valid_move in moves.iter() {
+                self.game = self.game.make_valid_move(valid_move);
+            }
+
+            return Ok(BoardUpdate::Moves(moves));
+        }
+
+        let history = self.game.history();
+
+        if let Some(index) = history.iter().rposition( |(game, _)| game.position().board == board ) {
+            self.game = history[index].0.clone();
+
+            return Ok(BoardUpdate::TakeBack(history.len() - index));
+        }
+
+        Ok(BoardUpdate::Unrecognized)
+    }
+}
+
+// The shortest sequence of at most `max_moves` legal moves which leads to the board, if there is exactly one
+fn moves_to(game: &Game, board: &Board, max_moves: usize) -> Option<Vec<ValidMove>> {
+    for length in 1..=max_moves {
+        let mut found = Vec::new();
+
+        collect_moves_to(game, board, length, &mut Vec::new(), &mut found);
+
+        match found.len() {
+            0 => continue,
+            1 => return found.pop(),
+            _ => return None
+        }
+    }
+
+    None
+}
+
+fn collect_moves_to(game: &Game, board: &Board, length: usize, line: &mut Vec<ValidMove>, found: &mut Vec<Vec<ValidMove>>) {
+    if length == 0 {
+        if game.position().board == *board {
+            found.push(line.clone());
+        }
+
+        return;
+    }
+
+    for valid_move in game.valid_moves() {
+        line.push(valid_move.clone());
+        collect_moves_to(&game.make_valid_move(&valid_move), board, length - 1, line, found);
+        line.pop();
+    }
+}
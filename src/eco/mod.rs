@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use super::models::*;
+use super::game::{Game, InvalidMoveError};
+
+// Main lines of common openings, by ECO code
+static STANDARD_OPENINGS: &[(&str, &str, &str)] = &[
+    ("A04", "Reti Opening",              "1. Nf3"),
+    ("A10", "English Opening",           "1. c4"),
+    ("A40", "Queen's Pawn Game",         "1. d4"),
+    ("A45", "Indian Defence",            "1. d4 Nf6"),
+    ("B00", "King's Pawn Opening",       "1. e4"),
+    ("B01", "Scandinavian Defence",      "1. e4 d5"),
+    ("B10", "Caro-Kann Defence",         "1. e4 c6"),
+    ("B20", "Sicilian Defence",          "1. e4 c5"),
+    ("C00", "French Defence",            "1. e4 e6"),
+    ("C20", "King's Pawn Game",          "1. e4 e5"),
+    ("C40", "King's Knight Opening",     "1. e4 e5 2. Nf3"),
+    ("C50", "Italian Game",              "1. e4 e5 2. Nf3 Nc6 3. Bc4"),
+    ("C60", "Ruy Lopez",                 "1. e4 e5 2. Nf3 Nc6 3. Bb5"),
+    ("D00", "Queen's Pawn Game",         "1. d4 d5"),
+    ("D06", "Queen's Gambit",            "1. d4 d5 2. c4"),
+    ("D10", "Slav Defence",              "1. d4 d5 2. c4 c6"),
+    ("D20", "Queen's Gambit Accepted",   "1. d4 d5 2. c4 dxc4"),
+    ("D30", "Queen's Gambit Declined",   "1. d4 d5 2. c4 e6"),
+    ("E20", "Nimzo-Indian Defence",      "1. d4 Nf6 2. c4 e6 3. Nc3 Bb4"),
+    ("E60", "King's Indian Defence",     "1. d4 Nf6 2. c4 g6")
+];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String
+}
+
+// Openings keyed by the position their line leads to, so a game gets the opening's name even if it reached
+// the position with a different move order
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct EcoIndex {
+    positions: HashMap<u64, Opening>
+}
+
+impl EcoIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn standard() -> Self {
+        let mut index = Self::new();
+
+        for (eco, name, line) in STANDARD_OPENINGS {
+            index.add_line(eco, name, line).expect("Invalid standard opening line");
+        }
+
+        index
+    }
+
+    // The line is a sequence of SAN moves from the starting position, move numbers are optional
+    pub fn add_line(&mut self, eco: &str, name: &str, line: &str) -> Result<(), InvalidMoveError> {
+        let mut game = Game::new(Game::standard_position());
+
+        for notation in line.split_whitespace().filter( |token| !token.ends_with('.') ) {
+            game = game.make_move(notation)?;
+        }
+
+        self.add_position(game.position(), Opening { eco: String::from(eco), name: String::from(name) });
+
+        Ok(())
+    }
+
+    pub fn add_position(&mut self, position: &Position, opening: Opening) {
+        self.positions.insert(position.stable_key(), opening);
+    }
+
+    pub fn lookup(&self, position: &Position) -> Option<&Opening> {
+        self.positions.get(&position.stable_key())
+    }
+
+    // The opening of the last position of the game which is in the index
+    pub fn classify(&self, game: &Game) -> Option<&Opening> {
+        self.lookup(game.position()).or_else( ||
+            game.history().iter().rev()
+                .find_map( |(previous, _)| self.lookup(previous.position()) )
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
@@ -0,0 +1,121 @@
+use super::models::*;
+use super::tablebase::MAX_PIECES;
+
+// Besides the kings and the pawns. Positions with more pieces than this are not treated as endgames.
+const MAX_ENDGAME_PIECES: usize = 4;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum EndgameType {
+    Pawn,
+    Knight,
+    SameColoredBishops,
+    OppositeColoredBishops,
+    BishopVsKnight,
+
+    // Any other mix of minor pieces, e.g. two bishops against a knight
+    MinorPiece,
+
+    Rook,
+    RookVsMinorPiece,
+    RookAndMinorPiece,
+    Queen,
+    QueenVsRook,
+
+    // Anything else with few pieces, e.g. a queen against two rooks
+    Mixed
+}
+
+impl EndgameType {
+    // For grouping training content, e.g. "Practice rook endgames"
+    pub fn name(&self) -> &'static str {
+        match self {
+            EndgameType::Pawn                   => "pawn endgame",
+            EndgameType::Knight                 => "knight endgame",
+            EndgameType::SameColoredBishops     => "same-colored bishop endgame",
+            EndgameType::OppositeColoredBishops => "opposite-colored bishop endgame",
+            EndgameType::BishopVsKnight         => "bishop against knight endgame",
+            EndgameType::MinorPiece             => "minor piece endgame",
+            EndgameType::Rook                   => "rook endgame",
+            EndgameType::RookVsMinorPiece       => "rook against minor piece endgame",
+            EndgameType::RookAndMinorPiece      => "rook and minor piece endgame",
+            EndgameType::Queen                  => "queen endgame",
+            EndgameType::QueenVsRook            => "queen against rook endgame",
+            EndgameType::Mixed                  => "endgame"
+        }
+    }
+}
+
+impl Position {
+    // None for positions which still have too many pieces to count as an endgame
+    pub fn endgame_type(&self) -> Option<EndgameType> {
+        classify(self)
+    }
+
+    // Few enough pieces and no castling rights, so a generated tablebase could cover the position
+    pub fn is_tablebase_size(&self) -> bool {
+        let pieces = self.board.squares.iter().filter( |square| square.is_some() ).count();
+        let castling = self.white_can_castle_king_side || self.white_can_castle_queen_side ||
+            self.black_can_castle_king_side || self.black_can_castle_queen_side;
+
+        pieces <= MAX_PIECES && !castling
+    }
+}
+
+pub fn classify(position: &Position) -> Option<EndgameType> {
+    let pieces = |color: Color| -> Vec<(Piece, usize)> {
+        position.board.squares.iter().enumerate()
+            .filter_map( |(index, square)| square.as_ref().map( |occupancy| (index, occupancy) ) )
+            .filter( |(_, occupancy)| occupancy.color == color && occupancy.piece != Piece::King && occupancy.piece != Piece::Pawn )
+            .map( |(index, occupancy)| (occupancy.piece, index) )
+            .collect()
+    };
+
+    let white = pieces(Color::White);
+    let black = pieces(Color::Black);
+
+    if white.len() + black.len() > MAX_ENDGAME_PIECES {
+        return None;
+    }
+
+    let count = |side: &[(Piece, usize)], piece: Piece| side.iter().filter( |(kind, _)| *kind == piece ).count();
+    let minors = |side: &[(Piece, usize)]| count(side, Piece::Knight) + count(side, Piece::Bishop);
+    let only = |side: &[(Piece, usize)], piece: Piece| side.len() == 1 && side[0].0 == piece;
+
+    let light = |index: usize| Board::square(index).is_light();
+
+    let endgame = match (white.len(), black.len()) {
+        (0, 0) => EndgameType::Pawn,
+
+        (1, 1) if only(&white, Piece::Bishop) && only(&black, Piece::Bishop) => {
+            if light(white[0].1) == light(black[0].1) {
+                EndgameType::SameColoredBishops
+            } else {
+                EndgameType::OppositeColoredBishops
+            }
+        },
+
+        _ if minors(&white) + minors(&black) == white.len() + black.len() => {
+            let knights = count(&white, Piece::Knight) + count(&black, Piece::Knight);
+
+            if knights == white.len() + black.len() {
+                EndgameType::Knight
+            } else if (only(&white, Piece::Bishop) && only(&black, Piece::Knight)) || (only(&white, Piece::Knight) && only(&black, Piece::Bishop)) {
+                EndgameType::BishopVsKnight
+            } else {
+                EndgameType::MinorPiece
+            }
+        },
+
+        _ if white.iter().chain(black.iter()).all( |(piece, _)| *piece == Piece::Rook ) && white.len() == black.len() => EndgameType::Rook,
+        _ if white.iter().chain(black.iter()).all( |(piece, _)| *piece == Piece::Queen ) && white.len() == black.len() => EndgameType::Queen,
+
+        (1, 1) if (only(&white, Piece::Rook) && minors(&black) == 1) || (only(&black, Piece::Rook) && minors(&white) == 1) => EndgameType::RookVsMinorPiece,
+        (1, 1) if (only(&white, Piece::Queen) && only(&black, Piece::Rook)) || (only(&white, Piece::Rook) && only(&black, Piece::Queen)) => EndgameType::QueenVsRook,
+
+        (2, 2) if [&white, &black].iter().all( |side| count(side, Piece::Rook) == 1 && minors(side) == 1 ) => EndgameType::RookAndMinorPiece,
+
+        _ => EndgameType::Mixed
+    };
+
+    Some(endgame)
+}
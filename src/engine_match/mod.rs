@@ -0,0 +1,414 @@
+use std::time::{Duration, Instant};
+
+use super::models::*;
+use super::game::{Game, ValidMove, MoveAnnotation, PgnProfile, TIME_FORFEIT};
+use super::clock::Clock;
+use super::search::{Searcher, SearchLimits, MATE_SCORE};
+use super::bot::Bot;
+use super::adjudication::{Adjudicator, AdjudicationRules};
+
+mod simul;
+mod sprt;
+mod uci;
+
+pub use simul::{Simul, SimulGame, SimulGameId, SimulTurn};
+pub use sprt::{Sprt, SprtDecision, SprtResult};
+pub use uci::UciEngine;
+
+// Share of the remaining time the built-in engine spends on a move when playing with a clock
+const MOVES_TO_GO: u32 = 30;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TimeControl {
+    // Time for the whole game, plus an increment after every move. With a delay, the time only starts
+    // running that long after the turn started.
+    Clock { base: Duration, increment: Duration, delay: Duration },
+
+    MoveTime(Duration),
+    Depth(u32)
+}
+
+impl TimeControl {
+    // A clock for both sides, None unless the time control is a Clock
+    pub fn clock(&self) -> Option<Clock> {
+        match *self {
+            TimeControl::Clock { base, increment, delay } => Some(Clock::with_delay(base, increment, delay)),
+            _ => None
+        }
+    }
+}
+
+// What an engine gets to think about its next move
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MoveLimits {
+    Clock { white: Duration, black: Duration, increment: Duration, delay: Duration },
+    MoveTime(Duration),
+    Depth(u32)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EngineError {
+    // The engine answered with a move which isn't legal in the position
+    IllegalMove(String),
+
+    // The engine crashed, exited or couldn't be talked to
+    Failed(String)
+}
+
+pub trait Engine {
+    fn name(&self) -> String;
+
+    // Called before every game of a match
+    fn new_game(&mut self) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn choose_move(&mut self, game: &Game, limits: &MoveLimits) -> Result<ValidMove, EngineError>;
+
+    // Centipawns from the engine's point of view with its last move, for adjudication. None if it doesn't
+    // tell.
+    fn last_score(&self) -> Option<i32> {
+        None
+    }
+}
+
+// The built-in search as an engine
+pub struct SearcherEngine {
+    name: String,
+    searcher: Searcher,
+    last_score: Option<i32>
+}
+
+impl SearcherEngine {
+    pub fn new(name: &str, searcher: Searcher) -> Self {
+        SearcherEngine { name: String::from(name), searcher, last_score: None }
+    }
+}
+
+impl Engine for SearcherEngine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) -> Result<(), EngineError> {
+        self.searcher.clear();
+
+        Ok(())
+    }
+
+    fn choose_move(&mut self, game: &Game, limits: &MoveLimits) -> Result<ValidMove, EngineError> {
+        let limits = match *limits {
+            MoveLimits::Depth(depth)        => SearchLimits::depth(depth),
+            MoveLimits::MoveTime(movetime)  => SearchLimits::movetime(movetime),
+
+            MoveLimits::Clock { white, black, increment, delay } => {
+                let remaining = match game.position().next_to_move {
+                    Color::White => white,
+                    Color::Black => black
+                };
+
+                SearchLimits::movetime(remaining / MOVES_TO_GO + increment / 2 + delay)
+            }
+        };
+
+        let result = self.searcher.search(game, &limits);
+        self.last_score = Some(result.score);
+
+        result.best_move.ok_or(EngineError::Failed(String::from("No legal moves")))
+    }
+
+    fn last_score(&self) -> Option<i32> {
+        self.last_score
+    }
+}
+
+// Bots play at their own strength, whatever the time control
+impl Engine for Bot {
+    fn name(&self) -> String {
+        format!("Bot {}", self.elo())
+    }
+
+    fn new_game(&mut self) -> Result<(), EngineError> {
+        Bot::new_game(self);
+
+        Ok(())
+    }
+
+    fn choose_move(&mut self, game: &Game, _limits: &MoveLimits) -> Result<ValidMove, EngineError> {
+        Bot::choose_move(self, game)
+            .map( |bot_move| bot_move.valid_move )
+            .ok_or(EngineError::Failed(String::from("No legal moves")))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchOptions {
+    pub games: usize,
+    pub time_control: TimeControl,
+
+    // Starting games, played in turn with each one played twice so that both engines get both colors.
+    // The standard starting position is used if there are none.
+    pub openings: Vec<Game>,
+
+    // Stops the match as soon as the test accepts one of its hypotheses
+    pub sprt: Option<Sprt>,
+
+    // Ends games early from the scores the engines report, see Engine::last_score. Its max_plies also bounds
+    // the length of the games, 400 half-moves by default.
+    pub adjudication: AdjudicationRules
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            games: 2,
+            time_control: TimeControl::Clock { base: Duration::from_secs(10), increment: Duration::from_millis(100), delay: Duration::ZERO },
+            openings: Vec::new(),
+            sprt: None,
+            adjudication: AdjudicationRules { max_plies: Some(400), ..AdjudicationRules::default() }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchGame {
+    pub white: String,
+    pub black: String,
+    pub game: Game,
+
+    // Time left on the clock of the engine after each of its moves, starting after the opening. Empty
+    // without a Clock time control.
+    pub clocks: Vec<Duration>
+}
+
+impl MatchGame {
+    // With the engines' names, the time control and the clock times as %clk comments
+    pub fn to_pgn(&self, time_control: &TimeControl) -> String {
+        let mut tags = vec![
+            (String::from("White"), self.white.clone()),
+            (String::from("Black"), self.black.clone())
+        ];
+
+        if let TimeControl::Clock { base, increment, .. } = time_control {
+            tags.push((String::from("TimeControl"), format!("{}+{}", base.as_secs(), increment.as_secs())));
+        }
+
+        let opening_plies = self.game.ply_count().saturating_sub(self.clocks.len());
+        let annotations: Vec<MoveAnnotation> = (0..opening_plies)
+            .map( |_| MoveAnnotation::default() )
+            .chain(self.clocks.iter().map( |clock| MoveAnnotation { clock: Some(*clock), ..MoveAnnotation::default() } ))
+            .collect();
+
+        self.game.to_pgn_with_profile(&tags, &annotations, &PgnProfile::export())
+    }
+}
+
+// Results are from the point of view of the first engine
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+
+    pub games: Vec<MatchGame>,
+    pub sprt: Option<SprtResult>
+}
+
+impl MatchResult {
+    pub fn games_played(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Points per game, between 0 and 1
+    pub fn score(&self) -> f64 {
+        if self.games_played() == 0 {
+            return 0.5;
+        }
+
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games_played() as f64
+    }
+
+    // None while the score is 0 or 1, where the difference can't be estimated
+    pub fn elo_difference(&self) -> Option<f64> {
+        sprt::elo_from_score(self.score())
+    }
+
+    // e.g. "Score of new vs old: 10 - 5 - 25 [0.562] 40, Elo +43.7, LLR 1.10 (-2.94, 2.94)"
+    pub fn summary(&self, first: &str, second: &str) -> String {
+        let elo = match self.elo_difference() {
+            Some(elo) => format!(", Elo {:+.1}", elo),
+            None => String::new()
+        };
+
+        let sprt = match &self.sprt {
+            Some(sprt) => format!(", LLR {:.2} ({:.2}, {:.2})", sprt.llr, sprt.lower_bound, sprt.upper_bound),
+            None => String::new()
+        };
+
+        format!(
+            "Score of {} vs {}: {} - {} - {} [{:.3}] {}{}{}",
+            first, second, self.wins, self.losses, self.draws, self.score(), self.games_played(), elo, sprt
+        )
+    }
+}
+
+// Only fails if an engine can't start a new game, anything going wrong during a game loses it
+pub fn play_match(first: &mut dyn Engine, second: &mut dyn Engine, options: &MatchOptions) -> Result<MatchResult, EngineError> {
+    let standard = vec![Game::new(Game::standard_position())];
+    let openings = if options.openings.is_empty() { &standard } else { &options.openings };
+
+    let mut result = MatchResult { wins: 0, draws: 0, losses: 0, games: Vec::new(), sprt: None };
+
+    for i in 0..options.games {
+        let opening = &openings[(i / 2) % openings.len()];
+        let first_plays_white = i % 2 == 0;
+
+        let (game, clocks) = if first_plays_white {
+            play_game(first, second, opening, options)?
+        } else {
+            play_game(second, first, opening, options)?
+        };
+
+        match (game.result(), first_plays_white) {
+            (GameResult::WhiteWins, true) | (GameResult::BlackWins, false) => result.wins += 1,
+            (GameResult::WhiteWins, false) | (GameResult::BlackWins, true) => result.losses += 1,
+            _ => result.draws += 1
+        }
+
+        let (white, black) = if first_plays_white { (first.name(), second.name()) } else { (second.name(), first.name()) };
+        result.games.push(MatchGame { white, black, game, clocks });
+
+        if let Some(sprt) = &options.sprt {
+            let sprt_result = sprt.test(result.wins, result.draws, result.losses);
+            let decided = sprt_result.decision != SprtDecision::Continue;
+
+            result.sprt = Some(sprt_result);
+
+            if decided {
+                break;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// The clock runs on the time the engines take to think, anything the match itself does in between isn't
+// counted. Returns the game with the clock times after each move.
+fn play_game(white: &mut dyn Engine, black: &mut dyn Engine, opening: &Game, options: &MatchOptions) -> Result<(Game, Vec<Duration>), EngineError> {
+    white.new_game()?;
+    black.new_game()?;
+
+    let mut game = opening.clone();
+    let mut clocks = Vec::new();
+
+    let mut clock = options.time_control.clock();
+    let mut now = Duration::ZERO;
+
+    let mut adjudicator = Adjudicator::new(options.adjudication.clone());
+
+    loop {
+        if game.is_over() {
+            return Ok((game, clocks));
+        }
+
+        let color = game.position().next_to_move;
+        let lost = match color {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins
+        };
+
+        let limits = match (options.time_control, &clock) {
+            (TimeControl::Clock { increment, delay, .. }, Some(clock)) => MoveLimits::Clock {
+                white: clock.remaining(Color::White, now),
+                black: clock.remaining(Color::Black, now),
+                increment,
+                delay
+            },
+
+            (TimeControl::MoveTime(movetime), _) => MoveLimits::MoveTime(movetime),
+            (TimeControl::Depth(depth), _)       => MoveLimits::Depth(depth),
+            (TimeControl::Clock { .. }, None)    => unreachable!("Clock time controls always have a clock")
+        };
+
+        if let Some(clock) = &mut clock {
+            clock.start(color, now);
+        }
+
+        let started_at = Instant::now();
+        let chosen = match color {
+            Color::White => white.choose_move(&game, &limits),
+            Color::Black => black.choose_move(&game, &limits)
+        };
+        now += started_at.elapsed();
+
+        let mut remaining = None;
+
+        if let Some(clock) = &mut clock {
+            if clock.press(now).is_some() {
+                return Ok((flag_fall(&game, color), clocks));
+            }
+
+            remaining = Some(clock.remaining(color, now));
+        }
+
+        let valid_move = match chosen {
+            Ok(valid_move) if game.valid_moves().contains(&valid_move) => valid_move,
+            Ok(_) | Err(EngineError::IllegalMove(_)) => return Ok((game.adjudicate(lost, "rules infraction"), clocks)),
+            Err(EngineError::Failed(_)) => return Ok((game.adjudicate(lost, "abandoned"), clocks))
+        };
+
+        game = game.make_valid_move(&valid_move);
+
+        // Only moves which were played get a clock time, so that they line up with the moves of the game
+        clocks.extend(remaining);
+
+        if game.is_threefold_repetition() {
+            return Ok((game.adjudicate(GameResult::Draw, "normal"), clocks));
+        }
+
+        let score = match color {
+            Color::White => white.last_score(),
+            Color::Black => black.last_score()
+        };
+
+        if let Some(adjudication) = adjudicator.record(&game, score) {
+            return Ok((adjudication.apply(&game), clocks));
+        }
+    }
+}
+
+// Running out of time loses, unless the opponent could not mate by any series of legal moves (FIDE 6.9),
+// which makes it a draw
+fn flag_fall(game: &Game, flagged: Color) -> Game {
+    if !game.can_win_on_time(flagged.opposite()) {
+        return game.adjudicate(GameResult::Draw, TIME_FORFEIT);
+    }
+
+    let lost = match flagged {
+        Color::White => GameResult::BlackWins,
+        Color::Black => GameResult::WhiteWins
+    };
+
+    game.adjudicate(lost, TIME_FORFEIT)
+}
+
+// One position per line. Only the first four fields (board, side to move, castling and en passant) are read,
+// the operations after them are ignored.
+pub fn openings_from_epd(epd: &str) -> Result<Vec<Game>, String> {
+    epd.lines()
+        .map( |line| line.trim() )
+        .filter( |line| !line.is_empty() )
+        .map( |line| {
+            let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+            let fen = format!("{} 0 1", fields.join(" "));
+
+            Game::new_from_fen(&fen).map_err( |error| format!("Invalid EPD line '{}': {}", line, error.message) )
+        })
+        .collect()
+}
+
+// Every game of the PGN, with its moves, becomes an opening which is continued from its last position
+pub fn openings_from_pgn(pgn: &str) -> Result<Vec<Game>, String> {
+    Game::new_from_pgn(pgn)?.into_iter().collect()
+}
@@ -0,0 +1,204 @@
+use super::*;
+
+pub type SimulGameId = usize;
+
+#[derive(Debug, Clone)]
+pub struct SimulGame {
+    pub id: SimulGameId,
+    pub game: Game,
+
+    // The color the engine plays
+    pub color: Color,
+    pub time_control: TimeControl,
+
+    // The engine's clock, only with a Clock time control. It runs while the game waits for the engine's move,
+    // the opponent's time isn't kept.
+    clock: Option<Clock>,
+    turn: u64
+}
+
+impl SimulGame {
+    pub fn engine_to_move(&self) -> bool {
+        !self.game.is_over() && self.game.position().next_to_move == self.color
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SimulTurn {
+    Played { id: SimulGameId, valid_move: ValidMove },
+
+    // The engine lost the game instead of moving, e.g. on time or by answering with an illegal move
+    Ended { id: SimulGameId, termination: String },
+
+    // No game waits for the engine
+    Idle
+}
+
+// One engine playing many games at once, e.g. a bot accepting several challenges. The engine thinks about
+// one game at a time and the games waiting for it take turns, longest waiting first, so none of them starves.
+// The clocks of all waiting games run while the engine thinks, so their time budgets are shared.
+#[derive(Debug)]
+pub struct Simul {
+    games: Vec<SimulGame>,
+    next_id: SimulGameId,
+    turns: u64,
+
+    // The clocks get the time since this
+    started_at: Instant
+}
+
+impl Default for Simul {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simul {
+    pub fn new() -> Self {
+        Simul { games: Vec::new(), next_id: 0, turns: 0, started_at: Instant::now() }
+    }
+
+    pub fn add_game(&mut self, game: Game, color: Color, time_control: TimeControl) -> SimulGameId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let clock = time_control.clock();
+
+        self.games.push(SimulGame { id, game, color, time_control, clock, turn: 0 });
+        self.update_waiting(id);
+
+        id
+    }
+
+    // Finished games stay until they are removed
+    pub fn remove_game(&mut self, id: SimulGameId) -> Option<SimulGame> {
+        let index = self.games.iter().position( |simul_game| simul_game.id == id )?;
+
+        Some(self.games.remove(index))
+    }
+
+    pub fn game(&self, id: SimulGameId) -> Option<&SimulGame> {
+        self.games.iter().find( |simul_game| simul_game.id == id )
+    }
+
+    pub fn games(&self) -> &[SimulGame] {
+        &self.games
+    }
+
+    // Left on the engine's clock in the game, None without a Clock time control
+    pub fn remaining(&self, id: SimulGameId) -> Option<Duration> {
+        let simul_game = self.game(id)?;
+        let now = self.now();
+
+        simul_game.clock.as_ref().map( |clock| clock.remaining(simul_game.color, now) )
+    }
+
+    pub fn opponent_moved(&mut self, id: SimulGameId, notation: &str) -> Result<(), String> {
+        let simul_game = self.games.iter_mut().find( |simul_game| simul_game.id == id )
+            .ok_or_else( || format!("There is no game {}", id) )?;
+
+        if simul_game.game.is_over() || simul_game.game.position().next_to_move == simul_game.color {
+            return Err(String::from("It is not the opponent's turn"));
+        }
+
+        simul_game.game = simul_game.game.make_move(notation).map_err( |_| format!("Invalid move '{}'", notation) )?;
+        self.update_waiting(id);
+
+        Ok(())
+    }
+
+    // The games waiting for the engine, in the order they will be played
+    pub fn waiting(&self) -> Vec<SimulGameId> {
+        let mut waiting: Vec<&SimulGame> = self.games.iter()
+            .filter( |simul_game| simul_game.engine_to_move() )
+            .collect();
+
+        waiting.sort_by_key( |simul_game| simul_game.turn );
+        waiting.iter().map( |simul_game| simul_game.id ).collect()
+    }
+
+    // Makes the engine's move in the game which has waited the longest.
+    // Like in a match, a failing engine or running out of time loses the game.
+    pub fn play_next(&mut self, engine: &mut dyn Engine) -> SimulTurn {
+        let waiting = self.waiting();
+        let id = match waiting.first() {
+            Some(id) => *id,
+            None => return SimulTurn::Idle
+        };
+
+        let index = self.games.iter().position( |simul_game| simul_game.id == id ).unwrap();
+        let limits = Self::limits(&self.games[index], waiting.len(), self.now());
+
+        let chosen = engine.choose_move(&self.games[index].game, &limits);
+
+        let now = self.now();
+        let simul_game = &mut self.games[index];
+
+        let lost = match simul_game.color {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins
+        };
+
+        if let Some(clock) = &mut simul_game.clock {
+            if clock.press(now).is_some() {
+                simul_game.game = flag_fall(&simul_game.game, simul_game.color);
+                return SimulTurn::Ended { id, termination: String::from(simul_game.game.termination()) };
+            }
+
+            clock.pause(now);
+        }
+
+        let valid_move = match chosen {
+            Ok(valid_move) if simul_game.game.valid_moves().contains(&valid_move) => valid_move,
+            Ok(_) | Err(EngineError::IllegalMove(_)) => {
+                simul_game.game = simul_game.game.adjudicate(lost, "rules infraction");
+                return SimulTurn::Ended { id, termination: String::from(simul_game.game.termination()) };
+            },
+            Err(EngineError::Failed(_)) => {
+                simul_game.game = simul_game.game.adjudicate(lost, "abandoned");
+                return SimulTurn::Ended { id, termination: String::from(simul_game.game.termination()) };
+            }
+        };
+
+        simul_game.game = simul_game.game.make_valid_move(&valid_move);
+        self.update_waiting(id);
+
+        SimulTurn::Played { id, valid_move }
+    }
+
+    // With a clock, the share of the remaining time a single game would get is split between the games
+    // waiting, as all of their clocks run
+    fn limits(simul_game: &SimulGame, waiting: usize, now: Duration) -> MoveLimits {
+        match (simul_game.time_control, &simul_game.clock) {
+            (TimeControl::Clock { increment, delay, .. }, Some(clock)) => {
+                let budget = clock.remaining(simul_game.color, now) / MOVES_TO_GO + increment / 2;
+
+                MoveLimits::MoveTime(budget / waiting.max(1) as u32 + delay)
+            },
+
+            (TimeControl::MoveTime(movetime), _) => MoveLimits::MoveTime(movetime),
+            (TimeControl::Depth(depth), _)       => MoveLimits::Depth(depth),
+            (TimeControl::Clock { .. }, None)    => unreachable!("Clock time controls always have a clock")
+        }
+    }
+
+    fn now(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    fn update_waiting(&mut self, id: SimulGameId) {
+        self.turns += 1;
+        let turn = self.turns;
+        let now = self.now();
+
+        if let Some(simul_game) = self.games.iter_mut().find( |simul_game| simul_game.id == id ) {
+            if simul_game.engine_to_move() {
+                simul_game.turn = turn;
+
+                if let Some(clock) = &mut simul_game.clock {
+                    clock.start(simul_game.color, now);
+                }
+            }
+        }
+    }
+}
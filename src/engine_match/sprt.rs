@@ -0,0 +1,85 @@
+// Sequential probability ratio test of the Elo difference between two engines, as used to decide whether an
+// engine change is an improvement without fixing the number of games up front
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Sprt {
+    // Hypotheses about the Elo difference: H0 is that it's elo0, H1 that it's elo1
+    pub elo0: f64,
+    pub elo1: f64,
+
+    // Probabilities of accepting H1 when H0 holds, and H0 when H1 holds
+    pub alpha: f64,
+    pub beta: f64
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SprtDecision {
+    AcceptH0,
+    AcceptH1,
+    Continue
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SprtResult {
+    // Log-likelihood ratio of H1 against H0
+    pub llr: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub decision: SprtDecision
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 }
+    }
+}
+
+impl Sprt {
+    // Uses the normal approximation of the trinomial win/draw/loss distribution
+    pub fn test(&self, wins: u32, draws: u32, losses: u32) -> SprtResult {
+        let lower_bound = (self.beta / (1.0 - self.alpha)).ln();
+        let upper_bound = ((1.0 - self.beta) / self.alpha).ln();
+
+        let games = (wins + draws + losses) as f64;
+        let llr = if games == 0.0 {
+            0.0
+        } else {
+            let score = (wins as f64 + draws as f64 / 2.0) / games;
+            let variance = (
+                wins as f64 * (1.0 - score).powi(2) +
+                draws as f64 * (0.5 - score).powi(2) +
+                losses as f64 * score.powi(2)
+            ) / games;
+
+            let score0 = score_from_elo(self.elo0);
+            let score1 = score_from_elo(self.elo1);
+
+            if variance == 0.0 {
+                0.0
+            } else {
+                games * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+            }
+        };
+
+        let decision = if llr >= upper_bound {
+            SprtDecision::AcceptH1
+        } else if llr <= lower_bound {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        };
+
+        SprtResult { llr, lower_bound, upper_bound, decision }
+    }
+}
+
+fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub(super) fn elo_from_score(score: f64) -> Option<f64> {
+    if score <= 0.0 || score >= 1.0 {
+        None
+    } else {
+        Some(-400.0 * (1.0 / score - 1.0).log10())
+    }
+}
@@ -0,0 +1,175 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use super::*;
+
+// An external engine speaking the UCI protocol over its standard input and output
+pub struct UciEngine {
+    name: String,
+
+    process: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+
+    // From the last `info` line with a score while searching
+    last_score: Option<i32>
+}
+
+impl UciEngine {
+    pub fn start(command: &str, args: &[&str]) -> Result<Self, EngineError> {
+        let mut process = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err( |error| EngineError::Failed(format!("Cannot start engine '{}': {}", command, error)) )?;
+
+        let input = process.stdin.take().ok_or(EngineError::Failed(String::from("Cannot write to the engine")))?;
+        let output = process.stdout.take().ok_or(EngineError::Failed(String::from("Cannot read from the engine")))?;
+
+        let mut engine = UciEngine {
+            name: String::from(command),
+
+            process,
+            input,
+            output: BufReader::new(output),
+            last_score: None
+        };
+
+        engine.send("uci")?;
+
+        loop {
+            let line = engine.read_line()?;
+
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = String::from(name.trim());
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+
+        engine.wait_until_ready()?;
+
+        Ok(engine)
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError> {
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.wait_until_ready()
+    }
+
+    fn wait_until_ready(&mut self) -> Result<(), EngineError> {
+        self.send("isready")?;
+
+        while self.read_line()?.trim() != "readyok" {}
+
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), EngineError> {
+        writeln!(self.input, "{}", command)
+            .and_then( |_| self.input.flush() )
+            .map_err( |error| EngineError::Failed(format!("Cannot write to the engine: {}", error)) )
+    }
+
+    fn read_line(&mut self) -> Result<String, EngineError> {
+        let mut line = String::new();
+
+        match self.output.read_line(&mut line) {
+            Ok(0) => Err(EngineError::Failed(String::from("The engine exited"))),
+            Ok(_) => Ok(line),
+            Err(error) => Err(EngineError::Failed(format!("Cannot read from the engine: {}", error)))
+        }
+    }
+}
+
+impl Engine for UciEngine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) -> Result<(), EngineError> {
+        self.send("ucinewgame")?;
+        self.wait_until_ready()
+    }
+
+    fn choose_move(&mut self, game: &Game, limits: &MoveLimits) -> Result<ValidMove, EngineError> {
+        let moves: Vec<String> = game.moves().iter().map( |valid_move| valid_move.uci() ).collect();
+
+        let mut position = format!("position fen {}", game.initial_position().to_fen());
+
+        if !moves.is_empty() {
+            position.push_str(" moves ");
+            position.push_str(&moves.join(" "));
+        }
+
+        let go = match *limits {
+            MoveLimits::Depth(depth)       => format!("go depth {}", depth),
+            MoveLimits::MoveTime(movetime) => format!("go movetime {}", movetime.as_millis()),
+
+            // UCI has no way to tell the engine about a delay
+            MoveLimits::Clock { white, black, increment, .. } => format!(
+                "go wtime {} btime {} winc {} binc {}",
+                white.as_millis(), black.as_millis(), increment.as_millis(), increment.as_millis()
+            )
+        };
+
+        self.send(&position)?;
+        self.send(&go)?;
+
+        self.last_score = None;
+
+        loop {
+            let line = self.read_line()?;
+
+            if line.starts_with("info") {
+                if let Some(score) = parse_score(&line) {
+                    self.last_score = Some(score);
+                }
+            } else if let Some(rest) = line.strip_prefix("bestmove") {
+                let notation = rest.split_whitespace().next().unwrap_or("");
+
+                return ValidMove::from_uci(game, notation).map_err( |_| EngineError::IllegalMove(String::from(notation)) );
+            }
+        }
+    }
+
+    fn last_score(&self) -> Option<i32> {
+        self.last_score
+    }
+}
+
+impl Drop for UciEngine {
+    // Gives the engine a second to quit before killing it
+    fn drop(&mut self) {
+        if self.send("quit").is_ok() {
+            for _ in 0..100 {
+                if let Ok(Some(_)) = self.process.try_wait() {
+                    return;
+                }
+
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+// The score of an info line, e.g. "info depth 12 score cp -35 nodes 10234 pv e2e4". Mates become scores
+// like the ones of the built-in search.
+fn parse_score(line: &str) -> Option<i32> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let index = tokens.iter().position( |token| *token == "score" )?;
+
+    let value: i32 = tokens.get(index + 2)?.parse().ok()?;
+
+    match *tokens.get(index + 1)? {
+        "cp"   => Some(value),
+        "mate" if value > 0 => Some(MATE_SCORE - (2 * value - 1)),
+        "mate" => Some(-MATE_SCORE - 2 * value),
+        _ => None
+    }
+}
+
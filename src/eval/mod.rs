@@ -0,0 +1,322 @@
+use super::models::*;
+use super::analysis;
+use serde::Serialize;
+
+// Scores are in centipawns from white's point of view unless stated otherwise
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EvalParams {
+    pub pawn_value: i32,
+    pub knight_value: i32,
+    pub bishop_value: i32,
+    pub rook_value: i32,
+    pub queen_value: i32,
+
+    pub doubled_pawn_penalty: i32,
+    pub isolated_pawn_penalty: i32,
+
+    // Per rank the pawn has advanced from its starting rank
+    pub passed_pawn_bonus: i32,
+
+    // Only counted while the opponent still has a queen
+    pub king_shield_bonus: i32,
+    pub king_open_file_penalty: i32,
+    pub king_zone_attacker_penalty: i32
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        EvalParams {
+            pawn_value: 100,
+            knight_value: 320,
+            bishop_value: 330,
+            rook_value: 500,
+            queen_value: 900,
+
+            doubled_pawn_penalty: 15,
+            isolated_pawn_penalty: 10,
+            passed_pawn_bonus: 10,
+
+            king_shield_bonus: 10,
+            king_open_file_penalty: 20,
+            king_zone_attacker_penalty: 8
+        }
+    }
+}
+
+impl EvalParams {
+    pub fn piece_value(&self, piece: Piece) -> i32 {
+        match piece {
+            Piece::Pawn   => self.pawn_value,
+            Piece::Knight => self.knight_value,
+            Piece::Bishop => self.bishop_value,
+            Piece::Rook   => self.rook_value,
+            Piece::Queen  => self.queen_value,
+            Piece::King   => 0
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct EvalTerm {
+    pub name: &'static str,
+    pub white: i32,
+    pub black: i32
+}
+
+impl EvalTerm {
+    pub fn score(&self) -> i32 {
+        self.white - self.black
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct EvalBreakdown {
+    pub terms: Vec<EvalTerm>
+}
+
+impl EvalBreakdown {
+    // Always equal to evaluate() for the same position and params
+    pub fn total(&self) -> i32 {
+        self.terms.iter().map( |term| term.score() ).sum()
+    }
+
+    pub fn term(&self, name: &str) -> Option<&EvalTerm> {
+        self.terms.iter().find( |term| term.name == name )
+    }
+}
+
+pub fn evaluate(position: &Position) -> i32 {
+    evaluate_with(position, &EvalParams::default())
+}
+
+pub fn evaluate_with(position: &Position, params: &EvalParams) -> i32 {
+    terms(position, params).iter().map( |(_, (white, black))| white - black ).sum()
+}
+
+pub fn evaluate_explain(position: &Position) -> EvalBreakdown {
+    evaluate_explain_with(position, &EvalParams::default())
+}
+
+pub fn evaluate_explain_with(position: &Position, params: &EvalParams) -> EvalBreakdown {
+    EvalBreakdown {
+        terms: terms(position, params).iter()
+            .map( |(name, (white, black))| EvalTerm { name, white: *white, black: *black } )
+            .collect()
+    }
+}
+
+// Same as evaluate, but positive when the side to move is better
+pub fn evaluate_for_side_to_move(position: &Position) -> i32 {
+    match position.next_to_move {
+        Color::White => evaluate(position),
+        Color::Black => -evaluate(position)
+    }
+}
+
+fn terms(position: &Position, params: &EvalParams) -> [(&'static str, (i32, i32)); 4] {
+    [
+        ("material",       material(position, params)),
+        ("piece_squares",  piece_squares(position)),
+        ("pawn_structure", pawn_structure(position, params)),
+        ("king_safety",    king_safety(position, params))
+    ]
+}
+
+fn material(position: &Position, params: &EvalParams) -> (i32, i32) {
+    sum_by_color(position, |piece, _, _| params.piece_value(piece) )
+}
+
+fn piece_squares(position: &Position) -> (i32, i32) {
+    sum_by_color(position, piece_square_value)
+}
+
+fn sum_by_color<F>(position: &Position, value: F) -> (i32, i32)
+    where F: Fn(Piece, Color, usize) -> i32
+{
+    let mut white = 0;
+    let mut black = 0;
+
+    for (i, occupancy) in position.board.squares.iter().enumerate() {
+        if let Some(OccupiedSquare { piece, color }) = occupancy {
+            match color {
+                Color::White => white += value(*piece, *color, i),
+                Color::Black => black += value(*piece, *color, i)
+            }
+        }
+    }
+
+    (white, black)
+}
+
+fn pawn_structure(position: &Position, params: &EvalParams) -> (i32, i32) {
+    (
+        pawn_structure_for(position, params, Color::White),
+        pawn_structure_for(position, params, Color::Black)
+    )
+}
+
+fn pawn_structure_for(position: &Position, params: &EvalParams, color: Color) -> i32 {
+    let pawns = pawn_squares(position, color);
+    let enemy_pawns = pawn_squares(position, color.opposite());
+
+    let mut pawns_per_file = [0; 8];
+    for pawn in pawns.iter() {
+        pawns_per_file[pawn.file as usize] += 1;
+    }
+
+    let mut score = 0;
+
+    for count in pawns_per_file.iter() {
+        if *count > 1 {
+            score -= params.doubled_pawn_penalty * (count - 1);
+        }
+    }
+
+    for pawn in pawns.iter() {
+        let file = pawn.file as usize;
+        let has_neighbour = (file > 0 && pawns_per_file[file - 1] > 0) || (file < 7 && pawns_per_file[file + 1] > 0);
+
+        if !has_neighbour {
+            score -= params.isolated_pawn_penalty;
+        }
+
+        let is_passed = !enemy_pawns.iter().any( |enemy| {
+            (enemy.file - pawn.file).abs() <= 1 && match color {
+                Color::White => enemy.rank > pawn.rank,
+                Color::Black => enemy.rank < pawn.rank
+            }
+        });
+
+        if is_passed {
+            let advanced = match color {
+                Color::White => pawn.rank - 1,
+                Color::Black => 6 - pawn.rank
+            };
+
+            score += params.passed_pawn_bonus * advanced as i32;
+        }
+    }
+
+    score
+}
+
+fn king_safety(position: &Position, params: &EvalParams) -> (i32, i32) {
+    let has_queen = |color: Color| position.board.squares.contains(&Some(OccupiedSquare { piece: Piece::Queen, color }));
+
+    if !has_queen(Color::White) && !has_queen(Color::Black) {
+        return (0, 0);
+    }
+
+    let score = |color: Color| {
+        if !has_queen(color.opposite()) {
+            return 0;
+        }
+
+        match analysis::board_king_safety(&position.board, color) {
+            Some(safety) =>
+                params.king_shield_bonus * safety.pawn_shield as i32 -
+                params.king_open_file_penalty * safety.open_files.len() as i32 -
+                params.king_open_file_penalty / 2 * safety.half_open_files.len() as i32 -
+                params.king_zone_attacker_penalty * safety.king_zone_attackers.len() as i32,
+            None => 0
+        }
+    };
+
+    (score(Color::White), score(Color::Black))
+}
+
+fn pawn_squares(position: &Position, color: Color) -> Vec<Square> {
+    position.board.squares.iter().enumerate()
+        .filter( |(_, occupancy)| **occupancy == Some(OccupiedSquare { piece: Piece::Pawn, color }) )
+        .map( |(i, _)| Board::square(i) )
+        .collect()
+}
+
+fn piece_square_value(piece: Piece, color: Color, index: usize) -> i32 {
+    // The tables are written from white's side, with a8 first
+    let index = match color {
+        Color::White => index,
+        Color::Black => {
+            let square = Board::square(index);
+
+            Board::index(Square { rank: 7 - square.rank, file: square.file })
+        }
+    };
+
+    let table = match piece {
+        Piece::Pawn   => &PAWN_TABLE,
+        Piece::Knight => &KNIGHT_TABLE,
+        Piece::Bishop => &BISHOP_TABLE,
+        Piece::Rook   => &ROOK_TABLE,
+        Piece::Queen  => &QUEEN_TABLE,
+        Piece::King   => &KING_TABLE
+    };
+
+    table[index]
+}
+
+static PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0
+];
+
+static KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50
+];
+
+static BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20
+];
+
+static ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0
+];
+
+static QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20
+];
+
+static KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20
+];
@@ -0,0 +1,105 @@
+use super::models::*;
+
+// Inputs for neural networks. Squares are numbered from a1 = 0 to h8 = 63 everywhere.
+//
+// Planes, 64 values each:
+//   0-5   white pawns, knights, bishops, rooks, queens and king
+//   6-11  the same for black
+//   12    all ones when white is to move
+//   13-16 all ones for each castling right: white king side, white queen side, black king side, black queen side
+//   17    the en passant target square, if a capture there is possible
+//
+// HalfKP: for each side the index of every piece other than the kings, relative to the king of that side. An index
+// is king_square * 641 + 1 + kind * 64 + square, where kind is 2 * piece + 1 for pieces of the other side, with the
+// pieces in the order pawn, knight, bishop, rook and queen. Black sees the board flipped vertically (square ^ 56),
+// so that its king starts on e1 as well. The first NNUE networks rotated it instead (square ^ 63), so their
+// weights don't fit these indices for black.
+pub const PLANE_COUNT: usize = 18;
+pub const HALFKP_FEATURES: usize = 64 * 641;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PositionFeatures {
+    // PLANE_COUNT * 64 values, plane after plane
+    pub planes: Vec<f32>,
+
+    // Active HalfKP features of the side to move, then of the other side. Empty for a side without a king.
+    pub halfkp: [Vec<usize>; 2]
+}
+
+pub fn extract(position: &Position) -> PositionFeatures {
+    let mut planes = vec![0.0; PLANE_COUNT * 64];
+
+    for (square, occupied) in pieces(position) {
+        let color_offset = if occupied.color == Color::White { 0 } else { 6 };
+
+        planes[(color_offset + occupied.piece.index()) * 64 + square_index(square)] = 1.0;
+    }
+
+    let flags = [
+        position.next_to_move == Color::White,
+        position.white_can_castle_king_side,
+        position.white_can_castle_queen_side,
+        position.black_can_castle_king_side,
+        position.black_can_castle_queen_side
+    ];
+
+    for (i, flag) in flags.iter().enumerate() {
+        if *flag {
+            planes[(12 + i) * 64..(13 + i) * 64].iter_mut().for_each( |value| *value = 1.0 );
+        }
+    }
+
+    if let Some(square) = position.en_passant_square.filter( |square| position.can_take_en_passant(*square) ) {
+        planes[17 * 64 + square_index(square)] = 1.0;
+    }
+
+    let side_to_move = position.next_to_move;
+
+    PositionFeatures {
+        planes,
+        halfkp: [halfkp(position, side_to_move), halfkp(position, side_to_move.opposite())]
+    }
+}
+
+// The active HalfKP features from the point of view of one side
+pub fn halfkp(position: &Position, perspective: Color) -> Vec<usize> {
+    let pieces = pieces(position);
+
+    let king = pieces.iter().find( |(_, occupied)| occupied.piece == Piece::King && occupied.color == perspective );
+    let king_square = match king {
+        Some((square, _)) => oriented(*square, perspective),
+        None => return Vec::new()
+    };
+
+    pieces.iter()
+        .filter( |(_, occupied)| occupied.piece != Piece::King )
+        .map( |(square, occupied)| halfkp_index(king_square, occupied, oriented(*square, perspective), perspective) )
+        .collect()
+}
+
+// Both squares as the perspective sees them
+pub fn halfkp_index(king_square: usize, occupied: &OccupiedSquare, square: usize, perspective: Color) -> usize {
+    let kind = 2 * occupied.piece.index() + if occupied.color == perspective { 0 } else { 1 };
+
+    king_square * 641 + 1 + kind * 64 + square
+}
+
+// The same square seen by the given side, flipped vertically for black
+pub fn oriented(square: Square, perspective: Color) -> usize {
+    match perspective {
+        Color::White => square_index(square),
+        Color::Black => square_index(square) ^ 56
+    }
+}
+
+fn pieces(position: &Position) -> Vec<(Square, OccupiedSquare)> {
+    position.board.squares.iter().enumerate()
+        .filter_map( |(index, occupied)| occupied.clone().map( |occupied| {
+            (Board::square(index), occupied)
+        }))
+        .collect()
+}
+
+fn square_index(square: Square) -> usize {
+    square.to_a1_index()
+}
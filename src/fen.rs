@@ -9,184 +9,79 @@ pub struct FenParseError {
 }
 
 impl Position {
+    // The fields have to be separated by single spaces and all six have to be there
     pub fn from_fen(fen: &str) -> Result<Position, FenParseError> {
-        let mut chars = fen.chars();
-        let mut squares: Vec<Option<OccupiedSquare>> = Vec::with_capacity(64);
-
-        let mut i = 0;
-        loop {
-            if i >= 8 * 8 {
-                break;
-            }
-
-            let square = Square { rank: 7 - i as i8 / 8, file: i as i8 % 8 };
-            let first_square_in_rank = square.file == 0;
-
-            match chars.next() {
-                Some('/') if first_square_in_rank => continue,
-
-                Some(c) if c.is_digit(10) => {
-                    let number_of_empty_squares = c as u8 - '0' as u8;
-
-                    i += number_of_empty_squares;
-
-                    for _ in 0..number_of_empty_squares {
-                        squares.push(None);
-                    }
-                },
-
-                Some(c) if c.is_alphabetic() => {
-                    let occupancy = Self::occupancy_from_char(c)?;
-
-                    i += 1;
-
-                    squares.push(Some(occupancy));
-                },
-
-                Some(c) => return Err(FenParseError {
-                    message: format!("Unexpected character '{}'", c)
-                }),
+        Self::from_fen_bytes(fen.as_bytes())
+    }
 
-                None => return Err(FenParseError {
-                    message: String::from("Unexpected end of FEN string")
-                })
-            }
-        }
+    // The same as from_fen, for bulk imports: reads the bytes in place, without building strings for the fields,
+    // and only allocates the board (and the message of an error)
+    pub fn from_fen_bytes(fen: &[u8]) -> Result<Position, FenParseError> {
+        let error = |message: &str| FenParseError { message: String::from(message) };
 
-        if chars.next() != Some(' ') {
-            return Err(FenParseError {
-                message: String::from("Expected ' ' after the piece positions")
-            });
-        }
+        let mut fields = fen.split( |byte| *byte == b' ' );
+        let mut field = |name: &str| fields.next().ok_or_else( || error(&format!("Expected the {} field", name)) );
 
-        let next_to_move = match chars.next() {
-            Some('w') => Color::White,
-            Some('b') => Color::Black,
-            Some(c) => return Err(FenParseError {
-                message: format!("Unexpected char '{}' instead of player to move", c)
-            }),
-            None => return Err(FenParseError {
-                message: String::from("Unexpected end of FEN string")
-            })
-        };
+        let placement = field("piece placement")?;
+        let side_to_move = field("side to move")?;
+        let castling = field("castling")?;
+        let en_passant = field("en passant")?;
+        let half_move_clock = field("half-move clock")?;
+        let full_move_counter = field("full move counter")?;
 
-        if chars.next() != Some(' ') {
-            return Err(FenParseError {
-                message: String::from("Expected ' ' after the player to move")
-            });
+        if fields.next().is_some() {
+            return Err(error("Unexpected characters after the full move counter"));
         }
 
-        let mut white_can_castle_king_side  = false;
-        let mut white_can_castle_queen_side = false;
-        let mut black_can_castle_king_side  = false;
-        let mut black_can_castle_queen_side = false;
-
-        loop {
-            match chars.next() {
-                Some(' ') => break,
-                Some('-') => continue,
+        let mut squares: Vec<Option<OccupiedSquare>> = Vec::with_capacity(64);
 
-                Some('K') => white_can_castle_king_side = true,
-                Some('Q') => white_can_castle_queen_side = true,
+        for (i, rank) in placement.split( |byte| *byte == b'/' ).enumerate() {
+            if i >= 8 {
+                return Err(error("More than 8 ranks"));
+            }
 
-                Some('k') => black_can_castle_king_side = true,
-                Some('q') => black_can_castle_queen_side = true,
+            let rank_start = squares.len();
 
-                Some(c) => return Err(FenParseError {
-                    message: format!("Unexpected character '{}'", c)
-                }),
+            for byte in rank {
+                match byte {
+                    b'1'..=b'8' => squares.resize(squares.len() + (byte - b'0') as usize, None),
+                    _           => squares.push(Some(Self::occupancy_from_char(*byte as char)?))
+                }
+            }
 
-                None => return Err(FenParseError {
-                    message: String::from("Unexpected end of FEN string")
-                })
+            if squares.len() - rank_start != 8 {
+                return Err(error(&format!("Expected 8 squares in rank {}", 8 - i)));
             }
         }
 
-        let en_passant_square = match chars.next() {
-            Some(file_char) if file_char.is_alphabetic() => {
-                if let Some(rank_char) = chars.next() {
-                    Some(
-                        Square::from_notation(&format!("{}{}", file_char, rank_char))
-                            .map_err( |_notation_error| FenParseError {
-                                message: String::from("Invalid en-passant notation")
-                            } )?
-                    )
-                } else {
-                    return Err(FenParseError {
-                        message: String::from("Unexpected end of FEN string while reading en-passant notation")
-                    });
-                }
-            },
-
-            Some('-') => None,
-
-            // TODO: Code duplication
-            Some(c) => return Err(FenParseError {
-                message: format!("Unexpected character '{}'", c)
-            }),
-
-            // TODO: Code duplication
-            None => return Err(FenParseError {
-                message: String::from("Unexpected end of FEN string")
-            })
-        };
-
-        if chars.next() != Some(' ') {
-            return Err(FenParseError {
-                message: String::from("Expected ' ' after the castling flags")
-            });
+        if squares.len() != 64 {
+            return Err(error("Expected 8 ranks"));
         }
 
-        let half_move_clock = {
-            let mut num_string = String::new();
-
-            loop {
-                match chars.next() {
-                    Some(c) if c.is_digit(10) => num_string.push(c),
-                    Some(' ') => break,
-                    Some(c) => return Err(FenParseError {
-                        message: format!("Unexpected character '{}'", c)
-                    }),
-
-                    None => return Err(FenParseError {
-                        message: String::from("Unexpected end of FEN string")
-                    })
-                }
-            }
-
-            let int = num_string.parse::<i64>();
-
-            match int {
-                Ok(value) => value,
-                Err(_) => return Err(FenParseError {
-                    message: String::from("Cannot parse half-move clock as int")
-                })
-            }
+        let next_to_move = match side_to_move {
+            b"w" => Color::White,
+            b"b" => Color::Black,
+            _    => return Err(error("Expected 'w' or 'b' as the player to move"))
         };
 
-        // TODO: Fix code duplication
-        let full_move_counter = {
-            let mut num_string = String::new();
-
-            loop {
-                match chars.next() {
-                    Some(c) if c.is_digit(10) => num_string.push(c),
-                    Some(c) => return Err(FenParseError {
-                        message: format!("Unexpected character '{}'", c)
-                    }),
+        let mut rights = [false; 4];
 
-                    None => break
+        if castling != b"-" {
+            for byte in castling {
+                match byte {
+                    b'K' => rights[0] = true,
+                    b'Q' => rights[1] = true,
+                    b'k' => rights[2] = true,
+                    b'q' => rights[3] = true,
+                    _    => return Err(error("Invalid castling flags"))
                 }
             }
+        }
 
-            let int = num_string.parse::<i64>();
-
-            match int {
-                Ok(value) => value,
-                Err(_) => return Err(FenParseError {
-                    message: String::from("Cannot parse half-move clock as int")
-                })
-            }
+        let en_passant_square = match en_passant {
+            b"-" => None,
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some(Square { rank: (rank - b'1') as i8, file: (file - b'a') as i8 }),
+            _ => return Err(error("Invalid en-passant notation"))
         };
 
         Ok(Position {
@@ -196,14 +91,14 @@ impl Position {
 
             en_passant_square,
 
-            white_can_castle_king_side,
-            white_can_castle_queen_side,
+            white_can_castle_king_side:  rights[0],
+            white_can_castle_queen_side: rights[1],
 
-            black_can_castle_king_side,
-            black_can_castle_queen_side,
+            black_can_castle_king_side:  rights[2],
+            black_can_castle_queen_side: rights[3],
 
-            half_move_clock,
-            full_move_counter
+            half_move_clock: parse_counter(half_move_clock).ok_or_else( || error("Cannot parse half-move clock as int") )?,
+            full_move_counter: parse_counter(full_move_counter).ok_or_else( || error("Cannot parse full move counter as int") )?
         })
     }
 
@@ -212,7 +107,7 @@ impl Position {
         let mut blank_square_count = 0;
 
         for (i, occupancy) in self.board.squares.iter().enumerate() {
-            let square = Square { rank: 7 - i as i8 / 8, file: i as i8 % 8 };
+            let square = Board::square(i);
             let last_square_in_rank = square.file == 7;
 
             match occupancy {
@@ -307,3 +202,23 @@ impl Position {
         }
     }
 }
+
+// One FEN per line, e.g. a column exported from a database, read with Position::from_fen_bytes. Empty lines are
+// skipped and lines may end with "\r\n".
+pub fn parse_fens(fens: &[u8]) -> impl Iterator<Item = Result<Position, FenParseError>> + '_ {
+    fens.split( |byte| *byte == b'\n' )
+        .map( |line| line.strip_suffix(b"\r").unwrap_or(line) )
+        .filter( |line| !line.is_empty() )
+        .map(Position::from_fen_bytes)
+}
+
+fn parse_counter(digits: &[u8]) -> Option<i64> {
+    if digits.is_empty() {
+        return None;
+    }
+
+    digits.iter().try_fold(0i64, |value, byte| match byte {
+        b'0'..=b'9' => value.checked_mul(10)?.checked_add((byte - b'0') as i64),
+        _ => None
+    })
+}
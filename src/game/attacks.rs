@@ -0,0 +1,136 @@
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SquareSafety {
+    pub piece: Option<OccupiedSquare>,
+
+    // Both sorted from the least to the most valuable piece
+    pub attackers: Vec<Piece>,
+    pub defenders: Vec<Piece>
+}
+
+impl SquareSafety {
+    pub fn attacker_value(&self) -> i32 {
+        self.attackers.iter().map( |piece| piece.value() ).sum()
+    }
+
+    pub fn defender_value(&self) -> i32 {
+        self.defenders.iter().map( |piece| piece.value() ).sum()
+    }
+
+    pub fn is_hanging(&self) -> bool {
+        let piece = match &self.piece {
+            Some(occupancy) => occupancy.piece,
+            None => return false
+        };
+
+        match self.attackers.first() {
+            Some(cheapest_attacker) => self.defenders.is_empty() || exchange_value(*cheapest_attacker) < piece.value(),
+            None => false
+        }
+    }
+}
+
+// The king can only capture last, when the square is no longer defended
+fn exchange_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::King => i32::MAX,
+        _ => piece.value()
+    }
+}
+
+impl Game {
+    pub fn attackers_of(&self, square: Square, by_color: Color) -> Vec<(Square, Piece)> {
+        let mut attackers = Vec::new();
+
+        for (i, occupancy) in self.position.board.squares.iter().enumerate() {
+            let from = Board::square(i);
+
+            match occupancy {
+                Some(OccupiedSquare { piece, color })
+                    if *color == by_color && self.attacked_squares(*piece, from, *color).contains(&square) =>
+                        attackers.push((from, *piece)),
+                _ => ()
+            }
+        }
+
+        attackers.sort_by_key( |(_, piece)| exchange_value(*piece) );
+        attackers
+    }
+
+    pub fn defenders_of(&self, square: Square, color: Color) -> Vec<(Square, Piece)> {
+        self.attackers_of(square, color)
+    }
+
+    pub fn square_safety(&self, square: Square) -> SquareSafety {
+        let piece = self.square_occupied(square).cloned();
+        let defending_color = match &piece {
+            Some(occupancy) => occupancy.color,
+            None => self.position.next_to_move
+        };
+
+        let pieces = |attackers: Vec<(Square, Piece)>| attackers.into_iter().map( |(_, piece)| piece ).collect();
+
+        SquareSafety {
+            attackers: pieces(self.attackers_of(square, defending_color.opposite())),
+            defenders: pieces(self.defenders_of(square, defending_color)),
+            piece
+        }
+    }
+
+    // Squares the piece controls, including ones occupied by pieces of its own color
+    pub(crate) fn attacked_squares(&self, piece: Piece, from: Square, color: Color) -> Vec<Square> {
+        match piece {
+            Piece::Pawn => {
+                let direction = match color {
+                    Color::White => 1,
+                    Color::Black => -1
+                };
+
+                [Square::new(from.rank + direction, from.file - 1), Square::new(from.rank + direction, from.file + 1)]
+                    .iter()
+                    .filter_map( |square| *square )
+                    .collect()
+            },
+
+            Piece::Knight => KNIGHT_JUMPS.iter()
+                .filter_map( |(rank_delta, file_delta)| Self::advance_square(from, *rank_delta, *file_delta) )
+                .collect(),
+
+            Piece::King => KING_STEPS.iter()
+                .filter_map( |(rank_delta, file_delta)| Self::advance_square(from, *rank_delta, *file_delta) )
+                .collect(),
+
+            Piece::Rook   => self.attacked_squares_in_lines(from, &KING_STEPS[4..]),
+            Piece::Bishop => self.attacked_squares_in_lines(from, &KING_STEPS[..4]),
+            Piece::Queen  => self.attacked_squares_in_lines(from, &KING_STEPS)
+        }
+    }
+
+    fn attacked_squares_in_lines(&self, from: Square, directions: &[(i8, i8)]) -> Vec<Square> {
+        let mut squares = Vec::new();
+
+        for (rank_delta, file_delta) in directions {
+            for square in self.squares_in_a_line(from, *rank_delta, *file_delta) {
+                squares.push(square);
+
+                if self.square_occupied(square).is_some() {
+                    break;
+                }
+            }
+        }
+
+        squares
+    }
+}
+
+static KNIGHT_JUMPS: [(i8, i8); 8] = [
+    (-2, -1), (-2, 1), (2, -1), (2, 1),
+    (-1, -2), (-1, 2), (1, -2), (1, 2)
+];
+
+// Diagonal steps first, then orthogonal ones
+static KING_STEPS: [(i8, i8); 8] = [
+    (-1, -1), (-1, 1), (1, -1), (1, 1),
+    (-1, 0), (1, 0), (0, -1), (0, 1)
+];
@@ -0,0 +1,296 @@
+use super::*;
+
+// English descriptive notation, e.g. "P-K4", "N-KB3", "PxP ep", "KtxB ch" or "P-Q8(Q)". Squares are counted from
+// the side of the player making the move and pieces are named by the file they started on.
+impl ValidMove {
+    pub fn from_descriptive(game: &Game, notation: &str) -> Result<ValidMove, InvalidMoveError> {
+        let color = game.position.next_to_move;
+        let description = DescriptiveMove::parse(notation, color).ok_or(InvalidMoveError::InvalidNotation)?;
+
+        let mut valid_moves: Vec<ValidMove> = game.valid_moves().into_iter()
+            .filter( |valid_move| description.matches(valid_move) )
+            .collect();
+
+        match valid_moves.len() {
+            0 => Err(InvalidMoveError::NoMatchingMove),
+            1 => Ok(valid_moves.pop().unwrap()),
+            _ => Err(InvalidMoveError::AmbiguousMove(valid_moves))
+        }
+    }
+
+    // The shortest description that no other legal move fits, the way the books wrote it
+    pub fn descriptive(&self, game: &Game) -> String {
+        let after = game.make_valid_move(self);
+        let suffix = if after.in_mate() {
+            " mate"
+        } else if after.in_check(self.color.opposite()) {
+            " ch"
+        } else {
+            ""
+        };
+
+        if self.is_castle() {
+            let castles = if self.to.file > self.from.file { "O-O" } else { "O-O-O" };
+
+            return format!("{}{}", castles, suffix);
+        }
+
+        let promotion = match self.promotion {
+            Some(piece) => format!("={}", piece.letter()),
+            None => String::new()
+        };
+
+        let movers = designations(self.piece, self.from, self.color);
+        let targets = match self.takes {
+            Some(taken) => designations(taken, self.to, self.color).into_iter()
+                .map( |taken| format!("x{}", taken) )
+                .collect(),
+            None => square_names(self.to, self.color).into_iter()
+                .map( |square| format!("-{}", square) )
+                .collect::<Vec<String>>()
+        };
+
+        for mover in movers.iter() {
+            for target in targets.iter() {
+                let notation = format!("{}{}{}", mover, target, promotion);
+
+                if ValidMove::from_descriptive(game, &notation).as_ref() == Ok(self) {
+                    return format!("{}{}", notation, suffix);
+                }
+            }
+        }
+
+        // Naming both squares in full always fits a single move
+        format!("{}{}{}", movers.last().unwrap(), targets.last().unwrap(), promotion) + suffix
+    }
+}
+
+#[derive(Debug)]
+struct DescriptiveMove {
+    piece: Piece,
+    from_files: Option<Vec<i8>>,
+    from_squares: Option<Vec<Square>>,
+
+    // Set for captures
+    taken: Option<(Piece, Option<Vec<i8>>)>,
+    to_squares: Option<Vec<Square>>,
+
+    promotion: Option<Piece>,
+    castles: Option<bool>
+}
+
+impl DescriptiveMove {
+    fn parse(notation: &str, color: Color) -> Option<Self> {
+        if !notation.is_ascii() {
+            return None;
+        }
+
+        let mut text: String = notation.to_uppercase().chars().filter( |c| !c.is_whitespace() ).collect();
+        text = text.replace("KT", "N");
+
+        for suffix in ["!", "?", "+", "#", "CH", "MATE", "DIS", "DBL", "E.P.", "EP"].iter().cycle().take(30) {
+            if text.ends_with(suffix) {
+                text.truncate(text.len() - suffix.len());
+            }
+        }
+
+        if text == "O-O" || text == "0-0" || text == "O-O-O" || text == "0-0-0" {
+            return Some(DescriptiveMove {
+                piece: Piece::King,
+                from_files: None,
+                from_squares: None,
+                taken: None,
+                to_squares: None,
+                promotion: None,
+                castles: Some(text.len() == 3)
+            });
+        }
+
+        let (rest, promotion) = split_promotion(&text);
+        let (mover, target, takes) = match (rest.find('-'), rest.find('X')) {
+            (Some(at), None) => (&rest[..at], &rest[at + 1..], false),
+            (None, Some(at)) => (&rest[..at], &rest[at + 1..], true),
+            _ => return None
+        };
+
+        let (mover, qualifier) = split_qualifier(mover);
+        let (piece, from_files) = parse_designation(mover)?;
+        let from_squares = match qualifier {
+            Some(qualifier) => Some(parse_qualifier(qualifier, color)?),
+            None => None
+        };
+
+        let (taken, to_squares) = if takes {
+            let (taken, qualifier) = split_qualifier(target);
+            let to_squares = match qualifier {
+                Some(qualifier) => Some(parse_qualifier(qualifier, color)?),
+                None => None
+            };
+
+            (Some(parse_designation(taken)?), to_squares)
+        } else {
+            (None, Some(parse_square(target, color)?))
+        };
+
+        Some(DescriptiveMove { piece, from_files, from_squares, taken, to_squares, promotion, castles: None })
+    }
+
+    fn matches(&self, valid_move: &ValidMove) -> bool {
+        if let Some(king_side) = self.castles {
+            return valid_move.is_castle() && (valid_move.to.file > valid_move.from.file) == king_side;
+        }
+
+        let taken_matches = match (&self.taken, valid_move.takes) {
+            (Some((piece, files)), Some(taken)) => *piece == taken && files.as_ref().is_none_or( |files| files.contains(&valid_move.to.file) ),
+            (None, None) => true,
+            _ => false
+        };
+
+        valid_move.piece == self.piece && taken_matches && valid_move.promotion == self.promotion &&
+            self.from_files.as_ref().is_none_or( |files| files.contains(&valid_move.from.file) ) &&
+            self.from_squares.as_ref().is_none_or( |squares| squares.contains(&valid_move.from) ) &&
+            self.to_squares.as_ref().is_none_or( |squares| squares.contains(&valid_move.to) )
+    }
+}
+
+// "P-K8=Q", "P-K8(Q)", "P-K8/Q" and "P-K8Q"
+fn split_promotion(text: &str) -> (&str, Option<Piece>) {
+    let promotion_at = |at: usize| text.get(at..at + 1).and_then( |letter| match letter {
+        "Q" => Some(Piece::Queen),
+        "R" => Some(Piece::Rook),
+        "B" => Some(Piece::Bishop),
+        "N" => Some(Piece::Knight),
+        _   => None
+    });
+
+    let length = text.len();
+
+    if length > 3 && text.ends_with(')') && text[..length - 2].ends_with('(') {
+        if let Some(piece) = promotion_at(length - 2) {
+            return (&text[..length - 3], Some(piece));
+        }
+    }
+
+    if length > 2 && text[..length - 1].ends_with(['=', '/']) {
+        if let Some(piece) = promotion_at(length - 1) {
+            return (&text[..length - 2], Some(piece));
+        }
+    }
+
+    if length > 2 && text[..length - 1].ends_with('8') {
+        if let Some(piece) = promotion_at(length - 1) {
+            return (&text[..length - 1], Some(piece));
+        }
+    }
+
+    (text, None)
+}
+
+// The square the piece is standing on, as in "N(Q2)-B4" or "R/1-Q1"
+fn split_qualifier(text: &str) -> (&str, Option<&str>) {
+    match text.find(['(', '/']) {
+        Some(at) => (&text[..at], Some(text[at + 1..].trim_end_matches(')'))),
+        None => (text, None)
+    }
+}
+
+fn parse_qualifier(qualifier: &str, color: Color) -> Option<Vec<Square>> {
+    match qualifier.as_bytes() {
+        [digit @ b'1'..=b'8'] => {
+            let rank = rank_from(*digit, color);
+
+            Some((0..8).map( |file| Square { rank, file } ).collect())
+        },
+        _ => parse_square(qualifier, color)
+    }
+}
+
+// A piece and the files it can be on, e.g. "KBP" is the pawn on the f-file and "QN" a knight on the queen side
+fn parse_designation(text: &str) -> Option<(Piece, Option<Vec<i8>>)> {
+    let piece = text.chars().last().filter( |letter| letter.is_ascii_uppercase() ).and_then(Piece::from_letter)?;
+
+    let prefix = &text[..text.len() - 1];
+
+    let files = match (piece, prefix) {
+        (_, "") => None,
+        (Piece::Pawn, prefix) => Some(files(prefix)?),
+        (Piece::Knight, "Q") | (Piece::Bishop, "Q") | (Piece::Rook, "Q") => Some((0..4).collect()),
+        (Piece::Knight, "K") | (Piece::Bishop, "K") | (Piece::Rook, "K") => Some((4..8).collect()),
+        _ => return None
+    };
+
+    Some((piece, files))
+}
+
+// "KB3" is f3 for White and f6 for Black, "B3" can be either of the bishop files
+fn parse_square(text: &str, color: Color) -> Option<Vec<Square>> {
+    let (prefix, digit) = text.split_at(text.len().checked_sub(1)?);
+    let rank = match digit.as_bytes() {
+        [digit @ b'1'..=b'8'] => rank_from(*digit, color),
+        _ => return None
+    };
+
+    Some(files(prefix)?.into_iter().map( |file| Square { rank, file } ).collect())
+}
+
+fn files(prefix: &str) -> Option<Vec<i8>> {
+    match prefix {
+        "QR" => Some(vec![0]),
+        "QN" => Some(vec![1]),
+        "QB" => Some(vec![2]),
+        "Q"  => Some(vec![3]),
+        "K"  => Some(vec![4]),
+        "KB" => Some(vec![5]),
+        "KN" => Some(vec![6]),
+        "KR" => Some(vec![7]),
+        "R"  => Some(vec![0, 7]),
+        "N"  => Some(vec![1, 6]),
+        "B"  => Some(vec![2, 5]),
+        _    => None
+    }
+}
+
+fn rank_from(digit: u8, color: Color) -> i8 {
+    match color {
+        Color::White => (digit - b'1') as i8,
+        Color::Black => (b'8' - digit) as i8
+    }
+}
+
+static FILE_NAMES: [&str; 8] = ["QR", "QN", "QB", "Q", "K", "KB", "KN", "KR"];
+
+fn square_names(square: Square, color: Color) -> Vec<String> {
+    let rank = match color {
+        Color::White => square.rank + 1,
+        Color::Black => 8 - square.rank
+    };
+
+    let full = format!("{}{}", FILE_NAMES[square.file as usize], rank);
+
+    match FILE_NAMES[square.file as usize] {
+        "Q" | "K" => vec![full],
+        name => vec![format!("{}{}", &name[1..], rank), full]
+    }
+}
+
+// From the shortest to the one naming the square, e.g. "P", "BP", "KBP", "P(KB2)"
+fn designations(piece: Piece, square: Square, color: Color) -> Vec<String> {
+    let letter = piece.letter();
+    let file_name = FILE_NAMES[square.file as usize];
+
+    let mut names = vec![String::from(letter)];
+
+    if piece == Piece::Pawn {
+        if file_name.len() == 2 {
+            names.push(format!("{}P", &file_name[1..]));
+        }
+
+        names.push(format!("{}P", file_name));
+    } else if [Piece::Knight, Piece::Bishop, Piece::Rook].contains(&piece) {
+        names.push(format!("{}{}", if square.file < 4 { "Q" } else { "K" }, letter));
+    }
+
+    names.push(format!("{}({})", letter, square_names(square, color).pop().unwrap()));
+    names
+}
+
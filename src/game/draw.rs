@@ -0,0 +1,221 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::*;
+
+// Positions visited while looking for a possible mate before giving up
+const DEAD_POSITION_SEARCH_LIMIT: usize = 10_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawReason {
+    Stalemate,
+    FiftyMoveRule,
+    InsufficientMaterial,
+
+    // No sequence of legal moves can lead to mate, e.g. kings behind a locked pawn chain (FIDE 5.2.2)
+    DeadPosition
+}
+
+impl Game {
+    // Draws which follow from the position alone. Repetitions need the whole game and are not included.
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        if !self.has_legal_move() {
+            return if self.in_check(self.position.next_to_move) { None } else { Some(DrawReason::Stalemate) };
+        }
+
+        if self.draw_by_fifty_move_rule() {
+            Some(DrawReason::FiftyMoveRule)
+        } else if self.insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.is_dead_position() {
+            Some(DrawReason::DeadPosition)
+        } else {
+            None
+        }
+    }
+
+    // Bare kings, a single minor piece, or only bishops which are all on squares of the same color
+    pub fn insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishop_square_colors = HashSet::new();
+
+        for (i, occupancy) in self.position.board.squares.iter().enumerate() {
+            match occupancy {
+                Some(OccupiedSquare { piece: Piece::King, .. }) => (),
+                Some(OccupiedSquare { piece: Piece::Knight, .. }) => knights += 1,
+                Some(OccupiedSquare { piece: Piece::Bishop, .. }) => { bishop_square_colors.insert(Board::square(i).is_light()); },
+                Some(_) => return false,
+                None => ()
+            }
+        }
+
+        match (knights, bishop_square_colors.len()) {
+            (0, _) => bishop_square_colors.len() <= 1,
+            (1, 0) => true,
+            _ => false
+        }
+    }
+
+    // Whether the color could still mate by some series of legal moves, so that it wins when the opponent runs out
+    // of time instead of drawing (FIDE 6.9). Judged by the material the way online servers do: a lone knight
+    // needs an opponent piece other than a queen to block its king, and bishops on squares of one color need an
+    // opponent pawn, knight or bishop on the other color.
+    pub fn can_win_on_time(&self, color: Color) -> bool {
+        let mut knights = 0;
+        let mut bishop_square_colors = HashSet::new();
+        let mut opponent_pieces = HashSet::new();
+        let mut opponent_bishop_square_colors = HashSet::new();
+
+        for (i, occupancy) in self.position.board.squares.iter().enumerate() {
+            let square_color = Board::square(i).is_light();
+
+            match occupancy {
+                Some(OccupiedSquare { piece: Piece::King, .. }) => (),
+                Some(OccupiedSquare { piece, color: piece_color }) if *piece_color == color => match piece {
+                    Piece::Knight => knights += 1,
+                    Piece::Bishop => { bishop_square_colors.insert(square_color); },
+                    _ => return true
+                },
+                Some(OccupiedSquare { piece, .. }) => {
+                    if *piece == Piece::Bishop {
+                        opponent_bishop_square_colors.insert(square_color);
+                    }
+
+                    opponent_pieces.insert(*piece);
+                },
+                None => ()
+            }
+        }
+
+        match (knights, bishop_square_colors.len()) {
+            (0, 0) => false,
+            (1, 0) => opponent_pieces.iter().any( |piece| *piece != Piece::Queen ),
+            (0, 1) => {
+                opponent_pieces.contains(&Piece::Pawn) ||
+                    opponent_pieces.contains(&Piece::Knight) ||
+                    !opponent_bishop_square_colors.is_subset(&bishop_square_colors)
+            },
+            _ => true
+        }
+    }
+
+    // Insufficient material, or a position where neither side can ever be mated. The latter is a helpmate
+    // search, but not the one in search::solve_problem: that stops after a fixed number of moves and so only shows
+    // there is no mate that soon, and it revisits transpositions. This one visits every reachable position once,
+    // which only finishes for closed positions where no captures or pawn moves are possible, so anything else is
+    // not considered dead.
+    pub fn is_dead_position(&self) -> bool {
+        if self.insufficient_material() {
+            return true;
+        }
+
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        seen.insert(self.hash);
+        queue.push_back(self.clone());
+
+        while let Some(game) = queue.pop_front() {
+            let valid_moves = game.valid_moves();
+
+            if valid_moves.is_empty() && game.in_check(game.position.next_to_move) {
+                return false;
+            }
+
+            for valid_move in valid_moves {
+                if valid_move.takes.is_some() || valid_move.piece == Piece::Pawn {
+                    return false;
+                }
+
+                let next = game.make_valid_move(&valid_move);
+
+                if seen.insert(next.hash) {
+                    if seen.len() > DEAD_POSITION_SEARCH_LIMIT {
+                        return false;
+                    }
+
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        true
+    }
+
+    // How many times the current position has occurred in the game, counting this time (FIDE 9.2). Only the
+    // positions since the last capture or pawn move can be the same.
+    pub fn repetition_count(&self) -> usize {
+        let reversible_plies = self.position.half_move_clock.max(0) as usize;
+        let hash = self.repetition_hash();
+
+        // How many plies ago the earlier positions with the same hash were
+        let mut plies_ago = Vec::new();
+        let mut node = &self.history;
+
+        for plies in 1..=reversible_plies {
+            let history = match node {
+                Some(history) => history,
+                None => break
+            };
+
+            if history.hash == hash {
+                plies_ago.push(plies);
+            }
+
+            node = &history.previous;
+        }
+
+        if plies_ago.is_empty() {
+            return 1;
+        }
+
+        // Only replayed when a hash matches, to rule out collisions and en passant captures which differ
+        let history = self.history();
+
+        let earlier = plies_ago.iter()
+            .filter( |plies| history[history.len() - **plies].0.position.same_for_repetition(&self.position) )
+            .count();
+
+        earlier + 1
+    }
+
+    // The zobrist hash without the en passant square, which is the same for all positions which are the same for
+    // repetition. The en passant square is left out since it is hashed even when no pawn can take there.
+    pub(super) fn repetition_hash(&self) -> u64 {
+        self.hash ^ zobrist::en_passant_key(self.position.en_passant_square)
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= THREEFOLD_REPETITION_COUNT
+    }
+
+    // Whether the move would repeat the position for the third time, so that the player about to make it can
+    // already claim the draw (FIDE 9.2.1.1). Moves which aren't legal here can't be claimed with.
+    pub fn would_be_threefold(&self, valid_move: &ValidMove) -> bool {
+        self.try_make_valid_move(valid_move).is_ok_and( |game| game.is_threefold_repetition() )
+    }
+}
+
+impl Position {
+    // Whether the positions are the same in the sense of FIDE 9.2.3: the same pieces on the same squares, the
+    // same side to move, the same castling rights and the same possibility to capture en passant. Unlike ==,
+    // the move counters don't matter and neither does an en passant square no pawn can legally capture on.
+    pub fn same_for_repetition(&self, other: &Position) -> bool {
+        self.board == other.board &&
+            self.next_to_move == other.next_to_move &&
+            self.white_can_castle_king_side == other.white_can_castle_king_side &&
+            self.white_can_castle_queen_side == other.white_can_castle_queen_side &&
+            self.black_can_castle_king_side == other.black_can_castle_king_side &&
+            self.black_can_castle_queen_side == other.black_can_castle_queen_side &&
+            self.en_passant_capture() == other.en_passant_capture()
+    }
+
+    // The en passant square, if there is a legal move capturing on it
+    fn en_passant_capture(&self) -> Option<Square> {
+        let square = self.en_passant_square.filter( |square| self.can_take_en_passant(*square) )?;
+
+        Game::new(self.clone()).valid_moves().into_iter()
+            .any( |valid_move| valid_move.takes_en_passant )
+            .then_some(square)
+    }
+}
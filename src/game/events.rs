@@ -0,0 +1,132 @@
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GameChange {
+    Move { valid_move: ValidMove, san: String },
+    DrawOffer(Color),
+
+    // The game ended, or its result was changed by an adjudication
+    Status(GameStatus),
+
+    // The color whose time ran out. Games don't keep clocks, so this comes from TIME_FORFEIT adjudications.
+    ClockFlag(Color)
+}
+
+impl Game {
+    // Everything that happened between an earlier state of the same game and this one, in order
+    pub fn changes_since(&self, earlier: &Game) -> Vec<GameChange> {
+        let mut changes = Vec::new();
+        let mut game = earlier.clone();
+
+        for valid_move in self.moves().into_iter().skip(earlier.ply_count()) {
+            changes.push(GameChange::Move { san: game.san(&valid_move), valid_move: valid_move.clone() });
+            game = game.make_valid_move(&valid_move);
+        }
+
+        if let Some(color) = self.draw_offer {
+            if earlier.draw_offer != Some(color) {
+                changes.push(GameChange::DrawOffer(color));
+            }
+        }
+
+        let status = self.status();
+
+        if status != GameStatus::Ongoing && status != earlier.status() {
+            if let GameStatus::Adjudicated { result, reason } = &status {
+                match (result, reason.as_str()) {
+                    (GameResult::WhiteWins, TIME_FORFEIT) => changes.push(GameChange::ClockFlag(Color::Black)),
+                    (GameResult::BlackWins, TIME_FORFEIT) => changes.push(GameChange::ClockFlag(Color::White)),
+                    _ => ()
+                }
+            }
+
+            changes.push(GameChange::Status(status));
+        }
+
+        changes
+    }
+}
+
+type Listener<T> = Box<dyn FnMut(&T) + Send>;
+
+// Holds a game together with callbacks which are called for every change made through it
+pub struct ObservedGame {
+    game: Game,
+
+    change_listeners: Vec<Listener<GameChange>>,
+    move_listeners: Vec<Listener<(ValidMove, String)>>,
+    status_listeners: Vec<Listener<GameStatus>>,
+    flag_listeners: Vec<Listener<Color>>
+}
+
+impl ObservedGame {
+    pub fn new(game: Game) -> Self {
+        ObservedGame {
+            game,
+
+            change_listeners: Vec::new(),
+            move_listeners: Vec::new(),
+            status_listeners: Vec::new(),
+            flag_listeners: Vec::new()
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn on_change<F: FnMut(&GameChange) + Send + 'static>(&mut self, listener: F) {
+        self.change_listeners.push(Box::new(listener));
+    }
+
+    // Called with the move and its SAN
+    pub fn on_move<F: FnMut(&(ValidMove, String)) + Send + 'static>(&mut self, listener: F) {
+        self.move_listeners.push(Box::new(listener));
+    }
+
+    pub fn on_status_change<F: FnMut(&GameStatus) + Send + 'static>(&mut self, listener: F) {
+        self.status_listeners.push(Box::new(listener));
+    }
+
+    pub fn on_clock_flag<F: FnMut(&Color) + Send + 'static>(&mut self, listener: F) {
+        self.flag_listeners.push(Box::new(listener));
+    }
+
+    pub fn make_move(&mut self, notation: &str) -> Result<(), InvalidMoveError> {
+        self.update( |game| game.make_move(notation) )
+    }
+
+    pub fn update<F, E>(&mut self, update: F) -> Result<(), E>
+        where F: FnOnce(&Game) -> Result<Game, E>
+    {
+        let updated = update(&self.game)?;
+
+        self.set(updated);
+
+        Ok(())
+    }
+
+    // Replaces the game, notifying the listeners of what changed since the previous one
+    pub fn set(&mut self, game: Game) {
+        let changes = game.changes_since(&self.game);
+
+        self.game = game;
+
+        for change in changes {
+            for listener in self.change_listeners.iter_mut() {
+                listener(&change);
+            }
+
+            match change {
+                GameChange::Move { valid_move, san } => {
+                    let played = (valid_move, san);
+
+                    self.move_listeners.iter_mut().for_each( |listener| listener(&played) );
+                },
+                GameChange::Status(status) => self.status_listeners.iter_mut().for_each( |listener| listener(&status) ),
+                GameChange::ClockFlag(color) => self.flag_listeners.iter_mut().for_each( |listener| listener(&color) ),
+                GameChange::DrawOffer(_) => ()
+            }
+        }
+    }
+}
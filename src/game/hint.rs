@@ -0,0 +1,68 @@
+use super::*;
+use super::super::search::{self, SearchLimits};
+use super::super::locale::Locale;
+
+// How much of the move a hint gives away, from least to most
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum HintLevel {
+    Piece,
+    FromSquare,
+    Move
+}
+
+impl HintLevel {
+    // None after the full move, there is nothing left to reveal
+    pub fn next(&self) -> Option<HintLevel> {
+        match self {
+            HintLevel::Piece      => Some(HintLevel::FromSquare),
+            HintLevel::FromSquare => Some(HintLevel::Move),
+            HintLevel::Move       => None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Hint {
+    pub valid_move: ValidMove,
+    pub san: String,
+
+    // Centipawns from the point of view of the side to move, as in `SearchResult`
+    pub score: i32,
+    pub mate_in: Option<i32>
+}
+
+impl Hint {
+    pub fn piece(&self) -> Piece {
+        self.valid_move.piece
+    }
+
+    pub fn from_square(&self) -> Square {
+        self.valid_move.from
+    }
+
+    // E.g. "Move your knight", "Move the knight on g1" and "Nf3"
+    pub fn text(&self, level: HintLevel) -> String {
+        let piece = Locale::English.vocabulary().pieces[self.piece().index()];
+
+        match level {
+            HintLevel::Piece      => format!("Move your {}", piece),
+            HintLevel::FromSquare => format!("Move the {} on {}", piece, self.from_square().to_notation(SquareNotationOptions::FileAndRank)),
+            HintLevel::Move       => self.san.clone()
+        }
+    }
+}
+
+impl Game {
+    // The move the engine would play, for trainers which reveal it a bit at a time. None when the game is over.
+    pub fn hint(&self, limits: &SearchLimits) -> Option<Hint> {
+        let result = search::search(self, limits);
+        let valid_move = result.best_move.clone()?;
+
+        Some(Hint {
+            san: self.san(&valid_move),
+            valid_move,
+            score: result.score,
+            mate_in: result.mate_in()
+        })
+    }
+}
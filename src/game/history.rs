@@ -0,0 +1,90 @@
+use super::*;
+use std::sync::Arc;
+
+// Moves are kept in a shared linked list, so making a move does not copy the previous ones
+#[derive(Debug)]
+pub(super) struct MoveHistory {
+    pub(super) valid_move: ValidMove,
+    pub(super) metadata: MoveMetadata,
+
+    // The repetition_hash of the position the move was made in
+    pub(super) hash: u64,
+
+    pub(super) previous: Option<Arc<MoveHistory>>
+}
+
+impl MoveHistory {
+    pub(super) fn push(previous: &Option<Arc<MoveHistory>>, valid_move: &ValidMove, metadata: MoveMetadata, hash: u64) -> Option<Arc<MoveHistory>> {
+        Some(Arc::new(MoveHistory {
+            valid_move: valid_move.clone(),
+            metadata,
+            hash,
+            previous: previous.clone()
+        }))
+    }
+}
+
+impl Game {
+    // The position the game was started from, before any of its moves
+    pub fn initial_position(&self) -> &Position {
+        &self.initial_position
+    }
+
+    // All moves made since the initial position, in order
+    pub fn moves(&self) -> Vec<ValidMove> {
+        let mut moves = Vec::new();
+        let mut node = &self.history;
+
+        while let Some(history) = node {
+            moves.push(history.valid_move.clone());
+            node = &history.previous;
+        }
+
+        moves.reverse();
+        moves
+    }
+
+    pub(super) fn moves_with_metadata(&self) -> Vec<(ValidMove, MoveMetadata)> {
+        let mut moves = Vec::new();
+        let mut node = &self.history;
+
+        while let Some(history) = node {
+            moves.push((history.valid_move.clone(), history.metadata.clone()));
+            node = &history.previous;
+        }
+
+        moves.reverse();
+        moves
+    }
+
+    pub fn last_move(&self) -> Option<&ValidMove> {
+        self.history.as_ref().map( |history| &history.valid_move )
+    }
+
+    pub fn ply_count(&self) -> usize {
+        let mut count = 0;
+        let mut node = &self.history;
+
+        while let Some(history) = node {
+            count += 1;
+            node = &history.previous;
+        }
+
+        count
+    }
+
+    // Every move together with the game right before it was made. The games keep the moves' metadata.
+    pub fn history(&self) -> Vec<(Game, ValidMove)> {
+        let mut game = Game::from_shared_position(self.initial_position.clone());
+        let mut history = Vec::new();
+
+        for (valid_move, metadata) in self.moves_with_metadata() {
+            let next = game.make_valid_move_with_metadata(&valid_move, metadata);
+
+            history.push((game, valid_move));
+            game = next;
+        }
+
+        history
+    }
+}
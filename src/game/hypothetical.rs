@@ -0,0 +1,43 @@
+use super::*;
+use std::sync::Arc;
+
+impl Game {
+    // Runs `probe` on the game with the piece put on the square, replacing whatever stood there, e.g. to ask
+    // what a bishop would attack from e4. Only the board and its hash change: the history and castling rights stay
+    // those of this game, so the game given to `probe` is for queries, not for making moves.
+    pub fn with_piece<T, F: FnOnce(&Game) -> T>(&self, square: Square, piece: Piece, color: Color, probe: F) -> T {
+        self.with_square(square, Some(OccupiedSquare { piece, color }), probe)
+    }
+
+    // The same with the square emptied, e.g. to see what a piece is shielding
+    pub fn without_piece<T, F: FnOnce(&Game) -> T>(&self, square: Square, probe: F) -> T {
+        self.with_square(square, None, probe)
+    }
+
+    // The legal moves the piece would have from the square, as if it were its color's turn
+    pub fn hypothetical_moves(&self, square: Square, piece: Piece, color: Color) -> Vec<ValidMove> {
+        self.with_piece(square, piece, color, |game| {
+            game.valid_moves_for(color).into_iter()
+                .filter( |valid_move| valid_move.from == square )
+                .collect()
+        })
+    }
+
+    fn with_square<T, F: FnOnce(&Game) -> T>(&self, square: Square, occupancy: Option<OccupiedSquare>, probe: F) -> T {
+        let mut scratch = self.clone();
+        let squares = &mut Arc::make_mut(&mut scratch.position).board.squares;
+        let index = Board::index(square);
+
+        if let Some(previous) = &squares[index] {
+            scratch.hash ^= zobrist::piece_key(previous, square);
+        }
+
+        if let Some(placed) = &occupancy {
+            scratch.hash ^= zobrist::piece_key(placed, square);
+        }
+
+        squares[index] = occupancy;
+
+        probe(&scratch)
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::*;
+
+// What is known about a move besides the move itself, e.g. filled in by a server as moves come in. It stays
+// with the move through clones, history() and later moves, and the PGN writer uses it for moves which
+// aren't given an annotation.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MoveMetadata {
+    // Time left on the clock of the player after the move
+    pub clock: Option<Duration>,
+
+    // Centipawns from White's point of view
+    pub eval: Option<i32>,
+
+    // Where the move came from, e.g. "book", "engine" or a player's name
+    pub source: Option<String>,
+    pub annotator: Option<String>,
+    pub comment: Option<String>,
+
+    // Anything else, by name
+    pub extra: BTreeMap<String, String>
+}
+
+impl MoveMetadata {
+    pub fn annotation(&self) -> MoveAnnotation {
+        MoveAnnotation { comment: self.comment.clone(), clock: self.clock, eval: self.eval }
+    }
+}
+
+impl Game {
+    pub fn make_valid_move_with_metadata(&self, move_to_make: &ValidMove, metadata: MoveMetadata) -> Self {
+        let mut game = self.make_valid_move(move_to_make);
+        game.history = MoveHistory::push(&self.history, move_to_make, metadata, self.repetition_hash());
+
+        game
+    }
+
+    // The same game with the metadata of the last move replaced, e.g. once the clock time for it is known.
+    // The game is returned unchanged if it has no moves.
+    pub fn with_last_move_metadata(&self, metadata: MoveMetadata) -> Self {
+        let mut game = self.clone();
+
+        if let Some(history) = &self.history {
+            game.history = MoveHistory::push(&history.previous, &history.valid_move, metadata, history.hash);
+        }
+
+        game
+    }
+
+    pub fn last_move_metadata(&self) -> Option<&MoveMetadata> {
+        self.history.as_ref().map( |history| &history.metadata )
+    }
+
+    // The metadata of every move since the initial position, in order
+    pub fn move_metadata(&self) -> Vec<MoveMetadata> {
+        self.moves_with_metadata().into_iter().map( |(_, metadata)| metadata ).collect()
+    }
+}
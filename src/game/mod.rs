@@ -1,22 +1,165 @@
 use super::models::*;
 use regex::Regex;
 use lazy_static::lazy_static;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Serializer, Deserialize};
+use serde::ser::SerializeStruct;
 
 use super::parser::lexer::{Lexer, LexerError};
-use super::parser::{Parser, ParseError, PGNMove};
+use super::parser::{Parser, ParseError, ParseLimits, ParsedGame};
 use super::fen::FenParseError;
-
-#[derive(Debug)]
+use super::zobrist;
+use super::locale::PieceLetters;
+
+mod attacks;
+mod draw;
+mod history;
+mod retro;
+mod status;
+mod events;
+mod pgn;
+mod spoken;
+mod descriptive;
+mod notation;
+mod hint;
+mod premove;
+mod odds;
+mod metadata;
+mod hypothetical;
+
+pub use attacks::SquareSafety;
+pub use draw::DrawReason;
+pub use status::{GameStatus, TIME_FORFEIT};
+pub use events::{GameChange, ObservedGame};
+pub use notation::MoveNotation;
+pub use hint::{Hint, HintLevel};
+pub use odds::Odds;
+pub use metadata::MoveMetadata;
+pub use pgn::{PgnProfile, TagSelection, ResultPlacement, MoveAnnotation};
+
+use history::MoveHistory;
+use std::sync::Arc;
+
+// The directions rooks move in, followed by the bishops'
+const LINE_DIRECTIONS: [(i8, i8); 8] = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (1, 1), (-1, 1)];
+
+#[derive(Debug, Clone)]
 pub struct Game {
-    position: Position
+    // Shared between clones, so keeping the game of every ply around only costs the positions themselves
+    position: Arc<Position>,
+    hash: u64,
+
+    initial_position: Arc<Position>,
+    history: Option<Arc<MoveHistory>>,
+
+    // Set by resignations, draw agreements and adjudications, which end the game outside of the rules
+    ending: Option<GameStatus>,
+    draw_offer: Option<Color>
 }
 
-#[derive(Debug, PartialEq, Eq)]
+pub struct Replay {
+    game: Game,
+    half_moves: std::vec::IntoIter<(Option<i64>, String)>,
+    strictness: NotationStrictness,
+    letters: PieceLetters,
+    notation: MoveNotation,
+    error: Option<ReplayError>
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum InvalidMoveError {
+    InvalidNotation,
+    NoMatchingMove,
+    AmbiguousMove(Vec<ValidMove>),
+
+    // The game was resigned, agreed drawn or adjudicated
+    GameOver
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IllegalReason {
+    NoPieceOnSquare,
+    NotYourTurn,
+    CannotReachSquare,
+    LeavesKingInCheck,
+    PromotionRequired,
+    InvalidPromotion
+}
+
+pub static PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NotationStrictness {
+    Strict,
+
+    // Tolerates a missing or superfluous capture marker, using it only to break ties
+    Lenient
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PromotionPolicy {
+    // A pawn reaching the last rank needs its promotion piece, as in arbiter or correspondence settings
+    Explicit,
+
+    // A missing promotion piece means a queen, as most casual play frontends do
+    AutoQueen
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MoveOptions {
+    pub strictness: NotationStrictness,
+    pub promotion: PromotionPolicy,
+    pub letters: PieceLetters
+}
+
+impl Default for MoveOptions {
+    fn default() -> Self {
+        MoveOptions {
+            strictness: NotationStrictness::Strict,
+            promotion: PromotionPolicy::Explicit,
+            letters: PieceLetters::English
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    InvalidPGN(String),
+    InvalidMove {
+        number: Option<i64>,
+        notation: String,
+        error: InvalidMoveError
+    }
+}
+
+impl From<ReplayError> for String {
+    fn from(error: ReplayError) -> String {
+        match error {
+            ReplayError::InvalidPGN(message) => message,
+
+            ReplayError::InvalidMove { number, notation, error } => {
+                let notation = match number {
+                    Some(move_number) => format!("{} (move #{})", notation, move_number),
+                    None => notation
+                };
+
+                match error {
+                    InvalidMoveError::AmbiguousMove(candidates) => format!(
+                        "Ambiguous move in PGN game: {}, could be played from {}",
+                        notation,
+                        candidates.iter()
+                            .map( |candidate| candidate.from.to_notation(SquareNotationOptions::FileAndRank) )
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+
+                    _ => format!("Invalid move in PGN game: {}", notation)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Deserialize)]
 pub struct ValidMove {
     pub color: Color,
 
@@ -28,9 +171,83 @@ pub struct ValidMove {
     pub takes: Option<Piece>,
     pub takes_en_passant: bool,
 
+    pub promotion: Option<Piece>,
+
     pub en_passant_square: Option<Square>
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveKind {
+    Normal,
+    Capture,
+    EnPassant,
+    Castle,
+    Promotion
+}
+
+// As JSON, squares in algebraic notation and pieces and colors in lowercase:
+//
+//   {"color": "white", "from": "e5", "to": "d6", "piece": "pawn", "takes": "pawn", "takes_en_passant": true,
+//    "promotion": null, "en_passant_square": null, "kind": "enpassant"}
+//
+// `en_passant_square` is the square a pawn skipped over with a double step, `kind` is one of "normal",
+// "capture", "enpassant", "castle" and "promotion". It is only written, reading ignores it. Fields which should
+// write the squares as numbers can use #[serde(with = "numeric_move")].
+impl Serialize for ValidMove {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with_squares(serializer, false)
+    }
+}
+
+impl ValidMove {
+    fn serialize_with_squares<S: Serializer>(&self, serializer: S, numeric: bool) -> Result<S::Ok, S::Error> {
+        let square = |square: Square| MoveSquare { square, numeric };
+
+        let mut state = serializer.serialize_struct("ValidMove", 9)?;
+
+        state.serialize_field("color", &self.color)?;
+        state.serialize_field("from", &square(self.from))?;
+        state.serialize_field("to", &square(self.to))?;
+        state.serialize_field("piece", &self.piece)?;
+        state.serialize_field("takes", &self.takes)?;
+        state.serialize_field("takes_en_passant", &self.takes_en_passant)?;
+        state.serialize_field("promotion", &self.promotion)?;
+        state.serialize_field("en_passant_square", &self.en_passant_square.map(square))?;
+        state.serialize_field("kind", &self.kind())?;
+
+        state.end()
+    }
+}
+
+struct MoveSquare {
+    square: Square,
+    numeric: bool
+}
+
+impl Serialize for MoveSquare {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.numeric {
+            numeric_square::serialize(&self.square, serializer)
+        } else {
+            self.square.serialize(serializer)
+        }
+    }
+}
+
+pub mod numeric_move {
+    use super::*;
+    use serde::Deserializer;
+
+    pub fn serialize<S: Serializer>(valid_move: &ValidMove, serializer: S) -> Result<S::Ok, S::Error> {
+        valid_move.serialize_with_squares(serializer, true)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ValidMove, D::Error> {
+        ValidMove::deserialize(deserializer)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct PartialSquare {
     rank: Option<i8>,
@@ -45,6 +262,7 @@ pub struct PartialMove {
     to: Square,
 
     takes: Option<bool>,
+    promotion: Option<Option<Piece>>,
     check_or_mate: Option<Option<CheckOrMate>>,
     castles: Option<Option<CastlesDirection>>
 }
@@ -67,7 +285,21 @@ impl From<ParseError> for PGNReadError {
 
 impl Game {
     pub fn new(initial_position: Position) -> Self {
-        Self { position: initial_position }
+        Self::from_shared_position(Arc::new(initial_position))
+    }
+
+    // Starts a game without copying the position, e.g. one taken from another game with `shared_position`
+    pub fn from_shared_position(initial_position: Arc<Position>) -> Self {
+        let hash = initial_position.zobrist_hash();
+
+        Self {
+            initial_position: initial_position.clone(),
+            position: initial_position,
+            hash,
+            history: None,
+            ending: None,
+            draw_offer: None
+        }
     }
 
     pub fn new_from_fen(fen: &str) -> Result<Self, FenParseError> {
@@ -76,62 +308,117 @@ impl Game {
         Ok(Self::new(position))
     }
 
-    // TODO: Starting positions from FEN
     pub fn new_from_pgn(pgn: &str) -> Result<Vec<Result<Self, String>>, String> {
-        let mut lexer = Lexer::new(pgn);
-        let tokens = match lexer.lex() {
-            Ok(tokens) => tokens,
-            Err(error) => return Err(error.into())
-        };
+        Self::new_from_pgn_with_limits(pgn, ParseLimits::default())
+    }
 
-        let mut parser = Parser::new(tokens);
-        let pgn_games = match parser.parse() {
-            Ok(games) => games,
-            Err(error) => return Err(error.into())
-        };
+    // For untrusted input, see ParseLimits::untrusted
+    pub fn new_from_pgn_with_limits(pgn: &str, limits: ParseLimits) -> Result<Vec<Result<Self, String>>, String> {
+        let pgn_games = Self::parse_pgn_with_limits(pgn, limits)?;
 
         Ok(pgn_games.into_iter().map( |pgn_game| {
-            let mut game;
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("replay_game", moves = pgn_game.moves.len()).entered();
 
-            // TODO: Check if setup is true?
-            if let Some(fen) = pgn_game.fen {
-                game = Game::new_from_fen(&fen).map_err( |e| e.message )?;
-            } else {
-                game = Game::new(Game::standard_position());
-            }
+            let mut replay = Game::replay(pgn_game);
 
-            for next_move in pgn_game.moves {
-                let moves = &[next_move.white_move, next_move.black_move];
-
-                for next_half_move in moves {
-                    if let Some(next_half_move) = next_half_move {
-                        game = match game.make_move(&next_half_move) {
-                            Ok(game) => game,
-                            Err(_) => {
-                                let message = match next_move.number {
-                                    Some(move_number) => format!("Invalid move in PGN game: {} (move #{})", next_half_move, move_number),
-                                    None => format!("Invalid move in PGN game: {}", next_half_move)
-                                };
-
-                                return Err(message);
-                            }
-                        }
-                    }
-                }
+            for step in &mut replay {
+                step?;
             }
 
-            Ok(game)
+            Ok(replay.into_game())
         }).collect())
     }
 
+    // Replays the first game inside the PGN, yielding every move together with the game after it
+    pub fn replay_pgn(pgn: &str) -> Replay {
+        let pgn_game = Self::parse_pgn(pgn).and_then( |pgn_games|
+            pgn_games.into_iter().next().ok_or(String::from("No games inside PGN"))
+        );
+
+        match pgn_game {
+            Ok(pgn_game) => Game::replay(pgn_game),
+            Err(error) => Replay::failed(ReplayError::InvalidPGN(error))
+        }
+    }
+
+    pub fn replay(pgn_game: ParsedGame) -> Replay {
+        let headers = pgn_game.headers();
+
+        if !headers.is_standard_variant() {
+            return Replay::failed(ReplayError::InvalidPGN(
+                format!("Unsupported variant '{}'", headers.variant.unwrap_or_default())
+            ));
+        }
+
+        // TODO: Check if setup is true?
+        let game = match pgn_game.fen {
+            Some(fen) => match Game::new_from_fen(&fen) {
+                Ok(game) => game,
+                Err(error) => return Replay::failed(ReplayError::InvalidPGN(error.message))
+            },
+            None => Game::new(Game::standard_position())
+        };
+
+        let mut half_moves = Vec::new();
+
+        for next_move in pgn_game.moves {
+            for next_half_move in [next_move.white_move, next_move.black_move].iter().flatten() {
+                half_moves.push((next_move.number, next_half_move.clone()));
+            }
+        }
+
+        Replay {
+            game,
+            half_moves: half_moves.into_iter(),
+            strictness: NotationStrictness::Strict,
+            letters: PieceLetters::English,
+            notation: MoveNotation::San,
+            error: None
+        }
+    }
+
+    pub(crate) fn parse_pgn(pgn: &str) -> Result<Vec<ParsedGame>, String> {
+        Self::parse_pgn_with_limits(pgn, ParseLimits::default())
+    }
+
+    pub(crate) fn parse_pgn_with_limits(pgn: &str, limits: ParseLimits) -> Result<Vec<ParsedGame>, String> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("parse_pgn", bytes = pgn.len()).entered();
+
+        let mut lexer = Lexer::new(pgn).with_limits(limits);
+        let tokens = match lexer.lex() {
+            Ok(tokens) => tokens,
+            Err(error) => return Err(error.into())
+        };
+
+        let mut parser = Parser::new(tokens).with_limits(limits);
+
+        match parser.parse() {
+            Ok(games) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(games = games.len(), "parsed PGN");
+
+                Ok(games)
+            },
+            Err(error) => Err(error.into())
+        }
+    }
+
+    // Parsed once, every call gets a copy
     pub fn standard_position() -> Position {
-        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap()
+        lazy_static! {
+            static ref STANDARD_POSITION: Position =
+                Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").expect("Invalid standard position");
+        }
+
+        STANDARD_POSITION.clone()
     }
 
     pub fn new_for_test(board: Board, next_to_move: Color) -> Self {
-        Self {
+        Self::new(
             // TODO: Pass position directly
-            position: Position {
+            Position {
                 board,
 
                 next_to_move,
@@ -146,7 +433,7 @@ impl Game {
                 half_move_clock: 0,
                 full_move_counter: 0
             }
-        }
+        )
     }
 
     pub fn position_to_fen(&self) -> String {
@@ -167,12 +454,24 @@ impl Game {
         &self.position.board
     }
 
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn shared_position(&self) -> Arc<Position> {
+        self.position.clone()
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
     pub fn in_mate(&self) -> bool {
-        self.in_check(self.position.next_to_move) && self.valid_moves().len() == 0
+        self.in_check(self.position.next_to_move) && !self.has_legal_move()
     }
 
     pub fn draw_by_fifty_move_rule(&self) -> bool {
-        self.position.half_move_clock >= 50
+        self.position.half_move_clock >= FIFTY_MOVE_RULE_HALF_MOVES
     }
 
     pub fn in_check(&self, color: Color) -> bool {
@@ -191,18 +490,23 @@ impl Game {
 
         match king_square {
             Some((i, _)) => self.square_attacked(
-                Square { rank: 7 - i as i8 / 8, file: i as i8 % 8 },
+                Board::square(i),
                 color.opposite()
-            ).is_some(),
+            ),
             None => false
         }
     }
 
-    fn square_attacked(&self, square: Square, by_color: Color) -> Option<ValidMove> {
-        let opposite_color_moves = self.valid_moves_for_color(by_color, false);
+    // Only looks at the squares each piece controls, which is a lot cheaper than generating its moves
+    fn square_attacked(&self, square: Square, by_color: Color) -> bool {
+        self.position.board.squares.iter().enumerate().any( |(i, occupancy)| match occupancy {
+            Some(OccupiedSquare { piece, color }) if *color == by_color => {
+                let from = Board::square(i);
 
-        // TODO: And not castles
-        opposite_color_moves.into_iter().find( |valid_move| valid_move.to == square )
+                self.attacked_squares(*piece, from, *color).contains(&square)
+            },
+            _ => false
+        })
     }
 
     // TODO: Cache this or not?
@@ -210,25 +514,16 @@ impl Game {
         self.valid_moves_for_color(self.position.next_to_move, true)
     }
 
+    // Legal moves as if it were the given color's turn
+    pub fn valid_moves_for(&self, color: Color) -> Vec<ValidMove> {
+        self.valid_moves_for_color(color, true)
+    }
+
     fn valid_moves_for_color(&self, for_color: Color, filter_out_discover_checks: bool) -> Vec<ValidMove> {
         let mut valid_moves = Vec::new();
 
-        for (i, occupied_square) in self.position.board.squares.iter().enumerate() {
-            let square = Square { rank: 7 - i as i8 / 8, file: i as i8 % 8 };
-
-            let occupied_square = match occupied_square {
-                Some(occupied_square) => occupied_square,
-                None => continue
-            };
-
-            let piece = match occupied_square {
-                OccupiedSquare { piece, color } if color == &for_color => piece,
-                _ => continue
-            };
-
-            let mut moves = self.possible_moves_for_piece(*piece, square, for_color);
-
-            valid_moves.append(&mut moves);
+        for (piece, from) in self.pieces_of(for_color) {
+            self.push_moves_for_piece(piece, from, for_color, &mut valid_moves);
         }
 
         if filter_out_discover_checks {
@@ -241,24 +536,172 @@ impl Game {
         }
     }
 
-    // TODO: Actual error
-    pub fn make_move(&self, notation: &str) -> Result<Self, ()> {
-        let move_to_make = ValidMove::from_notation(self, notation)?;
+    // Same as valid_moves().len(), but checks each move on a scratch board instead of making it
+    pub fn count_legal_moves(&self) -> usize {
+        let color = self.position.next_to_move;
+        let mut scratch = self.clone();
+        let mut moves = Vec::new();
+
+        for (piece, from) in self.pieces_of(color) {
+            self.push_moves_for_piece(piece, from, color, &mut moves);
+        }
+
+        moves.iter()
+            .filter( |valid_move| !self.leaves_king_in_check(&mut scratch, valid_move) )
+            .count()
+    }
+
+    // Stops at the first piece with a legal move
+    pub fn has_legal_move(&self) -> bool {
+        let color = self.position.next_to_move;
+        let mut scratch = self.clone();
+        let mut moves = Vec::new();
+
+        self.pieces_of(color).any( |(piece, from)| {
+            moves.clear();
+            self.push_moves_for_piece(piece, from, color, &mut moves);
+
+            moves.iter().any( |valid_move| !self.leaves_king_in_check(&mut scratch, valid_move) )
+        })
+    }
+
+    fn pieces_of(&self, color: Color) -> impl Iterator<Item = (Piece, Square)> + '_ {
+        self.position.board.squares.iter().enumerate()
+            .filter_map( move |(i, occupancy)| match occupancy {
+                Some(occupancy) if occupancy.color == color => Some((occupancy.piece, Board::square(i))),
+                _ => None
+            })
+    }
+
+    // Only the board of the scratch game is changed, which is all in_check looks at. The scratch position gets
+    // copied the first time, after that it isn't shared anymore.
+    fn leaves_king_in_check(&self, scratch: &mut Game, valid_move: &ValidMove) -> bool {
+        let squares = &mut Arc::make_mut(&mut scratch.position).board.squares;
+
+        squares.clone_from_slice(&self.position.board.squares);
+
+        squares[Board::index(valid_move.from)] = None;
+        squares[Board::index(valid_move.to)] = Some(OccupiedSquare {
+            piece: valid_move.promotion.unwrap_or(valid_move.piece),
+            color: valid_move.color
+        });
+
+        if valid_move.takes_en_passant {
+            squares[Board::index(Square { rank: valid_move.from.rank, file: valid_move.to.file })] = None;
+        }
+
+        if let Some((rook_from, rook_to)) = valid_move.castling_rook_move() {
+            squares[Board::index(rook_to)] = squares[Board::index(rook_from)].take();
+        }
+
+        scratch.in_check(valid_move.color)
+    }
+
+    pub fn make_move(&self, notation: &str) -> Result<Self, InvalidMoveError> {
+        self.make_move_with(notation, NotationStrictness::Strict)
+    }
+
+    pub fn make_move_with(&self, notation: &str, strictness: NotationStrictness) -> Result<Self, InvalidMoveError> {
+        self.make_move_with_options(notation, MoveOptions { strictness, ..MoveOptions::default() })
+    }
+
+    pub fn make_move_with_options(&self, notation: &str, options: MoveOptions) -> Result<Self, InvalidMoveError> {
+        if self.ending.is_some() {
+            return Err(InvalidMoveError::GameOver);
+        }
+
+        let move_to_make = ValidMove::from_notation_with_options(self, notation, options)?;
 
         Ok(self.make_valid_move(&move_to_make))
     }
 
-    fn make_valid_move(&self, move_to_make: &ValidMove) -> Self {
+    pub fn try_move(&self, from: Square, to: Square, promotion: Option<Piece>) -> Result<ValidMove, IllegalReason> {
+        self.try_move_with_options(from, to, promotion, MoveOptions::default())
+    }
+
+    pub fn try_move_with_options(&self, from: Square, to: Square, promotion: Option<Piece>, options: MoveOptions) -> Result<ValidMove, IllegalReason> {
+        let occupancy = self.square_occupied(from).ok_or(IllegalReason::NoPieceOnSquare)?;
+
+        if occupancy.color != self.position.next_to_move {
+            return Err(IllegalReason::NotYourTurn);
+        }
+
+        let candidates: Vec<ValidMove> = self.valid_moves().into_iter()
+            .filter( |valid_move| valid_move.from == from && valid_move.to == to )
+            .collect();
+
+        if candidates.is_empty() {
+            let pseudo_legal = self.possible_moves_for_piece(occupancy.piece, from, occupancy.color).into_iter()
+                .any( |valid_move| valid_move.to == to );
+
+            return Err(if pseudo_legal {
+                IllegalReason::LeavesKingInCheck
+            } else {
+                IllegalReason::CannotReachSquare
+            });
+        }
+
+        let is_promotion = candidates.iter().any( |valid_move| valid_move.promotion.is_some() );
+
+        let promotion = match (promotion, options.promotion) {
+            (None, PromotionPolicy::AutoQueen) if is_promotion => Some(Piece::Queen),
+            _ => promotion
+        };
+
+        match promotion {
+            None if is_promotion => Err(IllegalReason::PromotionRequired),
+            Some(_) if !is_promotion => Err(IllegalReason::InvalidPromotion),
+
+            _ => candidates.into_iter()
+                .find( |valid_move| valid_move.promotion == promotion )
+                .ok_or(IllegalReason::InvalidPromotion)
+        }
+    }
+
+    // Moves which didn't come from `valid_moves`, e.g. deserialized from a request, go through this instead.
+    // Their squares are not trusted to be on the board.
+    pub fn try_make_valid_move(&self, move_to_make: &ValidMove) -> Result<Self, InvalidMoveError> {
+        if self.ending.is_some() {
+            return Err(InvalidMoveError::GameOver);
+        }
+
+        if !self.valid_moves().contains(move_to_make) {
+            return Err(InvalidMoveError::NoMatchingMove);
+        }
+
+        Ok(self.make_valid_move(move_to_make))
+    }
+
+    // Expects one of the `valid_moves` of the game
+    pub fn make_valid_move(&self, move_to_make: &ValidMove) -> Self {
         let mut new_squares = self.position.board.squares.clone();
 
         let from = move_to_make.from;
         let to = move_to_make.to;
 
-        new_squares[((7 - from.rank) * 8 + from.file) as usize] = None;
-        new_squares[((7 - to.rank) * 8 + to.file) as usize] = Some(OccupiedSquare {
+        let moved_piece = OccupiedSquare {
             piece: move_to_make.piece,
             color: move_to_make.color
-        });
+        };
+
+        let placed_piece = OccupiedSquare {
+            piece: move_to_make.promotion.unwrap_or(move_to_make.piece),
+            color: move_to_make.color
+        };
+
+        let mut hash = self.hash ^
+            zobrist::side_to_move_key() ^
+            zobrist::en_passant_key(self.position.en_passant_square) ^
+            zobrist::en_passant_key(move_to_make.en_passant_square) ^
+            zobrist::piece_key(&moved_piece, from) ^
+            zobrist::piece_key(&placed_piece, to);
+
+        if let Some(taken_piece) = self.square_occupied(to) {
+            hash ^= zobrist::piece_key(taken_piece, to);
+        }
+
+        new_squares[Board::index(from)] = None;
+        new_squares[Board::index(to)] = Some(placed_piece);
 
         if move_to_make.takes_en_passant {
             let passing_pawn_direction = match move_to_make.color {
@@ -266,49 +709,151 @@ impl Game {
                 Color::Black => 1
             };
 
-            if move_to_make.to.rank + passing_pawn_direction < 0 || move_to_make.to.rank + passing_pawn_direction > 7 {
-                panic!("Cannot take en-passant on the first or last rank");
+            // Legal moves never take en passant towards the first or last rank
+            if let Some(pawn_to_take_square) = Square::new(move_to_make.to.rank + passing_pawn_direction, move_to_make.to.file) {
+                new_squares[Board::index(pawn_to_take_square)] = None;
+
+                hash ^= zobrist::piece_key(
+                    &OccupiedSquare { piece: Piece::Pawn, color: move_to_make.color.opposite() },
+                    pawn_to_take_square
+                );
             }
+        }
 
-            let pawn_to_take_square = Square {
-                file: move_to_make.to.file,
-                rank: move_to_make.to.rank + passing_pawn_direction
-            };
+        if let Some((rook_from, rook_to)) = move_to_make.castling_rook_move() {
+            let rook = OccupiedSquare { piece: Piece::Rook, color: move_to_make.color };
+
+            hash ^= zobrist::piece_key(&rook, rook_from) ^ zobrist::piece_key(&rook, rook_to);
 
-            new_squares[((7 - pawn_to_take_square.rank) * 8 + pawn_to_take_square.file) as usize] = None;
+            new_squares[Board::index(rook_from)] = None;
+            new_squares[Board::index(rook_to)] = Some(rook);
         }
 
-        Game {
-            position: Position {
-                board: Board {
-                    squares: new_squares
-                },
-
-                next_to_move: self.position.next_to_move.opposite(),
-
-                white_can_castle_king_side:  self.position.white_can_castle_king_side,
-                white_can_castle_queen_side: self.position.white_can_castle_queen_side,
-                black_can_castle_king_side:  self.position.black_can_castle_king_side,
-                black_can_castle_queen_side: self.position.black_can_castle_queen_side,
-
-                en_passant_square: move_to_make.en_passant_square,
-
-                // TODO: Add tests for this
-                half_move_clock: if move_to_make.takes.is_some() || move_to_make.piece == Piece::Pawn {
-                    0
-                } else {
-                    self.position.half_move_clock + 1
-                },
-
-                full_move_counter: if move_to_make.color == Color::White {
-                    self.position.full_move_counter
-                } else {
-                    self.position.full_move_counter + 1
-                }
+        // Moving the king or the rook gives up castling with it, and so does losing the rook
+        let keeps_right = |can_castle: bool, rank: i8, rook_file: i8| {
+            can_castle && ![Square { rank, file: 4 }, Square { rank, file: rook_file }].iter()
+                .any( |square| *square == from || *square == to )
+        };
+
+        let position = Position {
+            board: Board {
+                squares: new_squares
+            },
+
+            next_to_move: self.position.next_to_move.opposite(),
+
+            white_can_castle_king_side:  keeps_right(self.position.white_can_castle_king_side, 0, 7),
+            white_can_castle_queen_side: keeps_right(self.position.white_can_castle_queen_side, 0, 0),
+            black_can_castle_king_side:  keeps_right(self.position.black_can_castle_king_side, 7, 7),
+            black_can_castle_queen_side: keeps_right(self.position.black_can_castle_queen_side, 7, 0),
+
+            en_passant_square: move_to_make.en_passant_square,
+
+            half_move_clock: if move_to_make.takes.is_some() || move_to_make.piece == Piece::Pawn {
+                0
+            } else {
+                self.position.half_move_clock + 1
+            },
+
+            full_move_counter: if move_to_make.color == Color::White {
+                self.position.full_move_counter
+            } else {
+                self.position.full_move_counter + 1
             }
+        };
+
+        hash ^= zobrist::castling_key(&self.position) ^ zobrist::castling_key(&position);
+
+        Game {
+            hash,
+            initial_position: self.initial_position.clone(),
+            history: MoveHistory::push(&self.history, move_to_make, MoveMetadata::default(), self.repetition_hash()),
+
+            ending: self.ending.clone(),
+
+            // Moving instead of accepting declines the opponent's offer
+            draw_offer: self.draw_offer.filter( |color| *color == move_to_make.color ),
+
+            position: Arc::new(position)
         }
     }
 
+    pub fn gives_check(&self, valid_move: &ValidMove) -> bool {
+        self.make_valid_move(valid_move).in_check(valid_move.color.opposite())
+    }
+
+    pub fn gives_mate(&self, valid_move: &ValidMove) -> bool {
+        self.make_valid_move(valid_move).in_mate()
+    }
+
+    pub fn legal_sans(&self) -> Vec<String> {
+        let moves = self.valid_moves();
+
+        moves.iter()
+            .map( |valid_move| self.san_among(valid_move, &moves, PieceLetters::English) )
+            .collect()
+    }
+
+    pub fn san(&self, valid_move: &ValidMove) -> String {
+        self.san_with_letters(valid_move, PieceLetters::English)
+    }
+
+    pub fn san_with_letters(&self, valid_move: &ValidMove, letters: PieceLetters) -> String {
+        self.san_among(valid_move, &self.valid_moves(), letters)
+    }
+
+    fn san_among(&self, valid_move: &ValidMove, legal_moves: &[ValidMove], letters: PieceLetters) -> String {
+        let position_after = self.make_valid_move(valid_move);
+        let check_or_mate = if position_after.in_mate() {
+            "#"
+        } else if position_after.in_check(valid_move.color.opposite()) {
+            "+"
+        } else {
+            ""
+        };
+
+        if valid_move.is_castle() {
+            return format!("{}{}", valid_move.castling_notation(), check_or_mate);
+        }
+
+        let piece = match valid_move.piece {
+            Piece::Pawn => "",
+            piece       => letters.letter(piece)
+        };
+
+        let disambiguation = if valid_move.piece == Piece::Pawn {
+            if valid_move.takes.is_some() {
+                valid_move.from.to_notation(SquareNotationOptions::OnlyFile)
+            } else {
+                String::new()
+            }
+        } else {
+            let rivals: Vec<&ValidMove> = legal_moves.iter()
+                .filter( |other| other.piece == valid_move.piece && other.to == valid_move.to && other.from != valid_move.from )
+                .collect();
+
+            if rivals.is_empty() {
+                String::new()
+            } else if rivals.iter().all( |other| other.from.file != valid_move.from.file ) {
+                valid_move.from.to_notation(SquareNotationOptions::OnlyFile)
+            } else if rivals.iter().all( |other| other.from.rank != valid_move.from.rank ) {
+                valid_move.from.to_notation(SquareNotationOptions::OnlyRank)
+            } else {
+                valid_move.from.to_notation(SquareNotationOptions::FileAndRank)
+            }
+        };
+
+        let takes = if valid_move.takes.is_some() { "x" } else { "" };
+        let to_square = valid_move.to.to_notation(SquareNotationOptions::FileAndRank);
+
+        let promotion = match valid_move.promotion {
+            Some(promoted) => format!("={}", letters.letter(promoted)),
+            None => String::new()
+        };
+
+        format!("{}{}{}{}{}{}", piece, disambiguation, takes, to_square, promotion, check_or_mate)
+    }
+
     pub fn find_moves(&self, template: PartialMove) -> Vec<ValidMove> {
         let moves = self.valid_moves();
 
@@ -360,6 +905,12 @@ impl Game {
             None => ()
         }
 
+        if let Some(promotion) = template.promotion {
+            if m.promotion != promotion {
+                return false;
+            }
+        }
+
         // TODO
         // match template.check_or_mate
         // match template.castles
@@ -368,28 +919,39 @@ impl Game {
     }
 
     fn possible_moves_for_piece(&self, piece: Piece, from: Square, color: Color) -> Vec<ValidMove> {
-        let moves = match piece {
-            Piece::Pawn   => self.possible_pawn_moves(from, color),
-            Piece::Knight => self.possible_knight_moves(from, color),
-            Piece::Rook   => self.possible_rook_moves(from, color),
-            Piece::Bishop => self.possible_bishop_moves(from, color),
-            Piece::Queen  => self.possible_queen_moves(from, color),
-            Piece::King   => self.possible_king_moves(from, color)
-        };
+        let mut moves = Vec::new();
+        self.push_moves_for_piece(piece, from, color, &mut moves);
 
         moves
     }
 
+    // Appends to the moves instead of returning new ones, so that going through every piece needs one buffer
+    fn push_moves_for_piece(&self, piece: Piece, from: Square, color: Color, moves: &mut Vec<ValidMove>) {
+        match piece {
+            Piece::Pawn   => self.push_pawn_moves(from, color, moves),
+            Piece::Knight => self.push_knight_moves(from, color, moves),
+            Piece::Rook   => self.push_line_moves(Piece::Rook, from, color, &LINE_DIRECTIONS[..4], moves),
+            Piece::Bishop => self.push_line_moves(Piece::Bishop, from, color, &LINE_DIRECTIONS[4..], moves),
+            Piece::Queen  => self.push_line_moves(Piece::Queen, from, color, &LINE_DIRECTIONS, moves),
+            Piece::King   => self.push_king_moves(from, color, moves)
+        }
+    }
+
     fn can_pawn_double_move(square: Square, side_to_move: Color) -> bool {
         side_to_move == Color::White && square.rank == 1 ||
         side_to_move == Color::Black && square.rank == 6
     }
 
-    fn square_occupied(&self, square: Square) -> Option<&OccupiedSquare> {
-        self.position.board.squares[((7 - square.rank) * 8 + square.file) as usize].as_ref()
+    // None for squares off the board as well, since squares can come from outside, e.g. in try_move
+    pub(crate) fn square_occupied(&self, square: Square) -> Option<&OccupiedSquare> {
+        if !(0..8).contains(&square.rank) || !(0..8).contains(&square.file) {
+            return None;
+        }
+
+        self.position.board.squares[Board::index(square)].as_ref()
     }
 
-    fn possible_pawn_moves(&self, from: Square, color: Color) -> Vec<ValidMove> {
+    fn push_pawn_moves(&self, from: Square, color: Color, moves: &mut Vec<ValidMove>) {
         let direction = match color {
             Color::White => 1,
             Color::Black => -1
@@ -400,11 +962,9 @@ impl Game {
             self.square_occupied(next_square) == None
         } else { false };
 
-        let mut forward_moves = Vec::new();
-
         if let Some(next_square) = next_square {
             if can_move_forward {
-                forward_moves.push(
+                Self::push_with_promotions(
                     ValidMove {
                         piece: Piece::Pawn,
                         color,
@@ -412,8 +972,10 @@ impl Game {
                         to: next_square,
                         takes: None,
                         takes_en_passant: false,
+                        promotion: None,
                         en_passant_square: None
-                    }
+                    },
+                    moves
                 );
             }
         }
@@ -425,7 +987,7 @@ impl Game {
         );
 
         if let Some(double_move_square) = double_move_square {
-            forward_moves.push(
+            moves.push(
                 ValidMove {
                     piece: Piece::Pawn,
                     color,
@@ -433,6 +995,7 @@ impl Game {
                     to: double_move_square,
                     takes: None,
                     takes_en_passant: false,
+                    promotion: None,
                     en_passant_square: next_square
                 }
             );
@@ -443,54 +1006,53 @@ impl Game {
             Square::new(from.rank + direction, from.file + 1)
         ];
 
-        let take_moves = take_squares.iter()
-            .map( |square|
-                square.map( |square|
-                    (
-                        square,
-                        self.square_occupied(square).filter( |occupancy| occupancy.color != color )
-                    )
-                )
-            )
-            .filter_map( |to_and_occupancy|
-                match to_and_occupancy {
-                    Some((to, None)) if self.position.en_passant_square.is_some() => {
-                        if self.position.en_passant_square.unwrap() == to {
-                            Some(ValidMove {
-                                piece: Piece::Pawn,
-                                color,
-                                from, to,
-                                takes: Some(Piece::Pawn),
-                                takes_en_passant: true,
-                                en_passant_square: None
-                            })
-                        } else {
-                            None
-                        }
+        for to in take_squares.iter().filter_map( |square| *square ) {
+            match self.square_occupied(to) {
+                Some(occupancy) if occupancy.color != color => Self::push_with_promotions(
+                    ValidMove {
+                        piece: Piece::Pawn,
+                        color,
+                        from, to,
+                        takes: Some(occupancy.piece),
+                        takes_en_passant: false,
+                        promotion: None,
+                        en_passant_square: None
                     },
+                    moves
+                ),
 
-                    Some((to, Some(occupancy))) => Some(
-                        ValidMove {
-                            piece: Piece::Pawn,
-                            color,
-                            from, to,
-                            takes: Some(occupancy.piece),
-                            takes_en_passant: false,
-                            en_passant_square: None
-                        }
-                    ),
+                None if self.position.en_passant_square == Some(to) => moves.push(
+                    ValidMove {
+                        piece: Piece::Pawn,
+                        color,
+                        from, to,
+                        takes: Some(Piece::Pawn),
+                        takes_en_passant: true,
+                        promotion: None,
+                        en_passant_square: None
+                    }
+                ),
 
-                    Some(_) => None,
-                    None => None
-                }
-            );
+                _ => ()
+            }
+        }
+    }
 
-        forward_moves.into_iter()
-            .chain(take_moves)
-            .collect()
+    fn push_with_promotions(valid_move: ValidMove, moves: &mut Vec<ValidMove>) {
+        let last_rank = match valid_move.color {
+            Color::White => 7,
+            Color::Black => 0
+        };
+
+        if valid_move.to.rank != last_rank {
+            moves.push(valid_move);
+            return;
+        }
+
+        moves.extend(PROMOTION_PIECES.iter().map( |piece| ValidMove { promotion: Some(*piece), ..valid_move.clone() } ));
     }
 
-    fn possible_knight_moves(&self, from: Square, color: Color) -> Vec<ValidMove> {
+    fn push_knight_moves(&self, from: Square, color: Color, moves: &mut Vec<ValidMove>) {
         let reachable_squares = [
             Square::new(from.rank - 2, from.file - 1),
             Square::new(from.rank - 2, from.file + 1),
@@ -505,84 +1067,64 @@ impl Game {
             Square::new(from.rank + 1, from.file + 2),
         ];
 
-        reachable_squares.iter()
-            .filter_map( |square| *square )
-            .filter_map( |to| {
-                let occupancy = self.square_occupied(to);
-
-                match occupancy {
-                    Some(OccupiedSquare { piece: _, color: other_piece_color }) if other_piece_color != &color =>
-                        Some(ValidMove {
-                            piece: Piece::Knight,
-                            color,
-                            from,
-                            to,
-                            takes: occupancy.map( |occupancy| occupancy.piece ),
-                            takes_en_passant: false,
-                            en_passant_square: None
-                        }),
-
-                    None => Some(ValidMove {
-                        piece: Piece::Knight,
-                        color,
-                        from,
-                        to,
-                        takes: occupancy.map( |occupancy| occupancy.piece ),
-                        takes_en_passant: false,
-                        en_passant_square: None
-                    }),
-
-                    _ => None,
-                }
-            })
-            .collect()
+        self.push_steps(Piece::Knight, from, color, &reachable_squares, moves);
     }
 
-    fn possible_rook_moves(&self, from: Square, color: Color) -> Vec<ValidMove> {
-        let lines = [
-            self.squares_in_a_line(from, -1, 0),
-            self.squares_in_a_line(from, 1, 0),
-            self.squares_in_a_line(from, 0, -1),
-            self.squares_in_a_line(from, 0, 1),
-        ];
+    // Moves to each of the squares which isn't taken by a piece of the same color
+    fn push_steps(&self, piece: Piece, from: Square, color: Color, squares: &[Option<Square>], moves: &mut Vec<ValidMove>) {
+        for to in squares.iter().filter_map( |square| *square ) {
+            let occupancy = self.square_occupied(to);
 
-        lines.iter()
-            .flat_map( |line| self.valid_moves_in_a_line(line, Piece::Rook, from, color) )
-            .collect()
+            match occupancy {
+                Some(OccupiedSquare { color: piece_color, .. }) if *piece_color == color => (),
+
+                _ => moves.push(ValidMove {
+                    piece,
+                    color,
+                    from,
+                    to,
+                    takes: occupancy.map( |occupancy| occupancy.piece ),
+                    takes_en_passant: false,
+                    promotion: None,
+                    en_passant_square: None
+                })
+            }
+        }
     }
 
-    fn possible_queen_moves(&self, from: Square, color: Color) -> Vec<ValidMove> {
-        let lines = [
-            self.squares_in_a_line(from, -1, 0),
-            self.squares_in_a_line(from, 1, 0),
-            self.squares_in_a_line(from, 0, -1),
-            self.squares_in_a_line(from, 0, 1),
+    // Slides along each direction up to the first piece, which it takes if it is the other color's
+    fn push_line_moves(&self, piece: Piece, from: Square, color: Color, directions: &[(i8, i8)], moves: &mut Vec<ValidMove>) {
+        for (rank_delta, file_delta) in directions {
+            let mut next_square = Self::advance_square(from, *rank_delta, *file_delta);
 
-            self.squares_in_a_line(from, -1, -1),
-            self.squares_in_a_line(from, 1, -1),
-            self.squares_in_a_line(from, 1, 1),
-            self.squares_in_a_line(from, -1, 1),
-        ];
+            while let Some(to) = next_square {
+                let occupancy = self.square_occupied(to);
 
-        lines.iter()
-            .flat_map( |line| self.valid_moves_in_a_line(line, Piece::Queen, from, color) )
-            .collect()
-    }
+                if occupancy.is_some_and( |occupancy| occupancy.color == color ) {
+                    break;
+                }
 
-    fn possible_bishop_moves(&self, from: Square, color: Color) -> Vec<ValidMove> {
-        let lines = [
-            self.squares_in_a_line(from, -1, -1),
-            self.squares_in_a_line(from, 1, -1),
-            self.squares_in_a_line(from, 1, 1),
-            self.squares_in_a_line(from, -1, 1),
-        ];
+                moves.push(ValidMove {
+                    piece,
+                    color,
+                    from,
+                    to,
+                    takes: occupancy.map( |occupancy| occupancy.piece ),
+                    takes_en_passant: false,
+                    promotion: None,
+                    en_passant_square: None
+                });
 
-        lines.iter()
-            .flat_map( |line| self.valid_moves_in_a_line(line, Piece::Bishop, from, color) )
-            .collect()
+                if occupancy.is_some() {
+                    break;
+                }
+
+                next_square = Self::advance_square(to, *rank_delta, *file_delta);
+            }
+        }
     }
 
-    fn possible_king_moves(&self, from: Square, color: Color) -> Vec<ValidMove> {
+    fn push_king_moves(&self, from: Square, color: Color, moves: &mut Vec<ValidMove>) {
         let adjacent_squares = [
             Square::new(from.rank - 1, from.file - 1),
             Square::new(from.rank - 1, from.file    ),
@@ -596,100 +1138,187 @@ impl Game {
             Square::new(from.rank + 1, from.file + 1),
         ];
 
-        adjacent_squares.iter().filter_map( |to| *to ).filter_map( |to| {
-            let occupancy = self.square_occupied(to);
+        self.push_steps(Piece::King, from, color, &adjacent_squares, moves);
+        self.push_castling_moves(from, color, moves);
+    }
 
-            match occupancy {
-                Some(OccupiedSquare { piece: _, color: piece_color }) if color != *piece_color => {
-                    Some(ValidMove {
-                        piece: Piece::King,
-                        color,
-                        from,
-                        to,
-                        takes: occupancy.map( |occupancy| occupancy.piece ),
-                        takes_en_passant: false,
-                        en_passant_square: None
-                    })
-                },
+    // The king and the rook must not have moved, which the castling rights keep track of, the squares between
+    // them must be empty and the king can't castle out of, through or into check
+    fn push_castling_moves(&self, from: Square, color: Color, moves: &mut Vec<ValidMove>) {
+        let (home_rank, king_side, queen_side) = match color {
+            Color::White => (0, self.position.white_can_castle_king_side, self.position.white_can_castle_queen_side),
+            Color::Black => (7, self.position.black_can_castle_king_side, self.position.black_can_castle_queen_side)
+        };
 
-                Some(_) => None,
+        if !(king_side || queen_side) || from != (Square { rank: home_rank, file: 4 }) {
+            return;
+        }
 
-                None => Some(ValidMove {
-                    piece: Piece::King,
-                    color,
-                    from,
-                    to,
-                    takes: occupancy.map( |occupancy| occupancy.piece ),
-                    takes_en_passant: false,
-                    en_passant_square: None
-                })
-            }
-        }).collect()
+        let rook = OccupiedSquare { piece: Piece::Rook, color };
+
+        // The rook's file, the files which have to be empty and the ones the king passes, ending with its own
+        let sides: [(bool, i8, &[i8], &[i8]); 2] = [
+            (king_side, 7, &[5, 6], &[5, 6]),
+            (queen_side, 0, &[1, 2, 3], &[3, 2])
+        ];
+
+        let castling_moves = sides.iter()
+            .filter( |(can_castle, rook_file, empty, passed)| {
+                *can_castle &&
+                    self.square_occupied(Square { rank: home_rank, file: *rook_file }) == Some(&rook) &&
+                    empty.iter().all( |file| self.square_occupied(Square { rank: home_rank, file: *file }).is_none() ) &&
+                    !self.square_attacked(from, color.opposite()) &&
+                    !passed.iter().any( |file| self.square_attacked(Square { rank: home_rank, file: *file }, color.opposite()) )
+            })
+            .map( |(_, _, _, passed)| ValidMove {
+                piece: Piece::King,
+                color,
+                from,
+                to: Square { rank: home_rank, file: passed[passed.len() - 1] },
+                takes: None,
+                takes_en_passant: false,
+                promotion: None,
+                en_passant_square: None
+            });
+
+        moves.extend(castling_moves);
     }
 
-    fn valid_moves_in_a_line(&self, line: &[Square], piece: Piece, from: Square, color: Color) -> Vec<ValidMove> {
-        let mut valid_moves = Vec::new();
+    fn squares_in_a_line(&self, from: Square, rank_delta: i8, file_delta: i8) -> Vec<Square> {
+        ray(from, (rank_delta, file_delta))
+    }
 
-        for &to in line {
-            let occupancy = self.square_occupied(to);
+    fn advance_square(square: Square, rank_delta: i8, file_delta: i8) -> Option<Square> {
+        Square::new(square.rank + rank_delta, square.file + file_delta)
+    }
+}
 
-            match occupancy {
-                Some(OccupiedSquare { piece: _, color: piece_color }) if color != *piece_color => {
-                    valid_moves.push(ValidMove {
-                        piece,
-                        color,
-                        from,
-                        to,
-                        takes: occupancy.map( |occupancy| occupancy.piece ),
-                        takes_en_passant: false,
-                        en_passant_square: None
-                    });
-                    break
-                },
+impl Replay {
+    fn failed(error: ReplayError) -> Self {
+        Replay {
+            game: Game::new(Game::standard_position()),
+            half_moves: Vec::new().into_iter(),
+            strictness: NotationStrictness::Strict,
+            letters: PieceLetters::English,
+            notation: MoveNotation::San,
+            error: Some(error)
+        }
+    }
 
-                Some(_) => break,
+    pub fn with_strictness(mut self, strictness: NotationStrictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
 
-                None => valid_moves.push(ValidMove {
-                    piece,
-                    color,
-                    from,
-                    to,
-                    takes: occupancy.map( |occupancy| occupancy.piece ),
-                    takes_en_passant: false,
-                    en_passant_square: None
-                })
+    // For games written with non-English piece letters
+    pub fn with_piece_letters(mut self, letters: PieceLetters) -> Self {
+        self.letters = letters;
+        self
+    }
+
+    // For games written in another notation than SAN, e.g. descriptive notation
+    pub fn with_notation(mut self, notation: MoveNotation) -> Self {
+        self.notation = notation;
+        self
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+}
+
+impl Iterator for Replay {
+    type Item = Result<(ValidMove, Game), ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            self.half_moves = Vec::new().into_iter();
+
+            return Some(Err(error));
+        }
+
+        let (number, notation) = self.half_moves.next()?;
+
+        let options = MoveOptions { strictness: self.strictness, letters: self.letters, ..MoveOptions::default() };
+
+        let parsed = match self.notation {
+            MoveNotation::San => ValidMove::from_notation_with_options(&self.game, &notation, options),
+            other => ValidMove::from_notation_in(&self.game, &notation, other)
+        };
+
+        match parsed {
+            Ok(valid_move) => {
+                self.game = self.game.make_valid_move(&valid_move);
+
+                Some(Ok((valid_move, self.game.clone())))
+            },
+
+            Err(error) => {
+                self.half_moves = Vec::new().into_iter();
+
+                Some(Err(ReplayError::InvalidMove { number, notation, error }))
             }
         }
+    }
+}
 
-        valid_moves
+impl ValidMove {
+    pub fn kind(&self) -> MoveKind {
+        if self.is_promotion() {
+            MoveKind::Promotion
+        } else if self.is_castle() {
+            MoveKind::Castle
+        } else if self.takes_en_passant {
+            MoveKind::EnPassant
+        } else if self.is_capture() {
+            MoveKind::Capture
+        } else {
+            MoveKind::Normal
+        }
     }
 
-    fn squares_in_a_line(&self, from: Square, rank_delta: i8, file_delta: i8) -> Vec<Square> {
-        let mut squares = Vec::new();
-        let mut current_square = Self::advance_square(from, rank_delta, file_delta);
+    pub fn is_capture(&self) -> bool {
+        self.takes.is_some()
+    }
 
-        loop {
-            if let Some(square) = current_square {
-                squares.push(square);
-                current_square = Self::advance_square(square, rank_delta, file_delta);
-            } else {
-                break;
-            }
+    pub fn is_en_passant(&self) -> bool {
+        self.takes_en_passant
+    }
+
+    // The king is the only piece that can move two files at once
+    pub fn is_castle(&self) -> bool {
+        self.piece == Piece::King && (self.to.file - self.from.file).abs() == 2
+    }
+
+    // Where the rook goes from and to when castling, e.g. for animating the move
+    pub fn castling_rook_move(&self) -> Option<(Square, Square)> {
+        if !self.is_castle() {
+            return None;
         }
 
-        squares
+        let (from_file, to_file) = if self.to.file > self.from.file { (7, 5) } else { (0, 3) };
+
+        Some((Square { rank: self.from.rank, file: from_file }, Square { rank: self.from.rank, file: to_file }))
     }
 
-    fn advance_square(square: Square, rank_delta: i8, file_delta: i8) -> Option<Square> {
-        Square::new(square.rank + rank_delta, square.file + file_delta)
+    fn castling_notation(&self) -> &'static str {
+        if self.to.file > self.from.file { "O-O" } else { "O-O-O" }
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        self.promotion.is_some()
     }
-}
 
-impl ValidMove {
     pub fn notation(&self) -> String {
         // TODO: Disambiguation square
-        // TODO: Promotion
-        // TODO: Castling
+
+        if self.is_castle() {
+            return String::from(self.castling_notation());
+        }
 
         let piece = match self.piece {
             Piece::Pawn   => "",
@@ -709,35 +1338,160 @@ impl ValidMove {
         let to_square = self.to.to_notation(SquareNotationOptions::FileAndRank);
 
         format!(
-            "{}{}{}{}",
+            "{}{}{}{}{}",
             piece,
             disambiguation,
             takes,
-            to_square
+            to_square,
+            self.promotion_suffix()
         )
     }
 
-    pub fn from_notation(game: &Game, notation: &str) -> Result<ValidMove, ()> {
+    fn promotion_suffix(&self) -> String {
+        match self.promotion {
+            Some(piece) => format!("={}", piece.letter()),
+            None => String::new()
+        }
+    }
+
+    // Long algebraic notation as used by UCI engines, e.g. "e2e4" or "e7e8q"
+    pub fn uci(&self) -> String {
+        let promotion = match self.promotion {
+            Some(piece) => piece.letter().to_ascii_lowercase().to_string(),
+            None => String::new()
+        };
+
+        format!(
+            "{}{}{}",
+            self.from.to_notation(SquareNotationOptions::FileAndRank),
+            self.to.to_notation(SquareNotationOptions::FileAndRank),
+            promotion
+        )
+    }
+
+    pub fn from_uci(game: &Game, notation: &str) -> Result<ValidMove, InvalidMoveError> {
+        if !notation.is_ascii() || !(4..=5).contains(&notation.len()) {
+            return Err(InvalidMoveError::InvalidNotation);
+        }
+
+        let from = Square::from_notation(&notation[0..2]).map_err( |_| InvalidMoveError::InvalidNotation )?;
+        let to = Square::from_notation(&notation[2..4]).map_err( |_| InvalidMoveError::InvalidNotation )?;
+
+        let promotion = match &notation[4..] {
+            ""  => None,
+            "q" => Some(Piece::Queen),
+            "r" => Some(Piece::Rook),
+            "b" => Some(Piece::Bishop),
+            "n" => Some(Piece::Knight),
+            _ => return Err(InvalidMoveError::InvalidNotation)
+        };
+
+        game.try_move(from, to, promotion).map_err( |_| InvalidMoveError::NoMatchingMove )
+    }
+
+    // Packed the way engines store moves: bits 0-5 are the target square and bits 6-11 the source square,
+    // both counted from a1 = 0 to h8 = 63, and bits 12-14 are the promotion piece (1 knight to 4 queen)
+    pub fn to_u16(&self) -> u16 {
+        let square_index = |square: Square| square.to_a1_index() as u16;
+
+        let promotion = match self.promotion {
+            Some(Piece::Knight) => 1,
+            Some(Piece::Bishop) => 2,
+            Some(Piece::Rook)   => 3,
+            Some(Piece::Queen)  => 4,
+            _ => 0
+        };
+
+        square_index(self.to) | square_index(self.from) << 6 | promotion << 12
+    }
+
+    pub fn from_u16(game: &Game, packed: u16) -> Result<ValidMove, InvalidMoveError> {
+        let square = |index: u16| Square::from_a1_index(index as usize).unwrap();
+
+        let promotion = match packed >> 12 {
+            0 => None,
+            1 => Some(Piece::Knight),
+            2 => Some(Piece::Bishop),
+            3 => Some(Piece::Rook),
+            4 => Some(Piece::Queen),
+            _ => return Err(InvalidMoveError::InvalidNotation)
+        };
+
+        game.try_move(square(packed >> 6 & 63), square(packed & 63), promotion).map_err( |_| InvalidMoveError::NoMatchingMove )
+    }
+
+    pub fn from_notation(game: &Game, notation: &str) -> Result<ValidMove, InvalidMoveError> {
+        Self::from_notation_with(game, notation, NotationStrictness::Strict)
+    }
+
+    pub fn from_notation_with(game: &Game, notation: &str, strictness: NotationStrictness) -> Result<ValidMove, InvalidMoveError> {
+        Self::from_notation_with_options(game, notation, MoveOptions { strictness, ..MoveOptions::default() })
+    }
+
+    pub fn from_notation_with_options(game: &Game, notation: &str, options: MoveOptions) -> Result<ValidMove, InvalidMoveError> {
+        let strictness = options.strictness;
+        let notation = options.letters.to_english(notation);
+        let notation = notation.as_str();
+
         lazy_static! {
             static ref NOTATION_REGEX: regex::Regex =
-                Regex::new(r"^((?P<piece>[PNBRQK])?(?P<from>[a-h]?[1-8]?)(?P<takes>x)?(?P<to>[a-h][1-8])(=(?P<promotion>[PNBRQK]))?)|(?P<castles>O\-O(\-O))(?P<check_or_mate>[#\+])?$")
+                Regex::new(r"^((?P<piece>[PNBRQK])?(?P<from>[a-h]?[1-8]?)(?P<takes>x)?(?P<to>[a-h][1-8])(=(?P<promotion>[PNBRQK]))?|(?P<castles>O-O(-O)?|0-0(-0)?)(?P<check_or_mate>[#\+])?)")
                     .expect("Invalid regular expression");
         }
 
-        let chars: Vec<char> = notation.chars().collect();
-        let matches = NOTATION_REGEX.captures(notation).ok_or(())?;
+        let matches = NOTATION_REGEX.captures(notation).ok_or(InvalidMoveError::InvalidNotation)?;
+
+        // Castling is found among the legal moves by its direction, the king's squares aren't written
+        let castles = matches.name("castles").and_then( |m|
+            match m.as_str() {
+                "O-O"   | "0-0"   => Some(CastlesDirection::KingSide),
+                "O-O-O" | "0-0-0" => Some(CastlesDirection::QueenSide),
+                _                 => None
+            }
+        );
+
+        if let Some(castles) = castles {
+            let king_side = castles == CastlesDirection::KingSide;
+
+            return game.valid_moves().into_iter()
+                .find( |valid_move| valid_move.is_castle() && (valid_move.to.file > valid_move.from.file) == king_side )
+                .ok_or(InvalidMoveError::NoMatchingMove);
+        }
 
         let piece = matches.name("piece")
             .map( |m| m.as_str() )
             .and_then( |piece| Self::parse_piece_letter(piece) );
 
-        // let from_square = matches.name("from").map( |m| m.as_str() );
-        let takes = matches.name("takes").filter( |m| m.as_str().len() > 0 ) != None;
+        let from = matches.name("from")
+            .map( |m| m.as_str() )
+            .filter( |from| !from.is_empty() )
+            .map( |from| {
+                let mut partial_square = PartialSquare { rank: None, file: None };
+
+                for c in from.chars() {
+                    match c {
+                        'a'..='h' => partial_square.file = Some((c as u8 - b'a') as i8),
+                        _         => partial_square.rank = Some((c as u8 - b'1') as i8)
+                    }
+                }
+
+                partial_square
+            });
 
-        let to = matches.name("to").ok_or(())?;
-        let to = Square::from_notation(to.as_str())?;
+        let takes = matches.name("takes").filter( |m| !m.as_str().is_empty() ).is_some();
 
-        let promotion_piece = matches.name("promotion").and_then( |m| Self::parse_piece_letter(m.as_str()) );
+        let to = matches.name("to").ok_or(InvalidMoveError::InvalidNotation)?;
+        let to = Square::from_notation(to.as_str()).map_err( |_| InvalidMoveError::InvalidNotation )?;
+
+        let promotion = matches.name("promotion").and_then( |m| Self::parse_piece_letter(m.as_str()) );
+
+        // Pawns can only reach the first or last rank by promoting
+        let promotion = match options.promotion {
+            PromotionPolicy::AutoQueen if promotion.is_none() && piece.unwrap_or(Piece::Pawn) == Piece::Pawn && (to.rank == 0 || to.rank == 7) => {
+                Some(Piece::Queen)
+            },
+            _ => promotion
+        };
         let check_or_mate   = matches.name("check_or_mate").and_then( |m|
             match m.as_str() {
                 "#" => Some(CheckOrMate::Mate),
@@ -745,34 +1499,37 @@ impl ValidMove {
                 _   => None
             }
         );
-        let castles = matches.name("castles").and_then( |m|
-            match m.as_str() {
-                "O-O"   => Some(CastlesDirection::KingSide),
-                "O-O-O" => Some(CastlesDirection::QueenSide),
-                _       => None
-            }
-        );
-
         let mut valid_moves = game.find_moves(PartialMove {
-            piece: match piece {
-                Some(piece) => piece,
-                None => Piece::Pawn
-            },
+            piece: piece.unwrap_or(Piece::Pawn),
 
-            // TODO
-            from: None,
+            from,
             to,
 
-            castles: Some(castles),
+            promotion: Some(promotion),
+            castles: Some(None),
             check_or_mate: Some(check_or_mate),
 
-            takes: Some(takes)
+            takes: match strictness {
+                NotationStrictness::Strict  => Some(takes),
+                NotationStrictness::Lenient => None
+            }
         });
 
-        if valid_moves.len() == 1 {
-            Ok(valid_moves.pop().unwrap())
-        } else {
-            Err(())
+        if strictness == NotationStrictness::Lenient && valid_moves.len() > 1 {
+            let (mut matching_takes, others): (Vec<ValidMove>, Vec<ValidMove>) = valid_moves.into_iter()
+                .partition( |valid_move| valid_move.takes.is_some() == takes );
+
+            if matching_takes.len() == 1 {
+                return Ok(matching_takes.pop().unwrap());
+            }
+
+            valid_moves = matching_takes.into_iter().chain(others).collect();
+        }
+
+        match valid_moves.len() {
+            0 => Err(InvalidMoveError::NoMatchingMove),
+            1 => Ok(valid_moves.pop().unwrap()),
+            _ => Err(InvalidMoveError::AmbiguousMove(valid_moves))
         }
     }
 
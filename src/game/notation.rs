@@ -0,0 +1,141 @@
+use super::*;
+
+// The ways a single move can be written down
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MoveNotation {
+    San,
+
+    // Long algebraic notation as used by UCI engines, e.g. "e2e4"
+    Uci,
+
+    // English descriptive notation, e.g. "P-K4", found in older books
+    Descriptive,
+
+    // ICCF numeric notation used in correspondence chess, e.g. "5254" for e2-e4 and "17182" for a promotion to a rook
+    Iccf,
+
+    // Smith notation, e.g. "e4d5p" for a capture of a pawn or "e1g1c" for castling king side
+    Smith
+}
+
+impl ValidMove {
+    pub fn from_notation_in(game: &Game, notation: &str, style: MoveNotation) -> Result<ValidMove, InvalidMoveError> {
+        match style {
+            MoveNotation::San         => ValidMove::from_notation(game, notation),
+            MoveNotation::Uci         => ValidMove::from_uci(game, notation),
+            MoveNotation::Descriptive => ValidMove::from_descriptive(game, notation),
+            MoveNotation::Iccf        => ValidMove::from_iccf(game, notation),
+            MoveNotation::Smith       => ValidMove::from_smith(game, notation)
+        }
+    }
+
+    // The game is the one before the move
+    pub fn to_notation_in(&self, game: &Game, style: MoveNotation) -> String {
+        match style {
+            MoveNotation::San         => game.san(self),
+            MoveNotation::Uci         => self.uci(),
+            MoveNotation::Descriptive => self.descriptive(game),
+            MoveNotation::Iccf        => self.iccf(),
+            MoveNotation::Smith       => self.smith()
+        }
+    }
+
+    // Files and ranks as digits from 1 to 8, followed by 1 to 4 for a promotion to a queen, rook, bishop or knight.
+    // Castling is written as the king's move.
+    pub fn iccf(&self) -> String {
+        let square = |square: Square| format!("{}{}", square.file + 1, square.rank + 1);
+
+        let promotion = match self.promotion {
+            Some(Piece::Queen)  => "1",
+            Some(Piece::Rook)   => "2",
+            Some(Piece::Bishop) => "3",
+            Some(Piece::Knight) => "4",
+            _ => ""
+        };
+
+        format!("{}{}{}", square(self.from), square(self.to), promotion)
+    }
+
+    pub fn from_iccf(game: &Game, notation: &str) -> Result<ValidMove, InvalidMoveError> {
+        let digits: Vec<i8> = notation.trim().chars()
+            .map( |c| c.to_digit(10).map( |digit| digit as i8 ) )
+            .collect::<Option<Vec<i8>>>()
+            .ok_or(InvalidMoveError::InvalidNotation)?;
+
+        if !(4..=5).contains(&digits.len()) || digits[..4].iter().any( |digit| !(1..=8).contains(digit) ) {
+            return Err(InvalidMoveError::InvalidNotation);
+        }
+
+        let promotion = match digits.get(4) {
+            None    => None,
+            Some(1) => Some(Piece::Queen),
+            Some(2) => Some(Piece::Rook),
+            Some(3) => Some(Piece::Bishop),
+            Some(4) => Some(Piece::Knight),
+            _ => return Err(InvalidMoveError::InvalidNotation)
+        };
+
+        let from = Square { file: digits[0] - 1, rank: digits[1] - 1 };
+        let to = Square { file: digits[2] - 1, rank: digits[3] - 1 };
+
+        game.try_move(from, to, promotion).map_err( |_| InvalidMoveError::NoMatchingMove )
+    }
+
+    // Both squares, then the lowercase letter of the taken piece ("E" for en passant), "c" or "C" for castling
+    // king or queen side and the uppercase letter of the promotion piece
+    pub fn smith(&self) -> String {
+        let taken = if self.takes_en_passant {
+            String::from("E")
+        } else if self.is_castle() {
+            String::from(if self.to.file > self.from.file { "c" } else { "C" })
+        } else {
+            self.takes.map( |taken| taken.letter().to_ascii_lowercase().to_string() ).unwrap_or_default()
+        };
+
+        let promotion = self.promotion.map( |piece| piece.letter().to_string() ).unwrap_or_default();
+
+        format!(
+            "{}{}{}{}",
+            self.from.to_notation(SquareNotationOptions::FileAndRank),
+            self.to.to_notation(SquareNotationOptions::FileAndRank),
+            taken,
+            promotion
+        )
+    }
+
+    // The capture and castling letters are checked when present
+    pub fn from_smith(game: &Game, notation: &str) -> Result<ValidMove, InvalidMoveError> {
+        let notation = notation.trim();
+
+        if !notation.is_ascii() || !(4..=6).contains(&notation.len()) {
+            return Err(InvalidMoveError::InvalidNotation);
+        }
+
+        let from = Square::from_notation(&notation[0..2]).map_err( |_| InvalidMoveError::InvalidNotation )?;
+        let to = Square::from_notation(&notation[2..4]).map_err( |_| InvalidMoveError::InvalidNotation )?;
+
+        let mut taken = None;
+        let mut castles = None;
+        let mut en_passant = false;
+        let mut promotion = None;
+
+        for c in notation[4..].chars() {
+            match c {
+                'E' => en_passant = true,
+                'c' => castles = Some(true),
+                'C' => castles = Some(false),
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' => taken = Self::parse_piece_letter(&c.to_string()),
+                'N' | 'B' | 'R' | 'Q' => promotion = Self::parse_piece_letter(&c.to_string()),
+                _ => return Err(InvalidMoveError::InvalidNotation)
+            }
+        }
+
+        let valid_move = game.try_move(from, to, promotion).map_err( |_| InvalidMoveError::NoMatchingMove )?;
+
+        let matches = (taken.is_none() || valid_move.takes == taken) &&
+            (!en_passant || valid_move.takes_en_passant) &&
+            castles.is_none_or( |king_side| valid_move.is_castle() && (valid_move.to.file > valid_move.from.file) == king_side );
+
+        if matches { Ok(valid_move) } else { Err(InvalidMoveError::NoMatchingMove) }
+    }
+}
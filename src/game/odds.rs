@@ -0,0 +1,86 @@
+use super::*;
+
+// The classic handicaps of a stronger player, who gives up material from the starting position
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Odds {
+    // Without the f-pawn, and the opponent moves first whatever the colors
+    PawnAndMove,
+
+    // Without the f-pawn
+    Pawn,
+
+    // Without the queen's knight
+    Knight,
+
+    // Without the queen's rook, and so without queen side castling
+    Rook,
+    Queen
+}
+
+impl Odds {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Odds::PawnAndMove => "pawn and move",
+            Odds::Pawn        => "pawn",
+            Odds::Knight      => "knight",
+            Odds::Rook        => "rook",
+            Odds::Queen       => "queen"
+        }
+    }
+
+    // There is no standard tag for handicaps, games get e.g. [Handicap "knight odds by White"]. The position
+    // itself is recorded by SetUp and FEN, which Game::to_pgn adds for any game not starting from the
+    // standard position.
+    pub fn tags(&self, giver: Color) -> Vec<(String, String)> {
+        let giver = match giver {
+            Color::White => "White",
+            Color::Black => "Black"
+        };
+
+        vec![(String::from("Handicap"), format!("{} odds by {}", self.name(), giver))]
+    }
+
+    // The file of the piece which is taken off the board
+    fn removed(&self) -> (Piece, i8) {
+        match self {
+            Odds::PawnAndMove | Odds::Pawn => (Piece::Pawn, 5),
+            Odds::Knight                   => (Piece::Knight, 1),
+            Odds::Rook                     => (Piece::Rook, 0),
+            Odds::Queen                    => (Piece::Queen, 3)
+        }
+    }
+}
+
+impl Game {
+    // The standard position with the giver's piece taken off the board
+    pub fn odds_position(odds: Odds, giver: Color) -> Position {
+        let mut position = Game::standard_position();
+        let (piece, file) = odds.removed();
+
+        let rank = match (piece, giver) {
+            (Piece::Pawn, Color::White) => 1,
+            (Piece::Pawn, Color::Black) => 6,
+            (_, Color::White)           => 0,
+            (_, Color::Black)           => 7
+        };
+
+        position.board.squares[Board::index(Square { rank, file })] = None;
+
+        if odds == Odds::Rook {
+            match giver {
+                Color::White => position.white_can_castle_queen_side = false,
+                Color::Black => position.black_can_castle_queen_side = false
+            }
+        }
+
+        if odds == Odds::PawnAndMove {
+            position.next_to_move = giver.opposite();
+        }
+
+        position
+    }
+
+    pub fn new_with_odds(odds: Odds, giver: Color) -> Self {
+        Game::new(Game::odds_position(odds, giver))
+    }
+}
@@ -0,0 +1,266 @@
+use super::*;
+use std::time::Duration;
+
+// The PGN export format asks for lines of at most 80 characters
+const PGN_LINE_WIDTH: usize = 80;
+
+static SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// Which tags a PGN writer profile emits
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TagSelection {
+    All,
+
+    // The seven tag roster, and SetUp and FEN for games which don't start from the standard position
+    Roster,
+
+    // In the given order, whichever of them the game has
+    Listed(Vec<String>),
+    Omitted
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ResultPlacement {
+    TagAndMoveText,
+    TagOnly,
+    MoveTextOnly
+}
+
+// How the PGN of a game is written, so that the output matches what the program reading it expects.
+// There is no ChessBase profile: ChessBase reads the export format, and matching what it writes itself would
+// need tags and annotations the games don't keep, such as PlyCount, EventDate and [%emt] move times.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PgnProfile {
+    pub tags: TagSelection,
+    pub comments: bool,
+
+    // Written as [%clk h:mm:ss] inside the comment of the move
+    pub clocks: bool,
+
+    // None keeps the move text on a single line
+    pub line_width: Option<usize>,
+    pub result: ResultPlacement
+}
+
+// What gets written after a move besides its SAN
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MoveAnnotation {
+    pub comment: Option<String>,
+
+    // Time left on the clock of the player after the move
+    pub clock: Option<Duration>,
+
+    // Centipawns from White's point of view, written as [%eval 0.35] with the comments
+    pub eval: Option<i32>
+}
+
+impl PgnProfile {
+    // The PGN export format: all tags with the seven tag roster first, comments and lines of at most 80 characters
+    pub fn export() -> Self {
+        PgnProfile {
+            tags: TagSelection::All,
+            comments: true,
+            clocks: true,
+            line_width: Some(PGN_LINE_WIDTH),
+            result: ResultPlacement::TagAndMoveText
+        }
+    }
+
+    // The reduced export format for archives, only the seven tag roster and the moves
+    pub fn reduced() -> Self {
+        PgnProfile { tags: TagSelection::Roster, comments: false, clocks: false, ..Self::export() }
+    }
+
+    // As lichess exports and imports games, with the clocks and the whole move text on one line
+    pub fn lichess() -> Self {
+        PgnProfile { line_width: None, ..Self::export() }
+    }
+
+    // Just the moves and the result on a single line, e.g. for logs and chat messages
+    pub fn compact() -> Self {
+        PgnProfile {
+            tags: TagSelection::Omitted,
+            comments: false,
+            clocks: false,
+            line_width: None,
+            result: ResultPlacement::MoveTextOnly
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "export"  => Some(Self::export()),
+            "reduced" => Some(Self::reduced()),
+            "lichess" => Some(Self::lichess()),
+            "compact" => Some(Self::compact()),
+            _ => None
+        }
+    }
+}
+
+impl Default for PgnProfile {
+    fn default() -> Self {
+        Self::export()
+    }
+}
+
+impl Game {
+    // The seven tag roster (unknown values as "?"), the starting position if it isn't the standard one and the
+    // Termination tag, followed by the moves and the result
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_tags(&[])
+    }
+
+    // Tags of the seven tag roster (except Result) replace the default values, others are added after them
+    pub fn to_pgn_with_tags(&self, tags: &[(String, String)]) -> String {
+        self.to_pgn_with_profile(tags, &[], &PgnProfile::export())
+    }
+
+    // Annotations are given by half-move, starting with the first move of the game. Moves without one are
+    // annotated from their metadata.
+    pub fn to_pgn_with_profile(&self, tags: &[(String, String)], annotations: &[MoveAnnotation], profile: &PgnProfile) -> String {
+        let result = self.result().to_string();
+
+        let mut all_tags = vec![
+            (String::from("Event"), String::from("?")),
+            (String::from("Site"), String::from("?")),
+            (String::from("Date"), String::from("????.??.??")),
+            (String::from("Round"), String::from("?")),
+            (String::from("White"), String::from("?")),
+            (String::from("Black"), String::from("?")),
+            (String::from("Result"), String::from(result))
+        ];
+
+        for (name, value) in tags {
+            match all_tags.iter_mut().find( |(existing, _)| existing == name ) {
+                Some(tag) if name != "Result" => tag.1 = value.clone(),
+                Some(_) => (),
+                None => all_tags.push((name.clone(), value.clone()))
+            }
+        }
+
+        if *self.initial_position != Game::standard_position() {
+            all_tags.push((String::from("SetUp"), String::from("1")));
+            all_tags.push((String::from("FEN"), self.initial_position.to_fen()));
+        }
+
+        all_tags.push((String::from("Termination"), String::from(self.termination())));
+
+        let selected_tags: Vec<(String, String)> = match &profile.tags {
+            TagSelection::All => all_tags,
+            TagSelection::Roster => all_tags.into_iter()
+                .filter( |(name, _)| SEVEN_TAG_ROSTER.contains(&name.as_str()) || name == "SetUp" || name == "FEN" )
+                .collect(),
+            TagSelection::Listed(names) => names.iter()
+                .filter_map( |name| all_tags.iter().find( |(existing, _)| existing == name ).cloned() )
+                .collect(),
+            TagSelection::Omitted => Vec::new()
+        };
+
+        let selected_tags: Vec<(String, String)> = match profile.result {
+            ResultPlacement::MoveTextOnly => selected_tags.into_iter().filter( |(name, _)| name != "Result" ).collect(),
+            _ => selected_tags
+        };
+
+        let mut pgn: String = selected_tags.iter()
+            .map( |(name, value)| format!("[{} \"{}\"]\n", name, escape_tag_value(value)) )
+            .collect();
+
+        let mut tokens = self.movetext_tokens(0, annotations, profile);
+
+        if profile.result != ResultPlacement::TagOnly {
+            tokens.push(String::from(result));
+        }
+
+        if !pgn.is_empty() {
+            pgn.push('\n');
+        }
+
+        pgn.push_str(&wrap(&tokens, profile.line_width.unwrap_or(usize::MAX)));
+        pgn.push('\n');
+
+        pgn
+    }
+
+    // Numbered SAN of the moves from the given half-move on, which can be appended to the move text of the
+    // moves before it, e.g. "2... Nc6 3. Bb5"
+    pub fn movetext_since(&self, ply: usize) -> String {
+        self.movetext_tokens(ply, &[], &PgnProfile::compact()).join(" ")
+    }
+
+    fn movetext_tokens(&self, from_ply: usize, annotations: &[MoveAnnotation], profile: &PgnProfile) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut after_comment = false;
+
+        let metadata = self.move_metadata();
+
+        for (i, (game, valid_move)) in self.history().iter().enumerate().skip(from_ply) {
+            let number = game.position.full_move_counter;
+
+            match valid_move.color {
+                Color::White => tokens.push(format!("{}.", number)),
+                Color::Black if i == from_ply || after_comment => tokens.push(format!("{}...", number)),
+                Color::Black => ()
+            }
+
+            tokens.push(game.san(valid_move));
+
+            let annotation = annotations.get(i).cloned().unwrap_or_else( || metadata[i].annotation() );
+            let mut comment: Vec<String> = Vec::new();
+
+            if let Some(text) = annotation.comment.filter( |_| profile.comments ) {
+                comment.push(text);
+            }
+
+            if let Some(eval) = annotation.eval.filter( |_| profile.comments ) {
+                comment.push(format!("[%eval {:.2}]", eval as f64 / 100.0));
+            }
+
+            if let Some(clock) = annotation.clock.filter( |_| profile.clocks ) {
+                comment.push(format!("[%clk {}]", format_clock(clock)));
+            }
+
+            after_comment = !comment.is_empty();
+
+            if after_comment {
+                tokens.push(format!("{{{}}}", comment.join(" ").replace('}', ")")));
+            }
+        }
+
+        tokens
+    }
+}
+
+// Tenths of a second are only written if there are any, e.g. for engine games with a short time control
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let tenths = clock.subsec_millis() / 100;
+
+    let formatted = format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if tenths == 0 { formatted } else { format!("{}.{}", formatted, tenths) }
+}
+
+fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn wrap(tokens: &[String], width: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        line.push_str(token);
+    }
+
+    lines.push(line);
+    lines.join("\n")
+}
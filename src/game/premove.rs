@@ -0,0 +1,59 @@
+use super::*;
+
+impl Game {
+    // Where the piece on the square can legally move to, for the player it belongs to. Promotions to different
+    // pieces count as one destination.
+    pub fn destinations_from(&self, square: Square) -> Vec<Square> {
+        let color = match self.square_occupied(square) {
+            Some(occupancy) => occupancy.color,
+            None => return Vec::new()
+        };
+
+        let mut destinations: Vec<Square> = self.valid_moves_for(color).into_iter()
+            .filter( |valid_move| valid_move.from == square )
+            .map( |valid_move| valid_move.to )
+            .collect();
+
+        destinations.dedup();
+        destinations
+    }
+
+    // Whether the piece on `from` could possibly move to `to` a move from now, whatever the opponent does
+    // until then. Only the way the piece moves is checked: other pieces in the way may be gone by then, and a
+    // pawn may have something to capture.
+    pub fn is_plausible_premove(&self, from: Square, to: Square) -> bool {
+        let occupancy = match self.square_occupied(from) {
+            Some(occupancy) => occupancy,
+            None => return false
+        };
+
+        if from == to || Square::new(to.rank, to.file).is_none() {
+            return false;
+        }
+
+        let ranks = to.rank - from.rank;
+        let files = to.file - from.file;
+
+        match occupancy.piece {
+            Piece::Pawn => {
+                let forward = if occupancy.color == Color::White { 1 } else { -1 };
+                let start_rank = if occupancy.color == Color::White { 1 } else { 6 };
+
+                (ranks == forward && files.abs() <= 1) ||
+                    (ranks == 2 * forward && files == 0 && from.rank == start_rank)
+            },
+
+            Piece::Knight => (ranks.abs(), files.abs()) == (1, 2) || (ranks.abs(), files.abs()) == (2, 1),
+            Piece::Bishop => ranks.abs() == files.abs(),
+            Piece::Rook   => ranks == 0 || files == 0,
+            Piece::Queen  => ranks.abs() == files.abs() || ranks == 0 || files == 0,
+
+            Piece::King => {
+                let home_rank = if occupancy.color == Color::White { 0 } else { 7 };
+                let castles = ranks == 0 && from.rank == home_rank && from.file == 4 && (to.file == 6 || to.file == 2);
+
+                (ranks.abs() <= 1 && files.abs() <= 1) || castles
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use super::*;
+
+// A move as it would be undone: the piece standing on `to` goes back to `from` as `piece`,
+// and `uncaptures` is put back on the square it was taken from. Undoing castling also puts the rook back.
+struct UnMove {
+    from: Square,
+    to: Square,
+    piece: Piece,
+    uncaptures: Option<Piece>,
+    en_passant: bool,
+    castles: bool
+}
+
+static UNCAPTURABLE_PIECES: [Option<Piece>; 6] = [
+    None,
+    Some(Piece::Pawn),
+    Some(Piece::Knight),
+    Some(Piece::Bishop),
+    Some(Piece::Rook),
+    Some(Piece::Queen)
+];
+
+impl Game {
+    // All (move, previous game) pairs such that playing the move in the previous game gives this position.
+    // Castling rights are carried over unchanged, except that undoing castling gives back the right it used.
+    // Predecessors in which a moved king or rook would still have its castling rights are left out.
+    pub fn predecessor_moves(&self) -> Vec<(ValidMove, Game)> {
+        self.predecessors(true)
+    }
+
+    // Only the predecessors with the same material, i.e. without captures and promotions
+    pub(crate) fn predecessor_moves_with_same_material(&self) -> Vec<(ValidMove, Game)> {
+        self.predecessors(false)
+    }
+
+    fn predecessors(&self, material_changes: bool) -> Vec<(ValidMove, Game)> {
+        let color = self.position.next_to_move.opposite();
+        let mut predecessors = Vec::new();
+
+        for (i, occupancy) in self.position.board.squares.iter().enumerate() {
+            let to = Board::square(i);
+
+            match occupancy {
+                Some(occupancy) if occupancy.color == color => {
+                    let unmoves = self.unmoves_for_piece(occupancy.piece, to, color).into_iter()
+                        .filter( |unmove| material_changes || (unmove.uncaptures.is_none() && unmove.piece == occupancy.piece) );
+
+                    for unmove in unmoves {
+                        if let Some(predecessor) = self.verified_predecessor(&unmove, color) {
+                            predecessors.push(predecessor);
+                        }
+                    }
+                },
+                _ => ()
+            }
+        }
+
+        predecessors
+    }
+
+    fn unmoves_for_piece(&self, piece: Piece, to: Square, color: Color) -> Vec<UnMove> {
+        let mut unmoves = Vec::new();
+        let empty = |square: &Square| self.square_occupied(*square).is_none();
+
+        // Pawns cannot be captured on the first or last rank
+        let uncaptures = |to: Square| UNCAPTURABLE_PIECES.iter()
+            .filter( move |uncaptures| **uncaptures != Some(Piece::Pawn) || (1..7).contains(&to.rank) );
+
+        let backward = match color {
+            Color::White => -1,
+            Color::Black => 1
+        };
+
+        let promotion_rank = match color {
+            Color::White => 7,
+            Color::Black => 0
+        };
+
+        match piece {
+            Piece::Pawn => {
+                if to.rank == promotion_rank || to.rank == 7 - promotion_rank {
+                    return unmoves;
+                }
+
+                // A pawn can't have moved from its first rank
+                let behind = |rank_delta: i8, file_delta: i8| Square::new(to.rank + backward * rank_delta, to.file + file_delta)
+                    .filter( |square| square.rank != 7 - promotion_rank );
+
+                if let Some(from) = behind(1, 0).filter(empty) {
+                    unmoves.push(UnMove { from, to, piece, uncaptures: None, en_passant: false, castles: false });
+
+                    let starting_rank = 7 - promotion_rank - backward;
+
+                    if let Some(from) = behind(2, 0).filter(empty).filter( |square| square.rank == starting_rank ) {
+                        unmoves.push(UnMove { from, to, piece, uncaptures: None, en_passant: false, castles: false });
+                    }
+                }
+
+                for file_delta in [-1, 1].iter() {
+                    if let Some(from) = behind(1, *file_delta).filter(empty) {
+                        for uncaptured in uncaptures(to).filter( |uncaptured| uncaptured.is_some() ) {
+                            unmoves.push(UnMove { from, to, piece, uncaptures: *uncaptured, en_passant: false, castles: false });
+                        }
+
+                        // The captured pawn must have just moved two squares past `to`
+                        if to.rank == promotion_rank + 2 * backward {
+                            unmoves.push(UnMove { from, to, piece, uncaptures: Some(Piece::Pawn), en_passant: true, castles: false });
+                        }
+                    }
+                }
+            },
+
+            _ => {
+                for from in self.attacked_squares(piece, to, color).into_iter().filter(empty) {
+                    for uncaptured in uncaptures(to) {
+                        unmoves.push(UnMove { from, to, piece, uncaptures: *uncaptured, en_passant: false, castles: false });
+                    }
+                }
+
+                // Castling, which is undone from the square the king castled to
+                let home_rank = 7 - promotion_rank;
+                let king_home = Square { rank: home_rank, file: 4 };
+
+                if piece == Piece::King && to.rank == home_rank && (to.file == 6 || to.file == 2) && empty(&king_home) {
+                    unmoves.push(UnMove { from: king_home, to, piece, uncaptures: None, en_passant: false, castles: true });
+                }
+
+                if piece != Piece::King && to.rank == promotion_rank {
+                    for file_delta in [-1, 0, 1].iter() {
+                        let from = match Square::new(to.rank + backward, to.file + file_delta).filter(empty) {
+                            Some(from) => from,
+                            None => continue
+                        };
+
+                        for uncaptured in uncaptures(to).filter( |uncaptured| uncaptured.is_some() == (*file_delta != 0) ) {
+                            unmoves.push(UnMove { from, to, piece: Piece::Pawn, uncaptures: *uncaptured, en_passant: false, castles: false });
+                        }
+                    }
+                }
+            }
+        }
+
+        unmoves
+    }
+
+    // Builds the previous position and checks that the move really leads back to this one
+    fn verified_predecessor(&self, unmove: &UnMove, color: Color) -> Option<(ValidMove, Game)> {
+        let is_quiet = unmove.piece != Piece::Pawn && unmove.uncaptures.is_none();
+
+        // A non-zero clock means that the last move was neither a capture nor a pawn move.
+        // Zero is treated as unknown, as most composed positions don't set the clock.
+        if self.position.half_move_clock > 0 && !is_quiet {
+            return None;
+        }
+
+        let mut squares = self.position.board.squares.clone();
+
+        let uncaptured = unmove.uncaptures.map( |piece| OccupiedSquare { piece, color: color.opposite() } );
+        let mut en_passant_square = None;
+
+        if unmove.en_passant {
+            let passed_pawn = Square { rank: unmove.from.rank, file: unmove.to.file };
+            let pawn_origin = Square { rank: 2 * unmove.to.rank - unmove.from.rank, file: unmove.to.file };
+
+            if self.square_occupied(passed_pawn).is_some() || self.square_occupied(pawn_origin).is_some() {
+                return None;
+            }
+
+            squares[Board::index(unmove.to)] = None;
+            squares[Board::index(passed_pawn)] = uncaptured;
+            en_passant_square = Some(unmove.to);
+        } else {
+            squares[Board::index(unmove.to)] = uncaptured;
+        }
+
+        squares[Board::index(unmove.from)] = Some(OccupiedSquare { piece: unmove.piece, color });
+
+        let mut rights = castling_rights(&self.position);
+
+        if unmove.castles {
+            let king_side = unmove.to.file == 6;
+            let (rook_from, rook_to) = if king_side { (7, 5) } else { (0, 3) };
+
+            let rook_from = Square { rank: unmove.to.rank, file: rook_from };
+            let rook_to = Square { rank: unmove.to.rank, file: rook_to };
+            let rook = Some(OccupiedSquare { piece: Piece::Rook, color });
+
+            if squares[Board::index(rook_to)] != rook || squares[Board::index(rook_from)].is_some() {
+                return None;
+            }
+
+            squares[Board::index(rook_to)] = None;
+            squares[Board::index(rook_from)] = rook;
+
+            let index = match color {
+                Color::White => if king_side { 0 } else { 1 },
+                Color::Black => if king_side { 2 } else { 3 }
+            };
+
+            rights[index] = true;
+        }
+
+        let previous = Game::new(Position {
+            board: Board { squares },
+            next_to_move: color,
+
+            white_can_castle_king_side:  rights[0],
+            white_can_castle_queen_side: rights[1],
+            black_can_castle_king_side:  rights[2],
+            black_can_castle_queen_side: rights[3],
+
+            en_passant_square,
+
+            half_move_clock: if is_quiet { (self.position.half_move_clock - 1).max(0) } else { 0 },
+            full_move_counter: match color {
+                Color::White => self.position.full_move_counter,
+                Color::Black => (self.position.full_move_counter - 1).max(1)
+            }
+        });
+
+        // The side which did not move can't have been left in check, and castling rights need the king and the
+        // rook on their squares
+        if previous.in_check(color.opposite()) || !castling_rights_possible(&previous.position) {
+            return None;
+        }
+
+        let promotion = if unmove.piece == Piece::Pawn {
+            self.square_occupied(unmove.to).map( |occupancy| occupancy.piece ).filter( |piece| *piece != Piece::Pawn )
+        } else {
+            None
+        };
+
+        // Only the moves of the one piece are generated, as this runs for every candidate
+        let valid_move = previous.possible_moves_for_piece(unmove.piece, unmove.from, color).into_iter()
+            .find( |valid_move| valid_move.to == unmove.to && valid_move.promotion == promotion )?;
+
+        let next = previous.make_valid_move(&valid_move);
+
+        let leads_here = !next.in_check(color) &&
+            next.position.board.squares == self.position.board.squares &&
+            next.position.en_passant_square == self.position.en_passant_square &&
+            castling_rights(&next.position) == castling_rights(&self.position);
+
+        if leads_here {
+            Some((valid_move, previous))
+        } else {
+            None
+        }
+    }
+}
+
+// White king side, white queen side, black king side and black queen side
+fn castling_rights(position: &Position) -> [bool; 4] {
+    [
+        position.white_can_castle_king_side,
+        position.white_can_castle_queen_side,
+        position.black_can_castle_king_side,
+        position.black_can_castle_queen_side
+    ]
+}
+
+fn castling_rights_possible(position: &Position) -> bool {
+    let sides = [(Color::White, 0, 7), (Color::White, 0, 0), (Color::Black, 7, 7), (Color::Black, 7, 0)];
+
+    castling_rights(position).iter().zip(sides.iter()).all( |(can_castle, (color, rank, rook_file))| {
+        let on = |file: i8, piece: Piece| position.board.squares[Board::index(Square { rank: *rank, file })] == Some(OccupiedSquare { piece, color: *color });
+
+        !can_castle || (on(4, Piece::King) && on(*rook_file, Piece::Rook))
+    })
+}
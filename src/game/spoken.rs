@@ -0,0 +1,188 @@
+use super::*;
+use super::super::locale::{Locale, Vocabulary};
+
+impl ValidMove {
+    // Text for screen readers and voice output, e.g. "knight from g1 takes pawn on e5, check"
+    pub fn spoken(&self, game: &Game, locale: Locale) -> String {
+        let words = locale.vocabulary();
+        let square = |square: Square| square.to_notation(SquareNotationOptions::FileAndRank);
+
+        let mut text = if self.is_castle() {
+            String::from(if self.to.file > self.from.file { words.castles_king_side } else { words.castles_queen_side })
+        } else {
+            let piece = words.pieces[self.piece.index()];
+
+            match self.takes {
+                Some(taken) => format!(
+                    "{} {} {} {} {} {} {}",
+                    piece, words.from, square(self.from), words.takes, words.pieces[taken.index()], words.on, square(self.to)
+                ),
+                None => format!("{} {} {} {} {}", piece, words.from, square(self.from), words.to, square(self.to))
+            }
+        };
+
+        if self.takes_en_passant {
+            text.push(' ');
+            text.push_str(words.en_passant);
+        }
+
+        if let Some(promotion) = self.promotion {
+            text.push_str(&format!(", {} {}", words.promotes_to, words.pieces[promotion.index()]));
+        }
+
+        let after = game.make_valid_move(self);
+
+        if after.in_mate() {
+            text.push_str(&format!(", {}", words.checkmate));
+        } else if after.in_check(self.color.opposite()) {
+            text.push_str(&format!(", {}", words.check));
+        }
+
+        text
+    }
+
+    // Spoken-style input such as "knight takes e5", "pawn e4", "bishop takes knight", "rook a1 to d1",
+    // "e eight queen" or "castles king side". Anything left out only has to match a single legal move.
+    pub fn from_spoken(game: &Game, text: &str, locale: Locale) -> Result<ValidMove, InvalidMoveError> {
+        let words = locale.vocabulary();
+
+        let normalized: String = text.to_lowercase().chars()
+            .map( |c| if c.is_alphanumeric() || c.is_whitespace() { c } else { ' ' } )
+            .collect();
+
+        let tokens: Vec<&str> = normalized.split_whitespace().collect();
+        let spoken = SpokenMove::parse(&tokens, words)?;
+
+        let candidates: Vec<ValidMove> = game.valid_moves().into_iter()
+            .filter( |valid_move| spoken.matches(valid_move) )
+            .collect();
+
+        match candidates.len() {
+            0 => Err(InvalidMoveError::NoMatchingMove),
+            1 => Ok(candidates.into_iter().next().unwrap()),
+            _ => Err(InvalidMoveError::AmbiguousMove(candidates))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SpokenMove {
+    piece: Option<Piece>,
+    squares: Vec<Square>,
+
+    takes: bool,
+    taken: Option<Piece>,
+    promotion: Option<Piece>,
+
+    // Some(true) for king side
+    castles: Option<bool>
+}
+
+impl SpokenMove {
+    fn parse(tokens: &[&str], words: &Vocabulary) -> Result<Self, InvalidMoveError> {
+        let phrase = |phrase: &str, at: usize| -> Option<usize> {
+            let phrase_tokens: Vec<&str> = phrase.split_whitespace().collect();
+
+            if tokens[at..].starts_with(&phrase_tokens) { Some(phrase_tokens.len()) } else { None }
+        };
+
+        let mut spoken = SpokenMove::default();
+        let mut promoting = false;
+        let mut squares_when_taking = None;
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = tokens[i];
+
+            if let Some(length) = phrase(words.castles_king_side, i) {
+                spoken.castles = Some(true);
+                i += length;
+            } else if let Some(length) = phrase(words.castles_queen_side, i) {
+                spoken.castles = Some(false);
+                i += length;
+            } else if let Some(length) = phrase(words.promotes_to, i) {
+                promoting = true;
+                i += length;
+            } else if let Some(length) = phrase(words.en_passant, i) {
+                i += length;
+            } else if let Some(index) = words.pieces.iter().position( |piece| *piece == token ) {
+                let piece = PIECES[index];
+
+                // A piece right after "takes" is the taken one, other than the first one it can only be a promotion
+                let after_takes = spoken.takes && spoken.taken.is_none() && squares_when_taking == Some(spoken.squares.len());
+
+                if promoting {
+                    spoken.promotion = Some(piece);
+                } else if after_takes {
+                    spoken.taken = Some(piece);
+                } else if spoken.piece.is_none() && spoken.squares.is_empty() && !spoken.takes {
+                    spoken.piece = Some(piece);
+                } else {
+                    spoken.promotion = Some(piece);
+                }
+
+                i += 1;
+            } else if let Some((square, length)) = Self::parse_square(&tokens[i..], words) {
+                spoken.squares.push(square);
+                i += length;
+            } else if token == words.takes || words.capture_words.contains(&token) {
+                spoken.takes = true;
+                squares_when_taking = Some(spoken.squares.len());
+                i += 1;
+            } else if [words.from, words.to, words.on, words.check, words.checkmate].contains(&token) || words.filler.contains(&token) {
+                i += 1;
+            } else {
+                return Err(InvalidMoveError::InvalidNotation);
+            }
+        }
+
+        if spoken.squares.len() > 2 || (spoken.squares.is_empty() && spoken.castles.is_none() && spoken.taken.is_none()) {
+            return Err(InvalidMoveError::InvalidNotation);
+        }
+
+        Ok(spoken)
+    }
+
+    // "e4", or the file and the rank as separate words, e.g. "e four" or "e 4"
+    fn parse_square(tokens: &[&str], words: &Vocabulary) -> Option<(Square, usize)> {
+        if let Ok(square) = Square::from_notation(tokens[0]) {
+            if tokens[0].len() == 2 {
+                return Some((square, 1));
+            }
+        }
+
+        let file = match tokens[0].as_bytes() {
+            [file @ b'a'..=b'h'] => (file - b'a') as i8,
+            _ => return None
+        };
+
+        let rank_token = tokens.get(1)?;
+        let rank = match words.rank_numbers.iter().position( |number| number == rank_token ) {
+            Some(rank) => rank as i8,
+            None => match rank_token.as_bytes() {
+                [rank @ b'1'..=b'8'] => (rank - b'1') as i8,
+                _ => return None
+            }
+        };
+
+        Some((Square { rank, file }, 2))
+    }
+
+    fn matches(&self, valid_move: &ValidMove) -> bool {
+        if let Some(king_side) = self.castles {
+            return valid_move.is_castle() && (valid_move.to.file > valid_move.from.file) == king_side;
+        }
+
+        let squares_match = match self.squares.as_slice() {
+            [from, to] => valid_move.from == *from && valid_move.to == *to,
+            [to]       => valid_move.to == *to,
+            _          => true
+        };
+
+        squares_match &&
+            self.piece.is_none_or( |piece| piece == valid_move.piece ) &&
+            (!self.takes || valid_move.takes.is_some()) &&
+            self.taken.is_none_or( |taken| valid_move.takes == Some(taken) ) &&
+            self.promotion.is_none_or( |promotion| valid_move.promotion == Some(promotion) )
+    }
+}
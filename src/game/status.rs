@@ -0,0 +1,112 @@
+use super::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameStatus {
+    Ongoing,
+
+    // The side to move is mated
+    Checkmate,
+    Draw(DrawReason),
+
+    DrawAgreed,
+
+    // The color which resigned
+    Resigned(Color),
+
+    // Decided by an arbiter, e.g. after a time forfeit or an abandoned game
+    Adjudicated { result: GameResult, reason: String }
+}
+
+// The Termination tag of games decided by running out of time
+pub const TIME_FORFEIT: &str = "time forfeit";
+
+impl Game {
+    pub fn status(&self) -> GameStatus {
+        if let Some(ending) = &self.ending {
+            return ending.clone();
+        }
+
+        if self.in_mate() {
+            GameStatus::Checkmate
+        } else if let Some(reason) = self.draw_reason() {
+            GameStatus::Draw(reason)
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    pub fn result(&self) -> GameResult {
+        let win_for = |color: Color| match color {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins
+        };
+
+        match self.status() {
+            GameStatus::Ongoing                     => GameResult::Unknown,
+            GameStatus::Checkmate                   => win_for(self.position.next_to_move.opposite()),
+            GameStatus::Draw(_)                     => GameResult::Draw,
+            GameStatus::DrawAgreed                  => GameResult::Draw,
+            GameStatus::Resigned(color)             => win_for(color.opposite()),
+            GameStatus::Adjudicated { result, .. }  => result
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.status() != GameStatus::Ongoing
+    }
+
+    // The offer stands until the opponent accepts it or makes a move instead
+    pub fn offer_draw(&self, color: Color) -> Result<Self, String> {
+        self.check_not_over()?;
+
+        Ok(Game { draw_offer: Some(color), ..self.clone() })
+    }
+
+    pub fn pending_draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    pub fn accept_draw(&self) -> Result<Self, String> {
+        self.check_not_over()?;
+
+        if self.draw_offer.is_none() {
+            return Err(String::from("There is no draw offer to accept"));
+        }
+
+        Ok(self.ended(GameStatus::DrawAgreed))
+    }
+
+    pub fn resign(&self, color: Color) -> Result<Self, String> {
+        self.check_not_over()?;
+
+        Ok(self.ended(GameStatus::Resigned(color)))
+    }
+
+    // Overrides any other result, so arbiters can also correct one. The reason goes into the PGN
+    // Termination tag, e.g. "time forfeit" or "adjudication".
+    pub fn adjudicate(&self, result: GameResult, reason: &str) -> Self {
+        self.ended(GameStatus::Adjudicated { result, reason: String::from(reason) })
+    }
+
+    // The value of the PGN Termination tag
+    pub fn termination(&self) -> &str {
+        match &self.ending {
+            Some(GameStatus::Adjudicated { reason, .. }) => reason,
+            _ if self.is_over() => "normal",
+            _ => "unterminated"
+        }
+    }
+
+    fn ended(&self, status: GameStatus) -> Self {
+        Game { ending: Some(status), draw_offer: None, ..self.clone() }
+    }
+
+    fn check_not_over(&self) -> Result<(), String> {
+        if self.is_over() {
+            Err(String::from("The game is already over"))
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::path::Path;
+
+use super::game::{Game, Replay, ReplayError};
+
+const MAGIC: &[u8; 8] = b"PGNIDX01";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct GamePly {
+    // Index of the game inside the indexed corpus
+    pub game: u32,
+
+    // Half-moves played to reach the position, 0 is the starting position
+    pub ply: u32
+}
+
+// Inverted index from position hash to every game (and ply) which reached it
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PositionIndex {
+    positions: HashMap<u64, Vec<GamePly>>,
+    games: u32
+}
+
+impl PositionIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Games which cannot be replayed are indexed up to the first invalid move. Their numbers are returned
+    // together with the error, and they still take up a game id so that the ids match the PGN order.
+    pub fn from_pgn(pgn: &str) -> Result<(Self, Vec<(u32, ReplayError)>), String> {
+        let mut index = Self::new();
+        let mut errors = Vec::new();
+
+        for pgn_game in Game::parse_pgn(pgn)? {
+            let game_id = index.games;
+
+            if let Err(error) = index.add_game(Game::replay(pgn_game)) {
+                errors.push((game_id, error));
+            }
+        }
+
+        Ok((index, errors))
+    }
+
+    // Returns the id given to the game
+    pub fn add_game(&mut self, replay: Replay) -> Result<u32, ReplayError> {
+        let game = self.games;
+        self.games += 1;
+
+        self.insert(replay.game().hash(), GamePly { game, ply: 0 });
+
+        for (ply, step) in replay.enumerate() {
+            let (_, position) = step?;
+
+            self.insert(position.hash(), GamePly { game, ply: ply as u32 + 1 });
+        }
+
+        Ok(game)
+    }
+
+    fn insert(&mut self, hash: u64, game_ply: GamePly) {
+        self.positions.entry(hash).or_default().push(game_ply);
+    }
+
+    pub fn lookup(&self, game: &Game) -> &[GamePly] {
+        self.lookup_hash(game.hash())
+    }
+
+    pub fn lookup_hash(&self, hash: u64) -> &[GamePly] {
+        self.positions.get(&hash).map( |games| games.as_slice() ).unwrap_or(&[])
+    }
+
+    pub fn game_count(&self) -> u32 {
+        self.games
+    }
+
+    // Number of distinct positions
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    // Little-endian: magic, game count, position count, then per position the hash, entry count and entries
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.games.to_le_bytes())?;
+        writer.write_all(&(self.positions.len() as u64).to_le_bytes())?;
+
+        // Sorted so that the same index always produces the same file
+        let mut hashes: Vec<&u64> = self.positions.keys().collect();
+        hashes.sort();
+
+        for hash in hashes {
+            let games = &self.positions[hash];
+
+            writer.write_all(&hash.to_le_bytes())?;
+            writer.write_all(&(games.len() as u32).to_le_bytes())?;
+
+            for game_ply in games {
+                writer.write_all(&game_ply.game.to_le_bytes())?;
+                writer.write_all(&game_ply.ply.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a position index file"));
+        }
+
+        let games = read_u32(reader)?;
+        let position_count = read_u64(reader)?;
+
+        let mut positions = HashMap::new();
+
+        for _ in 0..position_count {
+            let hash = read_u64(reader)?;
+            let count = read_u32(reader)?;
+
+            let mut entries = Vec::new();
+
+            for _ in 0..count {
+                let game = read_u32(reader)?;
+                let ply = read_u32(reader)?;
+
+                entries.push(GamePly { game, ply });
+            }
+
+            positions.insert(hash, entries);
+        }
+
+        Ok(PositionIndex { positions, games })
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
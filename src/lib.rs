@@ -6,17 +6,58 @@ extern crate wasm_bindgen;
 
 mod models;
 mod fen;
+mod zobrist;
 
+pub mod rng;
 pub mod parser;
 pub mod game;
+pub mod analysis;
+pub mod eval;
+pub mod tuning;
+pub mod book;
+pub mod eco;
+pub mod annotate;
+pub mod tree;
+pub mod repertoire;
+pub mod training;
+pub mod index;
+pub mod tablebase;
+pub mod endgame;
+pub mod search;
+pub mod manager;
+pub mod dgt;
+pub mod broadcast;
+pub mod correspondence;
+pub mod clock;
+pub mod screen;
+pub mod render;
+pub mod locale;
+pub mod pgn_file;
+pub mod ndjson;
+pub mod features;
+pub mod nnue;
+pub mod bot;
+pub mod pairing;
+pub mod adjudication;
+
+#[cfg(feature = "protobuf")]
+pub mod proto;
+
+#[cfg(feature = "suites")]
+pub mod suites;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod engine_match;
+
 pub mod wasm;
 
 pub use parser::lexer::{Lexer, Token};
-pub use parser::{ParsedGame, PGNMove, Parser};
-pub use game::{Game, ValidMove};
+pub use parser::{ParsedGame, PGNHeaders, PGNMove, Parser, ParseLimits};
+pub use game::{Game, ValidMove, MoveKind, SquareSafety, DrawReason, GameStatus, GameChange, ObservedGame, MoveNotation, Hint, HintLevel, PgnProfile, TagSelection, ResultPlacement, MoveAnnotation, MoveMetadata, Replay, ReplayError, InvalidMoveError, IllegalReason, NotationStrictness, PromotionPolicy, MoveOptions, Odds, PROMOTION_PIECES, numeric_move};
 
 pub use models::*;
 pub use fen::*;
+pub use zobrist::{PositionSet};
 
 // pub use wasm::*;
 
@@ -0,0 +1,167 @@
+use super::models::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Locale {
+    English
+}
+
+// Words used when reading moves out loud, for screen readers and voice input
+pub struct Vocabulary {
+    // In the order of PIECES
+    pub pieces: [&'static str; 6],
+
+    pub from: &'static str,
+    pub to: &'static str,
+    pub takes: &'static str,
+    pub on: &'static str,
+    pub en_passant: &'static str,
+    pub promotes_to: &'static str,
+    pub castles_king_side: &'static str,
+    pub castles_queen_side: &'static str,
+    pub check: &'static str,
+    pub checkmate: &'static str,
+
+    pub rank_numbers: [&'static str; 8],
+
+    // Other words accepted as `takes` in spoken input
+    pub capture_words: &'static [&'static str],
+
+    // Words which can be left out of spoken input, e.g. "moves" in "knight moves to f3"
+    pub filler: &'static [&'static str]
+}
+
+static ENGLISH: Vocabulary = Vocabulary {
+    pieces: ["pawn", "knight", "bishop", "rook", "queen", "king"],
+
+    from: "from",
+    to: "to",
+    takes: "takes",
+    on: "on",
+    en_passant: "en passant",
+    promotes_to: "promotes to",
+    castles_king_side: "castles king side",
+    castles_queen_side: "castles queen side",
+    check: "check",
+    checkmate: "checkmate",
+
+    rank_numbers: ["one", "two", "three", "four", "five", "six", "seven", "eight"],
+
+    capture_words: &["captures", "capture", "take", "x"],
+    filler: &["moves", "move", "goes", "square", "the", "and", "mate", "plus"]
+};
+
+// Letters used for the pieces in SAN. Books and PGNs from other countries often use their own.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PieceLetters {
+    English,
+    German,
+    Spanish,
+
+    // Transliterated from the Cyrillic, e.g. "Kr" for the king and "F" for the queen
+    Russian,
+
+    // Figurine algebraic notation. Emits the white figurines and accepts those of both colors.
+    Figurine
+}
+
+impl Locale {
+    pub fn vocabulary(&self) -> &'static Vocabulary {
+        match self {
+            Locale::English => &ENGLISH
+        }
+    }
+
+    pub fn piece_letters(&self) -> PieceLetters {
+        match self {
+            Locale::English => PieceLetters::English
+        }
+    }
+}
+
+impl PieceLetters {
+    pub const ALL: [PieceLetters; 5] = [
+        PieceLetters::English, PieceLetters::German, PieceLetters::Spanish, PieceLetters::Russian, PieceLetters::Figurine
+    ];
+
+    // Every letter any of them uses for a piece, including the black figurines
+    pub(crate) fn all_known_letters() -> Vec<String> {
+        let mut known: Vec<String> = PieceLetters::ALL.iter()
+            .flat_map( |letters| letters.letters().to_vec() )
+            .map(String::from)
+            .chain(black_figurines().into_iter().map( |(figurine, _)| figurine ))
+            .collect();
+
+        known.sort();
+        known.dedup();
+
+        known
+    }
+
+    // In the order of PIECES
+    pub fn letters(&self) -> [&'static str; 6] {
+        match self {
+            PieceLetters::English  => ["P", "N", "B", "R", "Q", "K"],
+            PieceLetters::German   => ["B", "S", "L", "T", "D", "K"],
+            PieceLetters::Spanish  => ["P", "C", "A", "T", "D", "R"],
+            PieceLetters::Russian  => ["P", "K", "S", "L", "F", "Kr"],
+            PieceLetters::Figurine => ["♙", "♘", "♗", "♖", "♕", "♔"]
+        }
+    }
+
+    pub fn letter(&self, piece: Piece) -> &'static str {
+        self.letters()[piece.index()]
+    }
+
+    pub fn parse(&self, letter: &str) -> Option<Piece> {
+        let letters = self.letters();
+
+        letters.iter().position( |known| *known == letter )
+            .map( |index| PIECES[index] )
+            .or_else( || match self {
+                PieceLetters::Figurine => black_figurines().into_iter()
+                    .find( |(figurine, _)| figurine == letter )
+                    .map( |(_, piece)| piece ),
+                _ => None
+            })
+    }
+
+    // Replaces the piece letters in a move with the English ones, e.g. "Sxe5+" in German becomes "Nxe5+".
+    // Files and the other parts of the notation are written the same way everywhere.
+    pub fn to_english(&self, notation: &str) -> String {
+        if *self == PieceLetters::English {
+            return String::from(notation);
+        }
+
+        let mut known: Vec<(String, Piece)> = self.letters().iter().map( |letter| String::from(*letter) ).zip(PIECES.iter().cloned()).collect();
+
+        if *self == PieceLetters::Figurine {
+            known.extend(black_figurines());
+        }
+
+        // Longest first, so that the Russian king isn't read as a knight
+        known.sort_by_key( |(letter, _)| std::cmp::Reverse(letter.len()) );
+
+        let mut english = String::new();
+        let mut rest = notation;
+
+        while let Some(c) = rest.chars().next() {
+            match known.iter().find( |(letter, _)| rest.starts_with(letter.as_str()) ) {
+                Some((letter, piece)) => {
+                    english.push_str(PieceLetters::English.letter(*piece));
+                    rest = &rest[letter.len()..];
+                },
+                None => {
+                    english.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+
+        english
+    }
+}
+
+// Figurine notation is read with the figurines of either color
+fn black_figurines() -> Vec<(String, Piece)> {
+    PIECES.iter().map( |piece| (piece.figurine(Color::Black).to_string(), *piece) ).collect()
+}
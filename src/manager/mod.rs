@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use super::models::*;
+use super::game::{Game, ValidMove, GameStatus, GameChange, InvalidMoveError, MoveMetadata};
+
+pub type GameId = u64;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GameEvent {
+    // Only sent to subscribers of all games
+    Created { id: GameId },
+
+    Move { id: GameId, valid_move: ValidMove, san: String },
+    DrawOffer { id: GameId, color: Color },
+
+    // Sent before the End event of a time forfeit
+    ClockFlag { id: GameId, color: Color },
+    End { id: GameId, status: GameStatus, result: GameResult },
+
+    // Only sent to subscribers of all games, the channels of the game's own subscribers get disconnected
+    Removed { id: GameId }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ManagerError {
+    UnknownGame(GameId),
+    GameOver(GameId),
+    InvalidMove(InvalidMoveError),
+
+    // Returned by the update function, e.g. accepting a draw which wasn't offered
+    Rejected(String)
+}
+
+struct ManagedGame {
+    game: Game,
+    subscribers: Vec<Sender<GameEvent>>
+}
+
+// Games are locked one at a time, so moves in different games don't wait for each other. Events of a game are
+// sent while its lock is held, so every subscriber sees them in the order the changes were made.
+#[derive(Default)]
+pub struct GameManager {
+    games: RwLock<HashMap<GameId, Arc<Mutex<ManagedGame>>>>,
+    subscribers: Mutex<Vec<Sender<GameEvent>>>,
+    next_id: AtomicU64
+}
+
+impl GameManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, game: Game) -> GameId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        write(&self.games).insert(id, Arc::new(Mutex::new(ManagedGame { game, subscribers: Vec::new() })));
+        self.broadcast(GameEvent::Created { id });
+
+        id
+    }
+
+    pub fn remove(&self, id: GameId) -> Option<Game> {
+        let managed = write(&self.games).remove(&id)?;
+        let game = lock(&managed).game.clone();
+
+        self.broadcast(GameEvent::Removed { id });
+
+        Some(game)
+    }
+
+    // Games are immutable, so this is a snapshot which later changes don't affect
+    pub fn game(&self, id: GameId) -> Option<Game> {
+        self.managed(id).ok().map( |managed| lock(&managed).game.clone() )
+    }
+
+    pub fn status(&self, id: GameId) -> Option<GameStatus> {
+        self.game(id).map( |game| game.status() )
+    }
+
+    pub fn ids(&self) -> Vec<GameId> {
+        let mut ids: Vec<GameId> = read(&self.games).keys().cloned().collect();
+        ids.sort_unstable();
+
+        ids
+    }
+
+    pub fn len(&self) -> usize {
+        read(&self.games).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        read(&self.games).is_empty()
+    }
+
+    pub fn subscribe(&self, id: GameId) -> Result<Receiver<GameEvent>, ManagerError> {
+        let (sender, receiver) = channel();
+
+        let managed = self.managed(id)?;
+        lock(&managed).subscribers.push(sender);
+
+        Ok(receiver)
+    }
+
+    pub fn subscribe_all(&self) -> Receiver<GameEvent> {
+        let (sender, receiver) = channel();
+
+        lock(&self.subscribers).push(sender);
+
+        receiver
+    }
+
+    pub fn make_move(&self, id: GameId, notation: &str) -> Result<Game, ManagerError> {
+        self.play(id, |game| game.make_move(notation) )
+    }
+
+    pub fn make_uci_move(&self, id: GameId, notation: &str) -> Result<Game, ManagerError> {
+        self.play(id, |game| ValidMove::from_uci(game, notation).map( |valid_move| game.make_valid_move(&valid_move) ) )
+    }
+
+    // The metadata stays with the move in the game, e.g. the clock time or who played it
+    pub fn make_move_with_metadata(&self, id: GameId, notation: &str, metadata: MoveMetadata) -> Result<Game, ManagerError> {
+        self.play(id, |game| {
+            ValidMove::from_notation(game, notation)
+                .or_else( |_| ValidMove::from_uci(game, notation) )
+                .map( |valid_move| game.make_valid_move_with_metadata(&valid_move, metadata) )
+        })
+    }
+
+    pub fn offer_draw(&self, id: GameId, color: Color) -> Result<Game, ManagerError> {
+        self.update(id, |game| game.offer_draw(color) )
+    }
+
+    pub fn accept_draw(&self, id: GameId) -> Result<Game, ManagerError> {
+        self.update(id, |game| game.accept_draw() )
+    }
+
+    pub fn resign(&self, id: GameId, color: Color) -> Result<Game, ManagerError> {
+        self.update(id, |game| game.resign(color) )
+    }
+
+    pub fn adjudicate(&self, id: GameId, result: GameResult, reason: &str) -> Result<Game, ManagerError> {
+        self.update(id, |game| Ok(game.adjudicate(result, reason)) )
+    }
+
+    fn play<F>(&self, id: GameId, make_move: F) -> Result<Game, ManagerError>
+        where F: FnOnce(&Game) -> Result<Game, InvalidMoveError>
+    {
+        self.update_with(id, |game| {
+            if game.is_over() {
+                return Err(ManagerError::GameOver(id));
+            }
+
+            make_move(game).map_err(ManagerError::InvalidMove)
+        })
+    }
+
+    // Replaces the game with the result of `update`, all under the game's lock, and notifies the subscribers
+    // of what changed
+    pub fn update<F>(&self, id: GameId, update: F) -> Result<Game, ManagerError>
+        where F: FnOnce(&Game) -> Result<Game, String>
+    {
+        self.update_with(id, |game| update(game).map_err(ManagerError::Rejected) )
+    }
+
+    fn update_with<F>(&self, id: GameId, update: F) -> Result<Game, ManagerError>
+        where F: FnOnce(&Game) -> Result<Game, ManagerError>
+    {
+        let managed = self.managed(id)?;
+        let mut managed = lock(&managed);
+
+        let updated = update(&managed.game)?;
+        let events = changes(id, &managed.game, &updated);
+
+        managed.game = updated.clone();
+
+        for event in events {
+            managed.subscribers.retain( |subscriber| subscriber.send(event.clone()).is_ok() );
+            self.broadcast(event);
+        }
+
+        Ok(updated)
+    }
+
+    fn managed(&self, id: GameId) -> Result<Arc<Mutex<ManagedGame>>, ManagerError> {
+        read(&self.games).get(&id).cloned().ok_or(ManagerError::UnknownGame(id))
+    }
+
+    fn broadcast(&self, event: GameEvent) {
+        lock(&self.subscribers).retain( |subscriber| subscriber.send(event.clone()).is_ok() );
+    }
+}
+
+fn changes(id: GameId, before: &Game, after: &Game) -> Vec<GameEvent> {
+    after.changes_since(before).into_iter().map( |change| match change {
+        GameChange::Move { valid_move, san } => GameEvent::Move { id, valid_move, san },
+        GameChange::DrawOffer(color)         => GameEvent::DrawOffer { id, color },
+        GameChange::ClockFlag(color)         => GameEvent::ClockFlag { id, color },
+        GameChange::Status(status)           => GameEvent::End { id, status, result: after.result() }
+    }).collect()
+}
+
+// A panic while a lock is held leaves the data consistent (games are replaced as a whole), so poisoned locks
+// are used anyway instead of failing every later call
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else( |poisoned| poisoned.into_inner() )
+}
+
+fn read<T>(lock: &RwLock<T>) -> std::sync::RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else( |poisoned| poisoned.into_inner() )
+}
+
+fn write<T>(lock: &RwLock<T>) -> std::sync::RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else( |poisoned| poisoned.into_inner() )
+}
@@ -1,9 +1,10 @@
 use core::fmt::Debug;
 use wasm_bindgen::prelude::*;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
+// Ordered as declared, which is not by value
 #[wasm_bindgen]
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Piece {
     Pawn,
@@ -15,7 +16,7 @@ pub enum Piece {
 }
 
 #[wasm_bindgen]
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum GameResult {
     Unknown,
@@ -25,13 +26,63 @@ pub enum GameResult {
 }
 
 #[wasm_bindgen]
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Color {
     White,
     Black
 }
 
+impl Piece {
+    // Conventional material values in pawns, the king is priceless
+    pub fn value(&self) -> i32 {
+        match self {
+            Piece::Pawn   => 1,
+            Piece::Knight => 3,
+            Piece::Bishop => 3,
+            Piece::Rook   => 5,
+            Piece::Queen  => 9,
+            Piece::King   => 0
+        }
+    }
+
+    // The position in PIECES, which file formats, hash keys and feature indices number the pieces by
+    pub fn index(&self) -> usize {
+        match self {
+            Piece::Pawn   => 0,
+            Piece::Knight => 1,
+            Piece::Bishop => 2,
+            Piece::Rook   => 3,
+            Piece::Queen  => 4,
+            Piece::King   => 5
+        }
+    }
+
+    // The English letter, as in FEN and SAN
+    pub fn letter(&self) -> char {
+        PIECE_LETTERS[self.index()]
+    }
+
+    // Either case
+    pub fn from_letter(letter: char) -> Option<Piece> {
+        PIECE_LETTERS.iter().position( |known| *known == letter.to_ascii_uppercase() ).map( |index| PIECES[index] )
+    }
+
+    pub fn figurine(&self, color: Color) -> char {
+        match color {
+            Color::White => WHITE_FIGURINES[self.index()],
+            Color::Black => BLACK_FIGURINES[self.index()]
+        }
+    }
+
+    pub fn from_figurine(figurine: char) -> Option<OccupiedSquare> {
+        let find = |figurines: &[char; 6]| figurines.iter().position( |known| *known == figurine ).map( |index| PIECES[index] );
+
+        find(&WHITE_FIGURINES).map( |piece| OccupiedSquare { piece, color: Color::White } )
+            .or_else( || find(&BLACK_FIGURINES).map( |piece| OccupiedSquare { piece, color: Color::Black } ) )
+    }
+}
+
 impl Color {
     pub fn opposite(&self) -> Color {
         match self {
@@ -41,26 +92,31 @@ impl Color {
     }
 }
 
+// Serializes as the algebraic notation, e.g. "e4". Both that and {"rank": 3, "file": 4} (0-based, as in the
+// struct) are read back. Fields which should be written as numbers can use #[serde(with = "numeric_square")].
+// Sorts by rank first, so a1 < h1 < a2.
 #[wasm_bindgen]
-#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
 pub struct Square {
     pub rank: i8,
     pub file: i8
 }
 
 #[wasm_bindgen]
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct OccupiedSquare {
     pub piece: Piece,
     pub color: Color
 }
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 pub struct Board {
     pub squares: Vec<Option<OccupiedSquare>>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+// The derived Hash covers every field, the move counters too. For repetitions and transpositions use the
+// zobrist hash instead, which only covers what makes positions the same.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Position {
     pub board: Board,
 
@@ -77,6 +133,23 @@ pub struct Position {
     pub full_move_counter: i64,
 }
 
+pub const BOARD_SIZE: i8 = 8;
+
+// Both limits count half-moves (plies), the unit of Position::half_move_clock
+pub const FIFTY_MOVE_RULE_HALF_MOVES: i64 = 100;
+pub const SEVENTY_FIVE_MOVE_RULE_HALF_MOVES: i64 = 150;
+
+pub const THREEFOLD_REPETITION_COUNT: usize = 3;
+pub const FIVEFOLD_REPETITION_COUNT: usize = 5;
+
+// The pieces in their conventional order, which Piece::index follows. The declaration order of the enum is a
+// different one.
+pub const PIECES: [Piece; 6] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King];
+
+static PIECE_LETTERS: [char; 6] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+static WHITE_FIGURINES: [char; 6] = ['♙', '♘', '♗', '♖', '♕', '♔'];
+static BLACK_FIGURINES: [char; 6] = ['♟', '♞', '♝', '♜', '♛', '♚'];
+
 static FILE_LABELS: [char; 8] = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 
 impl Debug for Square {
@@ -129,13 +202,32 @@ impl Square {
         let file = (file_label as u8 - 'a' as u8) as i8;
         let rank = (rank_char as u8 - '0' as u8) as i8 - 1;
 
-        if rank < 1 || rank > 8 {
+        if !(0..=7).contains(&rank) {
             return Err(());
         }
 
         Ok(Square { rank, file })
     }
 
+    // Counted from a1 = 0 rank by rank to h8 = 63, the way most formats outside of the crate number squares.
+    // Board::index counts from a8 instead.
+    pub fn to_a1_index(&self) -> usize {
+        (self.rank * 8 + self.file) as usize
+    }
+
+    pub fn from_a1_index(index: usize) -> Option<Square> {
+        if index < 64 {
+            Some(Square { rank: (index / 8) as i8, file: (index % 8) as i8 })
+        } else {
+            None
+        }
+    }
+
+    // a1 is a dark square
+    pub fn is_light(&self) -> bool {
+        (self.rank + self.file) % 2 == 1
+    }
+
     pub fn to_notation(&self, options: SquareNotationOptions) -> String {
         let file_label = FILE_LABELS[self.file as usize];
 
@@ -147,34 +239,122 @@ impl Square {
     }
 }
 
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if Square::new(self.rank, self.file).is_none() {
+            return Err(serde::ser::Error::custom("Square is not on the board"));
+        }
+
+        serializer.serialize_str(&self.to_notation(SquareNotationOptions::FileAndRank))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SquareRepresentation {
+    Algebraic(String),
+    Numeric { rank: i8, file: i8 }
+}
+
+impl<'de> Deserialize<'de> for Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let square = match SquareRepresentation::deserialize(deserializer)? {
+            SquareRepresentation::Algebraic(notation) if notation.len() == 2 => Square::from_notation(&notation).ok(),
+            SquareRepresentation::Algebraic(_) => None,
+            SquareRepresentation::Numeric { rank, file } => Square::new(rank, file)
+        };
+
+        square.ok_or_else( || serde::de::Error::custom("Invalid square") )
+    }
+}
+
+pub mod numeric_square {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(square: &Square, serializer: S) -> Result<S::Ok, S::Error> {
+        SquareRepresentation::Numeric { rank: square.rank, file: square.file }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Square, D::Error> {
+        Square::deserialize(deserializer)
+    }
+}
+
+// The direction from one square to another as (rank delta, file delta) steps of at most one, if they share
+// a rank, file or diagonal
+fn line_direction(from: Square, to: Square) -> Option<(i8, i8)> {
+    let rank_delta = to.rank - from.rank;
+    let file_delta = to.file - from.file;
+
+    if from == to || (rank_delta != 0 && file_delta != 0 && rank_delta.abs() != file_delta.abs()) {
+        return None;
+    }
+
+    Some((rank_delta.signum(), file_delta.signum()))
+}
+
+// Squares from `from` (excluded) to the edge of the board, going in `direction` = (rank delta, file delta)
+pub fn ray(from: Square, direction: (i8, i8)) -> Vec<Square> {
+    let (rank_delta, file_delta) = direction;
+    let mut squares = Vec::new();
+
+    if direction == (0, 0) {
+        return squares;
+    }
+
+    let mut current_square = Square::new(from.rank + rank_delta, from.file + file_delta);
+
+    while let Some(square) = current_square {
+        squares.push(square);
+        current_square = Square::new(square.rank + rank_delta, square.file + file_delta);
+    }
+
+    squares
+}
+
+// Squares strictly between the two, which is empty unless they share a rank, file or diagonal
+pub fn squares_between(a: Square, b: Square) -> Vec<Square> {
+    match line_direction(a, b) {
+        Some(direction) => ray(a, direction).into_iter().take_while( |square| *square != b ).collect(),
+        None => Vec::new()
+    }
+}
+
+// Whether `c` is on the rank, file or diagonal going through `a` and `b`, e.g. a king, a pinned piece and
+// the piece pinning it
+pub fn aligned(a: Square, b: Square, c: Square) -> bool {
+    let direction = match line_direction(a, b) {
+        Some(direction) => direction,
+        None => return false
+    };
+
+    match line_direction(a, c) {
+        Some((rank_delta, file_delta)) => {
+            (rank_delta, file_delta) == direction || (-rank_delta, -file_delta) == direction
+        },
+        None => c == a
+    }
+}
+
+impl Board {
+    // Where the square is inside `squares`, which go from a8 to h8, then a7 to h7 and so on down to h1
+    pub fn index(square: Square) -> usize {
+        ((7 - square.rank) * 8 + square.file) as usize
+    }
+
+    pub fn square(index: usize) -> Square {
+        Square { rank: 7 - (index / 8) as i8, file: (index % 8) as i8 }
+    }
+}
+
 impl Debug for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        for (i, square) in self.squares.iter().enumerate() {
-            let letter = match square {
-                Some(OccupiedSquare { piece: Piece::Pawn, color: Color::White }) => 'P',
-                Some(OccupiedSquare { piece: Piece::Knight, color: Color::White }) => 'N',
-                Some(OccupiedSquare { piece: Piece::Bishop, color: Color::White }) => 'B',
-                Some(OccupiedSquare { piece: Piece::Rook, color: Color::White }) => 'R',
-                Some(OccupiedSquare { piece: Piece::Queen, color: Color::White }) => 'Q',
-                Some(OccupiedSquare { piece: Piece::King, color: Color::White }) => 'K',
-
-                Some(OccupiedSquare { piece: Piece::Pawn, color: Color::Black }) => 'p',
-                Some(OccupiedSquare { piece: Piece::Knight, color: Color::Black }) => 'n',
-                Some(OccupiedSquare { piece: Piece::Bishop, color: Color::Black }) => 'b',
-                Some(OccupiedSquare { piece: Piece::Rook, color: Color::Black }) => 'r',
-                Some(OccupiedSquare { piece: Piece::Queen, color: Color::Black }) => 'q',
-                Some(OccupiedSquare { piece: Piece::King, color: Color::Black }) => 'k',
-
-                None => ' '
-            };
-
-            write!(f, "|{}", letter)?;
-
-            if i % 8 == 7 {
-                write!(f, "|\n")?;
-            }
-        }
+        f.write_str(&super::render::render_text(self, &super::render::BoardView::plain()))
+    }
+}
 
-        Ok(())
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(&super::render::render_text(self, &super::render::BoardView::default()))
     }
 }
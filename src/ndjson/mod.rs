@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+use serde::{Serialize, Deserialize};
+
+use super::models::*;
+use super::game::{Game, GameStatus, DrawReason, ValidMove, TIME_FORFEIT};
+
+// One line of a newline-delimited JSON stream of games, in the shape of the lichess game export
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct JsonGame {
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+
+    // Only for games which don't start from the standard position
+    #[serde(rename = "initialFen", default, skip_serializing_if = "Option::is_none")]
+    pub initial_fen: Option<String>,
+
+    // UCI moves separated by spaces, e.g. "e2e4 e7e5"
+    pub moves: String,
+
+    // As lichess names them, e.g. "started", "mate", "resign" or "outoftime"
+    pub status: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner: Option<Color>,
+
+    // "1-0", "0-1", "1/2-1/2" or "*"
+    pub result: String
+}
+
+impl JsonGame {
+    pub fn from_game(game: &Game, tags: &[(String, String)]) -> Self {
+        let initial_position = game.initial_position();
+
+        let status = match game.status() {
+            GameStatus::Ongoing                          => "started",
+            GameStatus::Checkmate                        => "mate",
+            GameStatus::Draw(DrawReason::Stalemate)      => "stalemate",
+            GameStatus::Draw(_) | GameStatus::DrawAgreed => "draw",
+            GameStatus::Resigned(_)                      => "resign",
+            GameStatus::Adjudicated { reason, .. } if reason == TIME_FORFEIT => "outoftime",
+            GameStatus::Adjudicated { .. }               => "unknownFinish"
+        };
+
+        let winner = match game.result() {
+            GameResult::WhiteWins => Some(Color::White),
+            GameResult::BlackWins => Some(Color::Black),
+            _ => None
+        };
+
+        JsonGame {
+            tags: tags.iter().cloned().collect(),
+            initial_fen: if *initial_position != Game::standard_position() { Some(initial_position.to_fen()) } else { None },
+            moves: game.moves().iter().map( |valid_move| valid_move.uci() ).collect::<Vec<String>>().join(" "),
+            status: String::from(status),
+            winner,
+            result: String::from(game.result().to_string())
+        }
+    }
+
+    // Replays the moves and ends the game the way the status says, if the moves don't already end it
+    pub fn to_game(&self) -> Result<Game, String> {
+        let mut game = match &self.initial_fen {
+            Some(fen) => Game::new_from_fen(fen).map_err( |error| error.message )?,
+            None => Game::new(Game::standard_position())
+        };
+
+        for uci in self.moves.split_whitespace() {
+            let valid_move = ValidMove::from_uci(&game, uci).map_err( |_| format!("Invalid move '{}'", uci) )?;
+
+            game = game.make_valid_move(&valid_move);
+        }
+
+        let result = GameResult::from_string(&self.result).ok_or(format!("Invalid result '{}'", self.result))?;
+
+        if game.is_over() || result == GameResult::Unknown {
+            return Ok(game);
+        }
+
+        let loser = match result {
+            GameResult::WhiteWins => Some(Color::Black),
+            GameResult::BlackWins => Some(Color::White),
+            _ => None
+        };
+
+        Ok(match (self.status.as_str(), loser) {
+            ("resign", Some(loser))  => game.resign(loser)?,
+            ("draw", None)           => game.offer_draw(game.position().next_to_move)?.accept_draw()?,
+            ("outoftime", _)         => game.adjudicate(result, TIME_FORFEIT),
+            _                        => game.adjudicate(result, "adjudication")
+        })
+    }
+
+    pub fn tag_list(&self) -> Vec<(String, String)> {
+        self.tags.iter().map( |(name, value)| (name.clone(), value.clone()) ).collect()
+    }
+}
+
+// A single line without the line break
+pub fn to_line(game: &Game, tags: &[(String, String)]) -> String {
+    serde_json::to_string(&JsonGame::from_game(game, tags)).expect("Cannot serialize game to JSON")
+}
+
+pub fn from_line(line: &str) -> Result<(Game, Vec<(String, String)>), String> {
+    let json_game: JsonGame = serde_json::from_str(line).map_err( |error| error.to_string() )?;
+
+    Ok((json_game.to_game()?, json_game.tag_list()))
+}
+
+pub fn to_ndjson(games: &[(Game, Vec<(String, String)>)]) -> String {
+    games.iter()
+        .map( |(game, tags)| to_line(game, tags) + "\n" )
+        .collect()
+}
+
+// One result per non-empty line, so a bad line doesn't stop the games after it from being read
+pub fn read_ndjson<R: BufRead>(reader: R) -> impl Iterator<Item = Result<(Game, Vec<(String, String)>), String>> {
+    reader.lines()
+        .filter( |line| line.as_ref().map( |line| !line.trim().is_empty() ).unwrap_or(true) )
+        .map( |line| line.map_err( |error| error.to_string() ).and_then( |line| from_line(&line) ) )
+}
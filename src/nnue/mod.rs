@@ -0,0 +1,213 @@
+use std::convert::TryInto;
+
+use super::models::*;
+use super::features::{halfkp_index, oriented, HALFKP_FEATURES};
+
+mod simd;
+
+// A small NNUE network over HalfKP features (see the features module): one hidden layer per side, clipped to
+// 0..=127, followed by a single output. The network file is little endian:
+//
+//   "PGNN", version (u32, 1), hidden size N (u32)
+//   feature weights (i16, HALFKP_FEATURES * N, the N weights of a feature one after another)
+//   feature biases (i16, N)
+//   output weights (i16, 2 * N, first for the side to move, then for the other side)
+//   output bias (i32)
+//
+// The evaluation is the output divided by OUTPUT_DIVISOR, in centipawns for the side to move.
+pub const OUTPUT_DIVISOR: i32 = 64;
+
+const MAGIC: &[u8; 4] = b"PGNN";
+const VERSION: u32 = 1;
+const CLIP: i32 = 127;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Network {
+    hidden: usize,
+
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32
+}
+
+// The hidden layer before clipping, for white and for black. Kept up to date move by move during a search.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Accumulator {
+    sides: [Vec<i32>; 2]
+}
+
+impl Network {
+    pub fn new(feature_weights: Vec<i16>, feature_biases: Vec<i16>, output_weights: Vec<i16>, output_bias: i32) -> Result<Self, String> {
+        let hidden = feature_biases.len();
+
+        if hidden == 0 || feature_weights.len() != HALFKP_FEATURES * hidden || output_weights.len() != 2 * hidden {
+            return Err(String::from("The layer sizes don't match"));
+        }
+
+        Ok(Network { hidden, feature_weights, feature_biases, output_weights, output_bias })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err( |error| error.to_string() )?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = bytes;
+
+        if take(&mut reader, 4)? != MAGIC {
+            return Err(String::from("Not a network file"));
+        }
+
+        let version = u32::from_le_bytes(take(&mut reader, 4)?.try_into().unwrap());
+        if version != VERSION {
+            return Err(format!("Unsupported network version {}", version));
+        }
+
+        let hidden = u32::from_le_bytes(take(&mut reader, 4)?.try_into().unwrap()) as usize;
+        if hidden == 0 || hidden > 4096 {
+            return Err(format!("Unsupported hidden layer size {}", hidden));
+        }
+
+        let feature_weights = read_i16s(&mut reader, HALFKP_FEATURES * hidden)?;
+        let feature_biases = read_i16s(&mut reader, hidden)?;
+        let output_weights = read_i16s(&mut reader, 2 * hidden)?;
+        let output_bias = i32::from_le_bytes(take(&mut reader, 4)?.try_into().unwrap());
+
+        if !reader.is_empty() {
+            return Err(String::from("Unexpected data after the network"));
+        }
+
+        Self::new(feature_weights, feature_biases, output_weights, output_bias)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.hidden as u32).to_le_bytes());
+
+        for value in self.feature_weights.iter().chain(self.feature_biases.iter()).chain(self.output_weights.iter()) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    // Computes the accumulator from scratch
+    pub fn accumulator(&self, position: &Position) -> Accumulator {
+        Accumulator {
+            sides: [self.refresh(position, Color::White), self.refresh(position, Color::Black)]
+        }
+    }
+
+    // The accumulator of the position after a move, from the one before it. Only the pieces on squares which
+    // changed are added or removed, except for a side whose king moved, which needs all of its features again.
+    pub fn update(&self, accumulator: &Accumulator, before: &Position, after: &Position) -> Accumulator {
+        let mut updated = accumulator.clone();
+
+        for color in [Color::White, Color::Black].iter().cloned() {
+            let king_before = king_square(before, color);
+            let king_after = king_square(after, color);
+
+            let king = match (king_before, king_after) {
+                (Some(king_before), Some(king_after)) if king_before == king_after => oriented(king_after, color),
+                _ => {
+                    updated.sides[side(color)] = self.refresh(after, color);
+                    continue;
+                }
+            };
+
+            for (index, (old, new)) in before.board.squares.iter().zip(after.board.squares.iter()).enumerate() {
+                if old == new {
+                    continue;
+                }
+
+                let square = oriented(Board::square(index), color);
+
+                if let Some(old) = old.as_ref().filter( |old| old.piece != Piece::King ) {
+                    self.apply(&mut updated.sides[side(color)], halfkp_index(king, old, square, color), -1);
+                }
+
+                if let Some(new) = new.as_ref().filter( |new| new.piece != Piece::King ) {
+                    self.apply(&mut updated.sides[side(color)], halfkp_index(king, new, square, color), 1);
+                }
+            }
+        }
+
+        updated
+    }
+
+    // Centipawns for the side to move
+    pub fn evaluate(&self, accumulator: &Accumulator, side_to_move: Color) -> i32 {
+        let (own_weights, other_weights) = self.output_weights.split_at(self.hidden);
+
+        let output = simd::clipped_dot(&accumulator.sides[side(side_to_move)], own_weights, CLIP) +
+            simd::clipped_dot(&accumulator.sides[side(side_to_move.opposite())], other_weights, CLIP);
+
+        ((output + self.output_bias as i64) / OUTPUT_DIVISOR as i64) as i32
+    }
+
+    pub fn evaluate_position(&self, position: &Position) -> i32 {
+        self.evaluate(&self.accumulator(position), position.next_to_move)
+    }
+
+    fn refresh(&self, position: &Position, color: Color) -> Vec<i32> {
+        let mut values: Vec<i32> = self.feature_biases.iter().map( |bias| *bias as i32 ).collect();
+
+        let king = match king_square(position, color) {
+            Some(king) => oriented(king, color),
+            None => return values
+        };
+
+        for (index, occupied) in position.board.squares.iter().enumerate() {
+            if let Some(occupied) = occupied.as_ref().filter( |occupied| occupied.piece != Piece::King ) {
+                let feature = halfkp_index(king, occupied, oriented(Board::square(index), color), color);
+
+                self.apply(&mut values, feature, 1);
+            }
+        }
+
+        values
+    }
+
+    fn apply(&self, values: &mut [i32], feature: usize, sign: i32) {
+        simd::add_weights(values, &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden], sign);
+    }
+}
+
+fn side(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1
+    }
+}
+
+fn king_square(position: &Position, color: Color) -> Option<Square> {
+    position.board.squares.iter()
+        .position( |occupied| *occupied == Some(OccupiedSquare { piece: Piece::King, color }) )
+        .map( Board::square )
+}
+
+fn take<'a>(reader: &mut &'a [u8], length: usize) -> Result<&'a [u8], String> {
+    if reader.len() < length {
+        return Err(String::from("Truncated network file"));
+    }
+
+    let (taken, rest) = reader.split_at(length);
+    *reader = rest;
+
+    Ok(taken)
+}
+
+fn read_i16s(reader: &mut &[u8], count: usize) -> Result<Vec<i16>, String> {
+    Ok(take(reader, count * 2)?.chunks(2).map( |pair| i16::from_le_bytes([pair[0], pair[1]]) ).collect())
+}
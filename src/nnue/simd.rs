@@ -0,0 +1,162 @@
+// The inner loops of the network. With the `simd` feature they use AVX2 on x86_64 when the CPU has it, checked
+// at runtime, and the 128-bit SIMD of WebAssembly when the crate is built with it, e.g. with
+// RUSTFLAGS="-C target-feature=+simd128". Everywhere else, and for the elements left over after the last full
+// vector, the plain loops run. All of them give exactly the same results.
+
+// values += sign * weights, where sign is 1 or -1
+pub(super) fn add_weights(values: &mut [i32], weights: &[i16], sign: i32) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Only called once the CPU is known to support AVX2
+            unsafe { x86::add_weights(values, weights, sign) };
+            return;
+        }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        wasm::add_weights(values, weights, sign);
+        return;
+    }
+
+    #[allow(unreachable_code)]
+    scalar_add_weights(values, weights, sign)
+}
+
+// The sum of values clipped to 0..=clip times the weights
+pub(super) fn clipped_dot(values: &[i32], weights: &[i16], clip: i32) -> i64 {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Only called once the CPU is known to support AVX2
+            return unsafe { x86::clipped_dot(values, weights, clip) };
+        }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        return wasm::clipped_dot(values, weights, clip);
+    }
+
+    #[allow(unreachable_code)]
+    scalar_clipped_dot(values, weights, clip)
+}
+
+fn scalar_add_weights(values: &mut [i32], weights: &[i16], sign: i32) {
+    for (value, weight) in values.iter_mut().zip(weights.iter()) {
+        *value += sign * *weight as i32;
+    }
+}
+
+fn scalar_clipped_dot(values: &[i32], weights: &[i16], clip: i32) -> i64 {
+    values.iter()
+        .zip(weights.iter())
+        .map( |(value, weight)| (*value).clamp(0, clip) as i64 * *weight as i64 )
+        .sum()
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 8;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add_weights(values: &mut [i32], weights: &[i16], sign: i32) {
+        let length = values.len().min(weights.len());
+        let vectors = length / LANES;
+
+        for i in 0..vectors {
+            let value_pointer = values.as_mut_ptr().add(i * LANES) as *mut __m256i;
+            let weight = _mm256_cvtepi16_epi32(_mm_loadu_si128(weights.as_ptr().add(i * LANES) as *const __m128i));
+            let value = _mm256_loadu_si256(value_pointer);
+
+            let value = if sign < 0 { _mm256_sub_epi32(value, weight) } else { _mm256_add_epi32(value, weight) };
+
+            _mm256_storeu_si256(value_pointer, value);
+        }
+
+        super::scalar_add_weights(&mut values[vectors * LANES..length], &weights[vectors * LANES..length], sign);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn clipped_dot(values: &[i32], weights: &[i16], clip: i32) -> i64 {
+        let length = values.len().min(weights.len());
+        let vectors = length / LANES;
+
+        let zero = _mm256_setzero_si256();
+        let clip_vector = _mm256_set1_epi32(clip);
+        let mut sums = _mm256_setzero_si256();
+
+        for i in 0..vectors {
+            let value = _mm256_loadu_si256(values.as_ptr().add(i * LANES) as *const __m256i);
+            let weight = _mm256_cvtepi16_epi32(_mm_loadu_si128(weights.as_ptr().add(i * LANES) as *const __m128i));
+
+            // A clipped value times an i16 weight always fits, the sums are widened before adding them up
+            let product = _mm256_mullo_epi32(_mm256_min_epi32(_mm256_max_epi32(value, zero), clip_vector), weight);
+
+            sums = _mm256_add_epi64(sums, _mm256_cvtepi32_epi64(_mm256_castsi256_si128(product)));
+            sums = _mm256_add_epi64(sums, _mm256_cvtepi32_epi64(_mm256_extracti128_si256::<1>(product)));
+        }
+
+        let mut lanes = [0i64; 4];
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sums);
+
+        lanes.iter().sum::<i64>() + super::scalar_clipped_dot(&values[vectors * LANES..length], &weights[vectors * LANES..length], clip)
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm {
+    use std::arch::wasm32::*;
+
+    const LANES: usize = 8;
+
+    pub fn add_weights(values: &mut [i32], weights: &[i16], sign: i32) {
+        let length = values.len().min(weights.len());
+        let vectors = length / LANES;
+
+        for i in 0..vectors {
+            // Eight weights widen into two vectors of four values
+            let weight = unsafe { v128_load(weights.as_ptr().add(i * LANES) as *const v128) };
+            let halves = [i32x4_extend_low_i16x8(weight), i32x4_extend_high_i16x8(weight)];
+
+            for (half, weight) in halves.iter().enumerate() {
+                let value_pointer = unsafe { values.as_mut_ptr().add(i * LANES + half * 4) as *mut v128 };
+                let value = unsafe { v128_load(value_pointer) };
+
+                let value = if sign < 0 { i32x4_sub(value, *weight) } else { i32x4_add(value, *weight) };
+
+                unsafe { v128_store(value_pointer, value) };
+            }
+        }
+
+        super::scalar_add_weights(&mut values[vectors * LANES..length], &weights[vectors * LANES..length], sign);
+    }
+
+    pub fn clipped_dot(values: &[i32], weights: &[i16], clip: i32) -> i64 {
+        let length = values.len().min(weights.len());
+        let vectors = length / LANES;
+
+        let zero = i32x4_splat(0);
+        let clip_vector = i32x4_splat(clip);
+        let mut sums = i64x2_splat(0);
+
+        for i in 0..vectors {
+            let weight = unsafe { v128_load(weights.as_ptr().add(i * LANES) as *const v128) };
+            let halves = [i32x4_extend_low_i16x8(weight), i32x4_extend_high_i16x8(weight)];
+
+            for (half, weight) in halves.iter().enumerate() {
+                let value = unsafe { v128_load(values.as_ptr().add(i * LANES + half * 4) as *const v128) };
+                let product = i32x4_mul(i32x4_min(i32x4_max(value, zero), clip_vector), *weight);
+
+                sums = i64x2_add(sums, i64x2_extend_low_i32x4(product));
+                sums = i64x2_add(sums, i64x2_extend_high_i32x4(product));
+            }
+        }
+
+        i64x2_extract_lane::<0>(sums) + i64x2_extract_lane::<1>(sums) +
+            super::scalar_clipped_dot(&values[vectors * LANES..length], &weights[vectors * LANES..length], clip)
+    }
+}
@@ -0,0 +1,269 @@
+use serde::{Serialize, Deserialize};
+
+use super::models::GameResult;
+
+// The index of the player in the list the tournament was created with
+pub type PlayerId = usize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub name: String,
+    pub rating: u32
+}
+
+// A game of a round. Without a black player it is a bye, which scores a point for the white one.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Pairing {
+    pub white: PlayerId,
+    pub black: Option<PlayerId>
+}
+
+impl Pairing {
+    pub fn is_bye(&self) -> bool {
+        self.black.is_none()
+    }
+
+    pub fn involves(&self, player: PlayerId) -> bool {
+        self.white == player || self.black == Some(player)
+    }
+}
+
+// The rounds of a round-robin by the Berger tables, in which everyone plays everyone once and the colors
+// alternate as much as possible. With an odd number of players, the one who would play the missing player
+// of the table has a bye.
+pub fn round_robin(players: usize) -> Vec<Vec<Pairing>> {
+    if players < 2 {
+        return Vec::new();
+    }
+
+    let n = players + players % 2;
+
+    (0..n - 1)
+        .map( |round| {
+            (0..n / 2)
+                .map( |board| {
+                    let first = (round + board) % (n - 1);
+                    let second = if board == 0 { n - 1 } else { (round + n - 1 - board) % (n - 1) };
+
+                    // The last player stays in place and changes colors every round, the others change
+                    // colors by board
+                    let swapped = if board == 0 { round % 2 == 1 } else { board % 2 == 0 };
+                    let (white, black) = if swapped { (second, first) } else { (first, second) };
+
+                    if white >= players {
+                        Pairing { white: black, black: None }
+                    } else if black >= players {
+                        Pairing { white, black: None }
+                    } else {
+                        Pairing { white, black: Some(black) }
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// A Swiss tournament paired by a basic version of the Dutch system: players are ranked by score and rating,
+// every score group is split in halves and the top half plays the bottom half. Nobody meets twice and
+// nobody gets a second bye while there are other candidates. Leftover players float down to the next
+// group. The colors go to whoever had the other one more often, or last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swiss {
+    pub players: Vec<Player>,
+
+    // The games of every round with their results, GameResult::Unknown until they are recorded
+    rounds: Vec<Vec<(Pairing, GameResult)>>
+}
+
+impl Swiss {
+    pub fn new(players: Vec<Player>) -> Self {
+        Swiss { players, rounds: Vec::new() }
+    }
+
+    pub fn rounds(&self) -> &[Vec<(Pairing, GameResult)>] {
+        &self.rounds
+    }
+
+    // Pairs the next round, once every game of the last one has a result
+    pub fn pair_round(&mut self) -> Result<Vec<Pairing>, String> {
+        if self.players.len() < 2 {
+            return Err(String::from("A round needs at least two players"));
+        }
+
+        if let Some(round) = self.rounds.iter().position( |round| round.iter().any( |(_, result)| *result == GameResult::Unknown ) ) {
+            return Err(format!("Round {} has games without a result", round + 1));
+        }
+
+        let mut ranked = self.ranking();
+        let mut pairings = Vec::new();
+
+        let bye = if ranked.len() % 2 == 1 {
+            let index = ranked.iter().rposition( |player| !self.had_bye(*player) ).unwrap_or(ranked.len() - 1);
+
+            Some(ranked.remove(index))
+        } else {
+            None
+        };
+
+        let pairs = self.pair_players(&ranked)
+            .ok_or_else( || String::from("There is no pairing without a rematch") )?;
+
+        for (board, (higher, lower)) in pairs.into_iter().enumerate() {
+            let (white, black) = if self.prefers_white(higher, lower, board) { (higher, lower) } else { (lower, higher) };
+
+            pairings.push(Pairing { white, black: Some(black) });
+        }
+
+        if let Some(bye) = bye {
+            pairings.push(Pairing { white: bye, black: None });
+        }
+
+        self.rounds.push(pairings.iter().map( |pairing| {
+            let result = if pairing.is_bye() { GameResult::WhiteWins } else { GameResult::Unknown };
+
+            (*pairing, result)
+        }).collect());
+
+        Ok(pairings)
+    }
+
+    // Rounds are counted from 1. Results can be corrected until the next round is paired.
+    pub fn record_result(&mut self, round: usize, white: PlayerId, result: GameResult) -> Result<(), String> {
+        let games = match round.checked_sub(1).and_then( |index| self.rounds.get_mut(index) ) {
+            Some(games) => games,
+            None => return Err(format!("There is no round {}", round))
+        };
+
+        let game = games.iter_mut()
+            .find( |(pairing, _)| pairing.white == white && !pairing.is_bye() )
+            .ok_or_else( || format!("Player {} doesn't have white in round {}", white, round) )?;
+
+        game.1 = result;
+        Ok(())
+    }
+
+    // In half-points, as byes and wins count 2 and draws 1
+    pub fn half_points(&self, player: PlayerId) -> u32 {
+        self.games_of(player)
+            .map( |(pairing, result)| match (result, pairing.white == player) {
+                (GameResult::Draw, _)              => 1,
+                (GameResult::WhiteWins, true)      => 2,
+                (GameResult::BlackWins, false)     => 2,
+                _                                  => 0
+            })
+            .sum()
+    }
+
+    pub fn score(&self, player: PlayerId) -> f64 {
+        self.half_points(player) as f64 / 2.0
+    }
+
+    // The sum of the scores of the opponents, in half-points
+    pub fn buchholz(&self, player: PlayerId) -> u32 {
+        self.games_of(player)
+            .filter_map( |(pairing, _)| Self::opponent(pairing, player) )
+            .map( |opponent| self.half_points(opponent) )
+            .sum()
+    }
+
+    // Ordered by score, then Buchholz, then rating
+    pub fn standings(&self) -> Vec<(PlayerId, f64)> {
+        let mut players: Vec<PlayerId> = (0..self.players.len()).collect();
+
+        players.sort_by_key( |player| (std::cmp::Reverse((self.half_points(*player), self.buchholz(*player), self.players[*player].rating)), *player) );
+        players.into_iter().map( |player| (player, self.score(player)) ).collect()
+    }
+
+    pub fn have_played(&self, a: PlayerId, b: PlayerId) -> bool {
+        self.games_of(a).any( |(pairing, _)| pairing.involves(b) )
+    }
+
+    pub fn had_bye(&self, player: PlayerId) -> bool {
+        self.games_of(player).any( |(pairing, _)| pairing.is_bye() )
+    }
+
+    fn games_of(&self, player: PlayerId) -> impl Iterator<Item = &(Pairing, GameResult)> {
+        self.rounds.iter()
+            .flatten()
+            .filter( move |(pairing, _)| pairing.involves(player) )
+    }
+
+    fn opponent(pairing: &Pairing, player: PlayerId) -> Option<PlayerId> {
+        if pairing.white == player { pairing.black } else { Some(pairing.white) }
+    }
+
+    // By score, then rating, then the order of the players' list
+    fn ranking(&self) -> Vec<PlayerId> {
+        let mut players: Vec<PlayerId> = (0..self.players.len()).collect();
+
+        players.sort_by_key( |player| (std::cmp::Reverse((self.half_points(*player), self.players[*player].rating)), *player) );
+        players
+    }
+
+    // Pairs the highest ranked player first, trying the opponents in the order of the Dutch system and
+    // backtracking when the rest can't be paired without rematches
+    fn pair_players(&self, ranked: &[PlayerId]) -> Option<Vec<(PlayerId, PlayerId)>> {
+        let (first, rest) = match ranked.split_first() {
+            Some(split) => split,
+            None => return Some(Vec::new())
+        };
+
+        let score = self.half_points(*first);
+        let group = 1 + rest.iter().take_while( |player| self.half_points(**player) == score ).count();
+        let half = group / 2;
+
+        // The opposite player of the bottom half, the rest of the bottom half, the top half from the bottom
+        // up and then the lower score groups
+        let candidates = (half.max(1)..group)
+            .chain((1..half.max(1)).rev())
+            .chain(group..ranked.len());
+
+        for candidate in candidates {
+            let opponent = ranked[candidate];
+
+            if self.have_played(*first, opponent) {
+                continue;
+            }
+
+            let remaining: Vec<PlayerId> = rest.iter().copied().filter( |player| *player != opponent ).collect();
+
+            if let Some(mut pairs) = self.pair_players(&remaining) {
+                pairs.insert(0, (*first, opponent));
+                return Some(pairs);
+            }
+        }
+
+        None
+    }
+
+    // Whites minus blacks, and the color of the last game
+    fn color_history(&self, player: PlayerId) -> (i32, Option<bool>) {
+        let mut balance = 0;
+        let mut last_white = None;
+
+        for (pairing, _) in self.games_of(player).filter( |(pairing, _)| !pairing.is_bye() ) {
+            let white = pairing.white == player;
+
+            balance += if white { 1 } else { -1 };
+            last_white = Some(white);
+        }
+
+        (balance, last_white)
+    }
+
+    fn prefers_white(&self, higher: PlayerId, lower: PlayerId, board: usize) -> bool {
+        let (higher_balance, higher_last) = self.color_history(higher);
+        let (lower_balance, lower_last) = self.color_history(lower);
+
+        if higher_balance != lower_balance {
+            return higher_balance < lower_balance;
+        }
+
+        match (higher_last, lower_last) {
+            (Some(higher_last), Some(lower_last)) if higher_last != lower_last => !higher_last,
+
+            // Like in the first round, the higher ranked players have white on every other board
+            _ => board.is_multiple_of(2)
+        }
+    }
+}
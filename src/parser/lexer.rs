@@ -1,8 +1,18 @@
 use std::str::Chars;
 use std::iter::Peekable;
 
+use super::ParseLimits;
+use super::super::models::GameResult;
+
 // http://www.saremba.de/chessgml/standards/pgn/pgn-complete.htm
 
+// The standard's limit for symbols
+pub const MAX_SYMBOL_LENGTH: usize = 255;
+
+// With ParseLimits::max_moves, a game can have this many tokens per move: the move number, both moves with their
+// suffixes, glyphs and comments, variations and the tag pairs
+const MAX_TOKENS_PER_MOVE: usize = 32;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Token {
     // PGN character data is organized as tokens. A token is a contiguous sequence of characters that represents a basic semantic unit. Tokens may be separated from adjacent tokens by white space characters. (White space characters include space, newline, and tab characters.) Some tokens are self delimiting and do not require white space characters.
@@ -45,20 +55,47 @@ pub enum Token {
 pub enum LexerError {
     ParseIntError(PositionInPGN),
     UnterminatedString(PositionInPGN),
-    UnexpectedCharacter(PositionInPGN)
+    UnexpectedCharacter(PositionInPGN),
+    StringTooLong(PositionInPGN),
+    SymbolTooLong(PositionInPGN),
+    CommentTooLong(PositionInPGN),
+    LimitExceeded(String, PositionInPGN)
 }
 
-impl std::convert::Into<String> for LexerError {
-    fn into(self) -> String {
+impl LexerError {
+    // The message without the position
+    pub fn description(&self) -> String {
+        match self {
+            LexerError::ParseIntError(_) => String::from("Could not parse int"),
+            LexerError::UnterminatedString(_) => String::from("Unterminated string literal"),
+            LexerError::UnexpectedCharacter(_) => String::from("Unexpected character"),
+            LexerError::StringTooLong(_) => String::from("String too long"),
+            LexerError::SymbolTooLong(_) => String::from("Symbol too long"),
+            LexerError::CommentTooLong(_) => String::from("Comment too long"),
+            LexerError::LimitExceeded(limit, _) => limit.clone(),
+        }
+    }
+
+    pub fn position(&self) -> &PositionInPGN {
         match self {
-            LexerError::ParseIntError(position) => format!("Could not parse int @ {:?}", position),
-            LexerError::UnterminatedString(position) => format!("Unterminated string literal @ {:?}", position),
-            LexerError::UnexpectedCharacter(position) => format!("Unexpected character @ {:?}", position),
+            LexerError::ParseIntError(position) |
+            LexerError::UnterminatedString(position) |
+            LexerError::UnexpectedCharacter(position) |
+            LexerError::StringTooLong(position) |
+            LexerError::SymbolTooLong(position) |
+            LexerError::CommentTooLong(position) |
+            LexerError::LimitExceeded(_, position) => position
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl std::convert::Into<String> for LexerError {
+    fn into(self) -> String {
+        format!("{} @ {:?}", self.description(), self.position())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct PositionInPGN {
     pub line: i32,
     pub column: i32
@@ -68,7 +105,17 @@ pub struct Lexer<'a> {
     pgn: Peekable<Chars<'a>>,
 
     line: i32,
-    column: i32
+    column: i32,
+
+    // Everything is read into memory before the parser sees it, so the limits are also checked while reading
+    limits: ParseLimits,
+
+    games: usize,
+    game_tokens: usize,
+
+    // Where each token starts, for reporting issues with them
+    positions: Vec<PositionInPGN>,
+    token_start: PositionInPGN
 }
 
 impl<'a> Lexer<'a>  {
@@ -76,19 +123,39 @@ impl<'a> Lexer<'a>  {
         Self {
             pgn: pgn.chars().peekable(),
             line: 1,
-            column: 0
+            column: 0,
+            limits: ParseLimits::default(),
+            games: 0,
+            game_tokens: 0,
+            positions: Vec::new(),
+            token_start: PositionInPGN { line: 1, column: 1 }
         }
     }
 
+    pub fn with_max_string_length(mut self, max_string_length: Option<usize>) -> Self {
+        self.limits.max_tag_length = max_string_length;
+        self
+    }
+
+    // Strings are limited by max_tag_length and comments by max_comment_length. The games and moves are
+    // counted roughly, the parser checks them exactly.
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn lex(&mut self) -> Result<Vec<Token>, LexerError> {
         let mut tokens: Vec<Token> = Vec::new();
 
         loop {
+            self.token_start = PositionInPGN { line: self.line, column: self.column + 1 };
+
             let next_char = self.pgn.peek();
 
             match next_char {
                 None => {
                     tokens.push(Token::EndOfFile);
+                    self.positions.push(self.token_start);
                     break
                 },
 
@@ -113,11 +180,11 @@ impl<'a> Lexer<'a>  {
                         match c {
                             None => break,
                             Some('\n') => break,
-                            Some(c) => string.push(c)
+                            Some(c) => self.push_comment_char(&mut string, c)?
                         }
                     }
 
-                    tokens.push(Token::Comment(string));
+                    self.push(&mut tokens, Token::Comment(string))?;
                 },
 
                 Some('{') => {
@@ -131,11 +198,11 @@ impl<'a> Lexer<'a>  {
                         match c {
                             None => break,
                             Some('}') => break,
-                            Some(c) => string.push(c)
+                            Some(c) => self.push_comment_char(&mut string, c)?
                         }
                     }
 
-                    tokens.push(Token::Comment(string));
+                    self.push(&mut tokens, Token::Comment(string))?;
                 },
 
                 // Some(c) if c.is_digit(10) => {
@@ -187,24 +254,28 @@ impl<'a> Lexer<'a>  {
                                 string.push(c)
                             }
                         }
+
+                        if self.limits.max_tag_length.is_some_and( |max_length| string.len() > max_length && string.chars().count() > max_length ) {
+                            return Err(LexerError::StringTooLong(self.position()));
+                        }
                     }
 
-                    tokens.push(Token::String(string))
+                    self.push(&mut tokens, Token::String(string))?
                 },
 
                 Some(' ') | Some('\n') => { self.next(); },
 
-                Some('.') => { self.next(); tokens.push(Token::Period) },
-                Some('*') => { self.next(); tokens.push(Token::Asterisk) },
+                Some('.') => { self.next(); self.push(&mut tokens, Token::Period)? },
+                Some('*') => { self.next(); self.push(&mut tokens, Token::Asterisk)? },
 
-                Some('[') => { self.next(); tokens.push(Token::OpenBracket) },
-                Some(']') => { self.next(); tokens.push(Token::CloseBracket) },
+                Some('[') => { self.next(); self.push(&mut tokens, Token::OpenBracket)? },
+                Some(']') => { self.next(); self.push(&mut tokens, Token::CloseBracket)? },
 
-                Some('(') => { self.next(); tokens.push(Token::OpenParen) },
-                Some(')') => { self.next(); tokens.push(Token::CloseParen) },
+                Some('(') => { self.next(); self.push(&mut tokens, Token::OpenParen)? },
+                Some(')') => { self.next(); self.push(&mut tokens, Token::CloseParen)? },
 
-                Some('<') => { self.next(); tokens.push(Token::OpenAngleBracket) },
-                Some('>') => { self.next(); tokens.push(Token::CloseAngleBracket) },
+                Some('<') => { self.next(); self.push(&mut tokens, Token::OpenAngleBracket)? },
+                Some('>') => { self.next(); self.push(&mut tokens, Token::CloseAngleBracket)? },
 
                 Some('$') => {
                     self.next(); // $
@@ -212,7 +283,7 @@ impl<'a> Lexer<'a>  {
                     let int = self.read_int();
 
                     match int {
-                        Ok(value) => tokens.push(Token::NumericAnnotationGlyph(value)),
+                        Ok(value) => self.push(&mut tokens, Token::NumericAnnotationGlyph(value))?,
                         Err(_) => return Err(
                             LexerError::ParseIntError(self.position())
                         )
@@ -220,20 +291,20 @@ impl<'a> Lexer<'a>  {
                 },
 
                 Some(c) if Self::is_symbol_start(c) => {
-                    let string = self.read_symbol();
+                    let string = self.read_symbol()?;
                     let is_integer = string.chars().all( |c| c.is_digit(10) );
 
                     if is_integer {
                         let int = string.parse::<i64>();
 
                         match int {
-                            Ok(value) => tokens.push(Token::Integer(value)),
+                            Ok(value) => self.push(&mut tokens, Token::Integer(value))?,
                             Err(_) => return Err(
                                 LexerError::ParseIntError(self.position())
                             )
                         }
                     } else {
-                        tokens.push(Token::Symbol(string))
+                        self.push(&mut tokens, Token::Symbol(string))?
                     }
                 },
 
@@ -244,14 +315,63 @@ impl<'a> Lexer<'a>  {
         Ok(tokens)
     }
 
+    fn push(&mut self, tokens: &mut Vec<Token>, token: Token) -> Result<(), LexerError> {
+        let ends_game = match &token {
+            Token::Symbol(result) => GameResult::from_string(result).is_some(),
+            Token::Asterisk => true,
+            _ => false
+        };
+
+        self.game_tokens += 1;
+
+        if self.limits.max_moves.is_some_and( |max_moves| self.game_tokens > max_moves.max(1) * MAX_TOKENS_PER_MOVE ) {
+            return Err(LexerError::LimitExceeded(String::from("A game has too many moves"), self.position()));
+        }
+
+        if ends_game {
+            self.games += 1;
+            self.game_tokens = 0;
+
+            if self.limits.max_games.is_some_and( |max_games| self.games > max_games ) {
+                return Err(LexerError::LimitExceeded(format!("More than {} games", self.games - 1), self.position()));
+            }
+        }
+
+        tokens.push(token);
+        self.positions.push(self.token_start);
+
+        Ok(())
+    }
+
+    // Where each of the tokens returned by lex() starts
+    pub fn into_positions(self) -> Vec<PositionInPGN> {
+        self.positions
+    }
+
+    // Where the token being read starts, e.g. the one an error happened in
+    pub fn token_start(&self) -> PositionInPGN {
+        self.token_start
+    }
+
+    fn push_comment_char(&self, comment: &mut String, c: char) -> Result<(), LexerError> {
+        comment.push(c);
+
+        if self.limits.max_comment_length.is_some_and( |max_length| comment.len() > max_length && comment.chars().count() > max_length ) {
+            return Err(LexerError::CommentTooLong(self.position()));
+        }
+
+        Ok(())
+    }
+
+    // Also the chess figurines, for moves in figurine algebraic notation
     fn is_symbol_start(c: &char) -> bool {
-        c.is_alphanumeric()
+        c.is_alphanumeric() || ('\u{2654}'..='\u{265F}').contains(c)
     }
 
     fn is_symbol_continuation(c: &char) -> bool {
         match c {
             '_' | '+' | '#' | '=' | ':' | '-' => true,
-            _ => c.is_alphanumeric()
+            _ => Self::is_symbol_start(c)
         }
     }
 
@@ -271,20 +391,31 @@ impl<'a> Lexer<'a>  {
         string.parse::<i64>()
     }
 
-    fn read_symbol(&mut self) -> String {
+    fn read_symbol(&mut self) -> Result<String, LexerError> {
         let mut string = String::new();
+        let mut length = 0;
 
-        loop {
-            let c = self.peek();
+        while let Some(c) = self.peek().cloned().filter(Self::is_symbol_continuation) {
+            self.next();
+            string.push(c);
+            length += 1;
 
-            match c {
-                None => break,
-                Some(c) if Self::is_symbol_continuation(c) => string.push(self.next().unwrap()),
-                Some(_) => break
+            if length > MAX_SYMBOL_LENGTH {
+                return Err(LexerError::SymbolTooLong(self.position()));
+            }
+        }
+
+        // The draw result is the only token with slashes in it
+        let rest_of_draw = "/2-1/2";
+
+        if string == "1" && self.pgn.clone().take(rest_of_draw.len()).eq(rest_of_draw.chars()) {
+            for c in rest_of_draw.chars() {
+                self.next();
+                string.push(c);
             }
         }
 
-        string
+        Ok(string)
     }
 
     fn next(&mut self) -> Option<char> {
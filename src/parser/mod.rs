@@ -1,14 +1,28 @@
 use std::vec::Vec;
+use std::collections::HashMap;
+use std::time::Duration;
 use lexer::*;
 use regex::Regex;
 use lazy_static::lazy_static;
 
 use super::{GameResult};
+use super::locale::PieceLetters;
 
 pub mod lexer;
 
+// Variations are skipped without recursion, this only keeps absurd nesting from being accepted
+const MAX_VARIATION_DEPTH: usize = 32;
+
+// "1." and "1..." are the usual ones, anything past this is garbage
+const MAX_PERIODS: usize = 3;
+
+static END_OF_FILE: Token = Token::EndOfFile;
+
+// Written after the move in descriptive notation, e.g. "Q-R5 ch". "e.p." is lexed as "e", ".", "p", "."
+const MOVE_SUFFIXES: [&str; 5] = ["ch", "mate", "dis", "dbl", "ep"];
+
 impl GameResult {
-    fn from_string(string: &str) -> Option<GameResult> {
+    pub(crate) fn from_string(string: &str) -> Option<GameResult> {
         match string {
             "*" => Some(GameResult::Unknown),
             "1-0" => Some(GameResult::WhiteWins),
@@ -18,7 +32,7 @@ impl GameResult {
         }
     }
 
-    fn to_string(&self) -> &'static str {
+    pub(crate) fn to_string(self) -> &'static str {
         match self {
             GameResult::Unknown   => "*",
             GameResult::WhiteWins => "1-0",
@@ -28,14 +42,18 @@ impl GameResult {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct PGNMove {
     pub number: Option<i64>,
     pub white_move: Option<String>,
-    pub black_move: Option<String>
+    pub black_move: Option<String>,
+
+    // Comments following the move, joined with spaces if there are several
+    pub white_comment: Option<String>,
+    pub black_comment: Option<String>
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ParsedGame {
     pub setup: Option<bool>,
     pub fen: Option<String>,
@@ -46,15 +64,166 @@ pub struct ParsedGame {
     pub other_tags: Vec<(String, String)>
 }
 
-struct TagPairSection {
-    tag_pairs: Vec<(String, String)>
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PGNHeaders {
+    pub event: Option<String>,
+    pub site: Option<String>,
+    pub date: Option<String>,
+    pub round: Option<String>,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub result: Option<String>,
+
+    pub white_elo: Option<i64>,
+    pub black_elo: Option<i64>,
+    pub eco: Option<String>,
+    pub opening: Option<String>,
+
+    pub setup: Option<bool>,
+    pub fen: Option<String>,
+    pub variant: Option<String>,
+
+    pub termination: Option<String>,
+    pub time_control: Option<String>,
+
+    pub other: HashMap<String, String>
+}
+
+impl PGNHeaders {
+    pub fn is_standard_variant(&self) -> bool {
+        match &self.variant {
+            Some(variant) => matches!(variant.to_lowercase().as_str(), "standard" | "chess" | "from position"),
+            None => true
+        }
+    }
+}
+
+impl ParsedGame {
+    pub(crate) fn new(tag_pairs: Vec<(String, String)>, moves: Vec<PGNMove>, result: GameResult) -> Self {
+        ParsedGame {
+            setup: tag_pairs.iter()
+                .find( |(key, _)| key == "SetUp" )
+                .map( |(_, value)| value == "1" ),
+
+            fen: tag_pairs.iter()
+                .find( |(key, _)| key == "FEN" )
+                .map( |(_, value)| value.clone() ),
+
+            other_tags: tag_pairs,
+
+            moves,
+            result
+        }
+    }
+
+    pub fn headers(&self) -> PGNHeaders {
+        let mut headers = PGNHeaders::default();
+
+        for (name, value) in &self.other_tags {
+            let value = value.clone();
+
+            match name.as_str() {
+                "Event"       => headers.event = Some(value),
+                "Site"        => headers.site = Some(value),
+                "Date"        => headers.date = Some(value),
+                "Round"       => headers.round = Some(value),
+                "White"       => headers.white = Some(value),
+                "Black"       => headers.black = Some(value),
+                "Result"      => headers.result = Some(value),
+
+                "WhiteElo"    => headers.white_elo = value.parse().ok(),
+                "BlackElo"    => headers.black_elo = value.parse().ok(),
+                "ECO"         => headers.eco = Some(value),
+                "Opening"     => headers.opening = Some(value),
+
+                "SetUp"       => headers.setup = Some(value == "1"),
+                "FEN"         => headers.fen = Some(value),
+                "Variant"     => headers.variant = Some(value),
+
+                "Termination" => headers.termination = Some(value),
+                "TimeControl" => headers.time_control = Some(value),
+
+                _ => { headers.other.insert(name.clone(), value); }
+            }
+        }
+
+        headers
+    }
+
+    // One entry per half-move, in the order they are played
+    pub fn half_move_comments(&self) -> Vec<Option<&str>> {
+        let mut comments = Vec::new();
+
+        for pgn_move in self.moves.iter() {
+            if pgn_move.white_move.is_some() {
+                comments.push(pgn_move.white_comment.as_deref());
+            }
+
+            if pgn_move.black_move.is_some() {
+                comments.push(pgn_move.black_comment.as_deref());
+            }
+        }
+
+        comments
+    }
+
+    // Remaining clock time after every half-move, from `[%clk H:MM:SS]` comments
+    pub fn clocks(&self) -> Vec<Option<Duration>> {
+        self.half_move_comments().into_iter()
+            .map( |comment| comment.and_then(parse_clock) )
+            .collect()
+    }
+}
+
+fn parse_clock(comment: &str) -> Option<Duration> {
+    lazy_static! {
+        static ref CLOCK_REGEX: regex::Regex =
+            Regex::new(r"\[%clk\s+(\d+):(\d+):(\d+(?:\.\d+)?)\s*\]")
+                .expect("Invalid regular expression");
+    }
+
+    let captures = CLOCK_REGEX.captures(comment)?;
+
+    let hours: u64 = captures[1].parse().ok()?;
+    let minutes: u64 = captures[2].parse().ok()?;
+    let seconds: f64 = captures[3].parse().ok()?;
+
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+// Caps for parsing untrusted input, so that a malicious file can't use up the memory of a server.
+// The defaults don't limit anything.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ParseLimits {
+    pub max_games: Option<usize>,
+
+    // Numbered moves, so a white and a black move count as one
+    pub max_moves: Option<usize>,
+
+    // Applies to tag names and values. The PGN standard allows 255 characters.
+    pub max_tag_length: Option<usize>,
+
+    // In characters, for both brace and rest of line comments
+    pub max_comment_length: Option<usize>
+}
+
+impl ParseLimits {
+    pub fn untrusted() -> Self {
+        ParseLimits {
+            max_games: Some(100_000),
+            max_moves: Some(1000),
+            max_tag_length: Some(255),
+            max_comment_length: Some(10_000)
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum ParseError {
     UnexpectedToken(Token),
     InvalidGameResult(String),
-    UnexpectedEndOfFile
+    UnexpectedEndOfFile,
+    LimitExceeded(String)
 }
 
 impl std::convert::Into<String> for ParseError {
@@ -65,6 +234,7 @@ impl std::convert::Into<String> for ParseError {
 
             // TODO: Very very unhelpful. Idea: pass last token & expected next token
             ParseError::UnexpectedEndOfFile => format!("Unexpected end of file"),
+            ParseError::LimitExceeded(limit) => limit,
         }
     }
 }
@@ -119,7 +289,7 @@ macro_rules! consume_value_optional {
                 if let $pattern = $self.read()? {
                     Some($variable)
                 } else {
-                    panic!("Read token != the peeked one");
+                    return Err(ParseError::UnexpectedEndOfFile);
                 }
             } else {
                 None
@@ -136,7 +306,7 @@ macro_rules! consume_value_optional_if {
                     if let $pattern = $self.read()? {
                         Some($variable)
                     } else {
-                        panic!("Read token != the peeked one");
+                        return Err(ParseError::UnexpectedEndOfFile);
                     }
                 } else {
                     None
@@ -149,20 +319,41 @@ macro_rules! consume_value_optional_if {
 }
 
 pub struct Parser {
-    tokens: Vec<Token>
+    tokens: Vec<Token>,
+
+    // Where each token starts, reversed like the tokens. Empty unless given.
+    positions: Vec<PositionInPGN>,
+    limits: ParseLimits
 }
 
 impl Parser {
     pub fn new(mut tokens: Vec<Token>) -> Self {
         tokens.reverse();
 
-        Self { tokens }
+        Self { tokens, positions: Vec::new(), limits: ParseLimits::default() }
+    }
+
+    // The positions from Lexer::into_positions, so that errors and moves can be traced back to the PGN
+    pub fn with_positions(mut self, mut positions: Vec<PositionInPGN>) -> Self {
+        positions.reverse();
+
+        self.positions = positions;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
     }
 
     pub fn parse(&mut self) -> Result<Vec<ParsedGame>, ParseError> {
         let mut games: Vec<ParsedGame> = Vec::new();
 
         while self.peek() != &Token::EndOfFile {
+            if self.limits.max_games.is_some_and( |max_games| games.len() >= max_games ) {
+                return Err(ParseError::LimitExceeded(format!("More than {} games", games.len())));
+            }
+
             let game = self.parse_game()?;
 
             games.push(game);
@@ -171,29 +362,25 @@ impl Parser {
         Ok(games)
     }
 
-    fn parse_game(&mut self) -> Result<ParsedGame, ParseError> {
-        let tag_pair_section = self.parse_tag_pair_section()?;
-        let moves = self.parse_move_text_section()?;
-
-        let result = self.parse_game_result()?;
+    pub(crate) fn is_at_end(&self) -> bool {
+        self.peek() == &Token::EndOfFile
+    }
 
-        Ok(ParsedGame {
-            setup: tag_pair_section.tag_pairs.iter()
-                .find( |(key, _)| key == "SetUp" )
-                .map( |(_, value)| value == "1" ),
+    // Where the next token starts, if the parser was given positions
+    pub(crate) fn next_position(&self) -> Option<PositionInPGN> {
+        self.positions.last().copied()
+    }
 
-            fen: tag_pair_section.tag_pairs.iter()
-                .find( |(key, _)| key == "FEN" )
-                .map( |(_, value)| value.clone() ),
+    fn parse_game(&mut self) -> Result<ParsedGame, ParseError> {
+        let tag_pairs = self.parse_tag_pair_section()?;
 
-            other_tags: tag_pair_section.tag_pairs,
+        let mut moves = Vec::new();
+        let result = self.parse_move_text_with(&mut |pgn_move, _| moves.push(pgn_move) )?;
 
-            moves,
-            result
-        })
+        Ok(ParsedGame::new(tag_pairs, moves, result))
     }
 
-    fn parse_tag_pair_section(&mut self) -> Result<TagPairSection, ParseError> {
+    pub(crate) fn parse_tag_pair_section(&mut self) -> Result<Vec<(String, String)>, ParseError> {
         let mut tag_pairs = Vec::new();
 
         while self.peek() == &Token::OpenBracket {
@@ -202,7 +389,7 @@ impl Parser {
             tag_pairs.push(tag_pair);
         }
 
-        Ok(TagPairSection { tag_pairs })
+        Ok(tag_pairs)
     }
 
     fn parse_tag_pair(&mut self) -> Result<(String, String), ParseError> {
@@ -213,78 +400,169 @@ impl Parser {
 
         consume!(self, Token::CloseBracket);
 
+        if let Some(max_tag_length) = self.limits.max_tag_length {
+            if name.chars().count() > max_tag_length || value.chars().count() > max_tag_length {
+                return Err(ParseError::LimitExceeded(format!("Tag {} is longer than {} characters", name, max_tag_length)));
+            }
+        }
+
         Ok((name, value))
     }
 
-    fn parse_move_text_section(&mut self) -> Result<Vec<PGNMove>, ParseError> {
-        let mut moves = Vec::new();
+    // The move text and the result. The moves are handed to `visit` as they are read instead of being kept,
+    // together with where their white and black moves start.
+    pub(crate) fn parse_move_text_with(&mut self, visit: &mut dyn FnMut(PGNMove, [Option<PositionInPGN>; 2])) -> Result<GameResult, ParseError> {
+        let mut moves = 0;
 
         while !Self::is_game_end(self.peek()) {
-            let current_move = self.parse_move()?;
+            if self.limits.max_moves.is_some_and( |max_moves| moves >= max_moves ) {
+                return Err(ParseError::LimitExceeded(format!("A game has more than {} moves", moves)));
+            }
+
+            let remaining = self.tokens.len();
+            let (current_move, positions) = self.parse_move()?;
 
-            moves.push(current_move);
+            // Nothing could be read as a move, which would otherwise loop forever
+            if self.tokens.len() == remaining {
+                return Err(ParseError::UnexpectedToken(self.peek().clone()));
+            }
+
+            moves += 1;
+            visit(current_move, positions);
         }
 
-        Ok(moves)
+        self.parse_game_result()
     }
 
     fn is_game_end(token: &Token) -> bool {
         match token {
             Token::Symbol(result) => GameResult::from_string(result).is_some(),
+            Token::Asterisk => true,
             _ => false
         }
     }
 
-    fn parse_move(&mut self) -> Result<PGNMove, ParseError> {
+    fn parse_move(&mut self) -> Result<(PGNMove, [Option<PositionInPGN>; 2]), ParseError> {
         self.ignore_comments()?;
 
         let number = consume_value_optional!(self, Token::Integer(value), value);
         if number.is_some() {
             consume!(self, Token::Period);
 
-            while consume_optional!(self, Token::Period) {}
+            let mut periods = 1;
+
+            while consume_optional!(self, Token::Period) {
+                periods += 1;
+
+                if periods > MAX_PERIODS {
+                    return Err(ParseError::UnexpectedToken(Token::Period));
+                }
+            }
 
             self.ignore_comments()?;
         }
 
+        let white_position = self.next_position();
         let white_move = consume_value_optional_if!(
             self, Token::Symbol(value), value,
             Self::is_possibly_a_move(value)
         );
-        self.ignore_comments()?;
+        let white_move = self.read_move_suffixes(white_move)?;
+        let white_comment = self.read_comments()?;
 
+        let black_position = self.next_position();
         let black_move = consume_value_optional_if!(
             self, Token::Symbol(value), value,
             Self::is_possibly_a_move(value)
         );
-        self.ignore_comments()?;
+        let black_move = self.read_move_suffixes(black_move)?;
+        let black_comment = self.read_comments()?;
+
+        let positions = [white_position.filter( |_| white_move.is_some() ), black_position.filter( |_| black_move.is_some() )];
+
+        Ok((PGNMove { number, white_move, black_move, white_comment, black_comment }, positions))
+    }
+
+    // The suffixes are kept with the move, separated by spaces
+    fn read_move_suffixes(&mut self, notation: Option<String>) -> Result<Option<String>, ParseError> {
+        let mut notation = match notation {
+            Some(notation) => notation,
+            None => return Ok(None)
+        };
+
+        loop {
+            let next = |i: usize| self.tokens.len().checked_sub(i + 1).map( |index| &self.tokens[index] );
+
+            let is_symbol = |token: Option<&Token>, text: &str| matches!(token, Some(Token::Symbol(symbol)) if symbol.eq_ignore_ascii_case(text));
+            let is_period = |token: Option<&Token>| token == Some(&Token::Period);
+
+            let (suffix, tokens) = match next(0) {
+                Some(Token::Symbol(suffix)) if MOVE_SUFFIXES.iter().any( |known| suffix.eq_ignore_ascii_case(known) ) => (suffix.clone(), 1),
+                _ if is_symbol(next(0), "e") && is_period(next(1)) && is_symbol(next(2), "p") && is_period(next(3)) => (String::from("e.p."), 4),
+                _ => break
+            };
 
-        Ok(PGNMove { number, white_move, black_move })
+            for _ in 0..tokens {
+                self.read()?;
+            }
+
+            notation.push(' ');
+            notation.push_str(&suffix);
+        }
+
+        Ok(Some(notation))
     }
 
     fn parse_game_result(&mut self) -> Result<GameResult, ParseError> {
         self.ignore_comments()?;
 
+        if consume_optional!(self, Token::Asterisk) {
+            return Ok(GameResult::Unknown);
+        }
+
         let outcome = consume_value!(self, Token::Symbol(outcome), outcome);
 
         GameResult::from_string(&outcome)
             .ok_or(ParseError::InvalidGameResult(outcome))
     }
 
+    // The piece letters of any of the known languages, so that games using them or figurines can be read as well
     fn is_possibly_a_move(notation: &str) -> bool {
         lazy_static! {
-            static ref VALID_MOVE_REGEX: regex::Regex =
-                Regex::new(r"^(?i)[PNBRQK]?([a-h]?[1-8]?)x?[a-h][1-8](=[NBRQK])?[#\+]?$")
+            static ref VALID_MOVE_REGEX: regex::Regex = {
+                let mut letters = PieceLetters::all_known_letters();
+
+                // Longest first, so that the Russian king is tried before the knight
+                letters.sort_by_key( |letter| std::cmp::Reverse(letter.len()) );
+
+                let piece = letters.iter().map( |letter| regex::escape(letter) ).collect::<Vec<_>>().join("|");
+
+                Regex::new(&format!(r"^(?i)({})?([a-h]?[1-8]?)x?[a-h][1-8](=({}))?[#\+]?$", piece, piece))
+                    .expect("Invalid regular expression")
+            };
+        }
+
+        // Descriptive notation, e.g. "P-K4", "KtxP" or "P-QB8=Q"
+        lazy_static! {
+            static ref DESCRIPTIVE_MOVE_REGEX: regex::Regex =
+                Regex::new(r"^(?i)[KQRNBPT]+[-x][KQRNBPT]*[1-8]?(=[NBRQ])?(ch|mate|[#\+])?$")
                     .expect("Invalid regular expression");
         }
 
-        VALID_MOVE_REGEX.is_match(notation)
+        // Castling, also written with zeros
+        lazy_static! {
+            static ref CASTLING_REGEX: regex::Regex =
+                Regex::new(r"^(O-O(-O)?|0-0(-0)?)[#\+]?$").expect("Invalid regular expression");
+        }
+
+        VALID_MOVE_REGEX.is_match(notation) || DESCRIPTIVE_MOVE_REGEX.is_match(notation) || CASTLING_REGEX.is_match(notation)
     }
 
     fn ignore_comments(&mut self) -> Result<(), ParseError> {
         loop {
             match self.peek() {
-                Token::Comment(_) => { self.read()?; },
+                Token::Comment(_) | Token::NumericAnnotationGlyph(_) => { self.read()?; },
+                Token::OpenParen => self.skip_variation()?,
                 _ => break
             }
         }
@@ -292,12 +570,58 @@ impl Parser {
         Ok(())
     }
 
+    // Variations and annotation glyphs in between are skipped
+    fn read_comments(&mut self) -> Result<Option<String>, ParseError> {
+        let mut comments = Vec::new();
+
+        loop {
+            match self.peek() {
+                Token::Comment(_) => if let Token::Comment(comment) = self.read()? {
+                    comments.push(comment.trim().to_string());
+                },
+                Token::NumericAnnotationGlyph(_) => { self.read()?; },
+                Token::OpenParen => self.skip_variation()?,
+                _ => break
+            }
+        }
+
+        if comments.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(comments.join(" ")))
+        }
+    }
+
+    // Recursive annotation variations aren't kept, only their nesting is checked
+    fn skip_variation(&mut self) -> Result<(), ParseError> {
+        consume!(self, Token::OpenParen);
+
+        let mut depth = 1;
+
+        while depth > 0 {
+            match self.read()? {
+                Token::OpenParen => depth += 1,
+                Token::CloseParen => depth -= 1,
+                Token::EndOfFile => return Err(ParseError::UnexpectedEndOfFile),
+                _ => ()
+            }
+
+            if depth > MAX_VARIATION_DEPTH {
+                return Err(ParseError::LimitExceeded(format!("Variations nested deeper than {}", MAX_VARIATION_DEPTH)));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Token streams not ending with EndOfFile are treated as if they did
     fn peek(&self) -> &Token {
-        &self.tokens.last().expect("Tried to get token after the end of tokens")
+        self.tokens.last().unwrap_or(&END_OF_FILE)
     }
 
     fn read(&mut self) -> Result<Token, ParseError> {
         let token = self.tokens.pop();
+        self.positions.pop();
 
         match token {
             None => Err(ParseError::UnexpectedEndOfFile),
@@ -0,0 +1,154 @@
+use super::super::game::Game;
+use super::super::parser::ParseLimits;
+
+// The tags of a game and the game itself
+pub type TaggedGame = (Vec<(String, String)>, Game);
+
+// Reads the games of a large PGN one at a time, so that the caller can stop in between, e.g. to keep a page
+// responsive or to report progress. A game which can't be read doesn't stop the ones after it.
+pub struct PgnChunks<'a> {
+    pgn: &'a str,
+    offset: usize,
+    limits: ParseLimits,
+
+    pending: std::vec::IntoIter<Result<TaggedGame, String>>,
+    games_read: usize
+}
+
+impl<'a> PgnChunks<'a> {
+    pub fn new(pgn: &'a str) -> Self {
+        PgnChunks {
+            pgn,
+            offset: 0,
+            limits: ParseLimits::default(),
+
+            pending: Vec::new().into_iter(),
+            games_read: 0
+        }
+    }
+
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // Continues reading at `offset`, which should be the bytes_consumed() after an earlier next_chunk
+    pub fn starting_at(mut self, offset: usize) -> Self {
+        self.offset = offset.min(self.pgn.len());
+        self
+    }
+
+    // Tags and game of at least `max_games` games, unless the PGN ends before that. Pieces of text holding
+    // more than one game aren't split, so that bytes_consumed() is always a place to continue from.
+    pub fn next_chunk(&mut self, max_games: usize) -> Vec<Result<TaggedGame, String>> {
+        let mut games: Vec<_> = self.pending.by_ref().collect();
+
+        while games.len() < max_games && self.offset < self.pgn.len() {
+            self.read_next_game();
+            games.extend(self.pending.by_ref());
+        }
+
+        self.games_read += games.len();
+
+        games
+    }
+
+    pub fn games_read(&self) -> usize {
+        self.games_read
+    }
+
+    pub fn bytes_consumed(&self) -> usize {
+        self.offset
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.pgn.len()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.offset >= self.pgn.len() && self.pending.len() == 0
+    }
+
+    fn read_next_game(&mut self) {
+        let end = next_game_start(self.pgn, self.offset);
+        let text = &self.pgn[self.offset..end];
+
+        self.offset = end;
+
+        // Games without tags aren't split apart, so one piece of text can hold several
+        self.pending = match Game::parse_pgn_with_limits(text, self.limits) {
+            Ok(pgn_games) => pgn_games.into_iter()
+                .map( |pgn_game| {
+                    let tags = pgn_game.other_tags.clone();
+                    let mut replay = Game::replay(pgn_game);
+
+                    for step in &mut replay {
+                        step?;
+                    }
+
+                    Ok((tags, replay.into_game()))
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
+
+            Err(error) => vec![Err(error)].into_iter()
+        };
+    }
+}
+
+impl<'a> Iterator for PgnChunks<'a> {
+    type Item = Result<TaggedGame, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(game) = self.pending.next() {
+                self.games_read += 1;
+
+                return Some(game);
+            }
+
+            if self.offset >= self.pgn.len() {
+                return None;
+            }
+
+            self.read_next_game();
+        }
+    }
+}
+
+// Where the tag section of the next game starts: the first line starting with "[" after some move text,
+// not counting comments
+pub(super) fn next_game_start(pgn: &str, from: usize) -> usize {
+    let bytes = pgn.as_bytes();
+
+    let mut seen_move_text = false;
+    let mut in_comment = false;
+    let mut in_line_comment = false;
+    let mut line_start = true;
+    let mut tag_line = false;
+
+    for (index, &byte) in bytes.iter().enumerate().skip(from) {
+        if line_start && !in_comment && byte == b'[' && seen_move_text {
+            return index;
+        }
+
+        if line_start {
+            tag_line = !in_comment && byte == b'[';
+        }
+
+        line_start = byte == b'\n';
+
+        match byte {
+            b'\n'                                  => in_line_comment = false,
+            _ if in_line_comment                   => (),
+            b'{' if !in_comment                    => in_comment = true,
+            b'}' if in_comment                     => in_comment = false,
+            b';' if !in_comment                    => in_line_comment = true,
+            _ if in_comment || tag_line            => (),
+            _ if !byte.is_ascii_whitespace()       => seen_move_text = true,
+            _                                      => ()
+        }
+    }
+
+    bytes.len()
+}
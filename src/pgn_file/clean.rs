@@ -0,0 +1,305 @@
+use regex::Regex;
+use lazy_static::lazy_static;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CleanOptions {
+    // The embedded commands to keep, by name without the "%", e.g. "clk". The others ("eval", "csl", ...) are
+    // removed. None keeps all of them. Kept commands are normalized to "[%name value]" at the start of the
+    // comment.
+    pub keep_commands: Option<Vec<String>>,
+
+    // Comments written by engines or analysis tools, e.g. "+0.35/18 1.2s" or "Mistake. Nf3 was best."
+    pub remove_engine_comments: bool,
+
+    // Longer comments are split into several at spaces, longer tag values are cut. The PGN standard allows 255
+    // characters in strings.
+    pub max_string_length: usize
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        CleanOptions { keep_commands: None, remove_engine_comments: true, max_string_length: 255 }
+    }
+}
+
+impl CleanOptions {
+    // Only the text of the comments stays, e.g. for printing
+    pub fn text_only() -> Self {
+        CleanOptions { keep_commands: Some(Vec::new()), ..Self::default() }
+    }
+}
+
+// What the cleaning changed
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct CleanReport {
+    pub removed_commands: usize,
+    pub removed_comments: usize,
+    pub split_comments: usize,
+    pub truncated_tags: usize,
+
+    // Stray closing braces which were dropped and unterminated comments which were closed
+    pub fixed_braces: usize
+}
+
+// Cleans up the comments of a PGN for publishing, without reading the games, so that games which don't parse yet
+// because of a missing brace can be fixed too. A comment missing its closing brace is closed before the next
+// opening brace, or at the end of its line (before a result there) when it would run into the next game or the end
+// of the file. The moves, variations and tags are copied as they are, apart from cutting tag values which are too
+// long.
+pub fn clean_pgn(pgn: &str, options: &CleanOptions) -> (String, CleanReport) {
+    let mut cleaned = String::with_capacity(pgn.len());
+    let mut report = CleanReport::default();
+
+    let mut rest = pgn;
+    let mut line_start = true;
+
+    while let Some(c) = rest.chars().next() {
+        if line_start && (c == '[' || c == '%') {
+            let end = rest.find('\n').unwrap_or(rest.len());
+
+            // Lines starting with "%" are escaped from the PGN and copied as they are
+            if c == '%' {
+                cleaned.push_str(&rest[..end]);
+            } else {
+                cleaned.push_str(&clean_tag(&rest[..end], options, &mut report));
+            }
+
+            rest = &rest[end..];
+            continue;
+        }
+
+        match c {
+            '{' => {
+                let (body, consumed, terminated) = read_comment(&rest[1..]);
+
+                if !terminated {
+                    report.fixed_braces += 1;
+                }
+
+                let comments = clean_comment(body, options, &mut report);
+
+                if comments.is_empty() {
+                    // Keeps "e4 {} e5" from turning into "e4  e5"
+                    if cleaned.ends_with([' ', '\t', '\n']) {
+                        rest = rest[1 + consumed..].trim_start_matches([' ', '\t']);
+                        continue;
+                    }
+                } else {
+                    let braced: Vec<String> = comments.iter().map( |comment| format!("{{{}}}", comment) ).collect();
+                    cleaned.push_str(&braced.join(" "));
+                }
+
+                rest = &rest[1 + consumed..];
+                line_start = false;
+            },
+
+            '}' => {
+                report.fixed_braces += 1;
+                rest = &rest[1..];
+
+                if cleaned.ends_with([' ', '\t', '\n']) {
+                    rest = rest.trim_start_matches([' ', '\t']);
+                }
+            },
+
+            ';' => {
+                let end = rest.find('\n').unwrap_or(rest.len());
+
+                cleaned.push_str(&rest[..end]);
+                rest = &rest[end..];
+            },
+
+            _ => {
+                cleaned.push(c);
+                rest = &rest[c.len_utf8()..];
+
+                if c == '\n' {
+                    line_start = true;
+                } else if !c.is_whitespace() {
+                    line_start = false;
+                }
+            }
+        }
+    }
+
+    (cleaned, report)
+}
+
+// The body of the comment starting right after its "{", how much of the text it takes up including the "}",
+// and whether there was a "}"
+fn read_comment(text: &str) -> (&str, usize, bool) {
+    lazy_static! {
+        static ref TAG_LINE: Regex = Regex::new(r#"^\n\s*\[[A-Za-z0-9_]+\s+""#).expect("Invalid regular expression");
+    }
+
+    for (index, c) in text.char_indices() {
+        match c {
+            '}' => return (&text[..index], index + 1, true),
+            '{' => return (&text[..index], index, false),
+            '\n' if TAG_LINE.is_match(&text[index..]) => break,
+            _ => ()
+        }
+    }
+
+    // A result at the end of the line is left out of the comment
+    let line = &text[..text.find('\n').unwrap_or(text.len())];
+    let end = ["1-0", "0-1", "1/2-1/2", "*"].iter()
+        .find_map( |result| line.trim_end().strip_suffix(result) )
+        .map( |body| body.trim_end().len() )
+        .unwrap_or(line.len());
+
+    (&text[..end], end, false)
+}
+
+// The comments to write instead of the one with the body, none if nothing is left of it
+fn clean_comment(body: &str, options: &CleanOptions, report: &mut CleanReport) -> Vec<String> {
+    lazy_static! {
+        static ref COMMAND: Regex = Regex::new(r"\[%(\w+)([^\]]*)\]").expect("Invalid regular expression");
+    }
+
+    let mut words = Vec::new();
+
+    for captures in COMMAND.captures_iter(body) {
+        let name = &captures[1];
+        let kept = options.keep_commands.as_ref().is_none_or( |keep| keep.iter().any( |kept| kept == name ) );
+
+        if !kept {
+            report.removed_commands += 1;
+            continue;
+        }
+
+        let value = collapse_whitespace(&captures[2]);
+
+        if value.is_empty() {
+            words.push(format!("[%{}]", name));
+        } else {
+            words.push(format!("[%{} {}]", name, value));
+        }
+    }
+
+    let text = collapse_whitespace(&COMMAND.replace_all(body, " "));
+
+    let engine_comment = options.remove_engine_comments && is_engine_comment(&text);
+
+    if !text.is_empty() && !engine_comment {
+        words.push(text);
+    }
+
+    if words.is_empty() {
+        if !body.trim().is_empty() {
+            report.removed_comments += 1;
+        }
+
+        return Vec::new();
+    }
+
+    let comments = split_comment(&words.join(" "), options.max_string_length);
+
+    if comments.len() > 1 {
+        report.split_comments += 1;
+    }
+
+    comments
+}
+
+fn is_engine_comment(text: &str) -> bool {
+    lazy_static! {
+        static ref ENGINE_COMMENTS: Vec<Regex> = [
+            // cutechess-cli and Arena, the score and depth and maybe the time
+            r"^[+-]?(\d+(\.\d+)?|M\d+)/\d+(\s+\d+(\.\d+)?s?)?$",
+            r"^book$",
+
+            // The lichess analysis
+            r"^(Inaccuracy|Mistake|Blunder)\. \S+ was best\.$",
+            r"^\(?[+-]?\d+\.\d+\s*(→|->)\s*[+-]?\d+\.\d+\)?",
+
+            // Engine output pasted into the comment
+            r"\b(depth|nodes|nps)\s*[=:]?\s*\d+"
+        ].iter().map( |pattern| Regex::new(pattern).expect("Invalid regular expression") ).collect();
+    }
+
+    ENGINE_COMMENTS.iter().any( |pattern| pattern.is_match(text) )
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// At spaces where possible, words longer than the limit are split anywhere
+fn split_comment(comment: &str, max_length: usize) -> Vec<String> {
+    let max_length = max_length.max(1);
+    let mut comments: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_length = 0;
+
+    for word in comment.split(' ') {
+        let mut word: Vec<char> = word.chars().collect();
+
+        while word.len() > max_length {
+            if !current.is_empty() {
+                comments.push(std::mem::take(&mut current));
+            }
+
+            comments.push(word.drain(..max_length).collect());
+            current_length = 0;
+        }
+
+        if !current.is_empty() && current_length + 1 + word.len() > max_length {
+            comments.push(std::mem::take(&mut current));
+            current_length = 0;
+        }
+
+        if !current.is_empty() {
+            current.push(' ');
+            current_length += 1;
+        }
+
+        current.extend(word.iter());
+        current_length += word.len();
+    }
+
+    if !current.is_empty() {
+        comments.push(current);
+    }
+
+    comments
+}
+
+// Cuts the value of a tag pair line, keeping escape sequences whole. Anything which isn't a tag pair is kept.
+fn clean_tag(line: &str, options: &CleanOptions, report: &mut CleanReport) -> String {
+    lazy_static! {
+        static ref TAG: Regex = Regex::new(r#"^\[([A-Za-z0-9_]+)(\s+)"((?:[^"\\]|\\.)*)"\]"#).expect("Invalid regular expression");
+    }
+
+    let captures = match TAG.captures(line) {
+        Some(captures) => captures,
+        None => return String::from(line)
+    };
+
+    let value = &captures[3];
+    let mut cut = String::new();
+    let mut length = 0;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if length == options.max_string_length {
+            report.truncated_tags += 1;
+
+            let whole = captures.get(0).map( |whole| whole.end() ).unwrap_or(line.len());
+
+            return format!("[{}{}\"{}\"]{}", &captures[1], &captures[2], cut, &line[whole..]);
+        }
+
+        cut.push(c);
+
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                cut.push(escaped);
+            }
+        }
+
+        length += 1;
+    }
+
+    String::from(line)
+}
@@ -0,0 +1,111 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::game::{Game, MoveAnnotation, PgnProfile};
+
+mod chunks;
+mod clean;
+mod validate;
+
+pub use chunks::{PgnChunks, TaggedGame};
+pub use clean::{clean_pgn, CleanOptions, CleanReport};
+pub use validate::{validate, Issue, IssueKind};
+
+// Appends finished games to a PGN file or stream one at a time, so that a crash only loses the game being played
+pub struct PgnFileWriter<W: Write> {
+    out: W,
+    profile: PgnProfile,
+
+    // A second handle to the file, to wait for every game to reach the disk
+    sync: Option<File>,
+
+    // Set when something was written before, so that the next game starts after a blank line
+    needs_separator: bool,
+    games_written: usize
+}
+
+impl PgnFileWriter<File> {
+    // Creates the file if it doesn't exist, otherwise adds the games after the ones already there
+    pub fn append<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let length = file.metadata()?.len();
+
+        let mut tail = Vec::new();
+
+        if length > 0 {
+            file.seek(SeekFrom::Start(length.saturating_sub(2)))?;
+            file.read_to_end(&mut tail)?;
+        }
+
+        // Finish the last line of a file which doesn't end with one
+        if !tail.is_empty() && !tail.ends_with(b"\n") {
+            file.write_all(b"\n")?;
+        }
+
+        let mut writer = Self::new(file);
+        writer.needs_separator = !tail.is_empty() && !tail.ends_with(b"\n\n");
+
+        Ok(writer)
+    }
+
+    // Waits for every written game to reach the disk before returning from `write_game`
+    pub fn with_fsync(mut self) -> io::Result<Self> {
+        self.sync = Some(self.out.try_clone()?);
+
+        Ok(self)
+    }
+}
+
+impl<W: Write> PgnFileWriter<W> {
+    pub fn new(out: W) -> Self {
+        PgnFileWriter {
+            out,
+            profile: PgnProfile::export(),
+            sync: None,
+            needs_separator: false,
+            games_written: 0
+        }
+    }
+
+    pub fn with_profile(mut self, profile: PgnProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn write_game(&mut self, game: &Game, tags: &[(String, String)]) -> io::Result<()> {
+        self.write_annotated_game(game, tags, &[])
+    }
+
+    // Each game is written with a single call and flushed, so that a game is either in the file as a whole or not at all
+    pub fn write_annotated_game(&mut self, game: &Game, tags: &[(String, String)], annotations: &[MoveAnnotation]) -> io::Result<()> {
+        let mut text = String::new();
+
+        if self.needs_separator {
+            text.push('\n');
+        }
+
+        text.push_str(&game.to_pgn_with_profile(tags, annotations, &self.profile));
+
+        self.out.write_all(text.as_bytes())?;
+        self.out.flush()?;
+
+        if let Some(file) = &self.sync {
+            file.sync_data()?;
+        }
+
+        self.needs_separator = true;
+        self.games_written += 1;
+
+        Ok(())
+    }
+
+    // Games written since the writer was created
+    pub fn games_written(&self) -> usize {
+        self.games_written
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
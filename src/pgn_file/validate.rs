@@ -0,0 +1,190 @@
+use std::io::BufRead;
+
+use super::super::models::GameResult;
+use super::super::game::{Game, ValidMove, ReplayError};
+use super::super::parser::{Parser, ParseError, ParsedGame};
+use super::super::parser::lexer::{Lexer, Token, PositionInPGN};
+use super::chunks::next_game_start;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IssueKind {
+    // Characters which aren't PGN, e.g. a tab or an unterminated string
+    Lexical,
+
+    // Tokens in the wrong place, e.g. a missing result
+    Syntax,
+
+    // A FEN or variant which can't be set up, or an illegal move
+    Replay,
+
+    // The reader failed or the file isn't UTF-8, nothing after it is checked
+    Io
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Issue {
+    // The index of the game in the file, counted from 0
+    pub game: usize,
+
+    // Where the token starts, both counted from 1
+    pub line: usize,
+    pub column: usize,
+
+    pub kind: IssueKind,
+    pub message: String
+}
+
+// Checks that every game of the PGN would be read and replayed by Game::new_from_pgn, without keeping the games
+// or the file in memory: the file is read one line at a time and each game goes through the lexer and the parser
+// on its own, with only the current position of its moves kept. No issues means the file is fine. Only the first
+// issue of a game is reported, the rest of it is skipped up to the next tag pair at the start of a line.
+pub fn validate<R: BufRead>(mut reader: R) -> Vec<Issue> {
+    let mut validator = Validator { issues: Vec::new(), game: 0 };
+
+    // The lines of the game being read and the number of the first one
+    let mut text = String::new();
+    let mut first_line = 1;
+    let mut lines = 0;
+
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {
+                let start = text.len();
+                text.push_str(&line);
+
+                if line.starts_with('[') && next_game_start(&text, 0) == start {
+                    validator.check(&text[..start], first_line);
+
+                    text.drain(..start);
+                    first_line += lines;
+                    lines = 0;
+                }
+
+                lines += 1;
+            },
+            Err(error) => {
+                validator.issues.push(Issue { game: validator.game, line: first_line + lines, column: 1, kind: IssueKind::Io, message: error.to_string() });
+                return validator.issues;
+            }
+        }
+    }
+
+    validator.check(&text, first_line);
+    validator.issues
+}
+
+// Something with where in the text it starts
+type Located<T> = (Option<PositionInPGN>, T);
+
+struct Validator {
+    issues: Vec<Issue>,
+    game: usize
+}
+
+impl Validator {
+    // The text of one or more games, the ones after the first without tags
+    fn check(&mut self, text: &str, first_line: usize) {
+        let mut lexer = Lexer::new(text);
+
+        let tokens = match lexer.lex() {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                self.issue(first_line, Some(lexer.token_start()), IssueKind::Lexical, error.description());
+                self.game += 1;
+
+                return;
+            }
+        };
+
+        let mut parser = Parser::new(tokens).with_positions(lexer.into_positions());
+
+        while !parser.is_at_end() {
+            match Self::check_game(&mut parser) {
+                Ok(replay_issue) => {
+                    if let Some((position, message)) = replay_issue {
+                        self.issue(first_line, position, IssueKind::Replay, message);
+                    }
+
+                    self.game += 1;
+                },
+
+                // Where the parser is afterwards isn't known, so the rest of the text is skipped
+                Err((position, error)) => {
+                    self.issue(first_line, position, IssueKind::Syntax, syntax_message(error));
+                    self.game += 1;
+
+                    return;
+                }
+            }
+        }
+    }
+
+    // Replays the moves as they are parsed, like Game::replay. A syntax error is returned as an error, the first
+    // move or setup which can't be replayed as the issue.
+    fn check_game(parser: &mut Parser) -> Result<Option<Located<String>>, Located<ParseError>> {
+        let tag_pairs = parser.parse_tag_pair_section().map_err( |error| (parser.next_position(), error) )?;
+        let start = parser.next_position();
+
+        let mut replay = Game::replay(ParsedGame::new(tag_pairs, Vec::new(), GameResult::Unknown));
+        let mut issue = None;
+
+        let mut game = match replay.next() {
+            Some(Err(error)) => {
+                issue = Some((start, String::from(error)));
+                None
+            },
+            _ => Some(replay.into_game())
+        };
+
+        let result = parser.parse_move_text_with(&mut |pgn_move, positions| {
+            let half_moves = [(pgn_move.white_move, positions[0]), (pgn_move.black_move, positions[1])];
+
+            for (notation, position) in half_moves.iter() {
+                let (notation, current) = match (notation, &game) {
+                    (Some(notation), Some(current)) => (notation, current),
+                    _ => continue
+                };
+
+                game = match ValidMove::from_notation(current, notation) {
+                    Ok(valid_move) => Some(current.make_valid_move(&valid_move)),
+                    Err(error) => {
+                        let error = ReplayError::InvalidMove { number: pgn_move.number, notation: notation.clone(), error };
+
+                        issue = Some((*position, String::from(error)));
+                        None
+                    }
+                };
+            }
+        });
+
+        match result {
+            // Only the first issue counts, which is the replay one if the syntax error comes after it
+            Err(error) if issue.is_none() => Err((parser.next_position(), error)),
+            _ => Ok(issue)
+        }
+    }
+
+    fn issue(&mut self, first_line: usize, position: Option<PositionInPGN>, kind: IssueKind, message: String) {
+        let position = position.unwrap_or(PositionInPGN { line: 1, column: 1 });
+
+        self.issues.push(Issue {
+            game: self.game,
+            line: first_line + position.line as usize - 1,
+            column: position.column as usize,
+            kind,
+            message
+        });
+    }
+}
+
+fn syntax_message(error: ParseError) -> String {
+    match error {
+        ParseError::UnexpectedToken(Token::EndOfFile) | ParseError::UnexpectedEndOfFile => String::from("The game has no result"),
+        error => error.into()
+    }
+}
@@ -0,0 +1,239 @@
+use super::models::*;
+use super::game::{Game, ValidMove};
+
+mod wire;
+
+use wire::{WireReader, WireValue, WireWriter};
+
+// The protobuf schema the messages below follow, for generating the code on the other side. This crate doesn't
+// generate code from it, the messages are written and read by hand with the wire module, so changes to the schema
+// need the same changes here.
+pub const SCHEMA: &str = include_str!("chess.proto");
+
+pub fn encode_position(position: &Position) -> Vec<u8> {
+    let mut writer = WireWriter::new();
+
+    let board: Vec<u8> = (0..64).map( |index| {
+        let square = square_from_index(index);
+
+        match position.board.squares[Board::index(square)] {
+            Some(OccupiedSquare { piece, color }) => {
+                let code = piece.index() as u8 + 1;
+
+                match color {
+                    Color::White => code,
+                    Color::Black => code + 6
+                }
+            },
+            None => 0
+        }
+    }).collect();
+
+    let castling = [
+        position.white_can_castle_king_side,
+        position.white_can_castle_queen_side,
+        position.black_can_castle_king_side,
+        position.black_can_castle_queen_side
+    ].iter().enumerate().fold(0, |castling, (bit, allowed)| if *allowed { castling | 1 << bit } else { castling });
+
+    writer.bytes(1, &board);
+    writer.varint(2, color_code(position.next_to_move));
+    writer.varint(3, castling);
+    writer.varint(4, position.en_passant_square.map( |square| square_index(square) + 1 ).unwrap_or(0));
+    writer.varint(5, position.half_move_clock as u64);
+    writer.varint(6, position.full_move_counter as u64);
+
+    writer.finish()
+}
+
+pub fn decode_position(bytes: &[u8]) -> Result<Position, String> {
+    let mut board = Vec::new();
+    let mut next_to_move = Color::White;
+    let mut castling = 0;
+    let mut en_passant_square = None;
+    let mut half_move_clock = 0;
+    let mut full_move_counter = 0;
+
+    for field in WireReader::new(bytes) {
+        match field? {
+            (1, WireValue::Bytes(value))  => board = value.to_vec(),
+            (2, WireValue::Varint(value)) => next_to_move = if value == 0 { Color::White } else { Color::Black },
+            (3, WireValue::Varint(value)) => castling = value,
+            (4, WireValue::Varint(0))     => en_passant_square = None,
+            (4, WireValue::Varint(value)) if value <= 64 => en_passant_square = Some(square_from_index(value - 1)),
+            (4, WireValue::Varint(_))     => return Err(String::from("Invalid en passant square")),
+            (5, WireValue::Varint(value)) => half_move_clock = value as i64,
+            (6, WireValue::Varint(value)) => full_move_counter = value as i64,
+            _ => ()
+        }
+    }
+
+    if board.len() != 64 {
+        return Err(format!("Expected 64 squares, got {}", board.len()));
+    }
+
+    let mut squares = vec![None; 64];
+
+    for (index, code) in board.into_iter().enumerate() {
+        let square = square_from_index(index as u64);
+
+        squares[Board::index(square)] = match code {
+            0 => None,
+            1..=6 => Some(OccupiedSquare { piece: PIECES[code as usize - 1], color: Color::White }),
+            7..=12 => Some(OccupiedSquare { piece: PIECES[code as usize - 7], color: Color::Black }),
+            _ => return Err(format!("Invalid piece code {}", code))
+        };
+    }
+
+    // Only the square skipped by a double pawn push, on the third or the sixth rank
+    if en_passant_square.is_some_and( |square: Square| square.rank != 2 && square.rank != 5 ) {
+        return Err(String::from("Invalid en passant square"));
+    }
+
+    Ok(Position {
+        board: Board { squares },
+        next_to_move,
+
+        white_can_castle_king_side: castling & 1 != 0,
+        white_can_castle_queen_side: castling & 2 != 0,
+        black_can_castle_king_side: castling & 4 != 0,
+        black_can_castle_queen_side: castling & 8 != 0,
+
+        en_passant_square,
+        half_move_clock,
+        full_move_counter
+    })
+}
+
+pub fn encode_move(valid_move: &ValidMove) -> Vec<u8> {
+    let mut writer = WireWriter::new();
+
+    writer.varint(1, square_index(valid_move.from));
+    writer.varint(2, square_index(valid_move.to));
+    writer.varint(3, (valid_move.to_u16() >> 12) as u64);
+
+    writer.finish()
+}
+
+// Moves only make sense in the position they are played in
+pub fn decode_move(game: &Game, bytes: &[u8]) -> Result<ValidMove, String> {
+    let (mut from, mut to, mut promotion) = (0, 0, 0);
+
+    for field in WireReader::new(bytes) {
+        match field? {
+            (1, WireValue::Varint(value)) => from = value,
+            (2, WireValue::Varint(value)) => to = value,
+            (3, WireValue::Varint(value)) => promotion = value,
+            _ => ()
+        }
+    }
+
+    if from > 63 || to > 63 || promotion > 4 {
+        return Err(String::from("Invalid move"));
+    }
+
+    ValidMove::from_u16(game, (promotion << 12 | from << 6 | to) as u16)
+        .map_err( |_| format!("Illegal move {}", square_from_index(from).to_notation(SquareNotationOptions::FileAndRank)) )
+}
+
+pub fn encode_game(game: &Game, tags: &[(String, String)]) -> Vec<u8> {
+    let mut writer = WireWriter::new();
+
+    writer.bytes(1, &encode_position(game.initial_position()));
+
+    for valid_move in game.moves() {
+        writer.bytes(2, &encode_move(&valid_move));
+    }
+
+    writer.varint(3, match game.result() {
+        GameResult::Unknown   => 0,
+        GameResult::WhiteWins => 1,
+        GameResult::BlackWins => 2,
+        GameResult::Draw      => 3
+    });
+
+    writer.bytes(4, game.termination().as_bytes());
+
+    for (name, value) in tags {
+        let mut tag = WireWriter::new();
+
+        tag.bytes(1, name.as_bytes());
+        tag.bytes(2, value.as_bytes());
+
+        writer.bytes(5, &tag.finish());
+    }
+
+    writer.finish()
+}
+
+// Games which the moves don't end on their own are adjudicated with the stored result and termination
+pub fn decode_game(bytes: &[u8]) -> Result<(Game, Vec<(String, String)>), String> {
+    let mut initial_position = None;
+    let mut moves = Vec::new();
+    let mut result = GameResult::Unknown;
+    let mut termination = String::new();
+    let mut tags = Vec::new();
+
+    for field in WireReader::new(bytes) {
+        match field? {
+            (1, WireValue::Bytes(value))  => initial_position = Some(decode_position(value)?),
+            (2, WireValue::Bytes(value))  => moves.push(value),
+            (3, WireValue::Varint(value)) => result = match value {
+                1 => GameResult::WhiteWins,
+                2 => GameResult::BlackWins,
+                3 => GameResult::Draw,
+                _ => GameResult::Unknown
+            },
+            (4, WireValue::Bytes(value))  => termination = string(value)?,
+            (5, WireValue::Bytes(value))  => tags.push(decode_tag(value)?),
+            _ => ()
+        }
+    }
+
+    let mut game = Game::new(initial_position.ok_or("Missing initial position")?);
+
+    for bytes in moves {
+        let valid_move = decode_move(&game, bytes)?;
+
+        game = game.make_valid_move(&valid_move);
+    }
+
+    if !game.is_over() && result != GameResult::Unknown {
+        game = game.adjudicate(result, &termination);
+    }
+
+    Ok((game, tags))
+}
+
+fn decode_tag(bytes: &[u8]) -> Result<(String, String), String> {
+    let mut tag = (String::new(), String::new());
+
+    for field in WireReader::new(bytes) {
+        match field? {
+            (1, WireValue::Bytes(value)) => tag.0 = string(value)?,
+            (2, WireValue::Bytes(value)) => tag.1 = string(value)?,
+            _ => ()
+        }
+    }
+
+    Ok(tag)
+}
+
+fn string(bytes: &[u8]) -> Result<String, String> {
+    String::from_utf8(bytes.to_vec()).map_err( |_| String::from("Invalid UTF-8 string") )
+}
+
+fn color_code(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        Color::Black => 1
+    }
+}
+
+fn square_index(square: Square) -> u64 {
+    square.to_a1_index() as u64
+}
+
+fn square_from_index(index: u64) -> Square {
+    Square::from_a1_index(index as usize).expect("Square indices are checked before")
+}
@@ -0,0 +1,121 @@
+// The parts of the protobuf wire format the messages need. Fields with default values are left out, as proto3 does.
+
+pub enum WireValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+
+    // Fixed size values, which none of the messages use
+    Skipped
+}
+
+pub struct WireWriter {
+    bytes: Vec<u8>
+}
+
+impl WireWriter {
+    pub fn new() -> Self {
+        WireWriter { bytes: Vec::new() }
+    }
+
+    pub fn varint(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.raw_varint((field as u64) << 3);
+            self.raw_varint(value);
+        }
+    }
+
+    pub fn bytes(&mut self, field: u32, value: &[u8]) {
+        if !value.is_empty() {
+            self.raw_varint((field as u64) << 3 | 2);
+            self.raw_varint(value.len() as u64);
+            self.bytes.extend_from_slice(value);
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn raw_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push((value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+
+        self.bytes.push(value as u8);
+    }
+}
+
+// Yields the fields in the order they are written, unknown ones included
+pub struct WireReader<'a> {
+    bytes: &'a [u8],
+    failed: bool
+}
+
+impl<'a> WireReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        WireReader { bytes, failed: false }
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let (byte, rest) = self.bytes.split_first().ok_or("Truncated varint")?;
+            self.bytes = rest;
+
+            value |= ((byte & 0x7F) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(String::from("Varint is too long"))
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < length {
+            return Err(String::from("Truncated field"));
+        }
+
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn field(&mut self) -> Result<(u32, WireValue<'a>), String> {
+        let key = self.varint()?;
+        let field = (key >> 3) as u32;
+
+        let value = match key & 7 {
+            0 => WireValue::Varint(self.varint()?),
+            1 => { self.take(8)?; WireValue::Skipped },
+            2 => {
+                let length = self.varint()? as usize;
+
+                WireValue::Bytes(self.take(length)?)
+            },
+            5 => { self.take(4)?; WireValue::Skipped },
+            wire_type => return Err(format!("Unsupported wire type {}", wire_type))
+        };
+
+        Ok((field, value))
+    }
+}
+
+impl<'a> Iterator for WireReader<'a> {
+    type Item = Result<(u32, WireValue<'a>), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() || self.failed {
+            return None;
+        }
+
+        let field = self.field();
+        self.failed = field.is_err();
+
+        Some(field)
+    }
+}
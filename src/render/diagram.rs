@@ -0,0 +1,98 @@
+use super::BoardView;
+use super::text::render_text_with;
+use super::super::models::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DiagramOptions {
+    // Rank numbers after every rank and file letters below the board
+    pub coordinates: bool,
+
+    // Chess figurines instead of letters
+    pub unicode: bool
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        DiagramOptions { coordinates: true, unicode: false }
+    }
+}
+
+impl Board {
+    // The board from white's side as rows of "|r|n|b|q|k|b|n|r|", white pieces in uppercase, the same as
+    // render_text draws it. Handy for tests, documentation and bug reports, e.g. with
+    // Position::from_fen(fen)?.board.to_diagram(&DiagramOptions::default()).
+    pub fn to_diagram(&self, options: &DiagramOptions) -> String {
+        let view = BoardView { coordinates: options.coordinates, ..BoardView::default() };
+
+        render_text_with(self, &view, options.unicode)
+    }
+
+    // Reads what to_diagram writes, with or without coordinates and with letters or figurines. Empty squares
+    // can be blank or written as "." or "*", and blank lines are skipped. Rank numbers after or before the
+    // rows are optional, but when the first row is numbered 1 the diagram is read as seen from black's side.
+    pub fn from_diagram(diagram: &str) -> Result<Board, String> {
+        let mut squares = Vec::with_capacity(64);
+        let mut ranks = Vec::new();
+
+        for line in diagram.lines().map( |line| line.trim() ).filter( |line| !line.is_empty() ) {
+            let (first, last) = match (line.find('|'), line.rfind('|')) {
+                (Some(first), Some(last)) if first < last => (first, last),
+
+                // The file letters
+                _ if line.chars().all( |c| c.is_whitespace() || ('a'..='h').contains(&c) ) => continue,
+                _ => return Err(format!("Invalid line '{}'", line))
+            };
+
+            let label = format!("{}{}", &line[..first], &line[last + 1..]);
+            let label = label.trim();
+
+            if !label.is_empty() {
+                match label.parse::<u8>() {
+                    Ok(rank) if (1..=8).contains(&rank) => ranks.push(rank),
+                    _ => return Err(format!("Invalid rank '{}' in line '{}'", label, line))
+                }
+            }
+
+            let cells: Vec<&str> = line[first + 1..last].split('|').filter( |cell| !cell.is_empty() ).collect();
+
+            if cells.len() != 8 {
+                return Err(format!("Expected 8 squares in line '{}', found {}", line, cells.len()));
+            }
+
+            for cell in cells {
+                squares.push(read_square(cell.trim()).ok_or_else( || format!("Invalid square '{}' in line '{}'", cell, line) )?);
+            }
+        }
+
+        if squares.len() != 64 {
+            return Err(format!("Expected 8 ranks, found {}", squares.len() / 8));
+        }
+
+        if ranks.first() == Some(&1) {
+            squares.reverse();
+        }
+
+        Ok(Board { squares })
+    }
+}
+
+// None for anything which isn't a piece or an empty square
+fn read_square(cell: &str) -> Option<Option<OccupiedSquare>> {
+    let mut chars = cell.chars();
+
+    let c = match (chars.next(), chars.next()) {
+        (None, _)         => return Some(None),
+        (Some(c), None)   => c,
+        _                 => return None
+    };
+
+    if c == '.' || c == '*' {
+        return Some(None);
+    }
+
+    match Piece::from_letter(c) {
+        Some(piece) if c.is_ascii_uppercase() => Some(Some(OccupiedSquare { piece, color: Color::White })),
+        Some(piece)                           => Some(Some(OccupiedSquare { piece, color: Color::Black })),
+        None                                  => Piece::from_figurine(c).map(Some)
+    }
+}
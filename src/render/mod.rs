@@ -0,0 +1,48 @@
+use super::models::*;
+
+mod diagram;
+mod overlay;
+mod svg;
+mod text;
+
+pub use diagram::DiagramOptions;
+pub use overlay::{Overlay, Arrow, Mark, MarkColor, strip_commands};
+pub use svg::{render_svg, SvgOptions};
+pub use text::render_text;
+
+// How a board is shown, the same for every renderer
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BoardView {
+    // The color whose pieces start at the bottom
+    pub orientation: Color,
+
+    // File letters below the board and rank numbers next to it
+    pub coordinates: bool,
+
+    // e.g. the squares of the last move
+    pub highlights: Vec<Square>
+}
+
+impl Default for BoardView {
+    fn default() -> Self {
+        BoardView { orientation: Color::White, coordinates: true, highlights: Vec::new() }
+    }
+}
+
+impl BoardView {
+    // From white's side, without coordinates or highlights
+    pub fn plain() -> Self {
+        BoardView { coordinates: false, ..Self::default() }
+    }
+
+    // The squares in the order they are drawn, from the top left corner, rank by rank
+    pub fn squares(&self) -> Vec<Square> {
+        let mut squares: Vec<Square> = (0..64).map( Board::square ).collect();
+
+        if self.orientation == Color::Black {
+            squares.reverse();
+        }
+
+        squares
+    }
+}
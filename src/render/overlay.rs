@@ -0,0 +1,144 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::super::models::*;
+
+// The colors of the PGN drawing commands, by their letter in them
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MarkColor {
+    Green,
+    Red,
+    Yellow,
+    Blue
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub color: MarkColor
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Mark {
+    pub square: Square,
+    pub color: MarkColor
+}
+
+// What is drawn on top of a board, e.g. by the author of a study. Arrows and circles are read from and
+// written as the [%cal Ge2e4,Rd7d5] and [%csl Ye4] commands inside PGN comments. Colored squares have no
+// such command, so they don't survive a round trip through PGN.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Overlay {
+    pub arrows: Vec<Arrow>,
+    pub circles: Vec<Mark>,
+    pub squares: Vec<Mark>
+}
+
+lazy_static! {
+    static ref COMMAND_REGEX: Regex = Regex::new(r"\[%(cal|csl)\s+([^\]]*)\]").expect("Invalid regular expression");
+}
+
+impl MarkColor {
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'G' => Some(MarkColor::Green),
+            'R' => Some(MarkColor::Red),
+            'Y' => Some(MarkColor::Yellow),
+            'B' => Some(MarkColor::Blue),
+            _ => None
+        }
+    }
+
+    pub fn letter(&self) -> char {
+        match self {
+            MarkColor::Green  => 'G',
+            MarkColor::Red    => 'R',
+            MarkColor::Yellow => 'Y',
+            MarkColor::Blue   => 'B'
+        }
+    }
+
+    // As lichess draws them
+    pub fn svg_color(&self) -> &'static str {
+        match self {
+            MarkColor::Green  => "#15781b",
+            MarkColor::Red    => "#882020",
+            MarkColor::Yellow => "#e68f00",
+            MarkColor::Blue   => "#003088"
+        }
+    }
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arrows.is_empty() && self.circles.is_empty() && self.squares.is_empty()
+    }
+
+    // The arrows and circles of all commands inside the comment. Malformed entries are skipped.
+    pub fn from_comment(comment: &str) -> Self {
+        let mut overlay = Overlay::new();
+
+        for captures in COMMAND_REGEX.captures_iter(comment) {
+            for entry in captures[2].split(',').map( |entry| entry.trim() ) {
+                let color = match entry.chars().next().and_then(MarkColor::from_letter) {
+                    Some(color) => color,
+                    None => continue
+                };
+
+                let squares = &entry[1..];
+
+                match &captures[1] {
+                    "cal" if squares.len() == 4 && squares.is_ascii() => {
+                        if let (Ok(from), Ok(to)) = (Square::from_notation(&squares[..2]), Square::from_notation(&squares[2..])) {
+                            overlay.arrows.push(Arrow { from, to, color });
+                        }
+                    },
+
+                    "csl" if squares.len() == 2 => {
+                        if let Ok(square) = Square::from_notation(squares) {
+                            overlay.circles.push(Mark { square, color });
+                        }
+                    },
+
+                    _ => ()
+                }
+            }
+        }
+
+        overlay
+    }
+
+    // The commands for the arrows and circles, e.g. "[%csl Ye4][%cal Ge2e4]", or "" without any
+    pub fn to_commands(&self) -> String {
+        let notation = |square: Square| square.to_notation(SquareNotationOptions::FileAndRank);
+        let mut commands = String::new();
+
+        if !self.circles.is_empty() {
+            let circles: Vec<String> = self.circles.iter()
+                .map( |mark| format!("{}{}", mark.color.letter(), notation(mark.square)) )
+                .collect();
+
+            commands.push_str(&format!("[%csl {}]", circles.join(",")));
+        }
+
+        if !self.arrows.is_empty() {
+            let arrows: Vec<String> = self.arrows.iter()
+                .map( |arrow| format!("{}{}{}", arrow.color.letter(), notation(arrow.from), notation(arrow.to)) )
+                .collect();
+
+            commands.push_str(&format!("[%cal {}]", arrows.join(",")));
+        }
+
+        commands
+    }
+}
+
+// The comment without its drawing commands, which Overlay::from_comment reads instead
+pub fn strip_commands(comment: &str) -> String {
+    COMMAND_REGEX.replace_all(comment, "").split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use super::BoardView;
+use super::overlay::{Arrow, Overlay};
+use super::super::models::*;
+use super::super::screen::BoardLayout;
+
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const HIGHLIGHT: &str = "#9bc700";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SvgOptions {
+    // Width and height of the image, including the coordinates
+    pub size: f64,
+
+    pub overlay: Overlay
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions { size: 400.0, overlay: Overlay::new() }
+    }
+}
+
+pub fn render_svg(board: &Board, view: &BoardView, options: &SvgOptions) -> String {
+    let margin = if view.coordinates { options.size / 20.0 } else { 0.0 };
+    let layout = BoardLayout::new(options.size, margin, view.orientation);
+    let square_size = layout.square_size();
+
+    let mut svg = String::new();
+
+    // Writing to a String can't fail
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">",
+        size = options.size
+    );
+
+    for (index, occupancy) in board.squares.iter().enumerate() {
+        let square = Board::square(index);
+        let (x, y) = layout.square_origin(square);
+        let fill = if square.is_light() { LIGHT_SQUARE } else { DARK_SQUARE };
+
+        let _ = write!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\"/>", x, y, fill, s = square_size);
+
+        if view.highlights.contains(&square) {
+            let _ = write!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\" fill-opacity=\"0.5\"/>",
+                x, y, HIGHLIGHT, s = square_size
+            );
+        }
+
+        for mark in options.overlay.squares.iter().filter( |mark| mark.square == square ) {
+            let _ = write!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\" fill-opacity=\"0.5\"/>",
+                x, y, mark.color.svg_color(), s = square_size
+            );
+        }
+
+        if let Some(occupancy) = occupancy {
+            let (center_x, center_y) = layout.square_center(square);
+            let (fill, stroke) = match occupancy.color {
+                Color::White => ("#ffffff", "#000000"),
+                Color::Black => ("#000000", "#000000")
+            };
+
+            let _ = write!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" \
+                 fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\">{}</text>",
+                center_x, center_y, square_size * 0.8, fill, stroke, square_size / 50.0,
+                // The black glyphs for both colors, filled differently, since the white ones are outlines only in most fonts
+                occupancy.piece.figurine(Color::Black)
+            );
+        }
+    }
+
+    if view.coordinates {
+        for i in 0..BOARD_SIZE {
+            let (file_x, _) = layout.square_center(Square { rank: 0, file: i });
+            let (_, rank_y) = layout.square_center(Square { rank: i, file: 0 });
+
+            let _ = write!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"{f}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                file_x, options.size - margin / 2.0, (b'a' + i as u8) as char, f = margin * 0.6
+            );
+            let _ = write!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"{f}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                margin / 2.0, rank_y, i + 1, f = margin * 0.6
+            );
+        }
+    }
+
+    for circle in &options.overlay.circles {
+        let (x, y) = layout.square_center(circle.square);
+
+        let _ = write!(
+            svg,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" opacity=\"0.8\"/>",
+            x, y, square_size * 0.45, circle.color.svg_color(), square_size / 16.0
+        );
+    }
+
+    for arrow in &options.overlay.arrows {
+        svg.push_str(&render_arrow(&layout, arrow));
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+// A line ending in a triangle which points at the center of the target square
+fn render_arrow(layout: &BoardLayout, arrow: &Arrow) -> String {
+    let (from_x, from_y) = layout.square_center(arrow.from);
+    let (to_x, to_y) = layout.square_center(arrow.to);
+
+    let length = ((to_x - from_x).powi(2) + (to_y - from_y).powi(2)).sqrt();
+    if length == 0.0 {
+        return String::new();
+    }
+
+    let (direction_x, direction_y) = ((to_x - from_x) / length, (to_y - from_y) / length);
+    let head = layout.square_size() * 0.4;
+    let width = layout.square_size() * 0.15;
+
+    let (base_x, base_y) = (to_x - direction_x * head, to_y - direction_y * head);
+    let (side_x, side_y) = (-direction_y * head / 2.0, direction_x * head / 2.0);
+
+    format!(
+        "<g fill=\"{color}\" stroke=\"{color}\" opacity=\"0.8\">\
+         <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke-width=\"{}\"/>\
+         <polygon points=\"{},{} {},{} {},{}\" stroke=\"none\"/></g>",
+        from_x, from_y, base_x, base_y, width,
+        to_x, to_y, base_x + side_x, base_y + side_y, base_x - side_x, base_y - side_y,
+        color = arrow.color.svg_color()
+    )
+}
@@ -0,0 +1,59 @@
+use std::fmt::Write;
+
+use super::BoardView;
+use super::super::models::*;
+
+// The board as rows of "|r|n|b|q|k|b|n|r|", white pieces in uppercase. Highlighted empty squares are drawn as
+// "*", and the coordinates go after every rank and below the last one.
+pub fn render_text(board: &Board, view: &BoardView) -> String {
+    render_text_with(board, view, false)
+}
+
+// With figurines instead of letters, e.g. "|♜|♞|♝|♛|♚|♝|♞|♜|"
+pub(super) fn render_text_with(board: &Board, view: &BoardView, figurines: bool) -> String {
+    let mut text = String::new();
+    let squares = view.squares();
+
+    for row in squares.chunks(BOARD_SIZE as usize) {
+        for square in row {
+            let letter = match &board.squares[Board::index(*square)] {
+                Some(occupancy) if figurines => figurine(occupancy),
+                Some(occupancy) => letter(occupancy),
+                None if view.highlights.contains(square) => '*',
+                None => ' '
+            };
+
+            // Writing to a String can't fail
+            let _ = write!(text, "|{}", letter);
+        }
+
+        text.push('|');
+
+        if view.coordinates {
+            let _ = write!(text, " {}", row[0].rank + 1);
+        }
+
+        text.push('\n');
+    }
+
+    if view.coordinates {
+        for square in &squares[..BOARD_SIZE as usize] {
+            let _ = write!(text, " {}", (b'a' + square.file as u8) as char);
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
+
+fn letter(occupancy: &OccupiedSquare) -> char {
+    match occupancy.color {
+        Color::White => occupancy.piece.letter(),
+        Color::Black => occupancy.piece.letter().to_ascii_lowercase()
+    }
+}
+
+fn figurine(occupancy: &OccupiedSquare) -> char {
+    occupancy.piece.figurine(occupancy.color)
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use super::models::*;
+use super::game::{Game, ValidMove, InvalidMoveError};
+use super::tree::{GameTree, NodeId};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+const INITIAL_EASE: f64 = 2.5;
+const MINIMUM_EASE: f64 = 1.3;
+
+// Scheduling state of a single position, following the SM-2 spaced repetition algorithm
+#[derive(Debug, PartialEq, Clone)]
+pub struct Card {
+    // Unix timestamp in seconds
+    pub due: u64,
+    pub interval_days: u64,
+    pub ease: f64,
+    pub repetitions: u32,
+    pub mistakes: u32
+}
+
+#[derive(Debug, Clone)]
+pub struct Drill {
+    pub node: NodeId,
+    pub game: Game,
+
+    // Moves leading from the start of the repertoire to the position
+    pub line: Vec<ValidMove>
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DrillAnswer {
+    pub correct: bool,
+
+    // The repertoire moves, in SAN
+    pub expected: Vec<String>
+}
+
+// Where a played game left the repertoire
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Deviation {
+    // Index into Game::moves()
+    pub ply: usize,
+    pub node: NodeId,
+
+    pub played: ValidMove,
+    pub san: String,
+
+    // Whether the repertoire's side played the move, rather than the opponent playing something unprepared
+    pub by_player: bool,
+
+    // The moves which were prepared in the position, in SAN
+    pub expected: Vec<String>
+}
+
+// The repertoire moves are the moves of `color` inside the tree, and every position where `color` has
+// a move prepared is a card to drill
+pub struct Repertoire {
+    tree: GameTree,
+    color: Color,
+    cards: HashMap<NodeId, Card>
+}
+
+impl Repertoire {
+    // All cards are due right away
+    pub fn new(tree: GameTree, color: Color, now: u64) -> Self {
+        let cards = tree.node_ids()
+            .filter( |id| {
+                let node = tree.node(*id);
+
+                node.game.position().next_to_move == color && !node.children.is_empty()
+            })
+            .map( |id| (id, Card {
+                due: now,
+                interval_days: 0,
+                ease: INITIAL_EASE,
+                repetitions: 0,
+                mistakes: 0
+            }))
+            .collect();
+
+        Repertoire { tree, color, cards }
+    }
+
+    pub fn tree(&self) -> &GameTree {
+        &self.tree
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn card(&self, node: NodeId) -> Option<&Card> {
+        self.cards.get(&node)
+    }
+
+    // Most overdue first, then in tree order
+    pub fn due(&self, now: u64) -> Vec<Drill> {
+        let mut due: Vec<(&NodeId, &Card)> = self.cards.iter()
+            .filter( |(_, card)| card.due <= now )
+            .collect();
+
+        due.sort_by_key( |(node, card)| (card.due, **node) );
+
+        due.into_iter().map( |(node, _)| self.drill(*node) ).collect()
+    }
+
+    pub fn next_due(&self, now: u64) -> Option<Drill> {
+        self.due(now).into_iter().next()
+    }
+
+    pub fn drill(&self, node: NodeId) -> Drill {
+        Drill {
+            node,
+            game: self.tree.node(node).game.clone(),
+            line: self.tree.path(node)
+        }
+    }
+
+    // Moves with the worst mistake-to-repetition ratio, for a "review your weak spots" session
+    pub fn weakest(&self, count: usize) -> Vec<Drill> {
+        let mut cards: Vec<(&NodeId, &Card)> = self.cards.iter()
+            .filter( |(_, card)| card.mistakes > 0 )
+            .collect();
+
+        cards.sort_by_key( |(node, card)| (std::cmp::Reverse(card.mistakes), card.repetitions, **node) );
+
+        cards.into_iter().take(count).map( |(node, _)| self.drill(*node) ).collect()
+    }
+
+    // Accepts SAN ("Nf3") as well as UCI ("g1f3") answers. Invalid or illegal moves are errors and do not
+    // count as mistakes.
+    pub fn answer(&mut self, node: NodeId, answer: &str, now: u64) -> Result<DrillAnswer, InvalidMoveError> {
+        let game = &self.tree.node(node).game;
+
+        let answered_move = ValidMove::from_notation(game, answer)
+            .or_else( |_| ValidMove::from_uci(game, answer) )?;
+
+        let expected_moves: Vec<ValidMove> = self.tree.children(node).iter()
+            .filter_map( |child| self.tree.node(*child).valid_move.clone() )
+            .collect();
+
+        let correct = expected_moves.contains(&answered_move);
+        let expected = expected_moves.iter().map( |valid_move| game.san(valid_move) ).collect();
+
+        if let Some(card) = self.cards.get_mut(&node) {
+            schedule(card, correct, now);
+        }
+
+        Ok(DrillAnswer { correct, expected })
+    }
+
+    pub fn deviation(&self, game: &Game) -> Option<Deviation> {
+        find_deviation(&self.tree, game, self.color)
+    }
+}
+
+// The first move of the game which isn't in the tree, for reviewing a game against the repertoire of `color`.
+// Move orders which transpose back into the tree are followed. None when the game stays inside the tree until
+// the prepared line ends, or doesn't start from a position of the tree.
+pub fn find_deviation(tree: &GameTree, game: &Game, color: Color) -> Option<Deviation> {
+    let history = game.history();
+    let start = history.first().map_or(game.hash(), |(before, _)| before.hash() );
+    let mut node = *tree.find(start).first()?;
+
+    for (ply, (before, valid_move)) in history.iter().enumerate() {
+        let children = tree.children(node);
+
+        if children.is_empty() {
+            return None;
+        }
+
+        let after = match history.get(ply + 1) {
+            Some((after, _)) => after.hash(),
+            None => game.hash()
+        };
+
+        let next = tree.child_with_move(node, valid_move)
+            .or_else( || tree.find(after).first().cloned() );
+
+        match next {
+            Some(next) => node = next,
+            None => return Some(Deviation {
+                ply,
+                node,
+
+                played: valid_move.clone(),
+                san: before.san(valid_move),
+
+                by_player: valid_move.color == color,
+                expected: children.iter()
+                    .filter_map( |child| tree.node(*child).valid_move.as_ref() )
+                    .map( |expected| before.san(expected) )
+                    .collect()
+            })
+        }
+    }
+
+    None
+}
+
+fn schedule(card: &mut Card, correct: bool, now: u64) {
+    if correct {
+        card.repetitions += 1;
+        card.interval_days = match card.repetitions {
+            1 => 1,
+            2 => 3,
+            _ => (card.interval_days as f64 * card.ease).round() as u64
+        };
+        card.ease += 0.1;
+    } else {
+        card.repetitions = 0;
+        card.interval_days = 0;
+        card.mistakes += 1;
+        card.ease = (card.ease - 0.2).max(MINIMUM_EASE);
+    }
+
+    card.due = now + card.interval_days * SECONDS_PER_DAY;
+}
@@ -0,0 +1,48 @@
+// Random numbers for everything picking at random: book moves, the bot's mistakes and random positions. A
+// generator belongs to whoever draws from it, there is no global one, so the same seed gives the same
+// numbers on every platform regardless of what else is running.
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    // Uniformly below `bound`, which has to be above zero
+    fn below(&mut self, bound: u64) -> u64 {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+}
+
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// splitmix64, which is fast, small and good enough for games
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    // A generator of its own, e.g. for another game or thread, so that the order in which the two draw
+    // doesn't change either one's numbers
+    pub fn fork(&mut self) -> Self {
+        SplitMix64::new(mix(self.next_u64()))
+    }
+}
+
+impl Rng for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        let value = mix(self.state);
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+
+        value
+    }
+}
+
+// The splitmix64 output function, also useful for hashing seeds together
+pub fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(GOLDEN_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,67 @@
+use serde::{Serialize, Deserialize};
+
+use super::models::*;
+
+// Where the board is drawn. Coordinates grow to the right and down, as on canvases and screens.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct BoardLayout {
+    // Top left corner of the area the board is drawn in, including the margins
+    pub x: f64,
+    pub y: f64,
+
+    // Width and height of the area, the board is the square inside the margins
+    pub size: f64,
+
+    // Space left around the board, e.g. for the coordinate labels
+    pub margin: f64,
+
+    // The color whose pieces start at the bottom
+    pub orientation: Color
+}
+
+impl BoardLayout {
+    pub fn new(size: f64, margin: f64, orientation: Color) -> Self {
+        BoardLayout { x: 0.0, y: 0.0, size, margin, orientation }
+    }
+
+    pub fn square_size(&self) -> f64 {
+        (self.size - 2.0 * self.margin) / BOARD_SIZE as f64
+    }
+
+    // None for points in the margins or outside of the board
+    pub fn square_at(&self, x: f64, y: f64) -> Option<Square> {
+        let column = ((x - self.x - self.margin) / self.square_size()).floor();
+        let row = ((y - self.y - self.margin) / self.square_size()).floor();
+
+        if !(0.0..8.0).contains(&column) || !(0.0..8.0).contains(&row) {
+            return None;
+        }
+
+        let (column, row) = (column as i8, row as i8);
+
+        match self.orientation {
+            Color::White => Square::new(7 - row, column),
+            Color::Black => Square::new(row, 7 - column)
+        }
+    }
+
+    // Top left corner of the square
+    pub fn square_origin(&self, square: Square) -> (f64, f64) {
+        let (column, row) = match self.orientation {
+            Color::White => (square.file, 7 - square.rank),
+            Color::Black => (7 - square.file, square.rank)
+        };
+
+        (
+            self.x + self.margin + column as f64 * self.square_size(),
+            self.y + self.margin + row as f64 * self.square_size()
+        )
+    }
+
+    // Where pieces and arrow ends are drawn
+    pub fn square_center(&self, square: Square) -> (f64, f64) {
+        let (x, y) = self.square_origin(square);
+
+        (x + self.square_size() / 2.0, y + self.square_size() / 2.0)
+    }
+}
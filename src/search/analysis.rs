@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+
+use super::{Searcher, SearchLimits, SearchResult};
+use super::super::game::{Game, ValidMove};
+
+// An open-ended search running on a background thread.
+// The searcher (and its transposition table) is handed back on `stop`, so that the next search can reuse it.
+pub struct Analysis {
+    game: Game,
+    ponder_move: Option<ValidMove>,
+
+    abort: Arc<AtomicBool>,
+    latest: Arc<Mutex<Option<SearchResult>>>,
+    // Taken by `stop`, otherwise joined when the analysis is dropped
+    handle: Option<JoinHandle<(SearchResult, Searcher)>>
+}
+
+impl Analysis {
+    pub fn start(searcher: Searcher, game: &Game) -> Self {
+        Self::spawn(searcher, game.clone(), None)
+    }
+
+    // Searches the position after the predicted opponent move while waiting for the opponent.
+    // On a ponder hit keep the analysis running, otherwise stop it and start a new search with the returned searcher.
+    pub fn ponder(searcher: Searcher, game: &Game, predicted_move: &ValidMove) -> Self {
+        Self::spawn(searcher, game.make_valid_move(predicted_move), Some(predicted_move.clone()))
+    }
+
+    fn spawn(mut searcher: Searcher, game: Game, ponder_move: Option<ValidMove>) -> Self {
+        let abort = searcher.abort_flag();
+        let latest = Arc::new(Mutex::new(None));
+
+        let thread_game = game.clone();
+        let thread_latest = latest.clone();
+
+        let handle = thread::spawn(move || {
+            let result = searcher.search_with_progress(&thread_game, &SearchLimits::infinite(), |result| {
+                *thread_latest.lock().unwrap() = Some(result.clone());
+            });
+
+            (result, searcher)
+        });
+
+        Analysis { game, ponder_move, abort, latest, handle: Some(handle) }
+    }
+
+    // The position being analysed (after the predicted move when pondering)
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn ponder_move(&self) -> Option<&ValidMove> {
+        self.ponder_move.as_ref()
+    }
+
+    pub fn is_ponder_hit(&self, played_move: &ValidMove) -> bool {
+        self.ponder_move.as_ref() == Some(played_move)
+    }
+
+    // Result of the last completed depth, if any
+    pub fn current(&self) -> Option<SearchResult> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or( |handle| handle.is_finished() )
+    }
+
+    pub fn stop(mut self) -> (SearchResult, Searcher) {
+        self.abort.store(true, Ordering::Relaxed);
+
+        let handle = self.handle.take().expect("Analysis already stopped");
+        let (result, searcher) = handle.join().expect("Analysis thread panicked");
+
+        self.abort.store(false, Ordering::Relaxed);
+
+        (result, searcher)
+    }
+}
+
+// Dropping a running analysis stops the background search instead of leaving it running forever
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.abort.store(true, Ordering::Relaxed);
+
+            let _ = handle.join();
+        }
+    }
+}
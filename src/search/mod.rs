@@ -0,0 +1,622 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use super::game::{Game, ValidMove};
+use super::eval;
+use super::book::{self, OpeningBook, BookOptions};
+use super::rng::Rng;
+use super::nnue::{Network, Accumulator};
+
+mod problem;
+mod table;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod analysis;
+
+pub use problem::{Stipulation, solve_problem};
+
+use table::TranspositionTable;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use analysis::Analysis;
+
+pub const MATE_SCORE: i32 = 100_000;
+
+// Mate scores count down with the distance to mate, so anything above this is a forced mate
+const MATE_THRESHOLD: i32 = MATE_SCORE - 1000;
+
+// How often (in nodes) the abort flag and the limits are checked
+const CHECK_INTERVAL: u64 = 256;
+
+pub const MAX_THREADS: usize = 256;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits { depth: Some(depth), ..Default::default() }
+    }
+
+    pub fn movetime(movetime: Duration) -> Self {
+        SearchLimits { movetime: Some(movetime), ..Default::default() }
+    }
+
+    // Runs until the abort flag is set
+    pub fn infinite() -> Self {
+        SearchLimits::default()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub qnodes: u64,
+    pub tt_hits: u64,
+    pub cutoffs: u64,
+    pub depth: u32,
+    pub elapsed: Duration
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SearchResult {
+    pub best_move: Option<ValidMove>,
+
+    // Centipawns from the point of view of the side to move
+    pub score: i32,
+    pub principal_variation: Vec<ValidMove>,
+
+    pub stats: SearchStats,
+
+    // The move was taken from the opening book without searching
+    pub from_book: bool
+}
+
+impl SearchResult {
+    // Positive when the side to move mates, negative when it gets mated
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score > MATE_THRESHOLD {
+            Some((MATE_SCORE - self.score + 1) / 2)
+        } else if self.score < -MATE_THRESHOLD {
+            Some(-(MATE_SCORE + self.score) / 2)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TableEntry {
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best_move: Option<ValidMove>
+}
+
+pub struct Searcher {
+    // Shared with the helper threads of a search
+    table: Arc<TranspositionTable>,
+    abort: Arc<AtomicBool>,
+    book: Option<(Box<dyn OpeningBook + Send>, BookOptions)>,
+    book_rng: Option<Box<dyn Rng + Send>>,
+    network: Option<Arc<Network>>,
+    threads: usize,
+
+    stats: SearchStats,
+    started_at: f64,
+    limits: SearchLimits,
+    stopped: bool
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Searcher {
+    pub fn new() -> Self {
+        Searcher {
+            table: Arc::new(TranspositionTable::new()),
+            abort: Arc::new(AtomicBool::new(false)),
+            book: None,
+            book_rng: None,
+            network: None,
+            threads: 1,
+
+            stats: SearchStats::default(),
+            started_at: 0.0,
+            limits: SearchLimits::default(),
+            stopped: false
+        }
+    }
+
+    // Setting the flag stops the running search, which then returns the result of the last completed depth.
+    // The search does not clear the flag, so it has to be reset before searching again.
+    pub fn abort_flag(&self) -> Arc<AtomicBool> {
+        self.abort.clone()
+    }
+
+    pub fn with_book<B: OpeningBook + Send + 'static>(mut self, book: B, options: BookOptions) -> Self {
+        self.book = Some((Box::new(book), options));
+        self
+    }
+
+    // Weighted book moves are drawn from the generator, see book::choose_move_with_rng
+    pub fn with_book_rng<R: Rng + Send + 'static>(mut self, rng: R) -> Self {
+        self.book_rng = Some(Box::new(rng));
+        self
+    }
+
+    // Evaluates with the network instead of the handwritten evaluation
+    pub fn with_network(mut self, network: Arc<Network>) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    // Searches with helper threads which share the transposition table, each on its own copy of the game
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.clamp(1, MAX_THREADS);
+        self
+    }
+
+    // The options a UCI frontend passes on, named as in the UCI protocol
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "Threads" => {
+                let threads: usize = value.parse().map_err( |_| format!("Invalid thread count '{}'", value) )?;
+
+                if !(1..=MAX_THREADS).contains(&threads) {
+                    return Err(format!("Threads must be between 1 and {}", MAX_THREADS));
+                }
+
+                self.threads = threads;
+
+                Ok(())
+            },
+            _ => Err(format!("Unknown option '{}'", name))
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn search(&mut self, game: &Game, limits: &SearchLimits) -> SearchResult {
+        self.search_with_progress(game, limits, |_| ())
+    }
+
+    // Calls `on_depth` with the intermediate result after every completed depth
+    pub fn search_with_progress<F>(&mut self, game: &Game, limits: &SearchLimits, mut on_depth: F) -> SearchResult
+        where F: FnMut(&SearchResult)
+    {
+        self.stats = SearchStats::default();
+        self.started_at = now_in_milliseconds();
+        self.limits = limits.clone();
+        self.stopped = false;
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            principal_variation: Vec::new(),
+            stats: SearchStats::default(),
+            from_book: false
+        };
+
+        if let Some((opening_book, options)) = &self.book {
+            let book_move = match self.book_rng.as_mut() {
+                Some(rng) => book::choose_move_with_rng(opening_book.as_ref(), game, options, rng.as_mut()),
+                None => book::choose_move(opening_book.as_ref(), game, options)
+            };
+
+            if let Some(book_move) = book_move {
+                result.best_move = Some(book_move.clone());
+                result.principal_variation = vec![book_move];
+                result.from_book = true;
+
+                on_depth(&result);
+
+                return result;
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("search", threads = self.threads).entered();
+
+        let helpers = self.start_helpers(game, limits);
+        let mut result = self.iterative_deepening(game, limits, 1, result, &mut on_depth);
+
+        for (stop, helper) in helpers {
+            stop.store(true, Ordering::Relaxed);
+
+            if let Ok(stats) = helper.join() {
+                result.stats.nodes += stats.nodes;
+                result.stats.qnodes += stats.qnodes;
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            depth = result.stats.depth,
+            nodes = result.stats.nodes + result.stats.qnodes,
+            nodes_per_second = ((result.stats.nodes + result.stats.qnodes) as f64 / result.stats.elapsed.as_secs_f64().max(0.001)) as u64,
+            "search finished"
+        );
+
+        result
+    }
+
+    // Only follows the captures and promotions from the position, without the book, the helper threads or the
+    // table, e.g. for quickly comparing many positions. From the point of view of the side to move.
+    pub fn quiescence_score(&mut self, game: &Game) -> i32 {
+        self.stats = SearchStats::default();
+        self.limits = SearchLimits::default();
+        self.stopped = false;
+
+        if !game.has_legal_move() {
+            return if game.in_check(game.position().next_to_move) { -MATE_SCORE } else { 0 };
+        }
+
+        let accumulator = self.network.as_ref().map( |network| network.accumulator(game.position()) );
+
+        self.quiescence(game, accumulator.as_ref(), -MATE_SCORE - 1, MATE_SCORE + 1)
+    }
+
+    fn iterative_deepening<F>(&mut self, game: &Game, limits: &SearchLimits, start_depth: u32, mut result: SearchResult, on_depth: &mut F) -> SearchResult
+        where F: FnMut(&SearchResult)
+    {
+        let max_depth = limits.depth.unwrap_or(u32::MAX);
+        let accumulator = self.network.as_ref().map( |network| network.accumulator(game.position()) );
+
+        for depth in start_depth.min(max_depth)..=max_depth {
+            if depth > 1 && self.abort.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let score = self.negamax(game, accumulator.as_ref(), depth, 0, -MATE_SCORE - 1, MATE_SCORE + 1);
+
+            if self.stopped && depth > 1 {
+                break;
+            }
+
+            let principal_variation = self.principal_variation(game, depth);
+
+            result.best_move = principal_variation.first().cloned();
+            result.principal_variation = principal_variation;
+            result.score = score;
+            self.stats.depth = depth;
+
+            self.stats.elapsed = self.elapsed();
+            result.stats = self.stats.clone();
+            on_depth(&result);
+
+            if self.stopped || result.best_move.is_none() || result.mate_in().is_some() {
+                break;
+            }
+        }
+
+        // Aborted before finishing the first depth
+        if result.best_move.is_none() {
+            let mut moves = game.valid_moves();
+            order_moves(&mut moves, None);
+
+            result.best_move = moves.into_iter().next();
+        }
+
+        self.stats.elapsed = self.elapsed();
+        result.stats = self.stats.clone();
+
+        result
+    }
+
+    // Lazy SMP: the helpers search the same position and only help by filling the shared table. Every other one
+    // starts one depth deeper, so that they don't all search the same nodes in the same order.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_helpers(&self, game: &Game, limits: &SearchLimits) -> Vec<(Arc<AtomicBool>, std::thread::JoinHandle<SearchStats>)> {
+        (1..self.threads).map( |helper| {
+            let stop = Arc::new(AtomicBool::new(false));
+
+            let mut searcher = Searcher {
+                table: self.table.clone(),
+                abort: stop.clone(),
+                network: self.network.clone(),
+                ..Searcher::new()
+            };
+
+            searcher.started_at = self.started_at;
+            searcher.limits = limits.clone();
+
+            let game = game.clone();
+            let limits = limits.clone();
+
+            let handle = std::thread::spawn(move || {
+                let empty = SearchResult {
+                    best_move: None,
+                    score: 0,
+                    principal_variation: Vec::new(),
+                    stats: SearchStats::default(),
+                    from_book: false
+                };
+
+                searcher.iterative_deepening(&game, &limits, 1 + (helper as u32 % 2), empty, &mut |_| ());
+                searcher.stats
+            });
+
+            (stop, handle)
+        }).collect()
+    }
+
+    // There are no threads on wasm32-unknown-unknown
+    #[cfg(target_arch = "wasm32")]
+    fn start_helpers(&self, _game: &Game, _limits: &SearchLimits) -> Vec<(Arc<AtomicBool>, std::thread::JoinHandle<SearchStats>)> {
+        Vec::new()
+    }
+
+    fn negamax(&mut self, game: &Game, accumulator: Option<&Accumulator>, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        self.stats.nodes += 1;
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        let original_alpha = alpha;
+        let hash = game.hash();
+        let mut table_move = None;
+
+        if let Some(entry) = self.table.get(hash) {
+            self.stats.tt_hits += 1;
+            table_move = entry.best_move.clone();
+
+            if ply > 0 && entry.depth >= depth {
+                let score = from_table_score(entry.score, ply);
+
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => ()
+                }
+            }
+        }
+
+        let mut moves = game.valid_moves();
+
+        if moves.is_empty() {
+            return if game.in_check(game.position().next_to_move) {
+                -MATE_SCORE + ply
+            } else {
+                0
+            };
+        }
+
+        if ply > 0 && game.draw_by_fifty_move_rule() {
+            return 0;
+        }
+
+        if depth == 0 {
+            return self.quiescence(game, accumulator, alpha, beta);
+        }
+
+        order_moves(&mut moves, table_move.as_ref());
+
+        let mut best_score = -MATE_SCORE - 1;
+        let mut best_move = None;
+
+        for valid_move in moves {
+            let child = game.make_valid_move(&valid_move);
+            let child_accumulator = self.child_accumulator(accumulator, game, &child);
+
+            let score = -self.negamax(&child, child_accumulator.as_ref(), depth - 1, ply + 1, -beta, -alpha);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(valid_move);
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+
+            if alpha >= beta {
+                self.stats.cutoffs += 1;
+                break;
+            }
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.insert(hash, TableEntry {
+            depth,
+            score: to_table_score(best_score, ply),
+            bound,
+            best_move
+        });
+
+        best_score
+    }
+
+    fn quiescence(&mut self, game: &Game, accumulator: Option<&Accumulator>, mut alpha: i32, beta: i32) -> i32 {
+        self.stats.qnodes += 1;
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        let stand_pat = match (&self.network, accumulator) {
+            (Some(network), Some(accumulator)) => network.evaluate(accumulator, game.position().next_to_move),
+            _ => eval::evaluate_for_side_to_move(game.position())
+        };
+
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        let mut moves: Vec<ValidMove> = game.valid_moves().into_iter()
+            .filter( |valid_move| valid_move.is_capture() || valid_move.is_promotion() )
+            .collect();
+
+        order_moves(&mut moves, None);
+
+        for valid_move in moves {
+            let child = game.make_valid_move(&valid_move);
+            let child_accumulator = self.child_accumulator(accumulator, game, &child);
+
+            let score = -self.quiescence(&child, child_accumulator.as_ref(), -beta, -alpha);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                self.stats.cutoffs += 1;
+                return score;
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        alpha
+    }
+
+    // Updated from the parent's accumulator instead of being computed again for every node
+    fn child_accumulator(&self, accumulator: Option<&Accumulator>, game: &Game, child: &Game) -> Option<Accumulator> {
+        match (&self.network, accumulator) {
+            (Some(network), Some(accumulator)) => Some(network.update(accumulator, game.position(), child.position())),
+            _ => None
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        let visited = self.stats.nodes + self.stats.qnodes;
+
+        if !visited.is_multiple_of(CHECK_INTERVAL) {
+            return false;
+        }
+
+        let out_of_nodes = self.limits.nodes.is_some_and( |nodes| visited >= nodes );
+        let out_of_time = self.limits.movetime.is_some_and( |movetime|
+            now_in_milliseconds() - self.started_at >= movetime.as_secs_f64() * 1000.0
+        );
+
+        self.stopped = out_of_nodes || out_of_time || self.abort.load(Ordering::Relaxed);
+        self.stopped
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::from_micros(((now_in_milliseconds() - self.started_at) * 1000.0) as u64)
+    }
+
+    fn principal_variation(&self, game: &Game, depth: u32) -> Vec<ValidMove> {
+        let mut line = Vec::new();
+        let mut current = game.clone();
+
+        while line.len() < depth as usize {
+            let next_move = match self.table.get(current.hash()).and_then( |entry| entry.best_move ) {
+                Some(next_move) => next_move,
+                None => break
+            };
+
+            // The table can contain colliding entries, so only follow moves that are still legal
+            if !current.valid_moves().contains(&next_move) {
+                break;
+            }
+
+            current = current.make_valid_move(&next_move);
+            line.push(next_move);
+        }
+
+        line
+    }
+}
+
+pub fn search(game: &Game, limits: &SearchLimits) -> SearchResult {
+    Searcher::new().search(game, limits)
+}
+
+// Table move first, then captures by most valuable victim / least valuable attacker
+fn order_moves(moves: &mut [ValidMove], table_move: Option<&ValidMove>) {
+    moves.sort_by_key( |valid_move| {
+        if Some(valid_move) == table_move {
+            return i32::MIN;
+        }
+
+        let capture_score = match valid_move.takes {
+            Some(piece) => 10 * piece.value() - valid_move.piece.value() + 100,
+            None => 0
+        };
+
+        let promotion_score = valid_move.promotion.map_or(0, |piece| piece.value());
+
+        -(capture_score + promotion_score)
+    });
+}
+
+// Mate scores are stored relative to the node so that they stay correct when reached through another path
+fn to_table_score(score: i32, ply: i32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score + ply
+    } else if score < -MATE_THRESHOLD {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn from_table_score(score: i32, ply: i32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score - ply
+    } else if score < -MATE_THRESHOLD {
+        score + ply
+    } else {
+        score
+    }
+}
+
+// std::time::Instant is not available on wasm32-unknown-unknown
+#[cfg(target_arch = "wasm32")]
+fn now_in_milliseconds() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_in_milliseconds() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now().duration_since(UNIX_EPOCH).map( |duration| duration.as_secs_f64() * 1000.0 ).unwrap_or(0.0)
+}
@@ -0,0 +1,116 @@
+use super::super::game::{Game, ValidMove};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Stipulation {
+    // The side to move mates against any defence
+    Mate,
+
+    // Both sides cooperate so that the side to move gets mated, e.g. in a helpmate in two black moves first
+    // and is mated by white's second move
+    Helpmate,
+
+    // The side to move forces the opponent to mate it, whatever the opponent plays
+    Selfmate
+}
+
+// `moves` counts the moves of the side to move. The solution starts with the key move and follows one line:
+// the longest defence for mates and selfmates, and the cooperating moves of both sides for helpmates.
+// The search is exhaustive, so anything beyond a few moves takes a long time.
+pub fn solve_problem(game: &Game, stipulation: Stipulation, moves: u32) -> Option<Vec<ValidMove>> {
+    if moves == 0 {
+        return None;
+    }
+
+    match stipulation {
+        Stipulation::Mate     => direct_mate(game, moves),
+        Stipulation::Helpmate => helpmate(game, moves * 2),
+        Stipulation::Selfmate => selfmate(game, moves)
+    }
+}
+
+fn direct_mate(game: &Game, moves: u32) -> Option<Vec<ValidMove>> {
+    for key in game.valid_moves() {
+        let after = game.make_valid_move(&key);
+        let replies = after.valid_moves();
+
+        if replies.is_empty() {
+            if after.in_check(after.position().next_to_move) {
+                return Some(vec![key]);
+            }
+
+            // Stalemate
+            continue;
+        }
+
+        if moves == 1 {
+            continue;
+        }
+
+        let defence = longest_defence(&after, replies, |next| direct_mate(next, moves - 1) );
+
+        if let Some(defence) = defence {
+            return Some(std::iter::once(key).chain(defence).collect());
+        }
+    }
+
+    None
+}
+
+fn selfmate(game: &Game, moves: u32) -> Option<Vec<ValidMove>> {
+    for key in game.valid_moves() {
+        let after = game.make_valid_move(&key);
+        let replies = after.valid_moves();
+
+        // Mating or stalemating the opponent doesn't solve a selfmate
+        if replies.is_empty() {
+            continue;
+        }
+
+        let defence = longest_defence(&after, replies, |next| {
+            if next.in_mate() {
+                Some(Vec::new())
+            } else if moves == 1 {
+                None
+            } else {
+                selfmate(next, moves - 1)
+            }
+        });
+
+        if let Some(defence) = defence {
+            return Some(std::iter::once(key).chain(defence).collect());
+        }
+    }
+
+    None
+}
+
+// Every reply has to keep the stipulation going. Returns the reply which does so for the longest.
+fn longest_defence<F>(game: &Game, replies: Vec<ValidMove>, continuation: F) -> Option<Vec<ValidMove>>
+    where F: Fn(&Game) -> Option<Vec<ValidMove>> {
+    let mut longest: Option<Vec<ValidMove>> = None;
+
+    for reply in replies {
+        let line = continuation(&game.make_valid_move(&reply))?;
+
+        if longest.as_ref().is_none_or( |longest| line.len() + 1 > longest.len() ) {
+            longest = Some(std::iter::once(reply).chain(line).collect());
+        }
+    }
+
+    longest
+}
+
+// The side which is to move after `plies` half-moves has to be mated right then
+fn helpmate(game: &Game, plies: u32) -> Option<Vec<ValidMove>> {
+    if plies == 0 {
+        return if game.in_mate() { Some(Vec::new()) } else { None };
+    }
+
+    for valid_move in game.valid_moves() {
+        if let Some(line) = helpmate(&game.make_valid_move(&valid_move), plies - 1) {
+            return Some(std::iter::once(valid_move).chain(line).collect());
+        }
+    }
+
+    None
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use super::TableEntry;
+
+// Split into shards so that search threads sharing the table rarely wait for each other
+const SHARDS: usize = 64;
+
+pub struct TranspositionTable {
+    shards: Vec<Mutex<HashMap<u64, TableEntry>>>
+}
+
+impl TranspositionTable {
+    pub fn new() -> Self {
+        TranspositionTable {
+            shards: (0..SHARDS).map( |_| Mutex::new(HashMap::new()) ).collect()
+        }
+    }
+
+    pub fn get(&self, hash: u64) -> Option<TableEntry> {
+        self.shard(hash).get(&hash).cloned()
+    }
+
+    pub fn insert(&self, hash: u64, entry: TableEntry) {
+        self.shard(hash).insert(hash, entry);
+    }
+
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            lock(shard).clear();
+        }
+    }
+
+    fn shard(&self, hash: u64) -> MutexGuard<'_, HashMap<u64, TableEntry>> {
+        lock(&self.shards[(hash % SHARDS as u64) as usize])
+    }
+}
+
+// A thread which panicked while holding a shard can't have left an entry half written
+fn lock(shard: &Mutex<HashMap<u64, TableEntry>>) -> MutexGuard<'_, HashMap<u64, TableEntry>> {
+    shard.lock().unwrap_or_else( |poisoned| poisoned.into_inner() )
+}
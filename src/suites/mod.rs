@@ -0,0 +1,282 @@
+use super::game::{Game, ValidMove};
+use super::search::{Searcher, SearchLimits};
+
+// The bundled data. The opening suite has the balanced main lines of 44 common openings, five moves deep, to be
+// played from both sides in engine matches. Of the 300 Win at Chess positions only the first 10 are included,
+// Bratko-Kopec has all 24.
+pub const OPENINGS_PGN: &str = include_str!("openings.pgn");
+pub const BRATKO_KOPEC_EPD: &str = include_str!("bratko_kopec.epd");
+pub const WIN_AT_CHESS_EPD: &str = include_str!("win_at_chess.epd");
+pub const PERFT_EPD: &str = include_str!("perft.epd");
+
+// Small positions for the rules move generators get wrong, e.g. en passant captures exposing the king or
+// castling through an attacked square. Together with PERFT_EPD they make up the perft corpus.
+pub const PERFT_CORPUS_EPD: &str = include_str!("perft_corpus.epd");
+
+// A position of an EPD file with its operations, e.g. ("bm", "Qd1+") and ("id", "BK.01")
+#[derive(Debug, Clone)]
+pub struct EpdPosition {
+    pub game: Game,
+    pub operations: Vec<(String, String)>
+}
+
+impl EpdPosition {
+    pub fn operation(&self, opcode: &str) -> Option<&str> {
+        self.operations.iter()
+            .find( |(name, _)| name == opcode )
+            .map( |(_, operand)| operand.as_str() )
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")
+    }
+
+    // The moves of the bm operand, which lists them in SAN separated by spaces
+    pub fn best_moves(&self) -> Result<Vec<ValidMove>, String> {
+        self.moves_of("bm")
+    }
+
+    pub fn avoid_moves(&self) -> Result<Vec<ValidMove>, String> {
+        self.moves_of("am")
+    }
+
+    // A move solves the position when it is one of the best moves and none of the moves to avoid
+    pub fn is_solved_by(&self, valid_move: &ValidMove) -> bool {
+        let best_moves = self.best_moves().unwrap_or_default();
+        let avoid_moves = self.avoid_moves().unwrap_or_default();
+
+        (best_moves.is_empty() || best_moves.contains(valid_move)) && !avoid_moves.contains(valid_move)
+    }
+
+    fn moves_of(&self, opcode: &str) -> Result<Vec<ValidMove>, String> {
+        self.operation(opcode).unwrap_or("")
+            .split_whitespace()
+            .map( |notation| {
+                ValidMove::from_notation(&self.game, notation)
+                    .map_err( |error| format!("Invalid move '{}' in {:?}: {:?}", notation, self.id(), error) )
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PerftPosition {
+    pub name: String,
+    pub game: Game,
+
+    // The expected node count for each depth, starting with depth 1
+    pub nodes: Vec<u64>,
+
+    pub tags: Vec<String>
+}
+
+// One node count of the perft corpus
+#[derive(Debug, Clone)]
+pub struct PerftCase {
+    pub name: String,
+    pub game: Game,
+    pub depth: u32,
+    pub nodes: u64,
+
+    // What the position tests, e.g. "en-passant-pin" or "castling-through-check"
+    pub tags: Vec<String>
+}
+
+impl PerftCase {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any( |name| name == tag )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PerftFailure {
+    pub name: String,
+    pub depth: u32,
+    pub expected: u64,
+    pub found: u64,
+    pub tags: Vec<String>
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PerftReport {
+    pub passed: usize,
+
+    // Cases with more nodes than allowed by the run
+    pub skipped: usize,
+
+    pub failures: Vec<PerftFailure>
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SuiteResult {
+    pub solved: usize,
+
+    // The ids of the positions which weren't solved
+    pub failed: Vec<String>
+}
+
+// One position per line: the board, side to move, castling and en passant fields, followed by operations
+// separated by semicolons. Operands in quotes lose their quotes.
+pub fn parse_epd(epd: &str) -> Result<Vec<EpdPosition>, String> {
+    epd.lines()
+        .map( |line| line.trim() )
+        .filter( |line| !line.is_empty() )
+        .map( |line| {
+            let mut segments = line.split(';');
+            let mut first = segments.next().unwrap_or("").split_whitespace();
+
+            let fields: Vec<&str> = first.by_ref().take(4).collect();
+            let fen = format!("{} 0 1", fields.join(" "));
+
+            let game = Game::new_from_fen(&fen)
+                .map_err( |error| format!("Invalid EPD line '{}': {}", line, error.message) )?;
+
+            let first_operation = first.collect::<Vec<_>>().join(" ");
+
+            let operations = std::iter::once(first_operation.as_str())
+                .chain(segments)
+                .map( |operation| operation.trim() )
+                .filter( |operation| !operation.is_empty() )
+                .map( |operation| {
+                    let (opcode, operand) = operation.split_once(char::is_whitespace).unwrap_or((operation, ""));
+
+                    (String::from(opcode), String::from(operand.trim().trim_matches('"')))
+                })
+                .collect();
+
+            Ok(EpdPosition { game, operations })
+        })
+        .collect()
+}
+
+// Every line of the opening suite, ready to be used as the openings of a match
+pub fn openings() -> Vec<Game> {
+    Game::new_from_pgn(OPENINGS_PGN)
+        .expect("Invalid bundled opening suite")
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .expect("Invalid bundled opening suite")
+}
+
+pub fn bratko_kopec() -> Vec<EpdPosition> {
+    parse_epd(BRATKO_KOPEC_EPD).expect("Invalid bundled Bratko-Kopec suite")
+}
+
+pub fn win_at_chess() -> Vec<EpdPosition> {
+    parse_epd(WIN_AT_CHESS_EPD).expect("Invalid bundled Win at Chess suite")
+}
+
+// The positions of PERFT_EPD, where the D1, D2, ... operations hold the node counts
+pub fn perft_positions() -> Vec<PerftPosition> {
+    parse_epd(PERFT_EPD).expect("Invalid bundled perft positions").into_iter()
+        .map( |position| {
+            let nodes = (1..)
+                .map( |depth| position.operation(&format!("D{}", depth)).map( |nodes| nodes.parse().expect("Invalid perft node count") ) )
+                .take_while( |nodes| nodes.is_some() )
+                .flatten()
+                .collect();
+
+            PerftPosition {
+                name: String::from(position.id().unwrap_or("")),
+                tags: tags_of(&position),
+                game: position.game,
+                nodes
+            }
+        })
+        .collect()
+}
+
+// Every node count of PERFT_EPD and PERFT_CORPUS_EPD, with the tags of its position. The depths of a position
+// don't have to follow each other, e.g. "D1 18; D6 1134888".
+pub fn perft_corpus() -> Vec<PerftCase> {
+    let positions = [PERFT_EPD, PERFT_CORPUS_EPD].iter()
+        .map( |epd| parse_epd(epd) )
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Invalid bundled perft corpus");
+
+    let mut cases = Vec::new();
+
+    for position in positions.iter().flatten() {
+        for (opcode, operand) in position.operations.iter() {
+            let depth = match opcode.strip_prefix('D').and_then( |depth| depth.parse().ok() ) {
+                Some(depth) => depth,
+                None => continue
+            };
+
+            cases.push(PerftCase {
+                name: String::from(position.id().unwrap_or("")),
+                game: position.game.clone(),
+                depth,
+                nodes: operand.parse().expect("Invalid perft node count"),
+                tags: tags_of(position)
+            });
+        }
+    }
+
+    cases
+}
+
+// Runs perft for every case with at most max_nodes nodes and reports the ones with another count, e.g.
+// run_perft(&perft_corpus().into_iter().filter( |case| case.has_tag("castling") ).collect::<Vec<_>>(), 1_000_000)
+pub fn run_perft(cases: &[PerftCase], max_nodes: u64) -> PerftReport {
+    let mut report = PerftReport::default();
+
+    for case in cases {
+        if case.nodes > max_nodes {
+            report.skipped += 1;
+            continue;
+        }
+
+        let found = perft(&case.game, case.depth);
+
+        if found == case.nodes {
+            report.passed += 1;
+        } else {
+            report.failures.push(PerftFailure {
+                name: case.name.clone(),
+                depth: case.depth,
+                expected: case.nodes,
+                found,
+                tags: case.tags.clone()
+            });
+        }
+    }
+
+    report
+}
+
+// The tags operation lists them separated by spaces
+fn tags_of(position: &EpdPosition) -> Vec<String> {
+    position.operation("tags").unwrap_or("").split_whitespace().map(String::from).collect()
+}
+
+// The number of move sequences of the given length
+pub fn perft(game: &Game, depth: u32) -> u64 {
+    match depth {
+        0 => 1,
+        1 => game.count_legal_moves() as u64,
+        _ => game.valid_moves().iter()
+            .map( |valid_move| perft(&game.make_valid_move(valid_move), depth - 1) )
+            .sum()
+    }
+}
+
+// Searches every position and checks the best move against its bm and am operations
+pub fn run_suite(searcher: &mut Searcher, positions: &[EpdPosition], limits: &SearchLimits) -> SuiteResult {
+    let mut result = SuiteResult { solved: 0, failed: Vec::new() };
+
+    for (i, position) in positions.iter().enumerate() {
+        searcher.clear();
+
+        let solved = searcher.search(&position.game, limits).best_move
+            .is_some_and( |best_move| position.is_solved_by(&best_move) );
+
+        if solved {
+            result.solved += 1;
+        } else {
+            result.failed.push(position.id().map_or_else( || (i + 1).to_string(), String::from ));
+        }
+    }
+
+    result
+}
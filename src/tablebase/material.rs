@@ -0,0 +1,246 @@
+use super::super::models::*;
+use super::super::game::{Game, PROMOTION_PIECES};
+
+// The pieces of both sides, always listed from the king down to the pawns, e.g. "KRvK"
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(super) struct Material {
+    pub white: Vec<Piece>,
+    pub black: Vec<Piece>
+}
+
+impl Material {
+    pub fn parse(signature: &str) -> Result<Material, String> {
+        let sides: Vec<&str> = signature.split('v').collect();
+
+        if sides.len() != 2 {
+            return Err(format!("Invalid material signature {}, expected something like KQvK", signature));
+        }
+
+        let white = parse_side(sides[0])?;
+        let black = parse_side(sides[1])?;
+
+        Ok(Material::new(white, black))
+    }
+
+    pub fn from_board(board: &Board) -> Material {
+        let pieces = |color: Color| board.squares.iter()
+            .filter_map( |occupancy| occupancy.as_ref() )
+            .filter( |occupancy| occupancy.color == color )
+            .map( |occupancy| occupancy.piece )
+            .collect();
+
+        Material::new(pieces(Color::White), pieces(Color::Black))
+    }
+
+    fn new(mut white: Vec<Piece>, mut black: Vec<Piece>) -> Material {
+        white.sort_by_key(order);
+        black.sort_by_key(order);
+
+        Material { white, black }
+    }
+
+    pub fn signature(&self) -> String {
+        let side = |pieces: &[Piece]| pieces.iter().map(Piece::letter).collect::<String>();
+
+        format!("{}v{}", side(&self.white), side(&self.black))
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.white.len() + self.black.len()
+    }
+
+    pub fn has_kings(&self) -> bool {
+        let kings = |pieces: &[Piece]| pieces.iter().filter( |piece| **piece == Piece::King ).count();
+
+        kings(&self.white) == 1 && kings(&self.black) == 1
+    }
+
+    pub fn flipped(&self) -> Material {
+        Material { white: self.black.clone(), black: self.white.clone() }
+    }
+
+    // Tables are only generated with the stronger side as white, the other color is probed by mirroring the board
+    pub fn is_canonical(&self) -> bool {
+        strength(&self.white) >= strength(&self.black)
+    }
+
+    // Every material which can be reached by a capture or a promotion
+    pub fn successors(&self) -> Vec<Material> {
+        let mut successors = Vec::new();
+
+        for color in [Color::White, Color::Black].iter() {
+            let (pieces, others) = match color {
+                Color::White => (&self.white, &self.black),
+                Color::Black => (&self.black, &self.white)
+            };
+
+            for (i, piece) in pieces.iter().enumerate() {
+                let mut remaining = pieces.clone();
+                remaining.remove(i);
+
+                if *piece == Piece::Pawn {
+                    for promotion in PROMOTION_PIECES.iter() {
+                        let mut promoted = remaining.clone();
+                        promoted.push(*promotion);
+
+                        push_unique(&mut successors, Material::for_color(*color, promoted, others.clone()));
+                    }
+                }
+
+                if *piece != Piece::King {
+                    push_unique(&mut successors, Material::for_color(*color, remaining, others.clone()));
+                }
+            }
+        }
+
+        successors
+    }
+
+    fn for_color(color: Color, pieces: Vec<Piece>, others: Vec<Piece>) -> Material {
+        match color {
+            Color::White => Material::new(pieces, others),
+            Color::Black => Material::new(others, pieces)
+        }
+    }
+
+    // All white pieces followed by all black pieces
+    fn pieces(&self) -> Vec<OccupiedSquare> {
+        let white = self.white.iter().map( |piece| OccupiedSquare { piece: *piece, color: Color::White } );
+        let black = self.black.iter().map( |piece| OccupiedSquare { piece: *piece, color: Color::Black } );
+
+        white.chain(black).collect()
+    }
+
+    pub fn table_size(&self) -> usize {
+        64usize.pow(self.piece_count() as u32) * 2
+    }
+
+    // The index is built from the square of every piece (in `pieces()` order) and the side to move.
+    // `mirrored` looks the position up with colors swapped and the board flipped, for non-canonical material.
+    pub fn index(&self, position: &Position, mirrored: bool) -> usize {
+        let mut squares: Vec<(OccupiedSquare, usize)> = Vec::new();
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let occupancy = match &position.board.squares[Board::index(Square { rank, file })] {
+                    Some(occupancy) => occupancy.clone(),
+                    None => continue
+                };
+
+                if mirrored {
+                    squares.push((OccupiedSquare { piece: occupancy.piece, color: occupancy.color.opposite() }, Board::index(Square { rank, file })));
+                } else {
+                    squares.push((occupancy, Square { rank, file }.to_a1_index()));
+                }
+            }
+        }
+
+        // Identical pieces are always indexed in increasing square order
+        squares.sort_by_key( |(_, square)| *square );
+
+        let mut index = 0;
+
+        for piece in self.pieces() {
+            let found = squares.iter().position( |(occupancy, _)| *occupancy == piece )
+                .expect("The position doesn't match the table material");
+
+            index = index * 64 + squares.remove(found).1;
+        }
+
+        let next_to_move = if mirrored { position.next_to_move.opposite() } else { position.next_to_move };
+
+        index * 2 + match next_to_move {
+            Color::White => 0,
+            Color::Black => 1
+        }
+    }
+
+    // None for indices which don't describe a legal position
+    pub fn position(&self, index: usize) -> Option<Game> {
+        let next_to_move = if index.is_multiple_of(2) { Color::White } else { Color::Black };
+        let pieces = self.pieces();
+
+        let mut squares = vec![None; 64];
+        let mut remaining = index / 2;
+        let mut piece_squares = vec![0; pieces.len()];
+
+        for i in (0..pieces.len()).rev() {
+            piece_squares[i] = remaining % 64;
+            remaining /= 64;
+        }
+
+        for (i, piece) in pieces.iter().enumerate() {
+            let square = piece_squares[i];
+            let rank = square / 8;
+
+            if piece.piece == Piece::Pawn && (rank == 0 || rank == 7) {
+                return None;
+            }
+
+            // Skip the other orderings of identical pieces
+            if i > 0 && pieces[i - 1] == *piece && piece_squares[i - 1] >= square {
+                return None;
+            }
+
+            let board_index = Board::index(Square { rank: rank as i8, file: (square % 8) as i8 });
+
+            if squares[board_index].is_some() {
+                return None;
+            }
+
+            squares[board_index] = Some(piece.clone());
+        }
+
+        let game = Game::new(Position {
+            board: Board { squares },
+            next_to_move,
+
+            white_can_castle_king_side: false,
+            white_can_castle_queen_side: false,
+            black_can_castle_king_side: false,
+            black_can_castle_queen_side: false,
+
+            en_passant_square: None,
+
+            half_move_clock: 0,
+            full_move_counter: 1
+        });
+
+        if game.in_check(next_to_move.opposite()) {
+            None
+        } else {
+            Some(game)
+        }
+    }
+}
+
+impl Position {
+    // Pieces of both sides, strongest first, e.g. "KRPvKR"
+    pub fn material_signature(&self) -> String {
+        Material::from_board(&self.board).signature()
+    }
+}
+
+fn push_unique(materials: &mut Vec<Material>, material: Material) {
+    if !materials.contains(&material) {
+        materials.push(material);
+    }
+}
+
+fn parse_side(side: &str) -> Result<Vec<Piece>, String> {
+    side.chars()
+        .map( |letter| Piece::from_letter(letter).ok_or(format!("Invalid piece {} in material signature", letter)) )
+        .collect()
+}
+
+// Kings first
+fn order(piece: &Piece) -> usize {
+    PIECES.len() - 1 - piece.index()
+}
+
+fn strength(pieces: &[Piece]) -> (i32, Vec<usize>) {
+    let value = pieces.iter().map( |piece| piece.value() ).sum();
+
+    // Ties are broken by the pieces themselves, so that only one side of e.g. KRvKB is canonical
+    (value, pieces.iter().map( |piece| piece.index() + 1 ).collect())
+}
@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::path::Path;
+
+use super::models::*;
+use super::game::{Game, ValidMove};
+
+mod material;
+
+use material::Material;
+
+const MAGIC: &[u8; 8] = b"PGNTB001";
+
+pub const MAX_PIECES: usize = 4;
+
+// Stored results. UNKNOWN is only used while generating.
+const INVALID: u8 = 0;
+const LOSS: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 3;
+const UNKNOWN: u8 = 4;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win
+}
+
+// For the side to move, assuming perfect play by both sides. The fifty move rule is not taken into account.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Probe {
+    pub wdl: Wdl,
+
+    // Half-moves until mate. None for draws and for tables which were saved without distances.
+    pub dtm: Option<u32>
+}
+
+impl Probe {
+    // In moves, positive when the side to move mates and negative when it gets mated
+    pub fn mate_in(&self) -> Option<i32> {
+        let dtm = self.dtm? as i32;
+
+        match self.wdl {
+            Wdl::Win  => Some((dtm + 1) / 2),
+            Wdl::Loss => Some(-dtm / 2),
+            Wdl::Draw => None
+        }
+    }
+}
+
+// The results for every placement of a single material, e.g. KRvK.
+// En passant is ignored, which only matters for tables with pawns on both sides.
+pub struct Table {
+    material: Material,
+    results: Vec<u8>,
+    dtm: Option<Vec<u16>>
+}
+
+// Anything that knows the results of endgame positions, e.g. the generated tables or a reader for another
+// tablebase format. Only probe has to be implemented.
+//
+// The crate itself only reads the tables it generates. Gaviota and Nalimov files are compressed with LZMA, zlib
+// or bzip2, which would need a decompression dependency, so readers for them are not part of it.
+pub trait EndgameTablebase {
+    // None if the position isn't covered
+    fn probe(&self, game: &Game) -> Option<Probe>;
+
+    // The fastest win, the longest defence, or a move keeping the draw. Without distances to mate any winning
+    // move is picked, which is not guaranteed to make progress.
+    fn best_move(&self, game: &Game) -> Option<ValidMove> {
+        let moves: Vec<(ValidMove, Probe)> = game.valid_moves().into_iter()
+            .map( |valid_move| {
+                let probe = self.probe(&game.make_valid_move(&valid_move))?;
+
+                Some((valid_move, probe))
+            })
+            .collect::<Option<_>>()?;
+
+        moves.into_iter()
+            .min_by_key( |(_, probe)| {
+                let dtm = probe.dtm.unwrap_or(0) as i64;
+
+                // The probe is for the opponent
+                match probe.wdl {
+                    Wdl::Loss => (0, dtm),
+                    Wdl::Draw => (1, 0),
+                    Wdl::Win  => (2, -dtm)
+                }
+            })
+            .map( |(valid_move, _)| valid_move )
+    }
+}
+
+#[derive(Default)]
+pub struct Tablebase {
+    tables: HashMap<String, Table>
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Also generates the tables for every material reachable through captures and promotions, e.g. KPvK
+    // needs KQvK, KRvK, KBvK, KNvK and KvK. Both colors are covered by the same table.
+    pub fn generate(&mut self, signature: &str) -> Result<(), String> {
+        let material = Material::parse(signature)?;
+
+        if !material.has_kings() {
+            return Err(format!("{} must have exactly one king per side", signature));
+        }
+
+        if material.piece_count() > MAX_PIECES {
+            return Err(format!("Only endgames with up to {} pieces can be generated", MAX_PIECES));
+        }
+
+        self.generate_material(material)
+    }
+
+    fn generate_material(&mut self, material: Material) -> Result<(), String> {
+        let material = if material.is_canonical() { material } else { material.flipped() };
+
+        if self.tables.contains_key(&material.signature()) {
+            return Ok(());
+        }
+
+        for successor in material.successors() {
+            self.generate_material(successor)?;
+        }
+
+        let table = generate_table(material, self)?;
+        self.add_table(table);
+
+        Ok(())
+    }
+
+    pub fn add_table(&mut self, table: Table) {
+        self.tables.insert(table.signature(), table);
+    }
+
+    pub fn table(&self, signature: &str) -> Option<&Table> {
+        self.tables.get(signature)
+    }
+
+    pub fn signatures(&self) -> Vec<String> {
+        let mut signatures: Vec<String> = self.tables.keys().cloned().collect();
+        signatures.sort();
+
+        signatures
+    }
+}
+
+impl EndgameTablebase for Tablebase {
+    // None if there is no table for the material of the position. Positions with castling rights are not
+    // covered by the tables either.
+    fn probe(&self, game: &Game) -> Option<Probe> {
+        let position = game.position();
+
+        if !position.is_tablebase_size() {
+            return None;
+        }
+
+        let material = Material::from_board(&position.board);
+
+        let mirrored = !material.is_canonical();
+        let material = if mirrored { material.flipped() } else { material };
+
+        let table = self.tables.get(&material.signature())?;
+
+        table.probe_index(material.index(position, mirrored))
+    }
+}
+
+impl Table {
+    pub fn signature(&self) -> String {
+        self.material.signature()
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn has_dtm(&self) -> bool {
+        self.dtm.is_some()
+    }
+
+    fn probe_index(&self, index: usize) -> Option<Probe> {
+        let wdl = match self.results[index] {
+            LOSS => Wdl::Loss,
+            DRAW => Wdl::Draw,
+            WIN  => Wdl::Win,
+            _    => return None
+        };
+
+        let dtm = match wdl {
+            Wdl::Draw => None,
+            _ => self.dtm.as_ref().map( |dtm| dtm[index] as u32 )
+        };
+
+        Some(Probe { wdl, dtm })
+    }
+
+    pub fn save(&self, path: &Path, include_dtm: bool) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        self.write_to(&mut writer, include_dtm)?;
+        writer.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    // Little-endian: magic, signature length and signature, a flag for distances to mate, the entry count,
+    // the results packed four to a byte, and then optionally one u16 distance per entry
+    pub fn write_to<W: Write>(&self, writer: &mut W, include_dtm: bool) -> io::Result<()> {
+        let signature = self.signature();
+        let dtm = self.dtm.as_ref().filter( |_| include_dtm );
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[signature.len() as u8])?;
+        writer.write_all(signature.as_bytes())?;
+        writer.write_all(&[dtm.is_some() as u8])?;
+        writer.write_all(&(self.results.len() as u64).to_le_bytes())?;
+
+        for results in self.results.chunks(4) {
+            let packed = results.iter().enumerate()
+                .fold(0u8, |packed, (i, result)| packed | (result << (i * 2)) );
+
+            writer.write_all(&[packed])?;
+        }
+
+        if let Some(dtm) = dtm {
+            for distance in dtm {
+                writer.write_all(&distance.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let invalid_data = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(invalid_data("Not a tablebase file"));
+        }
+
+        let mut signature = vec![0; read_u8(reader)? as usize];
+        reader.read_exact(&mut signature)?;
+
+        let material = String::from_utf8(signature).ok()
+            .and_then( |signature| Material::parse(&signature).ok() )
+            .ok_or_else( || invalid_data("Invalid material signature") )?;
+
+        let has_dtm = read_u8(reader)? != 0;
+        let count = read_u64(reader)? as usize;
+
+        if count != material.table_size() {
+            return Err(invalid_data("The entry count doesn't match the material"));
+        }
+
+        let mut packed = vec![0; count.div_ceil(4)];
+        reader.read_exact(&mut packed)?;
+
+        let results = (0..count)
+            .map( |i| (packed[i / 4] >> ((i % 4) * 2)) & 0b11 )
+            .collect();
+
+        let dtm = if has_dtm {
+            let mut bytes = vec![0; count * 2];
+            reader.read_exact(&mut bytes)?;
+
+            Some(bytes.chunks(2).map( |distance| u16::from_le_bytes([distance[0], distance[1]]) ).collect())
+        } else {
+            None
+        };
+
+        Ok(Table { material, results, dtm })
+    }
+}
+
+// Retrograde analysis: mates are resolved first and every resolved position resolves or counts down its
+// predecessors, one distance to mate at a time. Whatever is left unresolved at the end is a draw.
+fn generate_table(material: Material, tablebase: &Tablebase) -> Result<Table, String> {
+    let size = material.table_size();
+
+    let mut results = vec![INVALID; size];
+    let mut dtm = vec![0u16; size];
+
+    // In-table moves which haven't been shown to lose yet
+    let mut moves_left = vec![0u8; size];
+
+    // Captures and promotions which win or draw, so the position can't be lost
+    let mut has_escape = vec![false; size];
+
+    // Longest distance to mate after a losing capture or promotion
+    let mut exit_dtm = vec![0u16; size];
+
+    let mut queue: Vec<Vec<(usize, u8)>> = Vec::new();
+
+    for index in 0..size {
+        let game = match material.position(index) {
+            Some(game) => game,
+            None => continue
+        };
+
+        results[index] = UNKNOWN;
+
+        let valid_moves = game.valid_moves();
+
+        if valid_moves.is_empty() {
+            if game.in_check(game.position().next_to_move) {
+                schedule(&mut queue, 0, index, LOSS);
+            } else {
+                results[index] = DRAW;
+            }
+
+            continue;
+        }
+
+        for valid_move in valid_moves.iter() {
+            if valid_move.takes.is_none() && valid_move.promotion.is_none() {
+                moves_left[index] += 1;
+                continue;
+            }
+
+            let next = game.make_valid_move(valid_move);
+            let probe = tablebase.probe(&next)
+                .ok_or_else( || format!("Missing the table for {}", Material::from_board(&next.position().board).signature()) )?;
+
+            let distance = || probe.dtm
+                .map( |dtm| dtm as u16 + 1 )
+                .ok_or_else( || String::from("Tables used for generating need distances to mate") );
+
+            match probe.wdl {
+                Wdl::Loss => {
+                    has_escape[index] = true;
+                    schedule(&mut queue, distance()? as usize, index, WIN);
+                },
+
+                Wdl::Draw => has_escape[index] = true,
+                Wdl::Win  => exit_dtm[index] = exit_dtm[index].max(distance()?)
+            }
+        }
+
+        if moves_left[index] == 0 && !has_escape[index] {
+            schedule(&mut queue, exit_dtm[index] as usize, index, LOSS);
+        }
+    }
+
+    let mut distance = 0;
+
+    while distance < queue.len() {
+        for (index, result) in std::mem::take(&mut queue[distance]) {
+            // A shorter win was found first
+            if results[index] != UNKNOWN {
+                continue;
+            }
+
+            results[index] = result;
+            dtm[index] = distance as u16;
+
+            let game = material.position(index).expect("Only legal positions are scheduled");
+
+            for previous in predecessors(&game) {
+                let previous_index = material.index(previous.position(), false);
+
+                if results[previous_index] != UNKNOWN {
+                    continue;
+                }
+
+                if result == LOSS {
+                    schedule(&mut queue, distance + 1, previous_index, WIN);
+                } else {
+                    moves_left[previous_index] -= 1;
+
+                    if moves_left[previous_index] == 0 && !has_escape[previous_index] {
+                        let loss_distance = (distance + 1).max(exit_dtm[previous_index] as usize);
+
+                        schedule(&mut queue, loss_distance, previous_index, LOSS);
+                    }
+                }
+            }
+        }
+
+        distance += 1;
+    }
+
+    for result in results.iter_mut() {
+        if *result == UNKNOWN {
+            *result = DRAW;
+        }
+    }
+
+    Ok(Table { material, results, dtm: Some(dtm) })
+}
+
+fn schedule(queue: &mut Vec<Vec<(usize, u8)>>, distance: usize, index: usize, result: u8) {
+    if queue.len() <= distance {
+        queue.resize(distance + 1, Vec::new());
+    }
+
+    queue[distance].push((index, result));
+}
+
+// Positions with the same material that lead to this one. Table positions have no en passant square, so
+// double pawn pushes are undone from a copy which has it.
+fn predecessors(game: &Game) -> Vec<Game> {
+    let position = game.position();
+    let color = position.next_to_move.opposite();
+
+    let (pushed_rank, skipped_rank) = match color {
+        Color::White => (3, 2),
+        Color::Black => (4, 5)
+    };
+
+    let mut games = vec![game.clone()];
+
+    for file in 0..8 {
+        let pushed = &position.board.squares[Board::index(Square { rank: pushed_rank, file })];
+
+        if *pushed == Some(OccupiedSquare { piece: Piece::Pawn, color }) {
+            let mut with_en_passant = position.clone();
+            with_en_passant.en_passant_square = Some(Square { rank: skipped_rank, file });
+
+            games.push(Game::new(with_en_passant));
+        }
+    }
+
+    games.iter()
+        .flat_map( |game| game.predecessor_moves_with_same_material() )
+        .map( |(_, previous)| previous )
+        .collect()
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes[0])
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use super::*;
+use adjudication::*;
+use tablebase::{EndgameTablebase, Probe, Wdl};
+
+fn played(moves: &str) -> Vec<Game> {
+    let mut games = Vec::new();
+    let mut game = Game::new(Game::standard_position());
+
+    for san in moves.split_whitespace() {
+        game = game.make_move(san).unwrap();
+        games.push(game.clone());
+    }
+
+    games
+}
+
+// Every position with exactly two kings and a queen is won for the side with the queen
+struct QueenTablebase;
+
+impl EndgameTablebase for QueenTablebase {
+    fn probe(&self, game: &Game) -> Option<Probe> {
+        let pieces: Vec<&OccupiedSquare> = game.board().squares.iter().flatten().collect();
+        let queen = pieces.iter().find( |occupied| occupied.piece == Piece::Queen )?;
+
+        if pieces.len() != 3 {
+            return None;
+        }
+
+        let wdl = if queen.color == game.position().next_to_move { Wdl::Win } else { Wdl::Loss };
+
+        Some(Probe { wdl, dtm: None })
+    }
+}
+
+#[test]
+fn test_resign_and_draw_rules() {
+    let games = played("e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6");
+
+    let rules = AdjudicationRules {
+        resign: Some(ResignRule { moves: 3, score: 400, two_sided: true }),
+        ..Default::default()
+    };
+
+    // Black thinks it's lost from its first move on, but White only agrees from its second
+    let mut adjudicator = Adjudicator::new(rules.clone());
+    let scores = [50, -450, 450, -500, 450, -600, 500, -700];
+
+    let adjudications: Vec<Option<Adjudication>> = games.iter().zip(scores.iter())
+        .map( |(game, score)| adjudicator.record(game, Some(*score)) )
+        .collect();
+
+    assert!(adjudications[..6].iter().all( |adjudication| adjudication.is_none() ));
+    assert_eq!(adjudications[6], Some(Adjudication { result: GameResult::WhiteWins, reason: AdjudicationReason::Resign }));
+
+    let one_sided = AdjudicationRules { resign: Some(ResignRule { two_sided: false, ..rules.resign.unwrap() }), ..Default::default() };
+    let mut adjudicator = Adjudicator::new(one_sided);
+
+    let resigned = games.iter().zip(scores.iter()).position( |(game, score)| adjudicator.record(game, Some(*score)).is_some() );
+    assert_eq!(resigned, Some(5));
+
+    // A missing score breaks the streak
+    adjudicator.reset();
+    let missing = [Some(50), Some(-450), Some(450), None, Some(450), Some(-600), Some(500), Some(-700)];
+    let resigned = games.iter().zip(missing.iter()).position( |(game, score)| adjudicator.record(game, *score).is_some() );
+    assert_eq!(resigned, None);
+
+    let draw = AdjudicationRules {
+        draw: Some(DrawRule { move_number: 4, moves: 2, score: 20 }),
+        ..Default::default()
+    };
+    let mut adjudicator = Adjudicator::new(draw);
+    let level = [10, -10, 5, 0, -15, 20, 0, 0];
+
+    let drawn = games.iter().zip(level.iter()).position( |(game, score)| adjudicator.record(game, Some(*score)).is_some() );
+    assert_eq!(drawn, Some(5));
+    assert_eq!(adjudicator.check(&games[7]).map( |adjudication| adjudication.reason ), Some(AdjudicationReason::Draw));
+}
+
+#[test]
+fn test_tablebase_and_length_adjudication() {
+    let rules = AdjudicationRules {
+        tablebase: Some(Arc::new(QueenTablebase)),
+        max_plies: Some(6),
+        ..Default::default()
+    };
+    let adjudicator = Adjudicator::new(rules);
+
+    let won = Game::new_from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+    let adjudication = adjudicator.check(&won).unwrap();
+
+    assert_eq!(adjudication, Adjudication { result: GameResult::WhiteWins, reason: AdjudicationReason::Tablebase });
+    assert_eq!(adjudication.apply(&won).termination(), "adjudication");
+    assert_eq!(adjudication.apply(&won).result(), GameResult::WhiteWins);
+
+    let games = played("e4 e5 Nf3 Nc6 Bb5 a6");
+    assert_eq!(adjudicator.check(&games[4]), None);
+    assert_eq!(adjudicator.check(&games[5]).map( |adjudication| adjudication.reason ), Some(AdjudicationReason::MaxLength));
+
+    // Finished games are left alone
+    let mated = Game::new_from_fen("4k3/4Q3/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+    assert!(mated.is_over());
+    assert_eq!(adjudicator.check(&mated), None);
+}
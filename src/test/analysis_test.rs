@@ -0,0 +1,118 @@
+use super::*;
+
+#[test]
+fn test_metrics_in_starting_position() {
+    let metrics = analysis::metrics(&Game::new(Game::standard_position()));
+
+    for side in &[&metrics.white, &metrics.black] {
+        assert_eq!(side.mobility.pawn, 16);
+        assert_eq!(side.mobility.knight, 4);
+        assert_eq!(side.mobility.total(), 20);
+        assert_eq!(side.developed_minor_pieces, 0);
+        assert!(!side.castled);
+        assert_eq!(side.center_control, 0);
+    }
+}
+
+#[test]
+fn test_metrics_after_development() {
+    let game = Game::new_from_fen("r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4").unwrap();
+    let metrics = analysis::metrics(&game);
+
+    assert_eq!(metrics.white.developed_minor_pieces, 2);
+    assert!(metrics.white.castled);
+
+    assert_eq!(metrics.black.developed_minor_pieces, 3);
+    assert!(!metrics.black.castled);
+
+    // Bc4 and e4 on d5, Nf3 on d4 and e5
+    assert_eq!(metrics.white.center_control, 4);
+    // Nc6, Bc5 and e5 on d4, Nc6 on e5, Nf6 on d5 and e4
+    assert_eq!(metrics.black.center_control, 6);
+
+    assert_eq!(metrics.white.mobility.king, 1);
+}
+
+#[test]
+fn test_king_safety() {
+    let game = Game::new(Game::standard_position());
+    let safety = analysis::king_safety(&game, Color::White).unwrap();
+
+    assert_eq!(safety.king, Square::from_notation("e1").unwrap());
+    assert_eq!(safety.pawn_shield, 3);
+    assert_eq!(safety.missing_shield_pawns, 0);
+    assert!(safety.open_files.is_empty());
+    assert!(safety.king_zone_attackers.is_empty());
+    assert!(!safety.is_exposed());
+
+    // Castled king without the g- and h-pawns, an open g-file and a black pawn on the h-file
+    let game = Game::new_from_fen("r1b1k2r/ppp2p1p/8/8/7p/8/PPP2P2/R4RK1 w kq - 0 1").unwrap();
+    let safety = analysis::king_safety(&game, Color::White).unwrap();
+
+    assert_eq!(safety.pawn_shield, 1);
+    assert_eq!(safety.missing_shield_pawns, 2);
+    assert_eq!(safety.open_files, vec![6]);
+    assert_eq!(safety.half_open_files, vec![7]);
+    assert!(safety.is_exposed());
+
+    // Read from the board alone, the king zone attackers are the ones Game::attackers_of finds
+    for fen in ["1b4k1/8/8/8/8/5n2/4q1P1/6K1 w - - 0 1", "6k1/5ppp/8/3B4/8/2Q1r3/5PPP/4R1K1 b - - 0 1"].iter() {
+        let game = Game::new_from_fen(fen).unwrap();
+
+        for color in [Color::White, Color::Black].iter() {
+            let king = analysis::king_safety(&game, *color).unwrap().king;
+            let mut expected = Vec::new();
+
+            for rank in king.rank - 1..=king.rank + 1 {
+                for file in king.file - 1..=king.file + 1 {
+                    if let Some(square) = Square::new(rank, file) {
+                        for attacker in game.attackers_of(square, color.opposite()) {
+                            if !expected.contains(&attacker) {
+                                expected.push(attacker);
+                            }
+                        }
+                    }
+                }
+            }
+
+            assert_eq!(analysis::board_king_safety(game.board(), *color).unwrap().king_zone_attackers, expected);
+        }
+    }
+
+    let game = Game::new_from_fen("1b4k1/8/8/8/8/5n2/4q1P1/6K1 w - - 0 1").unwrap();
+    assert_eq!(analysis::king_safety(&game, Color::White).unwrap().king_zone_attackers.len(), 3);
+}
+
+#[test]
+fn test_proof_game() {
+    let start = Game::standard_position();
+    assert_eq!(analysis::reachable_from_startpos(&start, 4), Some(vec![]));
+
+    let target = Position::from_fen("rnbq1bnr/ppppkppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR w - - 2 3").unwrap();
+    let proof = analysis::reachable_from_startpos(&target, 6).unwrap();
+
+    let sans: Vec<String> = proof.iter()
+        .scan(Game::new(Game::standard_position()), |game, valid_move| {
+            let san = game.san(valid_move);
+            *game = game.make_valid_move(valid_move);
+
+            Some(san)
+        })
+        .collect();
+
+    assert_eq!(sans, vec!["e4", "e5", "Ke2", "Ke7"]);
+
+    assert_eq!(analysis::reachable_from_startpos(&target, 3), None);
+
+    // Pieces can't appear out of nowhere
+    let extra_knight = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(analysis::reachable_from_startpos(&extra_knight, 9), None);
+
+    // The knight needs an odd number of moves to reach f3, while the black knights need an even number to
+    // get back home, so the position is never reached with white to move
+    let knight_out = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 1").unwrap();
+    assert_eq!(analysis::reachable_from_startpos(&knight_out, 4), None);
+
+    let knight_out = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1").unwrap();
+    assert_eq!(analysis::reachable_from_startpos(&knight_out, 4).map( |proof| proof.len() ), Some(1));
+}
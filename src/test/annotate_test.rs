@@ -0,0 +1,230 @@
+use super::*;
+use std::time::Duration;
+use annotate::GamePhase;
+
+fn play(pgn: &str) -> Game {
+    Game::new_from_pgn(pgn).unwrap().remove(0).unwrap()
+}
+
+#[test]
+fn test_game_history() {
+    let game = play("1. e4 e5 2. Nf3 Nc6 1-0");
+
+    assert_eq!(game.ply_count(), 4);
+    assert_eq!(game.moves().iter().map( |valid_move| valid_move.notation() ).collect::<Vec<_>>(), vec!["e4", "e5", "Nf3", "Nc6"]);
+    assert_eq!(game.last_move().unwrap().notation(), "Nc6");
+    assert_eq!(game.initial_position().to_fen(), Game::standard_position().to_fen());
+
+    let history = game.history();
+
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[2].0.hash(), play("1. e4 e5 1-0").hash());
+}
+
+#[test]
+fn test_commentary() {
+    let game = play("1. e4 e5 2. Nf3 Nc6 3. Bc4 Nd4 4. Nxe5 Qg5 5. Nxf7 Qxg2 6. Rf1 Qxe4+ 7. Be2 Nf3# 0-1");
+    let comments = annotate::commentary(&game);
+
+    assert_eq!(comments.len(), 14);
+    assert_eq!(comments[0], "1. e4");
+    assert_eq!(comments[2], "2. Nf3: develops the knight and attacks e5");
+    assert_eq!(comments[3], "2... Nc6: develops the knight");
+    assert!(comments[6].starts_with("4. Nxe5: takes the pawn"), "{}", comments[6]);
+    assert!(comments[11].contains("gives check"), "{}", comments[11]);
+    assert!(comments[13].ends_with("delivers mate"), "{}", comments[13]);
+
+    assert_eq!(comments[12], "7. Be2: a blunder, allowing Nf3#");
+}
+
+const CLOCK_PGN: &str = "
+    [TimeControl \"180+0\"]
+
+    1. e4 {[%clk 0:03:00]} e5 {[%clk 0:03:00]} 2. Nf3 {[%clk 0:02:50]} Nc6 {[%clk 0:02:55]}
+    3. Bc4 {[%clk 0:02:30]} Nd4 {[%clk 0:02:50]} 4. Nxe5 {[%clk 0:01:00]} Qg5 {[%clk 0:02:40]}
+    5. Nxf7 {[%clk 0:00:30]} Qxg2 {[%clk 0:02:35]} 6. Rf1 {[%clk 0:00:10]} Qxe4+ {[%clk 0:02:30]}
+    7. Be2 {[%clk 0:00:05]} Nf3# {[%clk 0:02:29.5]} 0-1
+";
+
+#[test]
+fn test_time_usage() {
+    let pgn_game = Game::parse_pgn(CLOCK_PGN).unwrap().remove(0);
+    let moves = annotate::time_usage(&pgn_game);
+
+    assert_eq!(moves.len(), 14);
+
+    assert_eq!(moves[2].color, Color::White);
+    assert_eq!(moves[2].clock, Duration::from_secs(170));
+    assert_eq!(moves[2].spent, Some(Duration::from_secs(10)));
+    assert!(!moves[2].time_trouble);
+
+    assert_eq!(moves[13].clock, Duration::from_millis(149_500));
+    assert_eq!(moves[13].spent, Some(Duration::from_millis(500)));
+
+    assert!(moves[10].time_trouble);
+}
+
+#[test]
+fn test_analysis_cache() {
+    let game = play("1. e4 e5 2. Nf3 Nc6 3. Bc4 Nd4 4. Nxe5 1-0");
+    let mut cache = annotate::AnalysisCache::new();
+
+    let assessments = annotate::blunder_check_cached(&game, &Default::default(), &mut cache);
+
+    assert_eq!(assessments, annotate::blunder_check(&game, &Default::default()));
+    assert_eq!(cache.len(), 8);
+    assert!(cache.get(game.position().stable_key(), 2).is_some());
+    assert!(cache.get(game.position().stable_key(), 3).is_none());
+
+    let path = std::env::temp_dir().join(format!("pgn-lib-analysis-cache-{}.txt", std::process::id()));
+    let mut saved = annotate::AnalysisCache::open(&path).unwrap();
+
+    assert!(saved.is_empty());
+    annotate::blunder_check_cached(&game, &Default::default(), &mut saved);
+    saved.save().unwrap();
+
+    let mut loaded = annotate::AnalysisCache::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.to_text(), cache.to_text());
+    assert_eq!(annotate::blunder_check_cached(&game, &Default::default(), &mut loaded), assessments);
+    assert_eq!(loaded.len(), 8);
+
+    assert!(annotate::AnalysisCache::from_text("xyz 2 10 e2e4").is_err());
+}
+
+#[test]
+fn test_accuracy() {
+    assert!((annotate::expected_score(0) - 0.5).abs() < 1e-9);
+    assert!(annotate::expected_score(300) > 0.75 && annotate::expected_score(300) < 0.8);
+    assert!((annotate::expected_score(-300) + annotate::expected_score(300) - 1.0).abs() < 1e-9);
+
+    assert!(annotate::move_accuracy(50, 50) > 99.9);
+    assert!(annotate::move_accuracy(50, 80) > 99.9);
+    assert!(annotate::move_accuracy(500, -500) < 5.0);
+
+    let game = play("1. e4 e5 2. Nf3 Nc6 3. Bc4 Nd4 4. Nxe5 Qg5 5. Nxf7 Qxg2 6. Rf1 Qxe4+ 7. Be2 Nf3# 0-1");
+    let assessments = annotate::blunder_check(&game, &Default::default());
+    let accuracy = annotate::game_accuracy(&assessments);
+
+    let white = accuracy.of(Color::White).unwrap();
+    let black = accuracy.of(Color::Black).unwrap();
+
+    assert!(white < black);
+    assert!((0.0..=100.0).contains(&white) && (0.0..=100.0).contains(&black));
+    assert_eq!(annotate::game_accuracy(&[]).white, None);
+}
+
+#[test]
+fn test_time_report() {
+    let pgn_game = Game::parse_pgn(CLOCK_PGN).unwrap().remove(0);
+    let game = play(CLOCK_PGN);
+
+    let assessments = annotate::blunder_check(&game, &Default::default());
+    let report = annotate::time_report(&pgn_game, &assessments);
+
+    assert_eq!(report.white_time_trouble_from, Some(10));
+    assert_eq!(report.black_time_trouble_from, None);
+    assert_eq!(report.total_spent(Color::White), Duration::from_secs(175));
+
+    let sans: Vec<&str> = report.blunders_in_time_trouble.iter().map( |assessment| assessment.san.as_str() ).collect();
+    assert_eq!(sans, vec!["Rf1", "Be2"]);
+}
+
+#[test]
+fn test_screening() {
+    let pgns = [
+        "[White \"Carlsen\"]\n[Black \"Nakamura\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bc4 Nd4 4. Nxe5 Qg5 5. Nxf7 Qxg2 6. Rf1 Qxe4+ 7. Be2 Nf3# 0-1",
+        "[White \"Nakamura\"]\n[Black \"Someone\"]\n\n1. d4 d5 1-0\n\n[White \"Nakamura\"]\n[Black \"Carlsen\"]\n\n1. e4 1-0"
+    ];
+
+    let report = annotate::screen_player(&pgns, "Nakamura", &Default::default(), &mut annotate::AnalysisCache::new());
+
+    assert_eq!(report.games, 3);
+    assert!(report.errors.is_empty());
+    assert_eq!(report.overall.moves, 9);
+    assert_eq!(report.loss_distribution, [7, 0, 0, 0, 1, 1, 0, 0]);
+
+    // The games are short enough to be all opening
+    assert_eq!(report.opening, report.overall);
+    assert_eq!(report.phase(GamePhase::Endgame).accuracy(), None);
+
+    // Four of the nine moves were the engine's first choice
+    assert_eq!((report.overall.engine_matches, report.overall.forced_moves), (4, 0));
+    assert_eq!(report.overall.total_loss, 179);
+    assert_eq!(report.overall.engine_match_percentage().map( |percentage| percentage.round() ), Some(44.0));
+
+    let nobody = annotate::screen_player(&pgns, "Nobody", &Default::default(), &mut annotate::AnalysisCache::new());
+    assert_eq!((nobody.games, nobody.overall.engine_match_percentage()), (0, None));
+}
+
+#[test]
+fn test_resumable_analysis_job() {
+    let pgn = "[White \"First\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0\n\n[White \"Second\"]\n\n1. d4 Nf6 0-1\n\n[White \"Third\"]\n\n1. c4 c5 *\n";
+
+    let mut complete = annotate::AnalysisJob::new(Default::default());
+    while complete.step(pgn).unwrap() {}
+
+    assert_eq!(complete.reviews.len(), 3);
+    assert!(complete.errors.is_empty());
+    assert_eq!(complete.reviews[1].tags.iter().find( |(name, _)| name == "White" ).map( |(_, value)| value.as_str() ), Some("Second"));
+    assert_eq!(complete.reviews[0].commentary.len(), 4);
+    assert!(complete.reviews[2].white_accuracy.is_some());
+
+    // Stopped after the first game and resumed from the saved file
+    let path = std::env::temp_dir().join(format!("pgn-lib-analysis-job-{}.json", std::process::id()));
+    let mut job = annotate::AnalysisJob::open(&path, Default::default()).unwrap();
+
+    assert_eq!(job.run(pgn, 1).unwrap(), 1);
+    drop(job);
+
+    let mut resumed = annotate::AnalysisJob::open(&path, Default::default()).unwrap();
+    assert_eq!(resumed.reviews.len(), 1);
+    assert!(!resumed.cache.is_empty());
+
+    assert_eq!(resumed.run(pgn, 10).unwrap(), 2);
+    assert!(resumed.is_done(pgn));
+    assert!(!resumed.step(pgn).unwrap());
+
+    assert_eq!(resumed.reviews, complete.reviews);
+    assert_eq!(resumed.cache.to_text(), complete.cache.to_text());
+
+    // A saved job only continues with the same input and options
+    let other_options = annotate::CommentaryOptions { depth: 3 };
+    assert!(annotate::AnalysisJob::open(&path, other_options).is_err());
+
+    let mut reopened = annotate::AnalysisJob::open(&path, Default::default()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(reopened.step(&pgn.replace("Nf6", "Nc6")).is_err());
+    assert!(reopened.step(&pgn[1..]).is_err());
+    assert!(!reopened.step(pgn).unwrap());
+
+    assert!(annotate::AnalysisJob::from_json("{\"offset\": 1}").is_err());
+}
+
+#[test]
+fn test_analysis_job_checkpoints() {
+    // Twelve games, one of which can't be read and isn't counted towards a checkpoint
+    let mut pgn = String::new();
+    for index in 0..12 {
+        let moves = if index == 3 { "1. e4 Ke3 *" } else { "1. e4 e5 *" };
+        pgn.push_str(&format!("[Round \"{}\"]\n\n{}\n\n", index + 1, moves));
+    }
+
+    let path = std::env::temp_dir().join(format!("pgn-lib-analysis-checkpoints-{}.json", std::process::id()));
+    let mut job = annotate::AnalysisJob::open(&path, Default::default()).unwrap();
+
+    let mut saved = Vec::new();
+    let reviewed = job.run_with_progress(&pgn, 20, |_| {
+        let text = std::fs::read_to_string(&path).unwrap();
+        saved.push(annotate::AnalysisJob::from_json(&text).unwrap().reviews.len());
+    }).unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(reviewed, 11);
+    assert_eq!(job.errors.len(), 1);
+    assert_eq!(saved, vec![10, 11]);
+}
+
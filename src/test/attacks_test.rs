@@ -0,0 +1,135 @@
+use super::*;
+
+fn square(notation: &str) -> Square {
+    Square::from_notation(notation).unwrap()
+}
+
+#[test]
+fn test_attackers_and_defenders() {
+    let game = read_game(
+        "
+        | | | |r| | | | | 8
+        | | | | | | | | | 7
+        | | | | | |n| | | 6
+        | | | | |p| | | | 5
+        | | | |P| | | | | 4
+        | | | | | |N| | | 3
+        | | | | | | | | | 2
+        | | | | |R| | | | 1
+         a b c d e f g h
+        ",
+        Color::White
+    );
+
+    let attackers: Vec<(Square, Piece)> = game.attackers_of(square("e5"), Color::White);
+    assert_eq!(attackers, vec![(square("d4"), Piece::Pawn), (square("f3"), Piece::Knight), (square("e1"), Piece::Rook)]);
+
+    let defenders = game.defenders_of(square("e5"), Color::Black);
+    assert_eq!(defenders, vec![]);
+
+    let defenders_of_d4 = game.defenders_of(square("d4"), Color::White);
+    assert_eq!(defenders_of_d4, vec![(square("f3"), Piece::Knight)]);
+
+    let attackers_of_d4 = game.attackers_of(square("d4"), Color::Black);
+    assert_eq!(attackers_of_d4, vec![(square("e5"), Piece::Pawn), (square("d8"), Piece::Rook)]);
+}
+
+#[test]
+fn test_square_safety() {
+    let game = read_game(
+        "
+        | | | | |k| | | | 8
+        | | | | | | | | | 7
+        | | | | | | | | | 6
+        | | | |p| | | | | 5
+        | | | | |N| | | | 4
+        | | | | | | | | | 3
+        | | | | | | | | | 2
+        | | | | |R| | | | 1
+         a b c d e f g h
+        ",
+        Color::White
+    );
+
+    let knight = game.square_safety(square("e4"));
+    assert_eq!(knight.attackers, vec![Piece::Pawn]);
+    assert_eq!(knight.defenders, vec![Piece::Rook]);
+    assert_eq!(knight.attacker_value(), 1);
+    assert_eq!(knight.defender_value(), 5);
+    assert!(knight.is_hanging());
+
+    let rook = game.square_safety(square("e1"));
+    assert!(rook.attackers.is_empty());
+    assert!(!rook.is_hanging());
+
+    let empty = game.square_safety(square("a3"));
+    assert_eq!(empty.piece, None);
+    assert!(!empty.is_hanging());
+}
+
+#[test]
+fn test_king_attacks_count_last() {
+    let game = read_game(
+        "
+        | | | | | | | | | 8
+        | | | | | | | | | 7
+        | | | | | | | | | 6
+        | | | | |k| | | | 5
+        | | | | |N| | | | 4
+        | | | | | | | | | 3
+        | | | | |R| | | | 2
+        | | | | | | | | | 1
+         a b c d e f g h
+        ",
+        Color::White
+    );
+
+    let knight = game.square_safety(square("e4"));
+    assert_eq!(knight.attackers, vec![Piece::King]);
+    assert!(!knight.is_hanging());
+}
+
+#[test]
+fn test_line_geometry() {
+    let squares = |squares: &[&str]| squares.iter().map( |notation| square(notation) ).collect::<Vec<Square>>();
+
+    assert_eq!(ray(square("f6"), (1, 1)), squares(&["g7", "h8"]));
+    assert_eq!(ray(square("a1"), (-1, 0)), vec![]);
+
+    assert_eq!(squares_between(square("a1"), square("d4")), squares(&["b2", "c3"]));
+    assert_eq!(squares_between(square("e8"), square("e5")), squares(&["e7", "e6"]));
+    assert_eq!(squares_between(square("e1"), square("e2")), vec![]);
+    assert_eq!(squares_between(square("b1"), square("c3")), vec![]);
+
+    assert!(aligned(square("e1"), square("e4"), square("e8")));
+    assert!(aligned(square("c3"), square("e5"), square("a1")));
+    assert!(!aligned(square("e1"), square("e4"), square("f8")));
+    assert!(!aligned(square("b1"), square("c3"), square("d5")));
+}
+
+#[test]
+fn test_hypothetical_pieces() {
+    let game = Game::new(Game::standard_position());
+
+    let mut targets: Vec<Square> = game.hypothetical_moves(square("e4"), Piece::Bishop, Color::White).iter().map( |valid_move| valid_move.to ).collect();
+    targets.sort_by_key( |target| Board::index(*target) );
+
+    assert_eq!(targets, ["b7", "h7", "c6", "g6", "d5", "f5", "d3", "f3"].iter().map( |notation| square(notation) ).collect::<Vec<_>>());
+
+    let in_check = game.with_piece(square("d3"), Piece::Knight, Color::Black, |game| game.in_check(Color::White) );
+    assert!(in_check);
+
+    // The hash follows the changed board
+    game.with_piece(square("e2"), Piece::Queen, Color::Black, |game| assert_eq!(game.hash(), game.position().zobrist_hash()) );
+    game.without_piece(square("e1"), |game| assert_eq!(game.hash(), game.position().zobrist_hash()) );
+
+    let bishop_moves = game.without_piece(square("e2"), |game| {
+        game.valid_moves().iter().filter( |valid_move| valid_move.piece == Piece::Bishop ).count()
+    });
+    assert_eq!(bishop_moves, 5);
+
+    // The game itself is left as it was
+    assert_eq!(game.square_occupied(square("e4")), None);
+    assert_eq!(game.square_occupied(square("e2")), Some(&OccupiedSquare { piece: Piece::Pawn, color: Color::White }));
+    assert_eq!(game.count_legal_moves(), 20);
+}
@@ -0,0 +1,134 @@
+use super::*;
+use book::{OpeningTree, OpeningBook, BookOptions, BookSelection};
+use search::{Searcher, SearchLimits};
+
+const GAMES: &str = "
+    1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0
+
+    1. e4 c5 2. Nf3 d6 0-1
+
+    1. d4 d5 2. c4 e6 1-0
+
+    1. Nf3 e5 2. e4 Nc6 3. Bc4 1-0
+";
+
+fn sans(game: &Game, tree: &OpeningTree) -> HashSet<(String, u32)> {
+    tree.moves(game).iter().map( |book_move| (game.san(&book_move.valid_move), book_move.weight) ).collect()
+}
+
+#[test]
+fn test_opening_tree_from_pgn() {
+    let tree = OpeningTree::from_pgn(GAMES).unwrap();
+    let game = Game::new(Game::standard_position());
+
+    assert_eq!(sans(&game, &tree), vec![
+        (String::from("e4"), 2),
+        (String::from("d4"), 1),
+        (String::from("Nf3"), 1)
+    ].into_iter().collect());
+
+    // 1. e4 e5 2. Nf3 Nc6 and 1. Nf3 e5 2. e4 Nc6 transpose into the same position
+    let game = game.make_move("e4").unwrap().make_move("e5").unwrap()
+        .make_move("Nf3").unwrap().make_move("Nc6").unwrap();
+
+    assert_eq!(sans(&game, &tree), vec![
+        (String::from("Bb5"), 1),
+        (String::from("Bc4"), 1)
+    ].into_iter().collect());
+}
+
+#[test]
+fn test_opening_book_selection() {
+    let tree = OpeningTree::from_pgn(GAMES).unwrap();
+    let game = Game::new(Game::standard_position());
+
+    let most_played = book::choose_move(&tree, &game, &BookOptions::default()).unwrap();
+    assert_eq!(game.san(&most_played), "e4");
+
+    let weighted = BookOptions { selection: BookSelection::Weighted { seed: 42 }, ..Default::default() };
+    let first = book::choose_move(&tree, &game, &weighted);
+    let second = book::choose_move(&tree, &game, &weighted);
+
+    assert!(first.is_some());
+    assert_eq!(first, second);
+
+    let picked: HashSet<String> = (0..50)
+        .map( |seed| BookOptions { selection: BookSelection::Weighted { seed }, ..Default::default() } )
+        .map( |options| game.san(&book::choose_move(&tree, &game, &options).unwrap()) )
+        .collect();
+
+    assert_eq!(picked, vec!["e4", "d4", "Nf3"].into_iter().map(String::from).collect());
+}
+
+#[test]
+fn test_search_uses_opening_book() {
+    let tree = OpeningTree::from_pgn(GAMES).unwrap();
+    let mut searcher = Searcher::new().with_book(tree, BookOptions { max_full_moves: 1, ..Default::default() });
+
+    let game = Game::new(Game::standard_position());
+    let result = searcher.search(&game, &SearchLimits::depth(1));
+
+    assert!(result.from_book);
+    assert_eq!(game.san(&result.best_move.unwrap()), "e4");
+
+    // Past the book depth the search takes over
+    let game = game.make_move("e4").unwrap().make_move("e5").unwrap();
+    let result = searcher.search(&game, &SearchLimits::depth(1));
+
+    assert!(!result.from_book);
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn test_merge_with_transpositions() {
+    let mut tree = OpeningTree::from_pgn("1. d4 Nf6 2. c4 e6 3. Nf3 b6 1-0").unwrap();
+    let other = OpeningTree::from_pgn("1. d4 e6 2. c4 Nf6 3. Nf3 d5 0-1").unwrap();
+
+    tree.merge_with_transpositions(&other);
+
+    let game = Game::new(Game::standard_position());
+    let game = ["d4", "e6", "c4", "Nf6", "Nf3"].iter().fold(game, |game, notation| game.make_move(notation).unwrap() );
+
+    assert_eq!(sans(&game, &tree), vec![(String::from("b6"), 1), (String::from("d5"), 1)].into_iter().collect());
+    assert_eq!(tree.transpositions(&game), 1);
+
+    let after_c4 = ["d4", "Nf6", "c4", "e6"].iter()
+        .fold(Game::new(Game::standard_position()), |game, notation| game.make_move(notation).unwrap() );
+
+    assert_eq!(tree.transpositions(&after_c4), 2);
+
+    let move_orders: HashSet<String> = tree.move_orders(&game).iter()
+        .map( |line| line.iter().map( |valid_move| valid_move.notation() ).collect::<Vec<_>>().join(" ") )
+        .collect();
+
+    assert_eq!(move_orders, vec![
+        String::from("d4 Nf6 c4 e6 Nf3"),
+        String::from("d4 e6 c4 Nf6 Nf3")
+    ].into_iter().collect());
+}
+
+#[test]
+fn test_opening_tree_bytes() {
+    let tree = OpeningTree::from_pgn(GAMES).unwrap();
+    let bytes = tree.to_bytes();
+
+    assert_eq!(&bytes[0..4], b"PGOT");
+    assert_eq!(OpeningTree::from_pgn(GAMES).unwrap().to_bytes(), bytes);
+
+    let read = OpeningTree::from_bytes(&bytes).unwrap();
+    assert_eq!(read.len(), tree.len());
+    assert_eq!(read.to_bytes(), bytes);
+
+    // Moves from the starting position are stored under its stable key
+    let start_key = Game::standard_position().stable_key().to_le_bytes();
+    assert!(bytes[12..].chunks(28).any( |edge| edge[0..8] == start_key ));
+
+    let game = Game::new(Game::standard_position()).make_move("Nf3").unwrap().make_move("e5").unwrap()
+        .make_move("e4").unwrap().make_move("Nc6").unwrap();
+
+    assert_eq!(sans(&game, &read), sans(&game, &tree));
+    assert_eq!(read.move_orders(&game).len(), 2);
+
+    assert!(OpeningTree::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(OpeningTree::from_bytes(b"PGNN").is_err());
+}
@@ -0,0 +1,71 @@
+use super::*;
+use bot::*;
+
+#[test]
+fn test_bot_strength_follows_elo() {
+    let weakest = BotStrength::for_elo(MIN_ELO);
+    let strongest = BotStrength::for_elo(MAX_ELO);
+
+    assert_eq!(weakest.depth, 1);
+    assert_eq!(weakest.mistake_chance, 40);
+    assert_eq!(strongest.mistake_chance, 0);
+    assert_eq!(strongest.max_mistake, 0);
+
+    let club = BotStrength::for_elo(1200);
+    assert!(club.depth > weakest.depth && club.depth < strongest.depth);
+    assert!(club.nodes > weakest.nodes && club.nodes < strongest.nodes);
+
+    assert_eq!(Bot::new(3000).elo(), MAX_ELO);
+    assert_eq!(Bot::new(0).strength(), weakest);
+}
+
+#[test]
+fn test_strong_bot_finds_mate() {
+    let game = Game::new_from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let bot_move = Bot::new(MAX_ELO).choose_move(&game).unwrap();
+
+    assert_eq!(game.san(&bot_move.valid_move), "Ra8#");
+    assert!(!bot_move.mistake);
+}
+
+#[test]
+fn test_weak_bot_makes_mistakes() {
+    let game = Game::new(Game::standard_position());
+    let play = |seed: u64| -> Vec<BotMove> {
+        let mut bot = Bot::new(MIN_ELO).with_seed(seed);
+
+        (0..20).map( |_| bot.choose_move(&game).unwrap() ).collect()
+    };
+
+    let moves = play(7);
+    let valid_moves = game.valid_moves();
+
+    assert!(moves.iter().all( |bot_move| valid_moves.contains(&bot_move.valid_move) ));
+    assert!(moves.iter().any( |bot_move| bot_move.mistake && bot_move.valid_move != bot_move.best_move ));
+    assert!(moves.iter().any( |bot_move| !bot_move.mistake ));
+
+    assert_eq!(play(7), moves);
+}
+
+#[test]
+fn test_bot_with_shared_generator() {
+    let game = Game::new(Game::standard_position());
+    let play = |seed: u64| -> Vec<ValidMove> {
+        let mut bot = Bot::new(MIN_ELO).with_rng(rng::SplitMix64::new(seed));
+
+        // Unlike a seed, the generator isn't started over by a new game
+        (0..10).map( |ply| {
+            if ply == 5 {
+                bot.new_game();
+            }
+
+            bot.choose_move(&game).unwrap().valid_move
+        }).collect()
+    };
+
+    let moves = play(11);
+    assert_eq!(moves, play(11));
+
+    // The second game continues the sequence instead of repeating the first one
+    assert_ne!(moves[..5], moves[5..]);
+}
@@ -0,0 +1,64 @@
+use super::*;
+use broadcast::Broadcast;
+use manager::GameManager;
+
+fn tags(white: &str, black: &str) -> Vec<(String, String)> {
+    vec![
+        (String::from("Event"), String::from("Club Championship")),
+        (String::from("White"), String::from(white)),
+        (String::from("Black"), String::from(black))
+    ]
+}
+
+#[test]
+fn test_incremental_updates() {
+    let manager = GameManager::new();
+    let id = manager.create(Game::new(Game::standard_position()));
+    let other = manager.create(Game::new(Game::standard_position()));
+
+    let mut broadcast = Broadcast::new();
+    broadcast.add_game(id, tags("Alice", "Bob"), Game::new(Game::standard_position()));
+    broadcast.add_game(other, tags("Carol", "Dave"), Game::new(Game::standard_position()));
+
+    manager.make_move(id, "e4").unwrap();
+    manager.make_move(id, "e5").unwrap();
+    broadcast.sync(&manager);
+
+    let updates = broadcast.updates();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].moves, "1. e4 e5");
+    assert!(updates[0].pgn.contains("[White \"Alice\"]"));
+
+    assert!(broadcast.updates().is_empty());
+
+    manager.make_move(id, "Nf3").unwrap();
+    manager.make_move(id, "Nc6").unwrap();
+    manager.resign(id, Color::Black).unwrap();
+    broadcast.sync(&manager);
+
+    let updates = broadcast.updates();
+    assert_eq!(updates[0].moves, "2. Nf3 Nc6");
+    assert_eq!(updates[0].result, GameResult::WhiteWins);
+    assert!(!updates[0].replaced);
+
+    let pgn = broadcast.pgn();
+    assert!(pgn.contains("1. e4 e5 2. Nf3 Nc6 1-0\n\n[Event \"Club Championship\"]"));
+    assert!(pgn.ends_with("\n\n*\n"));
+}
+
+#[test]
+fn test_takebacks_replace_the_game() {
+    let mut broadcast = Broadcast::new();
+    let start = Game::new(Game::standard_position());
+
+    broadcast.add_game(1, tags("Alice", "Bob"), start.make_move("e4").unwrap());
+    assert_eq!(broadcast.updates()[0].moves, "1. e4");
+
+    broadcast.update_game(1, start.make_move("d4").unwrap()).unwrap();
+
+    let updates = broadcast.updates();
+    assert!(updates[0].replaced);
+    assert_eq!(updates[0].moves, "1. d4");
+
+    assert!(broadcast.update_game(2, start).is_err());
+}
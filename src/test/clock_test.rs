@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use super::*;
+use clock::Clock;
+
+fn seconds(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
+
+#[test]
+fn test_clock() {
+    let mut clock = Clock::new(seconds(60), seconds(2));
+
+    assert_eq!(clock.remaining(Color::White, seconds(100)), seconds(60));
+    assert_eq!(clock.press(seconds(100)), None);
+    assert_eq!(clock.running(), None);
+
+    clock.start(Color::White, seconds(100));
+    assert_eq!(clock.remaining(Color::White, seconds(110)), seconds(50));
+    assert_eq!(clock.remaining(Color::Black, seconds(110)), seconds(60));
+
+    assert_eq!(clock.press(seconds(110)), None);
+    assert_eq!(clock.running(), Some(Color::Black));
+    assert_eq!(clock.remaining(Color::White, seconds(120)), seconds(52));
+    assert_eq!(clock.remaining(Color::Black, seconds(120)), seconds(50));
+
+    clock.pause(seconds(120));
+    assert_eq!(clock.remaining(Color::Black, seconds(500)), seconds(50));
+
+    clock.start(Color::Black, seconds(500));
+    assert_eq!(clock.check_flag(seconds(549)), None);
+    assert_eq!(clock.check_flag(seconds(551)), Some(Color::Black));
+    assert_eq!(clock.remaining(Color::Black, seconds(600)), seconds(0));
+    assert_eq!(clock.running(), None);
+
+    // Pressing after the flag fell doesn't give the time back
+    assert_eq!(clock.press(seconds(600)), Some(Color::Black));
+    clock.start(Color::White, seconds(600));
+    assert_eq!(clock.running(), None);
+    assert_eq!(clock.flagged(), Some(Color::Black));
+}
+
+#[test]
+fn test_clock_delay() {
+    let mut clock = Clock::with_delay(seconds(60), seconds(0), seconds(5));
+
+    clock.start(Color::White, seconds(0));
+    assert_eq!(clock.remaining(Color::White, seconds(3)), seconds(60));
+    assert_eq!(clock.remaining(Color::White, seconds(8)), seconds(57));
+
+    assert_eq!(clock.press(seconds(4)), None);
+    assert_eq!(clock.remaining(Color::White, seconds(100)), seconds(60));
+
+    assert_eq!(clock.check_flag(seconds(68)), None);
+    assert_eq!(clock.check_flag(seconds(69)), Some(Color::Black));
+}
+
+#[test]
+fn test_clock_from_time_control() {
+    use engine_match::TimeControl;
+
+    let time_control = TimeControl::Clock { base: seconds(60), increment: seconds(2), delay: seconds(1) };
+
+    assert_eq!(time_control.clock(), Some(Clock::with_delay(seconds(60), seconds(2), seconds(1))));
+    assert_eq!(TimeControl::Depth(3).clock(), None);
+    assert_eq!(TimeControl::MoveTime(seconds(1)).clock(), None);
+}
@@ -0,0 +1,69 @@
+use super::*;
+use correspondence::ConditionalMoves;
+
+fn played(moves: &str) -> Game {
+    moves.split_whitespace().fold(Game::new(Game::standard_position()), |game, san| game.make_move(san).unwrap() )
+}
+
+#[test]
+fn test_conditional_moves() {
+    let game = played("e4 e5 Nf3");
+    let mut conditional = ConditionalMoves::from_pgn(&game, "
+        2... Nc6 3. Bb5 3... a6 4. Ba4
+        2... Nc6 3. Bb5 3... Nf6 4. d3
+        Nf6 Nxe5
+    ").unwrap();
+
+    assert_eq!(conditional.owner(&game), Color::White);
+    assert_eq!(conditional.lines.len(), 3);
+    assert_eq!(conditional.to_pgn(&game).lines().next(), Some("2... Nc6 3. Bb5 a6 4. Ba4"));
+
+    // Answering the same move differently, illegal moves and unanswered moves
+    assert!(conditional.add_line(&game, "Nc6 Bc4").is_err());
+    assert!(conditional.add_line(&game, "Nc6 Ke3").is_err());
+    assert!(conditional.add_line(&game, "d6").is_err());
+    assert!(conditional.add_line(&played("e4"), "e5 Nf3").is_err());
+
+    let game = game.make_move("Nc6").unwrap();
+    let game = conditional.execute(&game).unwrap();
+
+    assert_eq!(game.last_move().unwrap().notation(), "Bb5");
+    assert_eq!(conditional.ply, 5);
+    assert_eq!(conditional.lines.len(), 2);
+
+    // Nothing was prepared for 3... d6
+    let game = game.make_move("d6").unwrap();
+    assert_eq!(conditional.answer(&game), None);
+    assert!(conditional.is_empty());
+}
+
+#[test]
+fn test_conditional_moves_json() {
+    let game = played("d4");
+    let conditional = ConditionalMoves::from_pgn(&game, "1... d5 2. c4").unwrap();
+
+    let json = serde_json::to_string(&conditional).unwrap();
+    assert!(json.starts_with(&format!("{{\"ply\":1,\"position_key\":{},\"lines\":[[{{\"color\":\"black\",\"from\":\"d7\",\"to\":\"d5\"", game.position().stable_key())));
+
+    let parsed: ConditionalMoves = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(parsed, conditional);
+    assert!(parsed.validate(&game).is_ok());
+    assert!(parsed.validate(&played("c4")).is_err());
+    assert!(parsed.validate(&played("d4 d5")).is_err());
+
+    // The same number of moves, but another position
+    assert!(parsed.validate(&played("e4")).is_err());
+
+    // Lines without an answer are rejected when reading them
+    assert!(serde_json::from_str::<ConditionalMoves>("{\"ply\":1,\"position_key\":0,\"lines\":[[]]}").is_err());
+
+    let one_move = json.replacen(&json[json.find("},{").unwrap() + 1..json.find("]]").unwrap()], "", 1);
+    assert!(serde_json::from_str::<ConditionalMoves>(&one_move).is_err());
+
+    // Lines changed by hand after reading them don't panic either
+    let mut changed = parsed.clone();
+    changed.lines = vec![vec![ValidMove::from_notation(&game, "d5").unwrap()]];
+
+    assert_eq!(changed.answer(&game.make_move("d5").unwrap()), None);
+}
@@ -0,0 +1,51 @@
+use super::*;
+use dgt::{BoardTracker, BoardUpdate, decode_frame, encode_frame};
+
+fn frame_after(pgn: &str) -> Vec<u8> {
+    let game = Game::replay_pgn(pgn).last().unwrap().unwrap().1;
+
+    encode_frame(&game.position().board)
+}
+
+#[test]
+fn test_frames() {
+    let start = Game::standard_position();
+    let frame = encode_frame(&start.board);
+
+    assert_eq!(&frame[0..8], &[8, 9, 10, 12, 11, 10, 9, 8]);
+    assert_eq!(&frame[56..64], &[2, 3, 4, 6, 5, 4, 3, 2]);
+    assert_eq!(decode_frame(&frame), Ok(start.board));
+
+    assert!(decode_frame(&[0; 63]).is_err());
+    assert!(decode_frame(&[13; 64]).is_err());
+}
+
+#[test]
+fn test_tracking_moves() {
+    let mut tracker = BoardTracker::new(Game::new(Game::standard_position()));
+    let start = encode_frame(&Game::standard_position().board);
+
+    assert_eq!(tracker.feed(&start), Ok(BoardUpdate::Unchanged));
+
+    // The pawn is lifted from e2 and not put down yet
+    let mut lifted = start.clone();
+    lifted[52] = 0;
+
+    assert_eq!(tracker.feed(&lifted), Ok(BoardUpdate::Unrecognized));
+
+    match tracker.feed(&frame_after("1. e4 1-0")) {
+        Ok(BoardUpdate::Moves(moves)) => assert_eq!(moves.iter().map( |m| m.uci() ).collect::<Vec<_>>(), vec!["e2e4"]),
+        other => panic!("Unexpected update {:?}", other)
+    }
+
+    // Black replied and white moved again before the next frame
+    match tracker.feed(&frame_after("1. e4 e5 2. Nf3 1-0")) {
+        Ok(BoardUpdate::Moves(moves)) => assert_eq!(moves.len(), 2),
+        other => panic!("Unexpected update {:?}", other)
+    }
+
+    assert_eq!(tracker.game().ply_count(), 3);
+
+    assert_eq!(tracker.feed(&frame_after("1. e4 1-0")), Ok(BoardUpdate::TakeBack(2)));
+    assert_eq!(tracker.game().ply_count(), 1);
+}
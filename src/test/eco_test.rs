@@ -0,0 +1,40 @@
+use super::*;
+use eco::{EcoIndex, Opening};
+
+fn final_game(pgn: &str) -> Game {
+    Game::replay_pgn(pgn).last().unwrap().unwrap().1
+}
+
+#[test]
+fn test_classify_by_move_order() {
+    let index = EcoIndex::standard();
+    let game = final_game("1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 1-0");
+
+    assert_eq!(index.classify(&game), Some(&Opening { eco: String::from("C60"), name: String::from("Ruy Lopez") }));
+    assert_eq!(index.classify(&Game::new(Game::standard_position())), None);
+}
+
+#[test]
+fn test_classify_transposition() {
+    let index = EcoIndex::standard();
+
+    // Starts as an English, but reaches the Queen's Gambit Declined
+    let game = final_game("1. c4 e6 2. d4 d5 3. Nc3 1-0");
+
+    assert_eq!(index.classify(&game).map( |opening| opening.eco.as_str() ), Some("D30"));
+    assert_eq!(index.lookup(game.position()), None);
+}
+
+#[test]
+fn test_custom_lines() {
+    let mut index = EcoIndex::new();
+
+    assert!(index.is_empty());
+    assert!(index.add_line("C44", "Scotch Game", "1. e4 e5 2. Nf3 Nc6 3. d4").is_ok());
+    assert_eq!(index.add_line("C44", "Broken", "1. e4 e5 2. Ke3"), Err(InvalidMoveError::NoMatchingMove));
+    assert_eq!(index.len(), 1);
+
+    let game = final_game("1. Nf3 Nc6 2. e4 e5 3. d4 1-0");
+
+    assert_eq!(index.classify(&game).map( |opening| opening.name.as_str() ), Some("Scotch Game"));
+}
@@ -0,0 +1,42 @@
+use super::*;
+use endgame::EndgameType;
+
+fn position(fen: &str) -> Position {
+    Game::new_from_fen(fen).unwrap().position().clone()
+}
+
+#[test]
+fn test_material_signature() {
+    assert_eq!(position("8/5k2/8/3R4/8/2P5/4K3/6r1 w - - 0 1").material_signature(), "KRPvKR");
+    assert_eq!(Game::standard_position().material_signature(), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+}
+
+#[test]
+fn test_endgame_types() {
+    let endgame = |fen: &str| position(fen).endgame_type();
+
+    assert_eq!(endgame("8/5k2/8/3R4/8/2P5/4K3/6r1 w - - 0 1"), Some(EndgameType::Rook));
+    assert_eq!(endgame("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), Some(EndgameType::OppositeColoredBishops));
+    assert_eq!(endgame("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"), Some(EndgameType::SameColoredBishops));
+    assert_eq!(endgame("4k3/pp6/8/8/8/8/8/4K3 w - - 0 1"), Some(EndgameType::Pawn));
+    assert_eq!(endgame("4k3/8/8/8/8/8/3r4/3QK3 w - - 0 1"), Some(EndgameType::QueenVsRook));
+    assert_eq!(endgame("4k3/8/2n5/8/8/8/8/3BK3 w - - 0 1"), Some(EndgameType::BishopVsKnight));
+    assert_eq!(Game::standard_position().endgame_type(), None);
+
+    assert_eq!(EndgameType::Rook.name(), "rook endgame");
+
+    assert!(position("4k3/8/8/8/8/8/3r4/3QK3 w - - 0 1").is_tablebase_size());
+    assert!(!position("8/5k2/8/3R4/8/2P5/4K3/6r1 w - - 0 1").is_tablebase_size());
+}
+
+#[test]
+fn test_endgame_positions() {
+    let game = Game::new_from_fen("4k3/8/8/8/8/8/n6r/R3K3 w - - 0 1").unwrap();
+    let game = game.make_valid_move(&ValidMove::from_notation(&game, "Rxa2").unwrap());
+
+    let rook_endgames = training::endgame_positions(std::slice::from_ref(&game), EndgameType::Rook);
+
+    assert_eq!(rook_endgames.len(), 1);
+    assert_eq!(rook_endgames[0].position().material_signature(), "KRvKR");
+    assert!(training::endgame_positions(&[game], EndgameType::Pawn).is_empty());
+}
@@ -0,0 +1,297 @@
+use super::*;
+use engine_match::*;
+use search::Searcher;
+use std::time::Duration;
+use adjudication::{AdjudicationRules, ResignRule};
+
+const OPENINGS: &str = "
+    4k3/8/8/8/8/8/8/3QK3 w - - id \"KQvK\";
+    4k3/8/8/8/8/8/8/3RK3 b - - id \"KRvK\";
+";
+
+// Plays the first legal move, or answers with an illegal one
+struct FirstMoveEngine {
+    illegal: bool
+}
+
+impl Engine for FirstMoveEngine {
+    fn name(&self) -> String {
+        String::from("first")
+    }
+
+    fn choose_move(&mut self, game: &Game, _limits: &MoveLimits) -> Result<ValidMove, EngineError> {
+        if self.illegal {
+            return Err(EngineError::IllegalMove(String::from("e1g1")));
+        }
+
+        game.valid_moves().into_iter().next().ok_or(EngineError::Failed(String::from("No moves")))
+    }
+}
+
+fn options(games: usize) -> MatchOptions {
+    MatchOptions {
+        games,
+        time_control: TimeControl::Depth(1),
+        openings: openings_from_epd(OPENINGS).unwrap(),
+        sprt: None,
+        adjudication: AdjudicationRules { max_plies: Some(2), ..Default::default() }
+    }
+}
+
+#[test]
+fn test_openings_from_epd() {
+    let openings = openings_from_epd(OPENINGS).unwrap();
+
+    assert_eq!(openings.len(), 2);
+    assert_eq!(openings[1].position_to_fen(), "4k3/8/8/8/8/8/8/3RK3 b - - 0 1");
+    assert!(openings_from_epd("not a position").is_err());
+}
+
+#[test]
+fn test_match_alternates_colors() {
+    let mut search = SearcherEngine::new("search", Searcher::new());
+    let mut first = FirstMoveEngine { illegal: false };
+
+    let result = play_match(&mut search, &mut first, &options(4)).unwrap();
+
+    assert_eq!((result.wins, result.draws, result.losses), (0, 4, 0));
+
+    let players: Vec<(&str, &str)> = result.games.iter().map( |game| (game.white.as_str(), game.black.as_str()) ).collect();
+    assert_eq!(players, vec![("search", "first"), ("first", "search"), ("search", "first"), ("first", "search")]);
+
+    assert_eq!(result.games[2].game.initial_position().to_fen(), "4k3/8/8/8/8/8/8/3RK3 b - - 0 1");
+    assert_eq!(result.games[0].game.ply_count(), 2);
+    assert_eq!(result.games[0].game.termination(), "adjudication");
+}
+
+#[test]
+fn test_illegal_moves_lose() {
+    let mut first = FirstMoveEngine { illegal: false };
+    let mut illegal = FirstMoveEngine { illegal: true };
+
+    let result = play_match(&mut first, &mut illegal, &options(2)).unwrap();
+
+    assert_eq!((result.wins, result.draws, result.losses), (2, 0, 0));
+    assert_eq!(result.games[1].game.termination(), "rules infraction");
+    assert_eq!(result.elo_difference(), None);
+}
+
+#[test]
+fn test_illegal_moves_under_a_clock() {
+    let mut first = FirstMoveEngine { illegal: false };
+    let mut illegal = FirstMoveEngine { illegal: true };
+
+    let options = MatchOptions {
+        time_control: TimeControl::Clock { base: Duration::from_secs(60), increment: Duration::from_secs(1), delay: Duration::ZERO },
+        openings: vec![Game::replay_pgn("1. e4 e5 *").last().unwrap().unwrap().1],
+        adjudication: AdjudicationRules::default(),
+        ..options(2)
+    };
+
+    let result = play_match(&mut first, &mut illegal, &options).unwrap();
+
+    for match_game in &result.games {
+        assert_eq!(match_game.game.termination(), "rules infraction");
+        assert_eq!(match_game.clocks.len(), match_game.game.ply_count() - 2);
+    }
+
+    // The clock time goes with the move which was played, not with the illegal one
+    let pgn = result.games[0].to_pgn(&options.time_control);
+    assert!(pgn.contains("[TimeControl \"60+1\"]"));
+    assert!(pgn.contains("1. e4 e5 2. a3 {[%clk 0:01:00"));
+}
+
+// Thinks for a while before playing the first legal move
+struct SlowEngine {
+    thinking: Duration
+}
+
+impl Engine for SlowEngine {
+    fn name(&self) -> String {
+        String::from("slow")
+    }
+
+    fn choose_move(&mut self, game: &Game, _limits: &MoveLimits) -> Result<ValidMove, EngineError> {
+        std::thread::sleep(self.thinking);
+
+        game.valid_moves().into_iter().next().ok_or(EngineError::Failed(String::from("No moves")))
+    }
+}
+
+#[test]
+fn test_time_forfeit() {
+    let mut slow = SlowEngine { thinking: Duration::from_millis(100) };
+    let mut first = FirstMoveEngine { illegal: false };
+
+    let options = MatchOptions {
+        time_control: TimeControl::Clock { base: Duration::from_millis(150), increment: Duration::ZERO, delay: Duration::ZERO },
+        adjudication: AdjudicationRules { max_plies: Some(10), ..Default::default() },
+        ..options(2)
+    };
+
+    let result = play_match(&mut slow, &mut first, &options).unwrap();
+
+    // Flagging against a lone king is a draw
+    assert_eq!((result.wins, result.draws, result.losses), (0, 1, 1));
+    assert_eq!(result.games[0].game.termination(), "time forfeit");
+    assert_eq!(result.games[0].game.ply_count(), 2);
+    assert_eq!(result.games[1].game.result(), GameResult::WhiteWins);
+    assert_eq!(result.games[1].clocks.len(), 3);
+
+    let pgn = result.games[1].to_pgn(&options.time_control);
+    assert!(pgn.contains("[TimeControl \"0+0\"]"));
+    assert!(pgn.contains("[White \"first\"]"));
+    assert_eq!(pgn.matches("[%clk 0:00:00").count(), 3);
+
+    // The delay covers the thinking time
+    let options = MatchOptions {
+        time_control: TimeControl::Clock { base: Duration::from_millis(150), increment: Duration::ZERO, delay: Duration::from_secs(1) },
+        ..options
+    };
+
+    let result = play_match(&mut slow, &mut first, &options).unwrap();
+    assert_ne!(result.games[0].game.termination(), "time forfeit");
+    assert!(result.games[0].clocks.iter().all( |clock| *clock == Duration::from_millis(150) ));
+}
+
+#[test]
+fn test_time_forfeit_with_insufficient_mating_material() {
+    let mut slow = SlowEngine { thinking: Duration::from_millis(100) };
+    let mut first = FirstMoveEngine { illegal: false };
+
+    let options = MatchOptions {
+        time_control: TimeControl::Clock { base: Duration::from_millis(150), increment: Duration::ZERO, delay: Duration::ZERO },
+        openings: openings_from_epd("4k3/8/n7/8/8/8/8/3QK3 w - -").unwrap(),
+        adjudication: AdjudicationRules { max_plies: Some(10), ..Default::default() },
+        ..options(2)
+    };
+
+    let result = play_match(&mut slow, &mut first, &options).unwrap();
+
+    // A lone knight can't mate a king with a queen, but the queen can mate a king with a knight
+    assert_eq!(result.games[0].game.termination(), "time forfeit");
+    assert_eq!(result.games[0].game.result(), GameResult::Draw);
+    assert_eq!(result.games[1].game.termination(), "time forfeit");
+    assert_eq!(result.games[1].game.result(), GameResult::WhiteWins);
+}
+
+#[test]
+fn test_match_adjudication() {
+    let mut first = SearcherEngine::new("first", Searcher::new());
+    let mut second = SearcherEngine::new("second", Searcher::new());
+
+    let options = MatchOptions {
+        adjudication: AdjudicationRules {
+            resign: Some(ResignRule { moves: 2, score: 500, two_sided: true }),
+            max_plies: Some(20),
+            ..Default::default()
+        },
+        ..options(2)
+    };
+
+    let result = play_match(&mut first, &mut second, &options).unwrap();
+
+    for match_game in &result.games {
+        assert_eq!(match_game.game.result(), GameResult::WhiteWins);
+        assert_eq!(match_game.game.termination(), "adjudication");
+        assert_eq!(match_game.game.ply_count(), 4);
+    }
+}
+
+#[test]
+fn test_sprt() {
+    let sprt = Sprt { elo0: 0.0, elo1: 20.0, alpha: 0.05, beta: 0.05 };
+
+    let better = sprt.test(300, 100, 100);
+    assert_eq!(better.decision, SprtDecision::AcceptH1);
+    assert!((better.upper_bound - 2.944).abs() < 0.001);
+
+    assert_eq!(sprt.test(1000, 1000, 1000).decision, SprtDecision::AcceptH0);
+    assert_eq!(sprt.test(10, 10, 8).decision, SprtDecision::Continue);
+    assert_eq!(sprt.test(0, 0, 0).llr, 0.0);
+}
+
+#[test]
+fn test_simul() {
+    let mut simul = Simul::new();
+    let mut engine = FirstMoveEngine { illegal: false };
+
+    let standard = Game::new(Game::standard_position());
+    let first = simul.add_game(standard.clone(), Color::White, TimeControl::Depth(1));
+    let second = simul.add_game(standard.clone(), Color::Black, TimeControl::Depth(1));
+    let third = simul.add_game(standard, Color::White, TimeControl::MoveTime(Duration::from_millis(10)));
+
+    assert_eq!(simul.waiting(), vec![first, third]);
+    assert!(simul.opponent_moved(first, "e4").is_err());
+
+    assert!(matches!(simul.play_next(&mut engine), SimulTurn::Played { id, .. } if id == first));
+    simul.opponent_moved(second, "d4").unwrap();
+
+    // The game which waited the longest goes first
+    assert_eq!(simul.waiting(), vec![third, second]);
+    assert!(matches!(simul.play_next(&mut engine), SimulTurn::Played { id, .. } if id == third));
+    assert!(matches!(simul.play_next(&mut engine), SimulTurn::Played { id, .. } if id == second));
+    assert_eq!(simul.play_next(&mut engine), SimulTurn::Idle);
+
+    assert_eq!(simul.game(second).unwrap().game.ply_count(), 2);
+    assert!(simul.opponent_moved(first, "Ke3").is_err());
+
+    // An illegal answer loses only that game
+    let mut illegal = FirstMoveEngine { illegal: true };
+    simul.opponent_moved(first, "e5").unwrap();
+
+    assert_eq!(simul.play_next(&mut illegal), SimulTurn::Ended { id: first, termination: String::from("rules infraction") });
+    assert_eq!(simul.game(first).unwrap().game.result(), GameResult::BlackWins);
+    assert_eq!(simul.waiting(), Vec::<SimulGameId>::new());
+
+    assert!(simul.remove_game(first).is_some());
+    assert_eq!(simul.games().len(), 2);
+}
+
+#[test]
+fn test_simul_clock() {
+    let mut simul = Simul::new();
+    let mut slow = SlowEngine { thinking: Duration::from_millis(100) };
+
+    let standard = Game::new(Game::standard_position());
+    let timed = simul.add_game(standard.clone(), Color::White, TimeControl::Clock { base: Duration::from_millis(150), increment: Duration::ZERO, delay: Duration::ZERO });
+    let untimed = simul.add_game(standard, Color::Black, TimeControl::Depth(1));
+
+    assert!(matches!(simul.play_next(&mut slow), SimulTurn::Played { id, .. } if id == timed));
+    assert!(simul.remaining(timed).unwrap() <= Duration::from_millis(50));
+    assert_eq!(simul.remaining(untimed), None);
+
+    // The clock doesn't run while the opponent thinks
+    let remaining = simul.remaining(timed);
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(simul.remaining(timed), remaining);
+
+    simul.opponent_moved(timed, "e5").unwrap();
+    assert_eq!(simul.play_next(&mut slow), SimulTurn::Ended { id: timed, termination: String::from("time forfeit") });
+    assert_eq!(simul.game(timed).unwrap().game.result(), GameResult::BlackWins);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_uci_engine() {
+    let script = "
+        while read line; do
+            case \"$line\" in
+                uci) echo 'id name Fake'; echo uciok ;;
+                isready) echo readyok ;;
+                go*) echo 'info depth 1'; echo 'bestmove e2e4 ponder e7e5' ;;
+                quit) exit 0 ;;
+            esac
+        done
+    ";
+
+    let mut engine = UciEngine::start("sh", &["-c", script]).unwrap();
+    let game = Game::new(Game::standard_position());
+
+    assert_eq!(engine.name(), "Fake");
+    assert!(engine.new_game().is_ok());
+    assert_eq!(engine.choose_move(&game, &MoveLimits::Depth(1)).unwrap().uci(), "e2e4");
+
+    let black_to_move = game.make_move("d4").unwrap();
+    assert_eq!(engine.choose_move(&black_to_move, &MoveLimits::Depth(1)), Err(EngineError::IllegalMove(String::from("e2e4"))));
+}
@@ -0,0 +1,36 @@
+use super::*;
+use eval::EvalParams;
+
+#[test]
+fn test_explain_matches_evaluate() {
+    let positions = [
+        Game::standard_position(),
+        Game::new_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap().position().clone(),
+        Game::new_from_fen("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1").unwrap().position().clone()
+    ];
+
+    for position in positions.iter() {
+        let breakdown = eval::evaluate_explain(position);
+
+        assert_eq!(breakdown.total(), eval::evaluate(position));
+    }
+
+    let breakdown = eval::evaluate_explain(&positions[0]);
+
+    assert_eq!(breakdown.total(), 0);
+    assert_eq!(breakdown.term("material").unwrap().white, 4000);
+    assert_eq!(breakdown.term("material").unwrap().score(), 0);
+}
+
+#[test]
+fn test_pawn_structure() {
+    let params = EvalParams::default();
+
+    // White: doubled and isolated c-pawns, both passed. Black: isolated passed pawn on a3.
+    let game = Game::new_from_fen("4k3/8/8/2P5/8/p1P5/8/4K3 w - - 0 1").unwrap();
+
+    let pawn_structure = eval::evaluate_explain(game.position()).term("pawn_structure").unwrap().clone();
+
+    assert_eq!(pawn_structure.white, -params.doubled_pawn_penalty - 2 * params.isolated_pawn_penalty + (3 + 1) * params.passed_pawn_bonus);
+    assert_eq!(pawn_structure.black, -params.isolated_pawn_penalty + 4 * params.passed_pawn_bonus);
+}
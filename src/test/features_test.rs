@@ -0,0 +1,37 @@
+use super::*;
+use features::*;
+
+#[test]
+fn test_position_features() {
+    let start = features::extract(&Game::standard_position());
+
+    assert_eq!(start.planes.len(), PLANE_COUNT * 64);
+    assert_eq!(start.planes.iter().sum::<f32>(), 32.0 + 5.0 * 64.0);
+
+    // The white pawn on e2 and the black king on e8
+    assert_eq!(start.planes[12], 1.0);
+    assert_eq!(start.planes[11 * 64 + 60], 1.0);
+
+    assert_eq!(start.halfkp[0].len(), 30);
+    assert!(start.halfkp[0].contains(&(4 * 641 + 1 + 12)));
+    assert!(start.halfkp.iter().flatten().all( |index| *index < HALFKP_FEATURES ));
+
+    // Both sides see the starting position the same way
+    let mut white: Vec<usize> = start.halfkp[0].clone();
+    let mut black: Vec<usize> = start.halfkp[1].clone();
+    white.sort();
+    black.sort();
+
+    assert_eq!(white, black);
+
+    let game = Game::replay_pgn("1. e4 c5 2. e5 d5 1-0").last().unwrap().unwrap().1;
+    let after = features::extract(game.position());
+
+    assert_eq!(after.planes[12 * 64], 1.0);
+    assert_eq!(after.planes[17 * 64 + 43], 1.0);
+    assert_eq!(after.planes[17 * 64..].iter().sum::<f32>(), 1.0);
+
+    let no_king = Game::new_from_fen("8/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+    assert_eq!(halfkp(no_king.position(), Color::Black), Vec::<usize>::new());
+    assert_eq!(halfkp(no_king.position(), Color::White), vec![4 * 641 + 1 + 12]);
+}
@@ -104,3 +104,93 @@ fn expect_fen_moves(board: &str, moves: &[&str], fen: &str) {
     assert_eq!(game.position_to_fen(), fen);
     assert_eq!(Position::from_fen(fen).expect("Cannot parse FEN").to_fen(), fen);
 }
+
+#[test]
+fn test_untrusted_input() {
+    assert!(Position::from_fen("8/8/8/8/8/8/8/9 w - - 0 1").is_err());
+    assert!(Position::from_fen("4k3/8/8/8/8/8/8/36K w - - 0 1").is_err());
+    assert!(Position::from_fen("4k3/8/8/8/8/8/8/0K7 w - - 0 1").is_err());
+    assert!(Position::from_fen("4k3/8/8/8/8/8/8/35 w - - 0 1").is_ok());
+
+    let game = Game::new(Game::standard_position());
+    let off_board = Square { rank: 12, file: -3 };
+
+    let mut crafted = ValidMove::from_notation(&game, "e4").unwrap();
+    assert!(game.try_make_valid_move(&crafted).is_ok());
+
+    crafted.to = off_board;
+    assert_eq!(game.try_make_valid_move(&crafted).unwrap_err(), InvalidMoveError::NoMatchingMove);
+
+    assert!(game.try_move(off_board, Square::new(3, 4).unwrap(), None).is_err());
+    assert!(game.try_move(Square::new(1, 4).unwrap(), off_board, None).is_err());
+}
+
+#[test]
+fn test_bulk_fen_parsing() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w Kq d6 0 3",
+        "4k3/8/8/8/8/8/8/35 b - - 12 60"
+    ];
+
+    for fen in fens.iter() {
+        assert_eq!(Position::from_fen_bytes(fen.as_bytes()), Position::from_fen(fen), "{}", fen);
+    }
+
+    assert_eq!(Position::from_fen_bytes(fens[0].as_bytes()).unwrap(), Game::standard_position());
+
+    let positions: Vec<Position> = parse_fens(b"8/8/4k3/8/8/4K3/8/8 w - - 0 1\r\n\n8/8/4k3/8/8/4K3/8/8 b - - 1 1\n")
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[1].next_to_move, Color::Black);
+
+    let invalid = [
+        "8/8/8/8/8/8/8/9 w - - 0 1",
+        "4k3/8/8/8/8/8/8/36K w - - 0 1",
+        "4k3/8/8/8/8/8/8/0K7 w - - 0 1",
+        "4k3/8/8/8/8/8/8 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w KX - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - e9 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 99999999999999999999 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1 extra"
+    ];
+
+    for fen in invalid.iter() {
+        assert!(Position::from_fen_bytes(fen.as_bytes()).is_err(), "{}", fen);
+    }
+
+    assert_eq!(
+        Position::from_fen_bytes(b"4k3/8/8/8/8/8/8/4K2 w - - 0 1").unwrap_err().message,
+        "Expected 8 squares in rank 1"
+    );
+}
+
+#[test]
+fn test_fen_parsers_agree_on_malformed_input() {
+    assert!(Position::from_fen("4k3//8/8/8/8/8/8/4K3 w - - 0 1").is_err());
+
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w Kq d6 0 3"
+    ];
+
+    // Every FEN with one character left out, doubled or replaced
+    for fen in fens.iter() {
+        for i in 0..fen.len() {
+            let mut malformed = vec![format!("{}{}", &fen[..i], &fen[i + 1..]), format!("{}{}", &fen[..=i], &fen[i..])];
+
+            for replacement in ["/", " ", "-", "x", "0", "9"].iter() {
+                malformed.push(format!("{}{}{}", &fen[..i], replacement, &fen[i + 1..]));
+            }
+
+            for fen in malformed.iter() {
+                assert_eq!(Position::from_fen(fen), Position::from_fen_bytes(fen.as_bytes()), "{}", fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+use index::{PositionIndex, GamePly};
+
+const CORPUS: &str = "
+    1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0
+
+    1. Nf3 e5 2. e4 Nc6 3. Bc4 0-1
+
+    1. d4 d5 2. Ke3 Kd7 1-0
+
+    1. c4 e5 1-0
+";
+
+#[test]
+fn test_position_index() {
+    let (index, errors) = PositionIndex::from_pgn(CORPUS).unwrap();
+
+    assert_eq!(index.game_count(), 4);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, 2);
+
+    let start = Game::new(Game::standard_position());
+    assert_eq!(index.lookup(&start).len(), 4);
+
+    let position = start.make_move("e4").unwrap().make_move("e5").unwrap()
+        .make_move("Nf3").unwrap().make_move("Nc6").unwrap();
+
+    assert_eq!(index.lookup(&position), &[GamePly { game: 0, ply: 4 }, GamePly { game: 1, ply: 4 }]);
+
+    // The invalid game is indexed up to the invalid move
+    let position = start.make_move("d4").unwrap().make_move("d5").unwrap();
+    assert_eq!(index.lookup(&position), &[GamePly { game: 2, ply: 2 }]);
+
+    assert!(index.lookup(&position.make_move("Nc3").unwrap()).is_empty());
+}
+
+#[test]
+fn test_position_index_serialization() {
+    let (index, _) = PositionIndex::from_pgn(CORPUS).unwrap();
+
+    let mut bytes = Vec::new();
+    index.write_to(&mut bytes).unwrap();
+
+    assert_eq!(PositionIndex::read_from(&mut bytes.as_slice()).unwrap(), index);
+    assert!(PositionIndex::read_from(&mut &bytes[1..]).is_err());
+
+    let path = std::env::temp_dir().join(format!("pgn-lib-index-test-{}.idx", std::process::id()));
+
+    index.save(&path).unwrap();
+    let loaded = PositionIndex::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), index);
+}
@@ -0,0 +1,76 @@
+use super::*;
+use manager::{GameManager, GameEvent, ManagerError};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_moves_and_events() {
+    let manager = GameManager::new();
+    let all = manager.subscribe_all();
+
+    let id = manager.create(Game::new(Game::standard_position()));
+    let events = manager.subscribe(id).unwrap();
+
+    manager.make_move(id, "e4").unwrap();
+    manager.make_uci_move(id, "e7e5").unwrap();
+
+    assert_eq!(manager.make_move(id, "e4").err(), Some(ManagerError::InvalidMove(InvalidMoveError::NoMatchingMove)));
+    assert_eq!(manager.make_move(id + 1, "e4").err(), Some(ManagerError::UnknownGame(id + 1)));
+
+    manager.offer_draw(id, Color::White).unwrap();
+    assert!(manager.accept_draw(id).is_ok());
+    assert_eq!(manager.make_move(id, "Nf3").err(), Some(ManagerError::GameOver(id)));
+
+    let received: Vec<GameEvent> = events.try_iter().collect();
+    let sans: Vec<&str> = received.iter()
+        .filter_map( |event| match event { GameEvent::Move { san, .. } => Some(san.as_str()), _ => None } )
+        .collect();
+
+    assert_eq!(sans, vec!["e4", "e5"]);
+    assert_eq!(received[2], GameEvent::DrawOffer { id, color: Color::White });
+    assert_eq!(received[3], GameEvent::End { id, status: GameStatus::DrawAgreed, result: GameResult::Draw });
+    assert_eq!(received.len(), 4);
+
+    assert_eq!(all.try_iter().next(), Some(GameEvent::Created { id }));
+
+    assert!(manager.remove(id).is_some());
+    assert!(manager.is_empty());
+    assert!(events.recv().is_err());
+
+    let id = manager.create(Game::new(Game::standard_position()));
+    let metadata = MoveMetadata { source: Some(String::from("Carlsen")), ..MoveMetadata::default() };
+
+    manager.make_move_with_metadata(id, "d4", metadata.clone()).unwrap();
+    let game = manager.make_move_with_metadata(id, "d7d5", MoveMetadata::default()).unwrap();
+
+    assert_eq!(game.move_metadata(), vec![metadata, MoveMetadata::default()]);
+}
+
+#[test]
+fn test_concurrent_games() {
+    let manager = Arc::new(GameManager::new());
+
+    let ids: Vec<u64> = (0..4).map( |_| manager.create(Game::new(Game::standard_position())) ).collect();
+
+    let threads: Vec<_> = ids.iter().map( |id| {
+        let manager = manager.clone();
+        let id = *id;
+
+        thread::spawn(move || {
+            for notation in ["Nf3", "Nf6", "Ng1", "Ng8"].iter() {
+                manager.make_move(id, notation).unwrap();
+            }
+        })
+    }).collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    for id in ids {
+        assert_eq!(manager.game(id).unwrap().ply_count(), 4);
+        assert_eq!(manager.status(id), Some(GameStatus::Ongoing));
+    }
+
+    assert_eq!(manager.len(), 4);
+}
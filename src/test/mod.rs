@@ -1,10 +1,47 @@
 use super::*;
-use regex::Regex;
 use std::collections::HashSet;
 
 mod pgn_test;
 mod rules_test;
 mod fen_test;
+mod zobrist_test;
+mod attacks_test;
+mod analysis_test;
+mod search_test;
+mod clock_test;
+mod screen_test;
+mod render_test;
+mod book_test;
+mod broadcast_test;
+mod correspondence_test;
+mod dgt_test;
+mod eco_test;
+mod engine_match_test;
+mod eval_test;
+mod tuning_test;
+mod annotate_test;
+mod repertoire_test;
+mod training_test;
+mod index_test;
+mod manager_test;
+mod tablebase_test;
+mod endgame_test;
+mod spoken_test;
+mod notation_test;
+mod pgn_file_test;
+mod ndjson_test;
+mod features_test;
+mod nnue_test;
+mod bot_test;
+mod pairing_test;
+mod adjudication_test;
+mod rng_test;
+
+#[cfg(feature = "protobuf")]
+mod proto_test;
+
+#[cfg(feature = "suites")]
+mod suites_test;
 
 #[test]
 fn test_reading_positions() {
@@ -162,36 +199,5 @@ fn trim_lines(string: &str) -> String {
 }
 
 fn read_board(string: &str) -> Board {
-    let mut squares: Vec<Option<OccupiedSquare>> = Vec::new();
-    let rank_regex = Regex::new(r"^(?i)\s*\|+\s*([PNBRQK])?\s*\|+\s*([PNBRQK])?\s*\|+\s*([PNBRQK])?\s*\|+\s*([PNBRQK])?\s*\|+\s*([PNBRQK])?\s*\|+\s*([PNBRQK])?\s*\|+\s*([PNBRQK])?\s*\|+\s*([PNBRQK])?").expect("Invalid regex");
-
-    for line in string.lines().filter( |line| line.trim().len() > 0 ).take(8) {
-        let pieces = rank_regex.captures(line).expect(&format!("Invalid line '{}'", line));
-
-        for piece in pieces.iter().skip(1) {
-            squares.push(piece.map( |m| {
-                let letter = m.as_str();
-
-                match letter {
-                    "P" => OccupiedSquare { piece: Piece::Pawn, color: Color::White },
-                    "N" => OccupiedSquare { piece: Piece::Knight, color: Color::White },
-                    "B" => OccupiedSquare { piece: Piece::Bishop, color: Color::White },
-                    "R" => OccupiedSquare { piece: Piece::Rook, color: Color::White },
-                    "Q" => OccupiedSquare { piece: Piece::Queen, color: Color::White },
-                    "K" => OccupiedSquare { piece: Piece::King, color: Color::White },
-
-                    "p" => OccupiedSquare { piece: Piece::Pawn, color: Color::Black },
-                    "n" => OccupiedSquare { piece: Piece::Knight, color: Color::Black },
-                    "b" => OccupiedSquare { piece: Piece::Bishop, color: Color::Black },
-                    "r" => OccupiedSquare { piece: Piece::Rook, color: Color::Black },
-                    "q" => OccupiedSquare { piece: Piece::Queen, color: Color::Black },
-                    "k" => OccupiedSquare { piece: Piece::King, color: Color::Black },
-
-                    _ => panic!(format!("Invalid piece letter '{}'", letter))
-                }
-            }));
-        }
-    }
-
-    Board { squares }
+    Board::from_diagram(string).expect("Invalid diagram")
 }
@@ -0,0 +1,40 @@
+use super::*;
+use ndjson::{JsonGame, to_line, from_line, to_ndjson, read_ndjson};
+
+#[test]
+fn test_json_lines() {
+    let game = Game::replay_pgn("1. e4 e5 2. Nf3 Nc6 1-0").last().unwrap().unwrap().1.resign(Color::Black).unwrap();
+    let tags = vec![(String::from("White"), String::from("Carlsen")), (String::from("Event"), String::from("Blitz"))];
+
+    assert_eq!(
+        to_line(&game, &tags),
+        r#"{"tags":{"Event":"Blitz","White":"Carlsen"},"moves":"e2e4 e7e5 g1f3 b8c6","status":"resign","winner":"white","result":"1-0"}"#
+    );
+
+    let (read, read_tags) = from_line(&to_line(&game, &tags)).unwrap();
+
+    assert_eq!(read.position(), game.position());
+    assert_eq!(read.status(), GameStatus::Resigned(Color::Black));
+    assert_eq!(read_tags, vec![(String::from("Event"), String::from("Blitz")), (String::from("White"), String::from("Carlsen"))]);
+
+    let from_position = Game::new_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap()
+        .make_move("e4").unwrap()
+        .adjudicate(GameResult::BlackWins, "time forfeit");
+
+    let json_game = JsonGame::from_game(&from_position, &[]);
+
+    assert_eq!(json_game.initial_fen.as_deref(), Some("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+    assert_eq!(json_game.status, "outoftime");
+    assert_eq!(json_game.to_game().unwrap().termination(), "time forfeit");
+
+    let stream = to_ndjson(&[(game.clone(), tags.clone()), (from_position, Vec::new())]) + "\n{\"moves\": \"e2e5\"}\n";
+    let games: Vec<_> = read_ndjson(stream.as_bytes()).collect();
+
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].as_ref().unwrap().0.result(), GameResult::WhiteWins);
+    assert_eq!(games[1].as_ref().unwrap().0.result(), GameResult::BlackWins);
+    assert!(games[2].is_err());
+
+    let ongoing = r#"{"moves":"d2d4 d7d5","status":"started","result":"*"}"#;
+    assert_eq!(from_line(ongoing).unwrap().0.status(), GameStatus::Ongoing);
+}
@@ -0,0 +1,105 @@
+use super::*;
+use std::sync::Arc;
+use features::{halfkp, HALFKP_FEATURES};
+use nnue::Network;
+use search::{Searcher, SearchLimits};
+
+fn test_network() -> Network {
+    let mut state: u32 = 12345;
+    let mut next = move || {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        ((state >> 16) % 61) as i16 - 30
+    };
+
+    let feature_weights = (0..HALFKP_FEATURES * 4).map( |_| next() ).collect();
+    let feature_biases = (0..4).map( |_| next() ).collect();
+    let output_weights = (0..8).map( |_| next() ).collect();
+
+    Network::new(feature_weights, feature_biases, output_weights, 100).unwrap()
+}
+
+#[test]
+fn test_nnue_network() {
+    let network = test_network();
+
+    assert_eq!(Network::from_bytes(&network.to_bytes()), Ok(network.clone()));
+    assert!(Network::from_bytes(b"PGNN").is_err());
+    assert!(Network::from_bytes(&network.to_bytes()[..1000]).is_err());
+    assert!(Network::new(vec![0; 10], vec![0; 4], vec![0; 8], 0).is_err());
+
+    // Captures, king moves, en passant and a promotion
+    let games = [
+        "1. e4 d5 2. exd5 Qxd5 3. Ke2 Qe5+ 4. Kf3 Qf5+ 5. Kg3 1-0",
+        "1. e4 a6 2. e5 d5 3. exd6 c5 4. dxe7 Qb6 5. exf8=Q+ 1-0"
+    ];
+
+    for pgn in games.iter() {
+        let game = Game::replay_pgn(pgn).last().unwrap().unwrap().1;
+        let mut accumulator = network.accumulator(&Game::standard_position());
+
+        for (before, valid_move) in game.history() {
+            let after = before.make_valid_move(&valid_move);
+
+            accumulator = network.update(&accumulator, before.position(), after.position());
+
+            assert_eq!(accumulator, network.accumulator(after.position()));
+        }
+
+        let side_to_move = game.position().next_to_move;
+        assert_eq!(network.evaluate(&accumulator, side_to_move), network.evaluate_position(game.position()));
+    }
+
+    let game = Game::replay_pgn(games[0]).last().unwrap().unwrap().1;
+
+    let mut searcher = Searcher::new().with_network(Arc::new(network));
+    let result = searcher.search(&game, &SearchLimits::depth(2));
+
+    assert!(game.valid_moves().contains(&result.best_move.unwrap()));
+}
+
+#[test]
+fn test_nnue_evaluation_matches_reference() {
+    // An odd hidden size leaves elements after the last full vector, and the large weights get clipped
+    let hidden = 13;
+    let mut state: u32 = 99;
+    let mut next = move || {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        ((state >> 8) % 2001) as i16 - 1000
+    };
+
+    let feature_weights: Vec<i16> = (0..HALFKP_FEATURES * hidden).map( |_| next() ).collect();
+    let feature_biases: Vec<i16> = (0..hidden).map( |_| next() ).collect();
+    let output_weights: Vec<i16> = (0..2 * hidden).map( |_| next() ).collect();
+
+    let network = Network::new(feature_weights.clone(), feature_biases.clone(), output_weights.clone(), -50).unwrap();
+
+    let reference = |position: &Position| -> i32 {
+        let perspectives = [position.next_to_move, position.next_to_move.opposite()];
+
+        let output: i64 = perspectives.iter().enumerate()
+            .map( |(half, color)| {
+                let mut values: Vec<i64> = feature_biases.iter().map( |bias| *bias as i64 ).collect();
+
+                for feature in halfkp(position, *color) {
+                    for (i, value) in values.iter_mut().enumerate() {
+                        *value += feature_weights[feature * hidden + i] as i64;
+                    }
+                }
+
+                values.iter().enumerate().map( |(i, value)| (*value).clamp(0, 127) * output_weights[half * hidden + i] as i64 ).sum::<i64>()
+            })
+            .sum();
+
+        ((output - 50) / 64) as i32
+    };
+
+    let game = Game::replay_pgn("1. e4 d5 2. exd5 Qxd5 3. Nc3 Qa5 4. d4 c6 5. Nf3 Bg4 *").last().unwrap().unwrap().1;
+    let mut accumulator = network.accumulator(&Game::standard_position());
+
+    for (before, valid_move) in game.history() {
+        let after = before.make_valid_move(&valid_move);
+        accumulator = network.update(&accumulator, before.position(), after.position());
+
+        assert_eq!(network.evaluate(&accumulator, after.position().next_to_move), reference(after.position()));
+    }
+}
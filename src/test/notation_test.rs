@@ -0,0 +1,121 @@
+use super::*;
+
+static SAN_GAME: &str = "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. Qe2 Bg4 6. Kf1 Qd7 1-0";
+
+#[test]
+fn test_descriptive_notation() {
+    let english = Game::replay_pgn(SAN_GAME).last().unwrap().unwrap().1;
+
+    let descriptive = "1. P-K4 P-K4 2. Kt-KB3 N-QB3 3. B-N5 P-QR3 4. BxKt QPxB 5. Q-K2 B-KN5 6. K-B1 Q-Q2 1-0";
+    let game = Game::replay_pgn(descriptive).with_notation(MoveNotation::Descriptive).last().unwrap().unwrap().1;
+
+    assert_eq!(game.position(), english.position());
+
+    let written: Vec<String> = english.history().iter()
+        .map( |(game, valid_move)| valid_move.to_notation_in(game, MoveNotation::Descriptive) )
+        .collect();
+
+    assert_eq!(written, vec![
+        "P-K4", "P-K4", "N-KB3", "N-QB3", "B-N5", "P-QR3", "BxN", "QPxB", "Q-K2", "B-KN5", "K-B1", "Q-Q2"
+    ]);
+
+    // Ambiguous descriptions need a more specific name
+    let after_e5 = &english.history()[2].0;
+
+    assert!(matches!(ValidMove::from_descriptive(after_e5, "N-B3"), Err(InvalidMoveError::AmbiguousMove(_))));
+    assert_eq!(ValidMove::from_descriptive(after_e5, "p-kb4 ch").map( |valid_move| after_e5.san(&valid_move) ), Ok(String::from("f4")));
+    assert_eq!(ValidMove::from_descriptive(after_e5, "N(KN1)-K2").map( |valid_move| after_e5.san(&valid_move) ), Ok(String::from("Ne2")));
+    assert_eq!(ValidMove::from_descriptive(after_e5, "P-K5"), Err(InvalidMoveError::NoMatchingMove));
+    assert_eq!(ValidMove::from_descriptive(after_e5, "e4"), Err(InvalidMoveError::InvalidNotation));
+
+    let promotion = Game::new_from_fen("1n2k3/2P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+    for (notation, san) in [("P-B8(Q)", "c8=Q+"), ("P-QB8=N", "c8=N"), ("PxN/R", "cxb8=R+"), ("PxKt(Q) ch", "cxb8=Q+")].iter() {
+        let valid_move = ValidMove::from_descriptive(&promotion, notation).unwrap();
+
+        assert_eq!(promotion.san(&valid_move), *san);
+        assert_eq!(ValidMove::from_descriptive(&promotion, &valid_move.descriptive(&promotion)), Ok(valid_move));
+    }
+}
+
+#[test]
+fn test_descriptive_pgn_round_trip() {
+    let san = Game::replay_pgn("1. e4 f5 2. exf5 g5 3. Qh5# 1-0").last().unwrap().unwrap().1;
+
+    let moves: Vec<String> = san.history().iter()
+        .map( |(game, valid_move)| valid_move.descriptive(game) )
+        .collect();
+
+    assert_eq!(moves, vec!["P-K4", "P-KB4", "PxP", "P-KN4", "Q-R5 mate"]);
+
+    let pgn = format!("1. {} {} 2. {} {} 3. {} 1-0", moves[0], moves[1], moves[2], moves[3], moves[4]);
+    let game = Game::replay_pgn(&pgn).with_notation(MoveNotation::Descriptive).last().unwrap().unwrap().1;
+
+    assert_eq!(game.position(), san.position());
+
+    // Checks and en passant captures written after the move
+    let pgn = "1. P-K4 P-QR3 2. P-K5 P-Q4 3. PxP e.p. P-KB3 4. P-Q7 ch K-B2 5. Q-R5 ch P-KN3 6. PxB=Q EP 1-0";
+    let game = Game::replay_pgn(pgn).with_notation(MoveNotation::Descriptive).last().unwrap().unwrap().1;
+
+    assert_eq!(game.position_to_fen(), "rnQq1bnr/1pp1pk1p/p4pp1/7Q/8/8/PPPP1PPP/RNB1KBNR b KQ - 0 6");
+}
+
+#[test]
+fn test_correspondence_notations() {
+    let english = Game::replay_pgn(SAN_GAME).last().unwrap().unwrap().1;
+
+    let written = |notation: MoveNotation| english.history().iter()
+        .map( |(game, valid_move)| valid_move.to_notation_in(game, notation) )
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    assert_eq!(written(MoveNotation::Iccf), "5254 5755 7163 2836 6125 1716 2536 4736 4152 3874 5161 4847");
+    assert_eq!(written(MoveNotation::Smith), "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5c6n d7c6b d1e2 c8g4 e1f1 d8d7");
+
+    for notation in [MoveNotation::Iccf, MoveNotation::Smith, MoveNotation::Uci].iter() {
+        let moves: Vec<String> = written(*notation).split(' ').map( String::from ).collect();
+        let mut game = Game::new(Game::standard_position());
+
+        for text in moves {
+            game = game.make_valid_move(&ValidMove::from_notation_in(&game, &text, *notation).unwrap());
+        }
+
+        assert_eq!(game.position(), english.position());
+    }
+
+    let promotion = Game::new_from_fen("1n2k3/2P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let valid_move = ValidMove::from_notation(&promotion, "cxb8=R").unwrap();
+
+    assert_eq!(valid_move.iccf(), "37282");
+    assert_eq!(valid_move.smith(), "c7b8nR");
+    assert_eq!(ValidMove::from_iccf(&promotion, "37282"), Ok(valid_move.clone()));
+    assert_eq!(ValidMove::from_smith(&promotion, "c7b8nR"), Ok(valid_move));
+
+    assert_eq!(ValidMove::from_iccf(&promotion, "3728"), Err(InvalidMoveError::NoMatchingMove));
+    assert_eq!(ValidMove::from_iccf(&promotion, "3798"), Err(InvalidMoveError::InvalidNotation));
+    assert_eq!(ValidMove::from_smith(&promotion, "c7b8qR"), Err(InvalidMoveError::NoMatchingMove));
+
+    let en_passant = Game::replay_pgn("1. e4 a6 2. e5 d5 1-0").last().unwrap().unwrap().1;
+    let valid_move = ValidMove::from_smith(&en_passant, "e5d6E").unwrap();
+
+    assert!(valid_move.takes_en_passant);
+    assert_eq!(valid_move.smith(), "e5d6E");
+}
+
+#[test]
+fn test_piece_letters_and_figurines() {
+    for (index, piece) in PIECES.iter().enumerate() {
+        assert_eq!(piece.index(), index);
+        assert_eq!(Piece::from_letter(piece.letter()), Some(*piece));
+        assert_eq!(Piece::from_letter(piece.letter().to_ascii_lowercase()), Some(*piece));
+
+        for color in [Color::White, Color::Black].iter() {
+            assert_eq!(Piece::from_figurine(piece.figurine(*color)), Some(OccupiedSquare { piece: *piece, color: *color }));
+        }
+    }
+
+    assert_eq!(Piece::Knight.letter(), 'N');
+    assert_eq!(Piece::Queen.figurine(Color::Black), '♛');
+    assert_eq!(Piece::from_letter('X'), None);
+    assert_eq!(Piece::from_figurine('N'), None);
+}
@@ -0,0 +1,93 @@
+use super::*;
+use pairing::{Player, Pairing, Swiss, round_robin};
+
+#[test]
+fn test_round_robin() {
+    for players in 2..=9 {
+        let rounds = round_robin(players);
+        let mut met = std::collections::HashSet::new();
+
+        assert_eq!(rounds.len(), players - 1 + players % 2);
+
+        for round in &rounds {
+            for player in 0..players {
+                assert_eq!(round.iter().filter( |pairing| pairing.involves(player) ).count(), 1);
+            }
+
+            for pairing in round.iter().filter( |pairing| !pairing.is_bye() ) {
+                let black = pairing.black.unwrap();
+
+                assert!(met.insert((pairing.white.min(black), pairing.white.max(black))));
+            }
+        }
+
+        assert_eq!(met.len(), players * (players - 1) / 2);
+
+        for player in 0..players {
+            let whites = rounds.iter().flatten().filter( |pairing| pairing.white == player && !pairing.is_bye() ).count() as i32;
+            let blacks = rounds.iter().flatten().filter( |pairing| pairing.black == Some(player) ).count() as i32;
+
+            assert!((whites - blacks).abs() <= 1);
+        }
+    }
+
+    assert_eq!(round_robin(4)[0], vec![
+        Pairing { white: 0, black: Some(3) },
+        Pairing { white: 1, black: Some(2) }
+    ]);
+}
+
+#[test]
+fn test_swiss() {
+    let players = (0..7).map( |i| Player { name: format!("Player {}", i + 1), rating: 2400 - 50 * i as u32 } ).collect();
+    let mut swiss = Swiss::new(players);
+
+    let first = swiss.pair_round().unwrap();
+    assert_eq!(first, vec![
+        Pairing { white: 0, black: Some(3) },
+        Pairing { white: 4, black: Some(1) },
+        Pairing { white: 2, black: Some(5) },
+        Pairing { white: 6, black: None }
+    ]);
+
+    assert!(swiss.pair_round().is_err());
+
+    swiss.record_result(1, 0, GameResult::WhiteWins).unwrap();
+    swiss.record_result(1, 4, GameResult::Draw).unwrap();
+    swiss.record_result(1, 2, GameResult::BlackWins).unwrap();
+    assert!(swiss.record_result(1, 3, GameResult::Draw).is_err());
+    assert!(swiss.record_result(2, 0, GameResult::Draw).is_err());
+
+    assert_eq!(swiss.score(0), 1.0);
+    assert_eq!(swiss.score(1), 0.5);
+    assert_eq!(swiss.score(6), 1.0);
+
+    for round in 2..=5 {
+        let pairings = swiss.pair_round().unwrap();
+
+        assert_eq!(pairings.iter().filter( |pairing| pairing.is_bye() ).count(), 1);
+
+        for pairing in pairings.iter().filter( |pairing| !pairing.is_bye() ) {
+            swiss.record_result(round, pairing.white, GameResult::WhiteWins).unwrap();
+        }
+    }
+
+    // Nobody met twice and the byes went to different players
+    for a in 0..7 {
+        for b in 0..7 {
+            let games = swiss.rounds().iter().flatten().filter( |(pairing, _)| pairing.involves(a) && pairing.involves(b) && a != b ).count();
+            assert!(games <= 1);
+        }
+    }
+
+    let byes: std::collections::HashSet<usize> = swiss.rounds().iter().flatten()
+        .filter( |(pairing, _)| pairing.is_bye() )
+        .map( |(pairing, _)| pairing.white )
+        .collect();
+    assert_eq!(byes.len(), 5);
+
+    let standings = swiss.standings();
+    assert_eq!(standings.len(), 7);
+    assert!(standings.windows(2).all( |pair| pair[0].1 >= pair[1].1 ));
+    assert_eq!(standings.iter().map( |(_, score)| score ).sum::<f64>(), 5.0 * 4.0);
+}
@@ -0,0 +1,170 @@
+use super::*;
+use pgn_file::{PgnFileWriter, PgnChunks, clean_pgn, CleanOptions, CleanReport, validate, Issue, IssueKind};
+
+#[test]
+fn test_appending_games() {
+    let first = Game::replay_pgn("1. e4 e5 1-0").last().unwrap().unwrap().1.resign(Color::Black).unwrap();
+    let second = Game::replay_pgn("1. d4 d5 1-0").last().unwrap().unwrap().1.resign(Color::White).unwrap();
+
+    let mut writer = PgnFileWriter::new(Vec::new()).with_profile(PgnProfile::compact());
+
+    let drawn = Game::replay_pgn("1. Nf3 Nf6 *").last().unwrap().unwrap().1.offer_draw(Color::Black).unwrap().accept_draw().unwrap();
+
+    writer.write_game(&first, &[]).unwrap();
+    writer.write_game(&second, &[]).unwrap();
+    writer.write_game(&drawn, &[]).unwrap();
+
+    assert_eq!(writer.games_written(), 3);
+
+    let written = String::from_utf8(writer.into_inner()).unwrap();
+    assert_eq!(written, "1. e4 e5 1-0\n\n1. d4 d5 0-1\n\n1. Nf3 Nf6 1/2-1/2\n");
+
+    let read: Vec<Game> = Game::new_from_pgn(&written).unwrap().into_iter().map( |game| game.unwrap() ).collect();
+    assert_eq!(read[2].position(), drawn.position());
+
+    let path = std::env::temp_dir().join(format!("pgn-lib-append-{}.pgn", std::process::id()));
+    std::fs::write(&path, "[Event \"Earlier\"]\n\n1. c4 1-0").unwrap();
+
+    let mut writer = PgnFileWriter::append(&path).unwrap().with_fsync().unwrap();
+    writer.write_game(&first, &[(String::from("Event"), String::from("Later"))]).unwrap();
+    drop(writer);
+
+    let mut writer = PgnFileWriter::append(&path).unwrap();
+    writer.write_game(&second, &[]).unwrap();
+    drop(writer);
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(contents.starts_with("[Event \"Earlier\"]\n\n1. c4 1-0\n\n[Event \"Later\"]\n"));
+    assert!(contents.contains("1. e4 e5 1-0\n\n[Event \"?\"]\n"));
+
+    let games = Game::new_from_pgn(&contents).unwrap();
+
+    assert_eq!(games.len(), 3);
+    assert!(games.iter().all( |game| game.is_ok() ));
+}
+
+#[test]
+fn test_reading_in_chunks() {
+    let pgn = "[Event \"First\"]\n\n1. e4 e5 {[%clk 0:03:00]\n[not a tag]} 1-0\n\n\
+               [Event \"Second\"]\n; [not a tag either]\n\n1. d4 d5 0-1\n\n\
+               [Event \"Broken\"]\n\n1. e5 1-0\n\n\
+               [Event \"Fourth\"]\n\n1. c4 1-0\n";
+
+    let mut chunks = PgnChunks::new(pgn);
+
+    let first = chunks.next_chunk(2);
+    assert_eq!(first.len(), 2);
+    assert_eq!(chunks.games_read(), 2);
+    assert!(chunks.bytes_consumed() < chunks.total_bytes());
+    assert!(!chunks.is_done());
+
+    let (tags, game) = first[0].as_ref().unwrap();
+    assert_eq!(tags, &vec![(String::from("Event"), String::from("First"))]);
+    assert!(game.position_to_fen().starts_with("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w"));
+
+    let rest = PgnChunks::new(pgn).starting_at(chunks.bytes_consumed()).next_chunk(10);
+    assert_eq!(rest.len(), 2);
+    assert!(rest[0].is_err());
+    assert_eq!(rest[1].as_ref().unwrap().0[0].1, "Fourth");
+
+    let all: Vec<_> = PgnChunks::new(pgn).collect();
+    assert_eq!(all.len(), 4);
+
+    // Games without tags can't be told apart before parsing, but are still all read
+    let mut chunks = PgnChunks::new("1. e4 1-0\n\n1. d4 0-1\n");
+    assert_eq!(chunks.next_chunk(1).len(), 2);
+    assert!(chunks.is_done());
+}
+
+#[test]
+fn test_clean_pgn() {
+    let pgn = "[Event \"Cleaning\"]\n\n\
+               1. e4 {[%clk  0:03:00] [%eval 0.3]   Best by test} e5 {+0.25/18 1.2s} \
+               2. Nf3 {Mistake. Nc3 was best.} Nc6 } 3. Bb5 {[%csl Gb5] Spanish 1-0\n";
+
+    let (cleaned, report) = clean_pgn(pgn, &CleanOptions { keep_commands: Some(vec![String::from("clk")]), ..CleanOptions::default() });
+
+    assert_eq!(cleaned, "[Event \"Cleaning\"]\n\n\
+                         1. e4 {[%clk 0:03:00] Best by test} e5 2. Nf3 Nc6 3. Bb5 {Spanish} 1-0\n");
+
+    assert_eq!(report, CleanReport {
+        removed_commands: 2,
+        removed_comments: 2,
+        split_comments: 0,
+        truncated_tags: 0,
+        fixed_braces: 2
+    });
+
+    // The game parses again
+    assert!(Game::new_from_pgn(&cleaned).unwrap()[0].is_ok());
+
+    // An unterminated comment doesn't swallow the next game
+    let (cleaned, _) = clean_pgn("1. e4 {Open 1-0\n\n[Event \"Next\"]\n\n1. d4 0-1\n", &CleanOptions::text_only());
+    assert_eq!(cleaned, "1. e4 {Open} 1-0\n\n[Event \"Next\"]\n\n1. d4 0-1\n");
+
+    let long = format!("[Annotator \"{}\\\"\"]\n\n1. e4 {{{}}} *\n", "a".repeat(9), ["word"; 6].join(" "));
+    let (cleaned, report) = clean_pgn(&long, &CleanOptions { max_string_length: 10, ..CleanOptions::default() });
+
+    assert_eq!(cleaned, "[Annotator \"aaaaaaaaa\\\"\"]\n\n1. e4 {word word} {word word} {word word} *\n");
+    assert_eq!(report.split_comments, 1);
+    assert_eq!(report.truncated_tags, 0);
+
+    let (cleaned, report) = clean_pgn(&long, &CleanOptions { max_string_length: 5, ..CleanOptions::default() });
+
+    assert!(cleaned.starts_with("[Annotator \"aaaaa\"]\n"));
+    assert_eq!(report.truncated_tags, 1);
+}
+
+#[test]
+fn test_validate_pgn() {
+    let valid = "[Event \"First\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n\
+                 1. e4 {A comment\nover two lines} Kd7 (1... Ke7 $1 2. e5) 2. e5 *\n\n\
+                 [Event \"Second\"]\n\n1. d4 d5 2. c4 0-1\n";
+
+    assert_eq!(validate(valid.as_bytes()), Vec::new());
+    assert!(Game::new_from_pgn(valid).unwrap().iter().all( |game| game.is_ok() ));
+
+    // Including draws, as this crate writes them
+    let drawn = Game::replay_pgn("1. e4 e5 *").last().unwrap().unwrap().1.offer_draw(Color::White).unwrap().accept_draw().unwrap();
+    let drawn_file = format!("{}\n{}", drawn.to_pgn(), valid);
+    assert_eq!(validate(drawn_file.as_bytes()), Vec::new());
+
+    let invalid = "[Event \"Illegal\"]\n\n1. e4 e5 2. Ke3 Nc6 1-0\n\n\
+                   [Event \"No result\"]\n\n1. d4 d5\n\n\
+                   [Event \"Fine\"]\n\n1. c4 *\n\n\
+                   [Event \"Lexical\"]\n\n1. e4\te5 *\n\n\
+                   [Event \"Unfinished\"]\n\n1. Nf3";
+
+    let issues = validate(invalid.as_bytes());
+    let summary: Vec<(usize, usize, usize, IssueKind)> = issues.iter().map( |issue| (issue.game, issue.line, issue.column, issue.kind) ).collect();
+
+    assert_eq!(summary, vec![
+        (0, 3, 13, IssueKind::Replay),
+        (1, 9, 1, IssueKind::Syntax),
+        (3, 15, 6, IssueKind::Lexical),
+        (4, 19, 7, IssueKind::Syntax)
+    ]);
+
+    assert!(issues[0].message.contains("Ke3"));
+    assert_eq!(issues[1], Issue { game: 1, line: 9, column: 1, kind: IssueKind::Syntax, message: String::from("The game has no result") });
+
+    // The validator reads games like Game::new_from_pgn does
+    let pgns = [
+        "1. e4 $1 e5 $2 2. Nf3 *",
+        "1. e4 e5 2. Nf3 !? *",
+        "1. e4 e5 2. Ke2 ch *",
+        "1. P-K4 P-K4 *",
+        "1. e4 e5 {A comment}\n; Another one\n2. Nf3 (2. Nc3 (2. d4)) 0-1",
+        "[Variant \"Atomic\"]\n\n1. e4 *",
+        "1. e4 e5 2. Ke3 *",
+        "1. e4 e5 1/2-1/2"
+    ];
+
+    for pgn in pgns.iter() {
+        let readable = Game::new_from_pgn(pgn).map( |games| games.iter().all( |game| game.is_ok() ) ).unwrap_or(false);
+
+        assert_eq!(validate(pgn.as_bytes()).is_empty(), readable, "{}", pgn);
+    }
+}
@@ -56,9 +56,9 @@ fn test_parse_simple_pgns() {
             fen: None,
             other_tags: vec![(String::from("Event"), String::from("Casual Blitz game"))],
             moves: vec![
-                PGNMove { number: Some(1), white_move: Some(String::from("e4")), black_move: Some(String::from("e5")) },
-                PGNMove { number: Some(2), white_move: Some(String::from("Nf3")), black_move: Some(String::from("Nc6")) },
-                PGNMove { number: Some(3), white_move: Some(String::from("Qxg7#")), black_move: None },
+                PGNMove { number: Some(1), white_move: Some(String::from("e4")), black_move: Some(String::from("e5")), ..Default::default() },
+                PGNMove { number: Some(2), white_move: Some(String::from("Nf3")), black_move: Some(String::from("Nc6")), ..Default::default() },
+                PGNMove { number: Some(3), white_move: Some(String::from("Qxg7#")), black_move: None, white_comment: Some(String::from("White wins by checkmate.")), black_comment: None },
             ],
             result: GameResult::WhiteWins
         }
@@ -77,7 +77,7 @@ fn test_parse_weird_moves() {
             fen: None,
             other_tags: vec![(String::from("Event"), String::from("Casual Blitz game"))],
             moves: vec![
-                PGNMove { number: Some(1), white_move: Some(String::from("e4e5")), black_move: Some(String::from("e8=Q#")) },
+                PGNMove { number: Some(1), white_move: Some(String::from("e4e5")), black_move: Some(String::from("e8=Q#")), ..Default::default() },
             ],
             result: GameResult::WhiteWins
         }
@@ -96,7 +96,7 @@ fn test_parse_move_without_number() {
             fen: None,
             other_tags: vec![(String::from("Event"), String::from("Casual Blitz game"))],
             moves: vec![
-                PGNMove { number: None, white_move: Some(String::from("e4e5")), black_move: Some(String::from("e8=Q#")) },
+                PGNMove { number: None, white_move: Some(String::from("e4e5")), black_move: Some(String::from("e8=Q#")), ..Default::default() },
             ],
             result: GameResult::WhiteWins
         }
@@ -192,3 +192,374 @@ fn test_pgn_with_set_up_start_with_black_move_with_no_move_number() {
         "
     );
 }
+
+#[test]
+fn test_replay_pgn_yields_every_move() {
+    let steps: Vec<(ValidMove, Game)> = Game::replay_pgn("
+        [Event \"Fool's Mate\"]
+
+        1. f3 e5 2. g4 Qh4# 0-1
+    ").collect::<Result<_, _>>().expect("Could not replay PGN");
+
+    let notations: Vec<String> = steps.iter()
+        .map( |(valid_move, _)| valid_move.notation() )
+        .collect();
+
+    assert_eq!(notations, vec!["f3", "e5", "g4", "Qh4"]);
+
+    assert_eq!(steps[0].1.position_to_fen(), "rnbqkbnr/pppppppp/8/8/8/5P2/PPPPP1PP/RNBQKBNR b KQkq - 0 1");
+    assert_eq!(steps[3].1.position_to_fen(), "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    assert!(steps[3].1.in_mate());
+}
+
+#[test]
+fn test_replay_pgn_stops_at_invalid_move() {
+    let mut steps: Vec<Result<(ValidMove, Game), ReplayError>> = Game::replay_pgn("
+        1. e4 e5 2. Ke4 Nc6 1-0
+    ").collect();
+
+    assert_eq!(steps.len(), 3);
+    assert!(steps[1].is_ok());
+
+    let error: String = steps.pop().unwrap().unwrap_err().into();
+    assert_eq!(error, "Invalid move in PGN game: Ke4 (move #2)");
+}
+
+#[test]
+fn test_typed_headers() {
+    let mut lexer = Lexer::new("
+        [Event \"Rated Blitz game\"]
+        [White \"alice\"]
+        [WhiteElo \"1500\"]
+        [BlackElo \"?\"]
+        [Variant \"Standard\"]
+        [TimeControl \"180+2\"]
+        [Termination \"Normal\"]
+        [Annotator \"bob\"]
+
+        1. e4 e5 1-0
+    ");
+    let mut parser = Parser::new(lexer.lex().expect("Cannot lex pgn"));
+    let games = parser.parse().expect("Cannot parse pgn");
+
+    let headers = games[0].headers();
+
+    assert_eq!(headers.event, Some(String::from("Rated Blitz game")));
+    assert_eq!(headers.white, Some(String::from("alice")));
+    assert_eq!(headers.white_elo, Some(1500));
+    assert_eq!(headers.black_elo, None);
+    assert_eq!(headers.variant, Some(String::from("Standard")));
+    assert_eq!(headers.time_control, Some(String::from("180+2")));
+    assert_eq!(headers.termination, Some(String::from("Normal")));
+    assert_eq!(headers.other.get("Annotator"), Some(&String::from("bob")));
+    assert!(headers.is_standard_variant());
+}
+
+#[test]
+fn test_pgn_with_unsupported_variant() {
+    let games = Game::new_from_pgn("
+        [Variant \"Atomic\"]
+
+        1. e4 e5 0-1
+    ").expect("Could not parse PGN");
+
+    assert_eq!(games[0].as_ref().err(), Some(&String::from("Unsupported variant 'Atomic'")));
+}
+
+#[test]
+fn test_replay_with_disambiguated_moves() {
+    expect_pgn_state(
+        "
+        1. Nf3 d5 2. d3 e5 3. Nbd2 e4 4. Nd4 Nf6 5. N2b3 1-0
+        ",
+        "
+        |r|n|b|q|k|b| |r|
+        |p|p|p| | |p|p|p|
+        | | | | | |n| | |
+        | | | |p| | | | |
+        | | | |N|p| | | |
+        | |N| |P| | | | |
+        |P|P|P| |P|P|P|P|
+        |R| |B|Q|K|B| |R|
+        "
+    );
+}
+
+#[test]
+fn test_replay_reports_ambiguous_moves() {
+    let error = Game::replay_pgn("
+        1. Nf3 d5 2. d3 e5 3. Nd2 e4 1-0
+    ").find_map( |step| step.err() ).expect("Expected an error");
+
+    match &error {
+        ReplayError::InvalidMove { error: InvalidMoveError::AmbiguousMove(candidates), .. } => {
+            let mut from_squares: Vec<String> = candidates.iter().map( |m| format!("{:?}", m.from) ).collect();
+            from_squares.sort();
+
+            assert_eq!(from_squares, vec!["b1", "f3"]);
+        },
+        _ => panic!("Expected an ambiguous move error, got {:?}", error)
+    }
+
+    let message: String = error.into();
+    assert!(message.starts_with("Ambiguous move in PGN game: Nd2 (move #3), could be played from"));
+}
+
+#[test]
+fn test_lenient_replay_tolerates_capture_markers() {
+    let strict = Game::replay_pgn("1. e4 d5 2. ed5 Qd5 1-0").find_map( |step| step.err() );
+    assert!(strict.is_some());
+
+    let lenient = Game::replay_pgn("1. e4 d5 2. ed5 Qd5 1-0")
+        .with_strictness(NotationStrictness::Lenient)
+        .collect::<Result<Vec<(ValidMove, Game)>, ReplayError>>()
+        .expect("Lenient replay failed");
+
+    assert_eq!(lenient.last().unwrap().1.position_to_fen(), "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3");
+}
+
+#[test]
+fn test_parse_move_comments() {
+    let pgn_game = Game::parse_pgn("1. e4 {[%clk 0:01:00]} {best by test} 1... e5 { [%clk 0:00:59.1] } 2. Nf3 1-0").unwrap().remove(0);
+
+    assert_eq!(pgn_game.half_move_comments(), vec![Some("[%clk 0:01:00] best by test"), Some("[%clk 0:00:59.1]"), None]);
+    assert_eq!(pgn_game.clocks(), vec![
+        Some(std::time::Duration::from_secs(60)),
+        Some(std::time::Duration::from_millis(59_100)),
+        None
+    ]);
+}
+
+#[test]
+fn test_writing_pgn() {
+    let game = Game::replay_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0").last().unwrap().unwrap().1;
+    let game = game.resign(Color::Black).unwrap();
+
+    assert_eq!(game.to_pgn(), "\
+        [Event \"?\"]\n\
+        [Site \"?\"]\n\
+        [Date \"????.??.??\"]\n\
+        [Round \"?\"]\n\
+        [White \"?\"]\n\
+        [Black \"?\"]\n\
+        [Result \"1-0\"]\n\
+        [Termination \"normal\"]\n\
+        \n\
+        1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0\n");
+
+    let replayed = Game::replay_pgn(&game.to_pgn()).last().unwrap().unwrap().1;
+    assert_eq!(replayed.position(), game.position());
+
+    let from_position = Game::new_from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 10").unwrap()
+        .make_move("Kd7").unwrap()
+        .adjudicate(GameResult::Draw, "adjudication");
+
+    let pgn = from_position.to_pgn();
+
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 10\"]\n[Termination \"adjudication\"]\n"));
+    assert!(pgn.ends_with("\n\n10... Kd7 1/2-1/2\n"));
+
+    // Drawn games read back like the others
+    let read = Game::new_from_pgn(&pgn).unwrap().remove(0).unwrap();
+    assert_eq!(read.position(), from_position.position());
+
+    let agreed = Game::replay_pgn("1. e4 e5 *").last().unwrap().unwrap().1.offer_draw(Color::White).unwrap().accept_draw().unwrap();
+    let mut parser = Parser::new(Lexer::new(&agreed.to_pgn()).lex().unwrap());
+    assert_eq!(parser.parse().unwrap()[0].result, GameResult::Draw);
+
+    expect_lexing("1/2-1/2", &[Token::Symbol(String::from("1/2-1/2")), Token::EndOfFile]);
+    assert!(Lexer::new("1/2").lex().is_err());
+}
+
+#[test]
+fn test_foreign_piece_letters() {
+    use locale::PieceLetters;
+
+    let english = Game::replay_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. Qe2 Bg4 6. Kf1 Qd7 1-0").last().unwrap().unwrap().1;
+
+    let replays = [
+        (PieceLetters::German,   "1. e4 e5 2. Sf3 Sc6 3. Lb5 a6 4. Lxc6 dxc6 5. De2 Lg4 6. Kf1 Dd7 1-0"),
+        (PieceLetters::Spanish,  "1. e4 e5 2. Cf3 Cc6 3. Ab5 a6 4. Axc6 dxc6 5. De2 Ag4 6. Rf1 Dd7 1-0"),
+        (PieceLetters::Russian,  "1. e4 e5 2. Kf3 Kc6 3. Sb5 a6 4. Sxc6 dxc6 5. Fe2 Sg4 6. Krf1 Fd7 1-0"),
+        (PieceLetters::Figurine, "1. e4 e5 2. ♘f3 ♞c6 3. ♗b5 a6 4. ♗xc6 dxc6 5. ♕e2 ♝g4 6. ♔f1 ♛d7 1-0")
+    ];
+
+    for (letters, pgn) in replays.iter() {
+        let game = Game::replay_pgn(pgn).with_piece_letters(*letters).last().unwrap().unwrap().1;
+
+        assert_eq!(game.position(), english.position());
+    }
+
+    // English letters don't mean the same in other languages, e.g. B is a pawn in German
+    assert!(Game::replay_pgn("1. e4 e5 2. Bb5 1-0").with_piece_letters(PieceLetters::German).last().unwrap().is_err());
+
+    let sans = |letters: PieceLetters| english.history().iter()
+        .map( |(game, valid_move)| game.san_with_letters(valid_move, letters) )
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    assert_eq!(sans(PieceLetters::German), "e4 e5 Sf3 Sc6 Lb5 a6 Lxc6 dxc6 De2 Lg4 Kf1 Dd7");
+    assert_eq!(sans(PieceLetters::Figurine), "e4 e5 ♘f3 ♘c6 ♗b5 a6 ♗xc6 dxc6 ♕e2 ♗g4 ♔f1 ♕d7");
+
+    let promotion = Game::new_from_fen("8/2P1k3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let queen = ValidMove::from_notation_with_options(&promotion, "c8=D", MoveOptions { letters: PieceLetters::German, ..MoveOptions::default() }).unwrap();
+
+    assert_eq!(queen.promotion, Some(Piece::Queen));
+    assert_eq!(promotion.san_with_letters(&queen, PieceLetters::Russian), "c8=F");
+}
+
+#[test]
+fn test_pgn_profiles() {
+    use std::time::Duration;
+
+    let game = Game::replay_pgn("1. e4 e5 2. Nf3 Nc6 1-0").last().unwrap().unwrap().1.resign(Color::Black).unwrap();
+    let tags = vec![(String::from("White"), String::from("Carlsen")), (String::from("Annotator"), String::from("Tal"))];
+
+    let annotations = vec![
+        MoveAnnotation { comment: Some(String::from("Best by test")), clock: Some(Duration::from_secs(179)), eval: None },
+        MoveAnnotation { comment: None, clock: Some(Duration::from_secs(3725)), eval: None }
+    ];
+
+    assert_eq!(game.to_pgn_with_profile(&tags, &annotations, &PgnProfile::compact()), "1. e4 e5 2. Nf3 Nc6 1-0\n");
+
+    assert_eq!(game.to_pgn_with_profile(&tags, &annotations, &PgnProfile::lichess()), "\
+        [Event \"?\"]\n\
+        [Site \"?\"]\n\
+        [Date \"????.??.??\"]\n\
+        [Round \"?\"]\n\
+        [White \"Carlsen\"]\n\
+        [Black \"?\"]\n\
+        [Result \"1-0\"]\n\
+        [Annotator \"Tal\"]\n\
+        [Termination \"normal\"]\n\
+        \n\
+        1. e4 {Best by test [%clk 0:02:59]} 1... e5 {[%clk 1:02:05]} 2. Nf3 Nc6 1-0\n");
+
+    let reduced = game.to_pgn_with_profile(&tags, &annotations, &PgnProfile::reduced());
+
+    assert!(reduced.contains("[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0\n"));
+    assert!(!reduced.contains("Annotator") && !reduced.contains("Termination"));
+
+    let custom = PgnProfile {
+        tags: TagSelection::Listed(vec![String::from("Black"), String::from("Annotator"), String::from("Opening")]),
+        comments: false,
+        line_width: Some(12),
+        result: ResultPlacement::TagOnly,
+        ..PgnProfile::export()
+    };
+
+    assert_eq!(
+        game.to_pgn_with_profile(&tags, &annotations, &custom),
+        "[Black \"?\"]\n[Annotator \"Tal\"]\n\n1. e4\n{[%clk 0:02:59]}\n1... e5\n{[%clk 1:02:05]}\n2. Nf3 Nc6\n"
+    );
+
+    assert_eq!(PgnProfile::named("lichess"), Some(PgnProfile::lichess()));
+    assert_eq!(PgnProfile::named("unknown"), None);
+}
+
+#[test]
+fn test_move_metadata() {
+    use std::time::Duration;
+
+    let game = Game::new(Game::standard_position());
+    let e4 = ValidMove::from_notation(&game, "e4").unwrap();
+
+    let game = game.make_valid_move_with_metadata(&e4, MoveMetadata {
+        clock: Some(Duration::from_secs(179)),
+        eval: Some(35),
+        source: Some(String::from("book")),
+        ..MoveMetadata::default()
+    });
+    let game = game.make_move("e5").unwrap();
+
+    let mut metadata = MoveMetadata { comment: Some(String::from("Symmetrical")), eval: Some(-5), ..MoveMetadata::default() };
+    metadata.extra.insert(String::from("server"), String::from("eu-1"));
+
+    let game = game.with_last_move_metadata(metadata.clone()).make_move("Nf3").unwrap();
+
+    assert_eq!(game.ply_count(), 3);
+    assert_eq!(game.move_metadata()[1], metadata);
+    assert_eq!(game.last_move_metadata(), Some(&MoveMetadata::default()));
+
+    // Kept by the games of the history
+    let history = game.history();
+    assert_eq!(history[1].0.last_move_metadata().and_then( |metadata| metadata.source.as_deref() ), Some("book"));
+    assert_eq!(history[2].0.move_metadata(), game.move_metadata()[..2].to_vec());
+
+    assert!(game.to_pgn_with_profile(&[], &[], &PgnProfile::compact()).starts_with("1. e4 e5 2. Nf3"));
+    assert!(game.to_pgn().contains("1. e4 {[%eval 0.35] [%clk 0:02:59]} 1... e5 {Symmetrical [%eval -0.05]} 2. Nf3 *"));
+
+    // Annotations given to the writer take precedence
+    let annotations = vec![MoveAnnotation { comment: Some(String::from("Best by test")), ..MoveAnnotation::default() }];
+    assert!(game.to_pgn_with_profile(&[], &annotations, &PgnProfile::lichess()).contains("1. e4 {Best by test} 1... e5 {Symmetrical"));
+
+    assert_eq!(Game::new(Game::standard_position()).with_last_move_metadata(metadata).ply_count(), 0);
+}
+
+#[test]
+fn test_parse_limits() {
+    let pgn = "
+        [Event \"Casual\"]
+        1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0
+
+        [Event \"Casual\"]
+        1. d4 d5 0-1
+    ";
+
+    assert_eq!(Game::new_from_pgn_with_limits(pgn, ParseLimits::untrusted()).unwrap().len(), 2);
+
+    let limits = |max_games, max_moves, max_tag_length| ParseLimits { max_games, max_moves, max_tag_length, max_comment_length: None };
+
+    assert!(Game::new_from_pgn_with_limits(pgn, limits(Some(1), None, None)).unwrap_err().contains("games"));
+    assert!(Game::new_from_pgn_with_limits(pgn, limits(None, Some(2), None)).unwrap_err().contains("moves"));
+    assert!(Game::new_from_pgn_with_limits(pgn, limits(None, Some(3), Some(6))).is_ok());
+    assert!(Game::new_from_pgn_with_limits(pgn, limits(None, None, Some(5))).is_err());
+
+    let long_tag = format!("[Annotator \"{}\"] 1. e4 1-0", "x".repeat(300));
+
+    assert!(Game::new_from_pgn(&long_tag).is_ok());
+    assert!(Game::new_from_pgn_with_limits(&long_tag, ParseLimits::untrusted()).unwrap_err().contains("String too long"));
+
+    // Stopped while lexing, before the whole comment is in memory
+    let huge_comment = format!("1. e4 {{{}}} e5 1-0", "x".repeat(1_000_000));
+
+    assert!(Game::new_from_pgn(&huge_comment).is_ok());
+    assert!(Game::new_from_pgn_with_limits(&huge_comment, ParseLimits::untrusted()).unwrap_err().contains("Comment too long"));
+
+    let line_comment = format!("1. e4 e5\n;{}\n1-0", "x".repeat(20_000));
+    assert!(Game::new_from_pgn_with_limits(&line_comment, ParseLimits::untrusted()).unwrap_err().contains("Comment too long"));
+
+    // Games and moves are counted while lexing too
+    let many_games = "1. e4 1-0\n".repeat(5);
+    let many_comments = format!("1. e4 {} 1-0", "{x} ".repeat(100));
+
+    assert!(Game::new_from_pgn_with_limits(&many_games, limits(Some(3), None, None)).unwrap_err().contains("More than 3 games"));
+    assert!(Game::new_from_pgn_with_limits(&many_comments, limits(None, Some(2), None)).unwrap_err().contains("too many moves"));
+    assert!(Game::new_from_pgn_with_limits(&many_comments, limits(None, Some(10), None)).is_ok());
+}
+
+#[test]
+fn test_parser_rejects_malformed_input() {
+    let game = Game::new_from_pgn("1. e4 (1. d4 d5 (1... Nf6)) e5 {Open} (1... c5) 2. Nf3 *").unwrap().remove(0).unwrap();
+    assert_eq!(game.moves().iter().map( |valid_move| valid_move.notation() ).collect::<Vec<_>>(), vec!["e4", "e5", "Nf3"]);
+    assert_eq!(game.position().full_move_counter, 2);
+
+    let nested = format!("1. e4 {}{} e5 1-0", "(".repeat(40), ")".repeat(40));
+    assert!(Game::new_from_pgn(&nested).unwrap_err().contains("nested"));
+    assert!(Game::new_from_pgn("1. e4 (1. d4 1-0").is_err());
+
+    assert!(Game::new_from_pgn("1...... e4 1-0").is_err());
+
+    // Only letters some language uses for a piece
+    assert!(Game::new_from_pgn("1. e4 Ze5 1-0").is_err());
+    assert!(Game::new_from_pgn("1. e4 e5 2. Krf3 1-0").unwrap()[0].as_ref().unwrap_err().contains("Invalid move in PGN game: Krf3"));
+
+    let annotated = Game::new_from_pgn("1. e4 $1 e5 {Solid} $10 2. Nf3 $14 1-0").unwrap().remove(0).unwrap();
+    assert_eq!(annotated.position().full_move_counter, 2);
+    assert!(Game::new_from_pgn(&format!("1. e4 {} 1-0", "a".repeat(300))).unwrap_err().contains("Symbol too long"));
+
+    // Used to loop forever or panic
+    assert!(Game::new_from_pgn("1. e4 ) 1-0").is_err());
+    assert!(Parser::new(vec![Token::OpenBracket, Token::Symbol(String::from("Event"))]).parse().is_err());
+    assert!(Parser::new(vec![]).parse().unwrap().is_empty());
+}
@@ -0,0 +1,55 @@
+use super::*;
+use proto::*;
+
+#[test]
+fn test_protobuf_messages() {
+    let game = Game::replay_pgn("1. e4 c5 2. e5 d5 1-0").last().unwrap().unwrap().1;
+
+    assert_eq!(encode_move(&game.moves()[0]), vec![0x08, 12, 0x10, 28]);
+
+    let position = game.position().clone();
+    assert_eq!(decode_position(&encode_position(&position)), Ok(position.clone()));
+    assert_eq!(decode_position(&encode_position(&position)).unwrap().to_fen(), position.to_fen());
+
+    // Fields added to the schema later are skipped
+    let mut with_unknown_field = encode_position(&position);
+    with_unknown_field.extend_from_slice(&[0x38, 5, 0x42, 2, 1, 2]);
+
+    assert_eq!(decode_position(&with_unknown_field), Ok(position.clone()));
+    assert!(decode_position(&[0x0A, 3, 1, 2, 3]).is_err());
+    assert!(decode_position(&[0x0A, 64]).is_err());
+
+    // The en passant square is stored plus one, and only the third and sixth ranks are valid
+    let with_en_passant = |value: u8| {
+        let mut bytes = encode_position(&position);
+        bytes.extend_from_slice(&[0x20, value]);
+
+        decode_position(&bytes).map( |decoded| decoded.en_passant_square )
+    };
+
+    assert_eq!(with_en_passant(21), Ok(Square::from_notation("e3").ok()));
+    assert_eq!(with_en_passant(45), Ok(Square::from_notation("e6").ok()));
+    assert!(with_en_passant(29).is_err());
+    assert!(with_en_passant(65).is_err());
+
+    let promotion = Game::new_from_fen("1n2k3/2P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let under_promotion = ValidMove::from_notation(&promotion, "cxb8=N").unwrap();
+
+    assert_eq!(decode_move(&promotion, &encode_move(&under_promotion)), Ok(under_promotion));
+    assert!(decode_move(&promotion, &[0x08, 1, 0x10, 2]).is_err());
+
+    let resigned = game.resign(Color::Black).unwrap();
+    let tags = vec![(String::from("White"), String::from("Рубинштейн"))];
+    let (decoded, decoded_tags) = decode_game(&encode_game(&resigned, &tags)).unwrap();
+
+    assert_eq!(decoded.moves(), resigned.moves());
+    assert_eq!(decoded.result(), GameResult::WhiteWins);
+    assert_eq!(decoded_tags, tags);
+
+    let from_position = Game::new_from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 3 40").unwrap().make_move("Kd7").unwrap();
+    let (decoded, _) = decode_game(&encode_game(&from_position, &[])).unwrap();
+
+    assert_eq!(decoded.initial_position().to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 3 40");
+    assert_eq!(decoded.status(), GameStatus::Ongoing);
+    assert!(SCHEMA.contains("message Game"));
+}
@@ -0,0 +1,122 @@
+use super::*;
+use render::{render_svg, render_text, strip_commands, Arrow, BoardView, DiagramOptions, Mark, MarkColor, Overlay, SvgOptions};
+use tree::GameTree;
+
+#[test]
+fn test_rendering_svg() {
+    let game = Game::new_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let e4 = Square::from_notation("e4").unwrap();
+
+    let svg = render_svg(game.board(), &BoardView::default(), &SvgOptions::default());
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"400\" height=\"400\""));
+    assert!(svg.ends_with("</svg>"));
+    assert_eq!(svg.matches("<rect").count(), 64);
+    assert_eq!(svg.matches("♚").count(), 2);
+    assert!(svg.contains(">a</text>") && svg.contains(">8</text>"));
+
+    let view = BoardView { coordinates: false, highlights: vec![e4], ..BoardView::default() };
+    let svg = render_svg(game.board(), &view, &SvgOptions {
+        overlay: Overlay {
+            arrows: vec![Arrow { from: Square::from_notation("e1").unwrap(), to: e4, color: MarkColor::Red }],
+            circles: vec![Mark { square: e4, color: MarkColor::Blue }],
+            squares: vec![Mark { square: Square::from_notation("a1").unwrap(), color: MarkColor::Yellow }]
+        },
+        ..SvgOptions::default()
+    });
+
+    assert_eq!(svg.matches("<rect").count(), 66);
+    assert!(svg.contains("<rect x=\"200\" y=\"200\" width=\"50\" height=\"50\" fill=\"#9bc700\""));
+    assert!(svg.contains("<rect x=\"0\" y=\"350\" width=\"50\" height=\"50\" fill=\"#e68f00\" fill-opacity=\"0.5\""));
+    assert!(svg.contains("<circle cx=\"225\" cy=\"225\"") && svg.contains("stroke=\"#003088\""));
+    assert!(svg.contains("fill=\"#882020\""));
+    assert!(!svg.contains(">a</text>"));
+
+    // The white king is on top when drawn from black's side
+    let flipped = BoardView { orientation: Color::Black, coordinates: false, ..BoardView::default() };
+    assert!(render_svg(game.board(), &flipped, &SvgOptions::default()).contains("<text x=\"175\" y=\"25\""));
+}
+
+#[test]
+fn test_rendering_text() {
+    let game = Game::new_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let a4 = Square::from_notation("a4").unwrap();
+
+    assert_eq!(format!("{:?}", game.board()), render_text(game.board(), &BoardView::plain()));
+    assert!(format!("{:?}", game.board()).ends_with("|R| | | |K| | | |\n"));
+
+    let view = BoardView { highlights: vec![a4], ..BoardView::default() };
+    let text = render_text(game.board(), &view);
+
+    assert!(text.starts_with("| | | | |k| | | | 8\n"));
+    assert!(text.contains("\n|*| | | | | | | | 4\n"));
+    assert!(text.ends_with("|R| | | |K| | | | 1\n a b c d e f g h\n"));
+    assert_eq!(format!("{}", game.board()), render_text(game.board(), &BoardView::default()));
+
+    let flipped = render_text(game.board(), &BoardView { orientation: Color::Black, ..BoardView::default() });
+
+    assert!(flipped.starts_with("| | | |K| | | |R| 1\n"));
+    assert!(flipped.ends_with("| | | |k| | | | | 8\n h g f e d c b a\n"));
+}
+
+#[test]
+fn test_overlay_commands() {
+    let comment = "Attacking e5 [%csl Re5, Gd4][%cal Gf3e5,Xe2e4,Ge9e4] [%clk 0:05:00]";
+    let overlay = Overlay::from_comment(comment);
+
+    assert_eq!(overlay.circles, vec![
+        Mark { square: Square::from_notation("e5").unwrap(), color: MarkColor::Red },
+        Mark { square: Square::from_notation("d4").unwrap(), color: MarkColor::Green }
+    ]);
+    assert_eq!(overlay.arrows, vec![
+        Arrow { from: Square::from_notation("f3").unwrap(), to: Square::from_notation("e5").unwrap(), color: MarkColor::Green }
+    ]);
+
+    assert_eq!(overlay.to_commands(), "[%csl Re5,Gd4][%cal Gf3e5]");
+    assert_eq!(Overlay::from_comment(&overlay.to_commands()), overlay);
+    assert_eq!(strip_commands(comment), "Attacking e5 [%clk 0:05:00]");
+    assert!(Overlay::from_comment("Just a comment").is_empty());
+}
+
+#[test]
+fn test_overlay_in_game_tree() {
+    let mut tree = GameTree::from_pgn("1. e4 {[%cal Gg1f3]} e5 {Solid [%csl Ye5]} 1-0").unwrap();
+
+    let after_e4 = tree.children(tree.root())[0];
+    let after_e5 = tree.children(after_e4)[0];
+
+    assert_eq!(tree.node(after_e4).comment, None);
+    assert_eq!(tree.node(after_e4).overlay.arrows.len(), 1);
+    assert_eq!(tree.node(after_e5).comment.as_deref(), Some("Solid"));
+    assert_eq!(tree.pgn_comment(after_e5).as_deref(), Some("[%csl Ye5] Solid"));
+
+    tree.set_overlay(after_e5, Overlay::new());
+    assert_eq!(tree.pgn_comment(after_e5).as_deref(), Some("Solid"));
+    assert_eq!(tree.pgn_comment(tree.root()), None);
+}
+
+#[test]
+fn test_board_diagrams() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+    let diagram = position.board.to_diagram(&DiagramOptions::default());
+
+    assert_eq!(diagram.lines().nth(6), Some("| | | | |P| | | | 2"));
+    assert_eq!(diagram.lines().nth(8), Some(" a b c d e f g h"));
+    assert_eq!(Board::from_diagram(&diagram), Ok(position.board.clone()));
+
+    let unicode = position.board.to_diagram(&DiagramOptions { coordinates: false, unicode: true });
+
+    assert_eq!(unicode.lines().next(), Some("| | | | |♚| | | |"));
+    assert_eq!(Board::from_diagram(&unicode), Ok(position.board.clone()));
+
+    // Numbered from 1 at the top, the diagram is from black's side
+    let flipped = render_text(&position.board, &BoardView { orientation: Color::Black, ..BoardView::default() });
+    assert_eq!(Board::from_diagram(&flipped), Ok(position.board.clone()));
+
+    let dotted = "8 |.|.|.|.|k|.|.|.|\n".to_string() + &"  |.|.|.|.|.|.|.|.|\n".repeat(5) + "  |.|.|.|.|P|.|.|.|\n  |.|.|.|.|K|.|.|.|\n";
+    assert_eq!(Board::from_diagram(&dotted), Ok(position.board.clone()));
+
+    assert!(Board::from_diagram("|k| | |\n").is_err());
+    assert!(Board::from_diagram(&diagram.replace('P', "X")).is_err());
+    assert!(Board::from_diagram(&diagram.lines().take(7).collect::<Vec<_>>().join("\n")).is_err());
+}
@@ -0,0 +1,90 @@
+use super::*;
+use repertoire::{Repertoire, find_deviation};
+use tree::GameTree;
+
+const DAY: u64 = 24 * 60 * 60;
+
+const REPERTOIRE: &str = "
+    1. e4 e5 2. Nf3 {Attacking e5} Nc6 3. Bb5 1-0
+
+    1. e4 c5 2. Nf3 d6 3. d4 1-0
+
+    1. e4 e5 2. Nf3 Nf6 3. Nxe5 1-0
+";
+
+#[test]
+fn test_game_tree_from_pgn() {
+    let tree = GameTree::from_pgn(REPERTOIRE).unwrap();
+
+    assert_eq!(tree.len(), 12);
+    assert_eq!(tree.children(tree.root()).len(), 1);
+
+    let after_e4 = tree.children(tree.root())[0];
+    assert_eq!(tree.children(after_e4).len(), 2);
+
+    let after_nf3 = tree.children(tree.children(after_e4)[0])[0];
+    let node = tree.node(after_nf3);
+
+    assert_eq!(node.comment.as_deref(), Some("Attacking e5"));
+    assert_eq!(tree.path(after_nf3).iter().map( |valid_move| valid_move.notation() ).collect::<Vec<_>>(), vec!["e4", "e5", "Nf3"]);
+    assert_eq!(tree.find(node.game.hash()), vec![after_nf3]);
+}
+
+#[test]
+fn test_repertoire_drilling() {
+    let tree = GameTree::from_pgn(REPERTOIRE).unwrap();
+    let mut repertoire = Repertoire::new(tree, Color::White, 0);
+
+    // The start, 1. e4 e5, 1. e4 c5, 2. Nf3 Nc6, 2. Nf3 d6 and 2. Nf3 Nf6
+    let due = repertoire.due(0);
+    assert_eq!(due.len(), 6);
+    assert!(due.iter().all( |drill| drill.game.position().next_to_move == Color::White ));
+
+    let start = due[0].node;
+    assert!(due[0].line.is_empty());
+
+    let answer = repertoire.answer(start, "e2e4", 0).unwrap();
+    assert!(answer.correct);
+    assert_eq!(answer.expected, vec!["e4"]);
+    assert_eq!(repertoire.card(start).unwrap().due, DAY);
+
+    let after_e5 = due.iter().find( |drill| drill.line.len() == 2 && drill.line[1].notation() == "e5" ).unwrap().node;
+
+    let answer = repertoire.answer(after_e5, "Nc3", 0).unwrap();
+    assert!(!answer.correct);
+    assert_eq!(answer.expected, vec!["Nf3"]);
+    assert_eq!(repertoire.card(after_e5).unwrap().mistakes, 1);
+
+    assert!(repertoire.answer(after_e5, "Ke3", 0).is_err());
+
+    assert_eq!(repertoire.due(0).len(), 5);
+    assert_eq!(repertoire.due(DAY).len(), 6);
+    assert_eq!(repertoire.weakest(3).iter().map( |drill| drill.node ).collect::<Vec<_>>(), vec![after_e5]);
+
+    repertoire.answer(start, "e4", DAY).unwrap();
+    assert_eq!(repertoire.card(start).unwrap().due, DAY + 3 * DAY);
+}
+
+#[test]
+fn test_repertoire_deviation() {
+    let tree = GameTree::from_pgn(REPERTOIRE).unwrap();
+    let repertoire = Repertoire::new(tree.clone(), Color::White, 0);
+
+    let game = Game::new_from_pgn("1. e4 e5 2. Nc3 Nf6 1-0").unwrap().remove(0).unwrap();
+    let deviation = repertoire.deviation(&game).unwrap();
+
+    assert_eq!(deviation.ply, 2);
+    assert_eq!(deviation.san, "Nc3");
+    assert!(deviation.by_player);
+    assert_eq!(deviation.expected, vec!["Nf3"]);
+
+    let game = Game::new_from_pgn("1. e4 c5 2. Nf3 e6 1-0").unwrap().remove(0).unwrap();
+    let deviation = find_deviation(&tree, &game, Color::White).unwrap();
+
+    assert_eq!(deviation.ply, 3);
+    assert!(!deviation.by_player);
+    assert_eq!(deviation.expected, vec!["d6"]);
+
+    let game = Game::new_from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 1-0").unwrap().remove(0).unwrap();
+    assert_eq!(repertoire.deviation(&game), None);
+}
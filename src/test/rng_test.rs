@@ -0,0 +1,76 @@
+use super::*;
+use rng::{Rng, SplitMix64};
+use book::{OpeningTree, BookOptions, BookSelection};
+use training::random_position;
+
+#[test]
+fn test_seeded_generators() {
+    let draw = |rng: &mut SplitMix64| -> Vec<u64> { (0..10).map( |_| rng.below(100) ).collect() };
+
+    let first = draw(&mut SplitMix64::new(42));
+
+    assert_eq!(draw(&mut SplitMix64::new(42)), first);
+    assert_ne!(draw(&mut SplitMix64::new(43)), first);
+    assert!(first.iter().all( |value| *value < 100 ));
+
+    // A fork's numbers don't depend on how much the parent draws after forking
+    let mut parent = SplitMix64::new(7);
+    let mut fork = parent.fork();
+    let forked = draw(&mut fork);
+
+    let mut parent_again = SplitMix64::new(7);
+    let mut fork_again = parent_again.fork();
+    draw(&mut parent_again);
+
+    assert_eq!(draw(&mut fork_again), forked);
+    assert_ne!(draw(&mut parent), forked);
+}
+
+#[test]
+fn test_book_moves_from_generator() {
+    let tree = OpeningTree::from_pgn("1. e4 e5 1-0 1. d4 d5 1-0 1. c4 e5 1-0 1. Nf3 d5 1-0").unwrap();
+    let game = Game::new(Game::standard_position());
+    let options = BookOptions { selection: BookSelection::Weighted { seed: 0 }, ..Default::default() };
+
+    let picks = |seed: u64| -> Vec<String> {
+        let mut rng = SplitMix64::new(seed);
+
+        (0..20).map( |_| game.san(&book::choose_move_with_rng(&tree, &game, &options, &mut rng).unwrap()) ).collect()
+    };
+
+    let moves = picks(3);
+
+    assert_eq!(picks(3), moves);
+    assert!(moves.iter().collect::<HashSet<_>>().len() > 1);
+}
+
+#[test]
+fn test_random_position() {
+    let pieces = [
+        OccupiedSquare { piece: Piece::Rook, color: Color::White },
+        OccupiedSquare { piece: Piece::Pawn, color: Color::White },
+        OccupiedSquare { piece: Piece::Pawn, color: Color::Black },
+        OccupiedSquare { piece: Piece::Knight, color: Color::Black }
+    ];
+
+    for seed in 0..50 {
+        let game = random_position(&pieces, Color::White, &mut SplitMix64::new(seed)).unwrap();
+        let position = game.position();
+        let occupied: Vec<(usize, &OccupiedSquare)> = position.board.squares.iter()
+            .enumerate()
+            .filter_map( |(index, square)| square.as_ref().map( |square| (index, square) ) )
+            .collect();
+
+        assert_eq!(occupied.len(), 6);
+        assert_eq!(occupied.iter().filter( |(_, square)| square.piece == Piece::King ).count(), 2);
+        assert!(occupied.iter().all( |(index, square)| square.piece != Piece::Pawn || !matches!(Board::square(*index).rank, 0 | 7) ));
+        assert!(!game.in_check(Color::Black));
+        assert!(!position.white_can_castle_king_side && !position.black_can_castle_queen_side);
+
+        let again = random_position(&pieces, Color::White, &mut SplitMix64::new(seed)).unwrap();
+        assert_eq!(again.position(), position);
+    }
+
+    let too_many = vec![OccupiedSquare { piece: Piece::Queen, color: Color::White }; 63];
+    assert!(random_position(&too_many, Color::White, &mut SplitMix64::new(0)).is_none());
+}
@@ -255,7 +255,8 @@ fn test_queen_moves() {
         Color::White,
 
         &[
-            "f5", "e8", "fxe5",
+            "f5", "fxe5",
+            "e8=Q", "e8=R", "e8=B", "e8=N",
 
                                                 "Qf8",        "Qh8",
                                                 "Qf7", "Qg7",
@@ -282,7 +283,8 @@ fn test_bishop_moves() {
         Color::White,
 
         &[
-            "f5", "e8", "fxe5",
+            "f5", "fxe5",
+            "e8=Q", "e8=R", "e8=B", "e8=N",
 
                                                               "Bh8",
                                                        "Bg7",
@@ -313,7 +315,8 @@ fn test_king_moves() {
             "Ke6",         "Kg6",
             "Kxe5", "Kf5", "Kg5",
 
-            "f5", "e8", "fxe5"
+            "f5", "fxe5",
+            "e8=Q", "e8=R", "e8=B", "e8=N"
         ]
     );
 }
@@ -365,6 +368,67 @@ fn test_need_to_move_out_of_check() {
     );
 }
 
+#[test]
+fn test_castling() {
+    let game = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let sans = game.legal_sans();
+
+    assert!(sans.contains(&String::from("O-O")) && sans.contains(&String::from("O-O-O")));
+
+    let castled = game.make_move("O-O").unwrap();
+    let square = |notation: &str| castled.board().squares[Board::index(Square::from_notation(notation).unwrap())].clone();
+
+    assert_eq!(square("g1"), Some(OccupiedSquare { piece: Piece::King, color: Color::White }));
+    assert_eq!(square("f1"), Some(OccupiedSquare { piece: Piece::Rook, color: Color::White }));
+    assert_eq!(square("h1"), None);
+    assert_eq!(castled.position_to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    assert_eq!(castled.hash(), Game::new_from_fen(&castled.position_to_fen()).unwrap().hash());
+
+    let castled = castled.make_move("O-O-O").unwrap();
+    assert_eq!(castled.position_to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+    assert_eq!(castled.last_move().unwrap().castling_rook_move(), Some((Square::from_notation("a8").unwrap(), Square::from_notation("d8").unwrap())));
+
+    // Moving a rook or losing it gives up castling on its side
+    let rook_moved = game.make_move("Rb1").unwrap();
+    assert_eq!(rook_moved.position_to_fen(), "r3k2r/8/8/8/8/8/8/1R2K2R b Kkq - 1 1");
+
+    let rook_taken = game.make_move("Rxa8+").unwrap();
+    assert_eq!(rook_taken.position_to_fen(), "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1");
+
+    // Not out of, through or into check, and not with pieces in between
+    let in_check = Game::new_from_fen("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1").unwrap();
+    let through_check = Game::new_from_fen("4k3/8/8/8/8/8/5r2/R3K2R w KQ - 0 1").unwrap();
+    let into_check = Game::new_from_fen("4k3/8/8/8/8/8/2r5/R3K2R w KQ - 0 1").unwrap();
+    let blocked = Game::new_from_fen("4k3/8/8/8/8/8/8/RN2K1NR w KQ - 0 1").unwrap();
+
+    assert!(!in_check.valid_moves().iter().any( |valid_move| valid_move.is_castle() ));
+    assert_eq!(through_check.make_move("O-O").err(), Some(InvalidMoveError::NoMatchingMove));
+    assert!(through_check.make_move("O-O-O").is_ok());
+    assert_eq!(into_check.make_move("O-O-O").err(), Some(InvalidMoveError::NoMatchingMove));
+    assert!(into_check.make_move("O-O").is_ok());
+    assert!(!blocked.valid_moves().iter().any( |valid_move| valid_move.is_castle() ));
+
+    // The b-file square only has to be empty, the king doesn't pass it
+    let b1_attacked = Game::new_from_fen("4k3/8/8/8/8/8/1r6/R3K3 w Q - 0 1").unwrap();
+    assert_eq!(b1_attacked.make_move("O-O-O").unwrap().last_move().unwrap().kind(), MoveKind::Castle);
+}
+
+#[test]
+fn test_replaying_castles() {
+    let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. O-O Be7 5. d4 d6 6. Nc3 Bg4 7. Be3 Qd7 8. Qd3 O-O-O 9. Kh1 *";
+    let game = Game::new_from_pgn(pgn).unwrap().pop().unwrap().unwrap();
+
+    assert_eq!(game.position_to_fen(), "2kr3r/pppqbppp/2np1n2/4p3/2BPP1b1/2NQBN2/PPP2PPP/R4R1K b - - 7 9");
+
+    let sans: Vec<String> = game.history().iter().map( |(game, valid_move)| game.san(valid_move) ).collect();
+    assert_eq!(sans[6], "O-O");
+    assert_eq!(sans[15], "O-O-O");
+
+    let before = &game.history()[6].0;
+    assert_eq!(ValidMove::from_uci(before, "e1g1").unwrap().notation(), "O-O");
+    assert_eq!(ValidMove::from_notation_with(before, "0-0", NotationStrictness::Lenient).unwrap().uci(), "e1g1");
+}
+
 #[test]
 fn test_simple_moves() {
     expect_game_state(
@@ -447,3 +511,775 @@ fn test_simple_checks() {
 
     assert!(game.in_check(Color::White));
 }
+
+#[test]
+fn test_legal_sans_are_disambiguated() {
+    let game = read_game(
+        "
+        | | | | | | | | | 8
+        | | | | | | | | | 7
+        | | | | | | | | | 6
+        |R| | | | | | |R| 5
+        | | | | | | | | | 4
+        | | | | | |N| | | 3
+        | | | | | | | | | 2
+        | |N| | |K| | | | 1
+         a b c d e f g h
+        ",
+        Color::White
+    );
+
+    let sans: HashSet<String> = game.legal_sans().into_iter().collect();
+
+    assert!(sans.contains("Nbd2"));
+    assert!(sans.contains("Nfd2"));
+    assert!(sans.contains("Nc3"));
+    assert!(sans.contains("Ne5"));
+    assert!(sans.contains("Rae5"));
+    assert!(sans.contains("Rhe5"));
+    assert!(sans.contains("Ra8"));
+    assert!(!sans.contains("Nd2"));
+
+    for san in &sans {
+        assert!(game.make_move(san).is_ok(), "Cannot play {}", san);
+    }
+}
+
+#[test]
+fn test_legal_sans_use_rank_when_file_is_shared() {
+    let game = read_game(
+        "
+        | | | | | | | | | 8
+        |R| | | | | | | | 7
+        | | | | | | | | | 6
+        | | | | | | | | | 5
+        | | | | | | | | | 4
+        | | | | | | | | | 3
+        |R| | | | | | | | 2
+        | | | | |K| | | | 1
+         a b c d e f g h
+        ",
+        Color::White
+    );
+
+    let sans: HashSet<String> = game.legal_sans().into_iter().collect();
+
+    assert!(sans.contains("R7a5"));
+    assert!(sans.contains("R2a5"));
+    assert!(sans.contains("Rb7"));
+}
+
+#[test]
+fn test_pawn_promotions() {
+    expect_valid_moves(
+        "
+        | | |r| | | | | | 8
+        | | | |P| | | | | 7
+        | | | | | | | | | 6
+        | | | | | | | | | 5
+        | | | | | | | | | 4
+        | | | | | | | | | 3
+        | | | | | | | | | 2
+        | | | | | | | | | 1
+         a b c d e f g h
+        ",
+        Color::White,
+
+        &[
+            "d8=Q", "d8=R", "d8=B", "d8=N",
+            "dxc8=Q", "dxc8=R", "dxc8=B", "dxc8=N"
+        ]
+    );
+
+    expect_game_state(
+        "
+        | | |r| | | | | |
+        | | | |P| | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        ",
+
+        &["dxc8=N"],
+
+        "
+        | | |N| | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        ",
+    );
+}
+
+#[test]
+fn test_try_move() {
+    let game = read_game(
+        "
+        | | | | |k| | | | 8
+        | | | |P| | | | | 7
+        | | | | | | | | | 6
+        | | | | | | | | | 5
+        | | | | |r| | | | 4
+        | | | | | | | | | 3
+        | | | | |B| | | | 2
+        | | | | |K| |N| | 1
+         a b c d e f g h
+        ",
+        Color::White
+    );
+
+    let square = |notation: &str| Square::from_notation(notation).unwrap();
+
+    let knight_move = game.try_move(square("g1"), square("f3"), None).expect("Knight move should be legal");
+    assert_eq!(knight_move.piece, Piece::Knight);
+
+    let promotion = game.try_move(square("d7"), square("d8"), Some(Piece::Rook)).expect("Promotion should be legal");
+    assert_eq!(promotion.promotion, Some(Piece::Rook));
+    assert_eq!(game.make_valid_move(&promotion).position_to_fen(), "3Rk3/8/8/8/4r3/8/4B3/4K1N1 b KQkq - 0 0");
+
+    assert_eq!(game.try_move(square("a1"), square("a2"), None), Err(IllegalReason::NoPieceOnSquare));
+    assert_eq!(game.try_move(square("e8"), square("f8"), None), Err(IllegalReason::NotYourTurn));
+    assert_eq!(game.try_move(square("g1"), square("g3"), None), Err(IllegalReason::CannotReachSquare));
+    assert_eq!(game.try_move(square("e2"), square("d3"), None), Err(IllegalReason::LeavesKingInCheck));
+    assert_eq!(game.try_move(square("d7"), square("d8"), None), Err(IllegalReason::PromotionRequired));
+    assert_eq!(game.try_move(square("d7"), square("d8"), Some(Piece::King)), Err(IllegalReason::InvalidPromotion));
+    assert_eq!(game.try_move(square("g1"), square("f3"), Some(Piece::Queen)), Err(IllegalReason::InvalidPromotion));
+}
+
+#[test]
+fn test_destinations_and_premoves() {
+    let game = Game::new_from_fen("4k3/1P6/8/8/8/8/4P3/R3K1N1 b Q - 0 1").unwrap();
+    let square = |notation: &str| Square::from_notation(notation).unwrap();
+    let squares = |notations: &[&str]| notations.iter().map( |notation| square(notation) ).collect::<Vec<_>>();
+
+    let mut knight = game.destinations_from(square("g1"));
+    knight.sort_by_key( |square| (square.rank, square.file) );
+    assert_eq!(knight, squares(&["f3", "h3"]));
+
+    assert_eq!(game.destinations_from(square("b7")), squares(&["b8"]));
+    assert_eq!(game.destinations_from(square("d4")), vec![]);
+
+    // White is waiting for black to move
+    assert!(game.is_plausible_premove(square("e2"), square("e4")));
+    assert!(game.is_plausible_premove(square("e2"), square("d3")));
+    assert!(game.is_plausible_premove(square("a1"), square("a8")));
+    assert!(game.is_plausible_premove(square("e1"), square("c1")));
+    assert!(game.is_plausible_premove(square("g1"), square("e2")));
+
+    assert!(!game.is_plausible_premove(square("e2"), square("e5")));
+    assert!(!game.is_plausible_premove(square("e2"), square("e1")));
+    assert!(!game.is_plausible_premove(square("a1"), square("b3")));
+    assert!(!game.is_plausible_premove(square("d4"), square("d5")));
+    assert!(!game.is_plausible_premove(square("a1"), square("a1")));
+}
+
+#[test]
+fn test_auto_queen_promotion() {
+    let game = Game::new_from_fen("8/4P3/8/8/8/8/8/k1K5 w - - 0 1").unwrap();
+    let square = |notation: &str| Square::from_notation(notation).unwrap();
+
+    let auto_queen = MoveOptions { promotion: PromotionPolicy::AutoQueen, ..MoveOptions::default() };
+
+    let promotion = game.try_move_with_options(square("e7"), square("e8"), None, auto_queen).unwrap();
+    assert_eq!(promotion.promotion, Some(Piece::Queen));
+
+    let underpromotion = game.try_move_with_options(square("e7"), square("e8"), Some(Piece::Knight), auto_queen).unwrap();
+    assert_eq!(underpromotion.promotion, Some(Piece::Knight));
+
+    assert_eq!(game.try_move(square("e7"), square("e8"), None), Err(IllegalReason::PromotionRequired));
+
+    let after = game.make_move_with_options("e8", auto_queen).unwrap();
+    assert_eq!(after.position_to_fen(), "4Q3/8/8/8/8/8/8/k1K5 b - - 0 1");
+
+    assert!(game.make_move_with_options("e8=R", auto_queen).is_ok());
+    assert!(game.make_move_with_options("Kd2", auto_queen).is_ok());
+    assert_eq!(game.make_move("e8").err(), Some(InvalidMoveError::NoMatchingMove));
+}
+
+#[test]
+fn test_check_and_mate_flags() {
+    let game = read_game(
+        "
+        | | | | | | |k| | 8
+        | | | | | |p|p|p| 7
+        | | | | | | | | | 6
+        | | | | | | | | | 5
+        | | | | | | | | | 4
+        | | | | | | | | | 3
+        | | | | | | | | | 2
+        |R| | | |K| | | | 1
+         a b c d e f g h
+        ",
+        Color::White
+    );
+
+    let mate = game.try_move(Square::from_notation("a1").unwrap(), Square::from_notation("a8").unwrap(), None).unwrap();
+    let quiet = game.try_move(Square::from_notation("a1").unwrap(), Square::from_notation("a7").unwrap(), None).unwrap();
+
+    assert!(game.gives_check(&mate));
+    assert!(game.gives_mate(&mate));
+    assert!(!game.gives_check(&quiet));
+    assert!(!game.gives_mate(&quiet));
+
+    assert_eq!(game.san(&mate), "Ra8#");
+    assert_eq!(game.san(&quiet), "Ra7");
+
+    let sans: HashSet<String> = game.legal_sans().into_iter().collect();
+    assert!(sans.contains("Ra8#"));
+    assert!(!sans.contains("Ra8"));
+}
+
+#[test]
+fn test_check_flag_in_san() {
+    let game = Game::new_from_fen("4k3/8/8/8/8/8/8/Q3K3 w - - 0 1").unwrap();
+    let check = game.try_move(Square::from_notation("a1").unwrap(), Square::from_notation("a4").unwrap(), None).unwrap();
+
+    assert!(game.gives_check(&check));
+    assert!(!game.gives_mate(&check));
+    assert_eq!(game.san(&check), "Qa4+");
+}
+
+#[test]
+fn test_move_kinds() {
+    let game = Game::new_from_fen("r3k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    let square = |notation: &str| Square::from_notation(notation).unwrap();
+
+    let normal = game.try_move(square("e1"), square("e2"), None).unwrap();
+    let en_passant = game.try_move(square("e5"), square("d6"), None).unwrap();
+    let promotion = game.try_move(square("b7"), square("b8"), Some(Piece::Queen)).unwrap();
+    let capture_promotion = game.try_move(square("b7"), square("a8"), Some(Piece::Knight)).unwrap();
+
+    assert_eq!(normal.kind(), MoveKind::Normal);
+    assert!(!normal.is_capture() && !normal.is_castle() && !normal.is_promotion());
+
+    assert_eq!(en_passant.kind(), MoveKind::EnPassant);
+    assert!(en_passant.is_capture() && en_passant.is_en_passant());
+
+    assert_eq!(promotion.kind(), MoveKind::Promotion);
+    assert!(!promotion.is_capture());
+
+    assert_eq!(capture_promotion.kind(), MoveKind::Promotion);
+    assert!(capture_promotion.is_capture() && capture_promotion.is_promotion());
+
+    let json = serde_json::to_value(&en_passant).unwrap();
+    assert_eq!(json["kind"], "enpassant");
+    assert_eq!(json["piece"], "pawn");
+    assert_eq!(json["to"], "d6");
+
+    let parsed: ValidMove = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, en_passant);
+}
+
+#[test]
+fn test_board_indices() {
+    let square = |notation: &str| Square::from_notation(notation).unwrap();
+
+    assert_eq!(Board::index(square("a8")), 0);
+    assert_eq!(Board::index(square("h8")), 7);
+    assert_eq!(Board::index(square("a1")), 56);
+    assert_eq!(Board::index(square("h1")), 63);
+    assert_eq!(Board::square(36), square("e4"));
+
+    for index in 0..64 {
+        assert_eq!(Board::index(Board::square(index)), index);
+    }
+
+    // Counted from a1 instead
+    assert_eq!(square("a1").to_a1_index(), 0);
+    assert_eq!(square("h8").to_a1_index(), 63);
+    assert_eq!(square("e4").to_a1_index(), 28);
+    assert_eq!(Square::from_a1_index(12), Some(square("e2")));
+    assert_eq!(Square::from_a1_index(64), None);
+
+    assert!(!square("a1").is_light());
+    assert!(square("h1").is_light());
+    assert!(square("d1").is_light());
+    assert!(!square("e1").is_light());
+
+    let game = Game::new(Game::standard_position());
+    assert_eq!(game.board().squares[Board::index(square("e1"))], Some(OccupiedSquare { piece: Piece::King, color: Color::White }));
+}
+
+#[test]
+fn test_hashing_and_ordering() {
+    use std::collections::{BTreeSet, HashMap};
+
+    let game = Game::new(Game::standard_position());
+
+    let mut sorted = game.valid_moves();
+    sorted.sort();
+    assert_eq!(sorted.first().map( |valid_move| valid_move.uci() ), Some(String::from("b1a3")));
+    assert_eq!(sorted.iter().collect::<HashSet<_>>().len(), 20);
+
+    let squares: BTreeSet<Square> = ["h1", "a2", "a1"].iter().map( |notation| Square::from_notation(notation).unwrap() ).collect();
+    assert_eq!(squares.iter().map( |square| format!("{:?}", square) ).collect::<Vec<_>>(), vec!["a1", "h1", "a2"]);
+
+    assert!(Color::White < Color::Black);
+
+    let mut seen: HashMap<Position, usize> = HashMap::new();
+    *seen.entry(game.position().clone()).or_default() += 1;
+    *seen.entry(Game::standard_position()).or_default() += 1;
+    *seen.entry(game.make_move("e4").unwrap().position().clone()).or_default() += 1;
+
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[game.position()], 2);
+}
+
+#[test]
+fn test_games_share_positions() {
+    let game = Game::replay_pgn("1. e4 e5 2. Nf3 Nc6 1-0").last().unwrap().unwrap().1;
+    let copy = game.clone();
+
+    assert!(std::sync::Arc::ptr_eq(&game.shared_position(), &copy.shared_position()));
+
+    let history = game.history();
+    assert!(std::ptr::eq(history[0].0.position(), game.initial_position()));
+
+    let restarted = Game::from_shared_position(game.shared_position());
+    assert_eq!(restarted.hash(), game.hash());
+    assert!(std::ptr::eq(restarted.initial_position(), game.position()));
+    assert_eq!(restarted.valid_moves(), game.valid_moves());
+}
+
+#[test]
+fn test_square_serialization() {
+    use serde::{Serialize, Deserialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Numeric {
+        #[serde(with = "numeric_square")]
+        square: Square
+    }
+
+    let e4 = Square::from_notation("e4").unwrap();
+
+    assert_eq!(serde_json::to_string(&e4).unwrap(), "\"e4\"");
+    assert_eq!(serde_json::from_str::<Square>("\"e4\"").unwrap(), e4);
+    assert_eq!(serde_json::from_str::<Square>("{\"rank\": 3, \"file\": 4}").unwrap(), e4);
+
+    assert_eq!(serde_json::to_string(&Numeric { square: e4 }).unwrap(), "{\"square\":{\"rank\":3,\"file\":4}}");
+    assert_eq!(serde_json::from_str::<Numeric>("{\"square\": \"e4\"}").unwrap().square, e4);
+
+    #[derive(Serialize, Deserialize)]
+    struct NumericMove {
+        #[serde(with = "numeric_move")]
+        valid_move: ValidMove
+    }
+
+    let valid_move = ValidMove::from_notation(&Game::new(Game::standard_position()), "e4").unwrap();
+    let json = serde_json::to_value(&NumericMove { valid_move: valid_move.clone() }).unwrap();
+
+    assert_eq!(json["valid_move"]["from"], serde_json::json!({ "rank": 1, "file": 4 }));
+    assert_eq!(json["valid_move"]["en_passant_square"], serde_json::json!({ "rank": 2, "file": 4 }));
+    assert_eq!(serde_json::from_value::<NumericMove>(json).unwrap().valid_move, valid_move);
+    assert_eq!(serde_json::to_value(&valid_move).unwrap()["to"], "e4");
+
+    assert!(serde_json::from_str::<Square>("\"e9\"").is_err());
+    assert!(serde_json::from_str::<Square>("\"e44\"").is_err());
+    assert!(serde_json::from_str::<Square>("{\"rank\": 8, \"file\": 0}").is_err());
+    assert!(serde_json::to_string(&Square { rank: 9, file: 0 }).is_err());
+}
+
+#[test]
+fn test_half_move_clock() {
+    let game = Game::new_from_fen("r3k3/8/8/8/8/8/4P3/R3K1N1 w - - 7 10").unwrap();
+
+    let knight_move = game.make_move("Nf3").unwrap();
+    assert!(knight_move.position_to_fen().ends_with(" 8 10"));
+
+    let black_rook_move = knight_move.make_move("Rb8").unwrap();
+    assert!(black_rook_move.position_to_fen().ends_with(" 9 11"));
+
+    let pawn_move = black_rook_move.make_move("e4").unwrap();
+    assert!(pawn_move.position_to_fen().ends_with(" 0 11"));
+
+    let capture = game.make_move("Rxa8+").unwrap();
+    assert!(capture.position_to_fen().ends_with(" 0 10"));
+}
+
+#[test]
+fn test_fifty_move_rule() {
+    let before = Game::new_from_fen("4k3/8/8/8/8/8/8/4K1N1 w - - 99 80").unwrap();
+    assert!(!before.draw_by_fifty_move_rule());
+
+    let after_quiet_move = before.make_move("Nf3").unwrap();
+    assert!(after_quiet_move.draw_by_fifty_move_rule());
+
+    assert_eq!(FIFTY_MOVE_RULE_HALF_MOVES, 100);
+}
+
+#[test]
+fn test_en_passant_square_expires_after_one_ply() {
+    let game = Game::new_from_fen("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1").unwrap();
+
+    let after_double_move = game.make_move("d5").unwrap();
+    assert!(after_double_move.position_to_fen().contains(" d6 "));
+    assert!(after_double_move.make_move("exd6").is_ok());
+
+    let after_waiting_moves = after_double_move
+        .make_move("Kd2").unwrap()
+        .make_move("Kf7").unwrap();
+
+    assert!(after_waiting_moves.position_to_fen().contains(" - 2 3"));
+    assert!(after_waiting_moves.make_move("exd6").is_err());
+}
+
+#[test]
+fn test_single_pawn_step_does_not_set_en_passant_square() {
+    let game = Game::new_from_fen("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1").unwrap();
+    let after_single_step = game.make_move("d6").unwrap();
+
+    assert!(after_single_step.position_to_fen().contains(" - 0 2"));
+}
+
+#[test]
+fn test_uci_notation() {
+    let game = Game::new(Game::standard_position());
+    let valid_move = ValidMove::from_uci(&game, "g1f3").unwrap();
+
+    assert_eq!(game.san(&valid_move), "Nf3");
+    assert_eq!(valid_move.uci(), "g1f3");
+
+    assert_eq!(ValidMove::from_uci(&game, "g1g3"), Err(InvalidMoveError::NoMatchingMove));
+    assert_eq!(ValidMove::from_uci(&game, "g1"), Err(InvalidMoveError::InvalidNotation));
+
+    let game = Game::new_from_fen("8/4P3/8/8/8/8/8/k1K5 w - - 0 1").unwrap();
+    let promotion = ValidMove::from_uci(&game, "e7e8n").unwrap();
+
+    assert_eq!(promotion.promotion, Some(Piece::Knight));
+    assert_eq!(promotion.uci(), "e7e8n");
+}
+
+#[test]
+fn test_packed_moves() {
+    let game = Game::new(Game::standard_position());
+    let valid_move = ValidMove::from_uci(&game, "g1f3").unwrap();
+
+    assert_eq!(valid_move.to_u16(), 21 | 6 << 6);
+    assert_eq!(ValidMove::from_u16(&game, valid_move.to_u16()), Ok(valid_move));
+    assert_eq!(ValidMove::from_u16(&game, 0), Err(InvalidMoveError::NoMatchingMove));
+
+    let game = Game::new_from_fen("8/4P3/8/8/8/8/8/k1K5 w - - 0 1").unwrap();
+    let promotion = ValidMove::from_uci(&game, "e7e8n").unwrap();
+
+    assert_eq!(promotion.to_u16(), 60 | 52 << 6 | 1 << 12);
+    assert_eq!(ValidMove::from_u16(&game, promotion.to_u16()), Ok(promotion));
+    assert_eq!(ValidMove::from_u16(&game, 60 | 52 << 6 | 7 << 12), Err(InvalidMoveError::InvalidNotation));
+}
+
+#[test]
+fn test_predecessor_moves() {
+    let after_e4 = Game::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    let predecessors = after_e4.predecessor_moves();
+
+    // The en passant square only allows e2-e4
+    assert_eq!(predecessors.len(), 1);
+    assert_eq!(predecessors[0].0.uci(), "e2e4");
+    assert_eq!(predecessors[0].1.hash(), Game::new(Game::standard_position()).hash());
+
+    let game = Game::new_from_fen("8/8/8/8/8/8/8/k1K4R b - - 3 10").unwrap();
+    let predecessors = game.predecessor_moves();
+    let unmoves: HashSet<String> = predecessors.iter().map( |(valid_move, _)| valid_move.uci() ).collect();
+
+    assert!(unmoves.contains("h2h1"));
+    assert!(unmoves.contains("g1h1"));
+    assert!(unmoves.contains("d1c1"));
+
+    // The white king can't have come from next to the black king
+    assert!(!unmoves.contains("b2c1"));
+
+    for (valid_move, previous) in predecessors.iter() {
+        assert_eq!(previous.make_valid_move(valid_move).hash(), game.hash());
+
+        // The clock rules out captures
+        assert_eq!(valid_move.takes, None);
+    }
+}
+
+#[test]
+fn test_predecessor_castling() {
+    let castled = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1").unwrap();
+    let predecessors = castled.predecessor_moves();
+
+    let (castling, previous) = predecessors.iter().find( |(valid_move, _)| valid_move.is_castle() ).unwrap();
+
+    assert_eq!(castling.uci(), "e1g1");
+    assert_eq!(previous.position_to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1");
+    assert_eq!(previous.make_valid_move(castling).position_to_fen(), castled.position_to_fen());
+
+    // With castling rights left, the king and the rooks can't have moved
+    let game = Game::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 1 1").unwrap();
+    assert_eq!(game.predecessor_moves().len(), 0);
+}
+
+#[test]
+fn test_predecessor_captures_and_promotions() {
+    let game = Game::new_from_fen("4N3/8/8/8/8/8/8/k1K5 b - - 0 1").unwrap();
+    let predecessors = game.predecessor_moves();
+
+    let promotion = predecessors.iter()
+        .find( |(valid_move, _)| valid_move.uci() == "e7e8n" )
+        .unwrap();
+    assert_eq!(promotion.1.position().next_to_move, Color::White);
+
+    let capturing_promotion = predecessors.iter()
+        .find( |(valid_move, previous)| valid_move.uci() == "d7e8n" && previous.square_occupied(Square::new(7, 4).unwrap()).is_some() );
+    assert!(capturing_promotion.is_some());
+
+    let game = Game::new_from_fen("8/8/3P4/8/8/8/8/k1K5 b - - 0 1").unwrap();
+    let unmoves: Vec<String> = game.predecessor_moves().iter()
+        .filter( |(valid_move, _)| valid_move.takes_en_passant )
+        .map( |(valid_move, _)| valid_move.uci() )
+        .collect();
+
+    assert_eq!(unmoves.len(), 2);
+}
+
+#[test]
+fn test_insufficient_material() {
+    let drawn = [
+        "8/8/4k3/8/8/4K3/8/8 w - - 0 1",
+        "8/8/4k3/8/8/4K3/8/6N1 w - - 0 1",
+        "8/8/4k3/8/8/4K3/8/2B5 w - - 0 1",
+        "8/8/4k3/4b3/8/4K3/8/2B1B3 w - - 0 1"
+    ];
+
+    for fen in drawn.iter() {
+        let game = Game::new_from_fen(fen).unwrap();
+
+        assert!(game.insufficient_material(), "{}", fen);
+        assert_eq!(game.draw_reason(), Some(DrawReason::InsufficientMaterial));
+    }
+
+    let not_drawn = [
+        "8/8/4k3/8/8/4K3/8/1NN5 w - - 0 1",
+        "8/8/4k3/2n5/8/4K3/8/2B5 w - - 0 1",
+        "8/8/4k3/3b4/8/4K3/8/2B5 w - - 0 1",
+        "8/8/4k3/8/8/4K3/4P3/8 w - - 0 1"
+    ];
+
+    for fen in not_drawn.iter() {
+        let game = Game::new_from_fen(fen).unwrap();
+
+        assert!(!game.insufficient_material(), "{}", fen);
+        assert_eq!(game.draw_reason(), None);
+    }
+}
+
+#[test]
+fn test_winning_on_time() {
+    let can_win = |fen: &str, color: Color| Game::new_from_fen(fen).unwrap().can_win_on_time(color);
+
+    // A bare king, or a knight against a bare king, can't mate
+    assert!(!can_win("8/8/4k3/8/8/4K3/8/8 w - - 0 1", Color::White));
+    assert!(!can_win("8/8/4k3/8/8/4K3/8/6N1 b - - 0 1", Color::White));
+    assert!(!can_win("8/8/4k3/8/8/4K3/8/6N1 b - - 0 1", Color::Black));
+
+    // The opponent's own pieces can block the king in
+    assert!(can_win("8/8/4k3/4p3/8/4K3/8/6N1 b - - 0 1", Color::White));
+    assert!(!can_win("8/8/4k3/4q3/8/4K3/8/6N1 b - - 0 1", Color::White));
+    assert!(can_win("8/8/4k3/4q3/8/4K3/8/6N1 b - - 0 1", Color::Black));
+
+    // Bishops on squares of one color only mate with the help of a pawn, a knight or a bishop on the other color
+    assert!(!can_win("8/8/4k3/8/4r3/4K3/8/2B1B3 b - - 0 1", Color::White));
+    assert!(can_win("8/8/4k3/3b4/8/4K3/8/2B5 b - - 0 1", Color::White));
+    assert!(!can_win("8/8/4k3/4b3/8/4K3/8/2B5 b - - 0 1", Color::White));
+    assert!(can_win("8/8/4k3/8/8/4K3/8/2BB4 b - - 0 1", Color::White));
+    assert!(can_win("8/8/4k3/8/8/4K3/8/1NB5 b - - 0 1", Color::White));
+    assert!(can_win("8/8/4k3/8/8/4K3/4P3/8 b - - 0 1", Color::White));
+}
+
+#[test]
+fn test_repetitions() {
+    let mut game = Game::new(Game::standard_position());
+    assert_eq!(game.repetition_count(), 1);
+
+    for _ in 0..2 {
+        for notation in &["Nf3", "Nf6", "Ng1", "Ng8"] {
+            game = game.make_move(notation).unwrap();
+        }
+    }
+
+    assert_eq!(game.repetition_count(), 3);
+    assert!(game.is_threefold_repetition());
+    assert!(!game.make_move("e4").unwrap().is_threefold_repetition());
+
+    // The claim can be made with the move which would repeat the position
+    let before = game.history().last().unwrap().0.clone();
+    let ng8 = ValidMove::from_notation(&before, "Ng8").unwrap();
+    let nh5 = ValidMove::from_notation(&before, "Nh5").unwrap();
+
+    assert!(!before.is_threefold_repetition());
+    assert!(before.would_be_threefold(&ng8));
+    assert!(!before.would_be_threefold(&nh5));
+    assert!(!game.would_be_threefold(&ng8));
+
+    // An en passant square nobody can capture on doesn't make the position different
+    let after_push = Game::new(Game::standard_position()).make_move("e4").unwrap();
+    let without_square = Position { en_passant_square: None, ..after_push.position().clone() };
+
+    assert!(after_push.position().en_passant_square.is_some());
+    assert_ne!(after_push.position(), &without_square);
+    assert!(after_push.position().same_for_repetition(&without_square));
+
+    let mut repeated = after_push.clone();
+
+    for _ in 0..2 {
+        for notation in &["Nf6", "Nf3", "Ng8", "Ng1"] {
+            repeated = repeated.make_move(notation).unwrap();
+        }
+    }
+
+    assert_eq!(repeated.repetition_count(), 3);
+
+    let capturable = Game::new_from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 2").unwrap();
+    let not_capturable = Game::new_from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - - 7 9").unwrap();
+    assert!(!capturable.position().same_for_repetition(not_capturable.position()));
+
+    // The pawn which could take is pinned
+    let pinned = Game::new_from_fen("4k3/8/8/r2PpK2/8/8/8/8 w - e6 0 2").unwrap();
+    let pinned_without_square = Game::new_from_fen("4k3/8/8/r2PpK2/8/8/8/8 w - - 0 2").unwrap();
+    assert!(pinned.position().same_for_repetition(pinned_without_square.position()));
+
+    let castling = Game::new_from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    let no_castling = Game::new_from_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+    assert!(!castling.position().same_for_repetition(no_castling.position()));
+}
+
+#[test]
+fn test_dead_position() {
+    // Neither king can get past the locked pawns
+    let game = Game::new_from_fen("8/8/4k3/1p1p1p1p/1P1P1P1P/4K3/8/8 w - - 0 1").unwrap();
+
+    assert!(!game.insufficient_material());
+    assert!(game.is_dead_position());
+    assert_eq!(game.draw_reason(), Some(DrawReason::DeadPosition));
+
+    // Without the h-pawns the kings can get through on the h-file
+    let game = Game::new_from_fen("8/8/4k3/1p1p1p2/1P1P1P2/4K3/8/8 w - - 0 1").unwrap();
+    assert!(!game.is_dead_position());
+
+    assert_eq!(Game::new(Game::standard_position()).draw_reason(), None);
+
+    let stalemate = Game::new_from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(stalemate.draw_reason(), Some(DrawReason::Stalemate));
+
+    let fifty_moves = Game::new_from_fen("k7/8/1K6/8/8/8/8/7R w - - 100 80").unwrap();
+    assert_eq!(fifty_moves.draw_reason(), Some(DrawReason::FiftyMoveRule));
+}
+
+#[test]
+fn test_counting_legal_moves() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+
+        // Pinned pieces and an en passant capture which would expose the king
+        "8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1",
+        "4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1",
+        "7k/4P3/8/8/8/8/8/K7 w - - 0 1"
+    ];
+
+    for fen in fens.iter() {
+        let game = Game::new_from_fen(fen).unwrap();
+
+        assert_eq!(game.count_legal_moves(), game.valid_moves().len(), "{}", fen);
+        assert!(game.has_legal_move());
+    }
+
+    let stalemate = Game::new_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+
+    assert_eq!(stalemate.count_legal_moves(), 0);
+    assert!(!stalemate.has_legal_move());
+}
+
+#[test]
+fn test_game_lifecycle() {
+    let game = Game::new(Game::standard_position()).make_move("e4").unwrap();
+
+    assert_eq!(game.status(), GameStatus::Ongoing);
+    assert_eq!(game.result(), GameResult::Unknown);
+    assert!(game.accept_draw().is_err());
+
+    let offered = game.offer_draw(Color::White).unwrap();
+    assert_eq!(offered.pending_draw_offer(), Some(Color::White));
+
+    // Black moving instead of accepting declines the offer
+    assert_eq!(offered.make_move("e5").unwrap().pending_draw_offer(), None);
+
+    let agreed = offered.accept_draw().unwrap();
+    assert_eq!(agreed.status(), GameStatus::DrawAgreed);
+    assert_eq!(agreed.result(), GameResult::Draw);
+    assert!(agreed.resign(Color::Black).is_err());
+
+    let resigned = game.resign(Color::Black).unwrap();
+    assert_eq!(resigned.result(), GameResult::WhiteWins);
+    assert_eq!(resigned.termination(), "normal");
+
+    let adjudicated = resigned.adjudicate(GameResult::BlackWins, "time forfeit");
+    assert_eq!(adjudicated.result(), GameResult::BlackWins);
+    assert_eq!(adjudicated.termination(), "time forfeit");
+
+    assert_eq!(resigned.make_move("e5").err(), Some(InvalidMoveError::GameOver));
+    assert_eq!(agreed.make_move("e5").err(), Some(InvalidMoveError::GameOver));
+    assert_eq!(adjudicated.try_make_valid_move(&game.valid_moves()[0]).err(), Some(InvalidMoveError::GameOver));
+
+    let mate = Game::replay_pgn("1. f3 e5 2. g4 Qh4 0-1").last().unwrap().unwrap().1;
+    assert_eq!(mate.status(), GameStatus::Checkmate);
+    assert_eq!(mate.result(), GameResult::BlackWins);
+}
+
+#[test]
+fn test_game_listeners() {
+    use std::sync::{Arc, Mutex};
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut observed = ObservedGame::new(Game::new(Game::standard_position()));
+
+    let moves_log = log.clone();
+    observed.on_move(move |(_, san)| moves_log.lock().unwrap().push(san.clone()) );
+
+    let status_log = log.clone();
+    observed.on_status_change(move |status| status_log.lock().unwrap().push(format!("{:?}", status)) );
+
+    let flag_log = log.clone();
+    observed.on_clock_flag(move |color| flag_log.lock().unwrap().push(format!("flag {:?}", color)) );
+
+    observed.make_move("e4").unwrap();
+    assert!(observed.make_move("e4").is_err());
+    observed.update( |game| game.make_move("e5")?.make_move("Nf3") ).unwrap();
+    observed.set(observed.game().adjudicate(GameResult::WhiteWins, "time forfeit"));
+
+    assert_eq!(*log.lock().unwrap(), vec![
+        "e4", "e5", "Nf3", "flag Black",
+        "Adjudicated { result: WhiteWins, reason: \"time forfeit\" }"
+    ]);
+
+    let start = Game::new(Game::standard_position());
+    let game = start.make_move("d4").unwrap().resign(Color::Black).unwrap();
+
+    assert_eq!(game.changes_since(&start).len(), 2);
+    assert_eq!(game.changes_since(&game), vec![]);
+}
+
+#[test]
+fn test_odds_games() {
+    assert_eq!(Game::odds_position(Odds::Knight, Color::White).to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1");
+    assert_eq!(Game::odds_position(Odds::Rook, Color::White).to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1");
+    assert_eq!(Game::odds_position(Odds::Queen, Color::Black).to_fen(), "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(Game::odds_position(Odds::Pawn, Color::White).to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(Game::odds_position(Odds::PawnAndMove, Color::Black).to_fen(), "rnbqkbnr/ppppp1pp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(Game::odds_position(Odds::PawnAndMove, Color::White).next_to_move, Color::Black);
+
+    let game = Game::new_with_odds(Odds::Knight, Color::White).make_move("e4").unwrap();
+    let pgn = game.to_pgn_with_tags(&Odds::Knight.tags(Color::White));
+
+    assert!(pgn.contains("[Handicap \"knight odds by White\"]\n"));
+    assert!(pgn.contains("[FEN \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1\"]\n"));
+}
@@ -0,0 +1,37 @@
+use super::*;
+use screen::BoardLayout;
+
+fn square(notation: &str) -> Square {
+    Square::from_notation(notation).unwrap()
+}
+
+#[test]
+fn test_screen_coordinates() {
+    let white = BoardLayout::new(420.0, 10.0, Color::White);
+
+    assert_eq!(white.square_size(), 50.0);
+    assert_eq!(white.square_at(15.0, 15.0), Some(square("a8")));
+    assert_eq!(white.square_at(409.0, 409.0), Some(square("h1")));
+    assert_eq!(white.square_at(5.0, 200.0), None);
+    assert_eq!(white.square_at(200.0, 411.0), None);
+
+    assert_eq!(white.square_origin(square("e4")), (210.0, 210.0));
+    assert_eq!(white.square_center(square("a1")), (35.0, 385.0));
+
+    let black = BoardLayout { x: 100.0, y: 50.0, ..BoardLayout::new(400.0, 0.0, Color::Black) };
+
+    assert_eq!(black.square_at(101.0, 51.0), Some(square("h1")));
+    assert_eq!(black.square_origin(square("a8")), (450.0, 400.0));
+
+    // Every square maps back to itself from any point inside it
+    for layout in [white, black].iter() {
+        for rank in 0..8 {
+            for file in 0..8 {
+                let square = Square { rank, file };
+                let (x, y) = layout.square_center(square);
+
+                assert_eq!(layout.square_at(x, y), Some(square));
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use super::*;
+use search::{Analysis, Searcher, SearchLimits, Stipulation, solve_problem};
+use std::sync::atomic::Ordering;
+
+#[test]
+fn test_search_finds_mate_in_one() {
+    let game = Game::new_from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let result = search::search(&game, &SearchLimits::depth(3));
+
+    let best_move = result.best_move.clone().expect("No best move");
+    assert_eq!(game.san(&best_move), "Ra8#");
+    assert_eq!(result.mate_in(), Some(1));
+    assert_eq!(result.principal_variation, vec![best_move]);
+}
+
+#[test]
+fn test_search_wins_material() {
+    let game = Game::new_from_fen("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1").unwrap();
+    let result = search::search(&game, &SearchLimits::depth(2));
+
+    assert_eq!(game.san(&result.best_move.unwrap()), "Rxd5");
+    assert!(result.score > 300);
+}
+
+#[test]
+fn test_quiescence_score() {
+    let mut searcher = Searcher::new();
+
+    // Taking the queen is seen without searching the quiet moves
+    let game = Game::new_from_fen("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1").unwrap();
+    assert!(searcher.quiescence_score(&game) > 300);
+    assert!(searcher.quiescence_score(&game.make_move("Rxd5").unwrap()) < -300);
+
+    let mated = Game::new_from_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(searcher.quiescence_score(&mated), -search::MATE_SCORE);
+}
+
+#[test]
+fn test_search_stats() {
+    let game = Game::new(Game::standard_position());
+    let result = search::search(&game, &SearchLimits::depth(2));
+
+    assert_eq!(result.stats.depth, 2);
+    assert!(result.stats.nodes > 20);
+    assert!(result.stats.qnodes > 0);
+    assert!(result.stats.tt_hits > 0);
+    assert!(result.stats.cutoffs > 0);
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn test_multi_threaded_search() {
+    let mut searcher = Searcher::new();
+
+    assert_eq!(searcher.set_option("Threads", "4"), Ok(()));
+    assert_eq!(searcher.threads(), 4);
+    assert!(searcher.set_option("Threads", "0").is_err());
+    assert!(searcher.set_option("Threads", "many").is_err());
+    assert!(searcher.set_option("Ponder", "true").is_err());
+
+    let game = Game::new_from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let result = searcher.search(&game, &SearchLimits::depth(3));
+
+    assert_eq!(game.san(result.best_move.as_ref().unwrap()), "Ra8#");
+    assert_eq!(result.mate_in(), Some(1));
+
+    let game = Game::new_from_fen("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1").unwrap();
+    let single = Searcher::new().search(&game, &SearchLimits::depth(3));
+    let threaded = Searcher::new().with_threads(3).search(&game, &SearchLimits::depth(3));
+
+    assert_eq!(threaded.best_move, single.best_move);
+    assert_eq!(threaded.stats.depth, 3);
+    assert!(threaded.stats.nodes > single.stats.nodes / 2);
+}
+
+#[test]
+fn test_search_can_be_aborted() {
+    let game = Game::new(Game::standard_position());
+    let mut searcher = Searcher::new();
+
+    searcher.abort_flag().store(true, Ordering::Relaxed);
+
+    let result = searcher.search(&game, &SearchLimits::default());
+
+    assert!(result.best_move.is_some());
+    assert!(result.stats.depth <= 1);
+}
+
+#[test]
+fn test_search_respects_node_limit() {
+    let game = Game::new(Game::standard_position());
+    let result = search::search(&game, &SearchLimits { nodes: Some(300), ..Default::default() });
+
+    assert!(result.best_move.is_some());
+    assert!(result.stats.nodes + result.stats.qnodes <= 300 + 256);
+}
+
+#[test]
+fn test_infinite_analysis() {
+    let game = Game::new(Game::standard_position());
+    let analysis = Analysis::start(Searcher::new(), &game);
+
+    while analysis.current().is_none_or( |result| result.stats.depth < 2 ) {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    let (result, mut searcher) = analysis.stop();
+
+    assert!(result.best_move.is_some());
+    assert!(result.stats.depth >= 2);
+
+    // The abort flag is reset, so the searcher can be reused
+    let result = searcher.search(&game, &SearchLimits::depth(3));
+    assert_eq!(result.stats.depth, 3);
+}
+
+#[test]
+fn test_dropping_analysis_stops_the_search() {
+    let game = Game::new(Game::standard_position());
+    let analysis = Analysis::start(Searcher::new(), &game);
+
+    while analysis.current().is_none() {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    // Joins the background thread, so this returns instead of searching forever
+    drop(analysis);
+}
+
+#[test]
+fn test_ponder() {
+    let game = Game::new(Game::standard_position());
+    let predicted_move = game.valid_moves().into_iter()
+        .find( |valid_move| game.san(valid_move) == "e4" )
+        .unwrap();
+
+    let analysis = Analysis::ponder(Searcher::new(), &game, &predicted_move);
+
+    assert!(analysis.is_ponder_hit(&predicted_move));
+    assert_eq!(analysis.game().hash(), game.make_valid_move(&predicted_move).hash());
+
+    let (result, _) = analysis.stop();
+    let reply = result.best_move.unwrap();
+
+    assert_eq!(reply.color, Color::Black);
+}
+
+fn play_line(game: &Game, line: &[ValidMove]) -> Game {
+    line.iter().fold(game.clone(), |game, valid_move| game.make_valid_move(valid_move) )
+}
+
+#[test]
+fn test_direct_mate_problem() {
+    let game = Game::new_from_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1").unwrap();
+
+    assert_eq!(solve_problem(&game, Stipulation::Mate, 1), None);
+
+    let solution = solve_problem(&game, Stipulation::Mate, 2).unwrap();
+
+    assert_eq!(solution.len(), 3);
+    assert!(play_line(&game, &solution).in_mate());
+}
+
+#[test]
+fn test_helpmate_problem() {
+    // Black cooperates with Kg8 so that white can play Ra8#
+    let game = Game::new_from_fen("7k/8/6K1/8/8/8/8/R7 b - - 0 1").unwrap();
+    let solution = solve_problem(&game, Stipulation::Helpmate, 1).unwrap();
+
+    assert_eq!(solution.iter().map( |valid_move| valid_move.uci() ).collect::<Vec<String>>(), vec!["h8g8", "a1a8"]);
+    assert_eq!(solve_problem(&game, Stipulation::Mate, 1), None);
+}
+
+#[test]
+fn test_selfmate_problem() {
+    // After Qg5 black's only move is e2#
+    let game = Game::new_from_fen("8/1P6/N7/3Q4/4p3/4pk2/5p2/5K2 w - - 0 1").unwrap();
+    let solution = solve_problem(&game, Stipulation::Selfmate, 1).unwrap();
+
+    assert_eq!(solution.len(), 2);
+    assert_eq!(solution[1].uci(), "e3e2");
+
+    let mated = play_line(&game, &solution);
+    assert!(mated.in_mate());
+    assert_eq!(mated.position().next_to_move, Color::White);
+
+    let game = Game::new(Game::standard_position());
+    assert_eq!(solve_problem(&game, Stipulation::Selfmate, 1), None);
+}
+
+#[test]
+fn test_hint() {
+    let game = Game::new_from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    let hint = game.hint(&SearchLimits::depth(2)).unwrap();
+
+    assert_eq!(hint.piece(), Piece::Rook);
+    assert_eq!(hint.mate_in, Some(1));
+
+    assert_eq!(hint.text(HintLevel::Piece), "Move your rook");
+    assert_eq!(hint.text(HintLevel::FromSquare), "Move the rook on a1");
+    assert_eq!(hint.text(HintLevel::Move), "Ra8#");
+
+    assert_eq!(HintLevel::Piece.next(), Some(HintLevel::FromSquare));
+    assert_eq!(HintLevel::Move.next(), None);
+
+    let mated = game.make_valid_move(&hint.valid_move);
+    assert_eq!(mated.hint(&SearchLimits::depth(2)), None);
+}
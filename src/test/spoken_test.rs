@@ -0,0 +1,64 @@
+use super::*;
+use locale::Locale;
+
+#[test]
+fn test_speaking_moves() {
+    let game = Game::replay_pgn("1. e4 d5 1-0").last().unwrap().unwrap().1;
+    let takes = game.make_move("exd5").unwrap();
+
+    assert_eq!(game.moves()[0].spoken(&Game::new(Game::standard_position()), Locale::English), "pawn from e2 to e4");
+    assert_eq!(takes.moves()[2].spoken(&game, Locale::English), "pawn from e4 takes pawn on d5");
+
+    let game = read_game("
+        | | | | |k| | | |
+        | | |P| | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | |K| | | |
+    ", Color::White);
+
+    let promotion = ValidMove::from_notation(&game, "c8=Q").unwrap();
+
+    assert_eq!(promotion.spoken(&game, Locale::English), "pawn from c7 to c8, promotes to queen, check");
+}
+
+#[test]
+fn test_reading_spoken_moves() {
+    let game = Game::replay_pgn("1. e4 d5 1-0").last().unwrap().unwrap().1;
+    let spoken = |text: &str| ValidMove::from_spoken(&game, text, Locale::English).map( |valid_move| game.san(&valid_move) );
+
+    assert_eq!(spoken("pawn takes d5"), Ok(String::from("exd5")));
+    assert_eq!(spoken("Pawn takes pawn."), Ok(String::from("exd5")));
+    assert_eq!(spoken("knight f three"), Ok(String::from("Nf3")));
+    assert_eq!(spoken("knight from g1 to f3"), Ok(String::from("Nf3")));
+    assert_eq!(spoken("queen moves to the h 5"), Ok(String::from("Qh5")));
+    assert_eq!(spoken("e5"), Ok(String::from("e5")));
+
+    assert_eq!(spoken("bishop takes d5"), Err(InvalidMoveError::NoMatchingMove));
+    assert_eq!(spoken("knight please"), Err(InvalidMoveError::InvalidNotation));
+
+    // The words on both sides of a spoken move read back to the same move
+    for valid_move in game.valid_moves() {
+        assert_eq!(ValidMove::from_spoken(&game, &valid_move.spoken(&game, Locale::English), Locale::English), Ok(valid_move));
+    }
+
+    let game = read_game("
+        | | | | |k| | | |
+        | | |P| | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | | | | | |
+        | | | | |K| | | |
+    ", Color::White);
+
+    assert!(matches!(ValidMove::from_spoken(&game, "pawn c8", Locale::English), Err(InvalidMoveError::AmbiguousMove(_))));
+    assert_eq!(
+        ValidMove::from_spoken(&game, "c eight knight", Locale::English).map( |valid_move| valid_move.promotion ),
+        Ok(Some(Piece::Knight))
+    );
+}
@@ -0,0 +1,90 @@
+use super::*;
+use suites::*;
+
+#[test]
+fn test_bundled_openings() {
+    let openings = openings();
+
+    assert_eq!(openings.len(), 44);
+    assert!(openings.iter().all( |game| game.position().next_to_move == Color::White && game.position().full_move_counter == 6 ));
+}
+
+#[test]
+fn test_bundled_epd_suites() {
+    let bratko_kopec = bratko_kopec();
+    let win_at_chess = win_at_chess();
+
+    assert_eq!(bratko_kopec.len(), 24);
+    assert_eq!(win_at_chess.len(), 10);
+
+    for position in bratko_kopec.iter().chain(win_at_chess.iter()) {
+        assert!(!position.best_moves().unwrap().is_empty(), "No best move in {:?}", position.id());
+    }
+
+    assert_eq!(bratko_kopec[0].id(), Some("BK.01"));
+    assert_eq!(bratko_kopec[4].best_moves().unwrap().iter().map( |valid_move| valid_move.notation() ).collect::<Vec<_>>(), vec!["Nd5", "a4"]);
+
+    let position = &parse_epd("7k/8/8/8/8/8/8/R5K1 w - - am Ra2; id \"test\"").unwrap()[0];
+    assert!(position.is_solved_by(&ValidMove::from_notation(&position.game, "Ra8").unwrap()));
+    assert!(!position.is_solved_by(&ValidMove::from_notation(&position.game, "Ra2").unwrap()));
+
+    assert!(parse_epd("8/8 w - - bm e4;").is_err());
+}
+
+#[test]
+fn test_perft_positions() {
+    let positions = perft_positions();
+
+    assert_eq!(positions.len(), 6);
+    assert_eq!(positions[1].name, "kiwipete");
+
+    for (name, depth) in [("startpos", 3), ("kiwipete", 2), ("position 3", 3), ("position 4", 2), ("position 5", 2), ("position 6", 2)].iter() {
+        let position = positions.iter().find( |position| position.name == *name ).unwrap();
+
+        for depth in 1..=*depth {
+            assert_eq!(perft(&position.game, depth), position.nodes[depth as usize - 1], "Depth {} of {}", depth, name);
+        }
+    }
+}
+
+#[test]
+fn test_running_a_suite() {
+    let suite = parse_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"back rank\"").unwrap();
+    let result = run_suite(&mut search::Searcher::new(), &suite, &search::SearchLimits::depth(2));
+
+    assert_eq!(result, SuiteResult { solved: 1, failed: Vec::new() });
+}
+
+#[test]
+fn test_perft_corpus() {
+    let corpus = perft_corpus();
+
+    assert!(corpus.iter().any( |case| case.name == "illegal en passant 1" && case.depth == 6 && case.has_tag("en-passant-pin") ));
+    assert!(corpus.iter().any( |case| case.has_tag("castling-through-check") ));
+    assert_eq!(perft_positions()[2].tags, vec!["en-passant", "en-passant-pin", "check"]);
+
+    let report = run_perft(&corpus, 10_000);
+
+    assert_eq!(report.failures, Vec::new());
+    assert!(report.passed >= 30);
+    assert_eq!(report.passed + report.skipped, corpus.len());
+
+    // Every castling position runs at a shallow depth, including castling through an attacked square
+    let castling: Vec<PerftCase> = corpus.iter().filter( |case| case.has_tag("castling") ).cloned().collect();
+    let castling_report = run_perft(&castling, 10_000);
+
+    assert_eq!(castling_report.failures, Vec::new());
+    assert_eq!(castling_report.passed, 17);
+    assert!(castling.iter().any( |case| case.has_tag("castling-through-check") && case.depth == 2 && case.nodes <= 10_000 ));
+
+    let mut wrong = corpus.iter().find( |case| case.name == "self stalemate" && case.depth == 3 ).unwrap().clone();
+    wrong.nodes = 12;
+
+    assert_eq!(run_perft(&[wrong], 10_000).failures, vec![PerftFailure {
+        name: String::from("self stalemate"),
+        depth: 3,
+        expected: 12,
+        found: 13,
+        tags: vec![String::from("stalemate")]
+    }]);
+}
@@ -0,0 +1,134 @@
+use super::*;
+use tablebase::{EndgameTablebase, Probe, Tablebase, Table, Wdl};
+
+// Generating anything bigger than KvK takes minutes without optimizations, so the three-piece tables are
+// only generated by `cargo test --release`
+#[test]
+fn test_bare_kings_tablebase() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KvK").unwrap();
+
+    assert_eq!(tablebase.signatures(), vec!["KvK"]);
+
+    let game = Game::new_from_fen("8/8/3k4/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let probe = tablebase.probe(&game).unwrap();
+
+    assert_eq!(probe.wdl, Wdl::Draw);
+    assert_eq!(probe.dtm, None);
+    assert!(tablebase.best_move(&game).is_some());
+
+    // Adjacent kings can't happen
+    let game = Game::new_from_fen("8/8/8/8/8/8/3k4/4K3 w - - 0 1").unwrap();
+    assert_eq!(tablebase.probe(&game), None);
+
+    // Castling rights and other material aren't covered
+    let game = Game::new_from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert_eq!(tablebase.probe(&game), None);
+    assert_eq!(tablebase.probe(&Game::new(Game::standard_position())), None);
+}
+
+#[test]
+#[cfg_attr(debug_assertions, ignore)]
+fn test_queen_tablebase() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KQvK").unwrap();
+
+    assert_eq!(tablebase.signatures(), vec!["KQvK", "KvK"]);
+
+    let probe = |fen: &str| tablebase.probe(&Game::new_from_fen(fen).unwrap()).unwrap();
+
+    assert_eq!(probe("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"), Probe { wdl: Wdl::Win, dtm: Some(1) });
+    assert_eq!(probe("k5Q1/8/1K6/8/8/8/8/8 b - - 0 1"), Probe { wdl: Wdl::Loss, dtm: Some(0) });
+
+    // 1... Ka7 2. Qh8 Ka6 3. Qa8#
+    assert_eq!(probe("k7/8/2K5/8/8/8/8/7Q b - - 0 1"), Probe { wdl: Wdl::Loss, dtm: Some(4) });
+    assert_eq!(probe("8/k7/2K5/8/8/8/8/7Q w - - 0 1").mate_in(), Some(2));
+
+    // Black's queen, probed through the mirrored table
+    assert_eq!(probe("k7/8/8/8/8/8/8/K6q w - - 0 1").wdl, Wdl::Loss);
+    assert_eq!(probe("k7/8/8/8/8/8/1q6/K7 w - - 0 1").wdl, Wdl::Draw);
+
+    let game = Game::new_from_fen("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1").unwrap();
+    assert_eq!(game.san(&tablebase.best_move(&game).unwrap()), "Qg8#");
+}
+
+#[test]
+fn test_tablebase_signatures() {
+    let mut tablebase = Tablebase::new();
+
+    assert!(tablebase.generate("KQRPvK").is_err());
+    assert!(tablebase.generate("KQvQ").is_err());
+    assert!(tablebase.generate("KXvK").is_err());
+    assert!(tablebase.generate("KQK").is_err());
+}
+
+#[test]
+fn test_tablebase_file() {
+    let mut tablebase = Tablebase::new();
+    tablebase.generate("KvK").unwrap();
+
+    let table = tablebase.table("KvK").unwrap();
+
+    let mut bytes = Vec::new();
+    table.write_to(&mut bytes, true).unwrap();
+
+    let loaded = Table::read_from(&mut bytes.as_slice()).unwrap();
+
+    assert_eq!(loaded.signature(), "KvK");
+    assert_eq!(loaded.len(), table.len());
+    assert!(loaded.has_dtm());
+
+    let mut without_dtm = Vec::new();
+    table.write_to(&mut without_dtm, false).unwrap();
+
+    // Four results to a byte
+    assert_eq!(without_dtm.len(), bytes.len() - table.len() * 2);
+    assert!(without_dtm.len() < table.len() / 4 + 32);
+
+    let mut loaded_tablebase = Tablebase::new();
+    loaded_tablebase.add_table(Table::read_from(&mut without_dtm.as_slice()).unwrap());
+
+    let game = Game::new_from_fen("8/8/3k4/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(loaded_tablebase.probe(&game), tablebase.probe(&game));
+
+    assert!(Table::read_from(&mut &b"PGNIDX01"[..]).is_err());
+}
+
+// Stands in for a reader of another format, which only has a few KQvK positions. The results are the ones
+// of the generated KQvK table.
+struct KqkTablebase;
+
+impl EndgameTablebase for KqkTablebase {
+    fn probe(&self, game: &Game) -> Option<Probe> {
+        let fen = game.position_to_fen();
+        let fields: Vec<&str> = fen.split(' ').take(2).collect();
+
+        match fields.join(" ").as_str() {
+            "k7/8/2K5/8/8/8/8/7Q b"  => Some(Probe { wdl: Wdl::Loss, dtm: Some(4) }),
+            "8/k7/2K5/8/8/8/8/7Q w"  => Some(Probe { wdl: Wdl::Win, dtm: Some(3) }),
+            "1k6/8/2K5/8/8/8/8/7Q w" => Some(Probe { wdl: Wdl::Win, dtm: Some(3) }),
+            _                        => None
+        }
+    }
+}
+
+#[test]
+fn test_other_tablebase_formats() {
+    let game = Game::new_from_fen("k7/8/2K5/8/8/8/8/7Q b - - 0 1").unwrap();
+
+    assert_eq!(KqkTablebase.probe(&game).unwrap().mate_in(), Some(-2));
+    assert_eq!(Probe { wdl: Wdl::Win, dtm: Some(3) }.mate_in(), Some(2));
+    assert_eq!(Probe { wdl: Wdl::Draw, dtm: None }.mate_in(), None);
+
+    // Both king moves get mated in two
+    let best_move = KqkTablebase.best_move(&game).unwrap();
+    assert_eq!(KqkTablebase.probe(&game.make_valid_move(&best_move)).unwrap().mate_in(), Some(2));
+
+    // Positions missing from the other format leave best_move without an answer
+    let game = Game::new_from_fen("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1").unwrap();
+    assert_eq!(KqkTablebase.best_move(&game), None);
+
+    let tables: &dyn EndgameTablebase = &Tablebase::new();
+    assert_eq!(tables.probe(&game), None);
+}
+
@@ -0,0 +1,144 @@
+use super::*;
+use training::{BlindfoldEvent, BlindfoldGame, GuessOptions, MAX_POINTS, Puzzle, PuzzleVerdict};
+use training::{AnkiOptions, repertoire_flashcards, puzzle_flashcards, to_anki_text};
+use repertoire::Repertoire;
+use tree::GameTree;
+
+const GAME: &str = "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 1-0";
+
+#[test]
+fn test_guess_the_move() {
+    let pgn_game = Game::parse_pgn(GAME).unwrap().remove(0);
+    let exercise = training::guess_the_move(&pgn_game, 2).unwrap();
+
+    assert_eq!(exercise.game.san(&exercise.game_move), "Nf3");
+    assert_eq!(exercise.game.position().next_to_move, Color::White);
+
+    let options = GuessOptions { depth: 2 };
+
+    let score = exercise.score_guess("g1f3", &options).unwrap();
+    assert!(score.is_game_move);
+    assert_eq!(score.points, MAX_POINTS);
+
+    let score = exercise.score_guess("Nc3", &options).unwrap();
+    assert!(!score.is_game_move);
+    assert_eq!(score.guess, "Nc3");
+    assert_eq!(score.game_move, "Nf3");
+    assert!(score.points > 0);
+
+    // Gives away the bishop
+    let score = exercise.score_guess("Ba6", &options).unwrap();
+    assert_eq!(score.points, 0);
+    assert!(score.loss > 200);
+
+    assert!(exercise.score_guess("Ke3", &options).is_err());
+    assert!(training::guess_the_move(&pgn_game, 20).is_err());
+}
+
+#[test]
+fn test_puzzle_session() {
+    let puzzle = Puzzle::new("3r2k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1", &["Re8+", "d8e8", "Rxe8#"]).unwrap();
+    let mut session = puzzle.start();
+
+    let wrong = session.submit("Kf1").unwrap();
+    assert_eq!(wrong.verdict, PuzzleVerdict::Incorrect);
+    assert!(!wrong.solved);
+    assert_eq!(session.mistakes(), 1);
+    assert_eq!(session.game().position_to_fen(), puzzle.game.position_to_fen());
+
+    let first = session.submit("e2e8").unwrap();
+    assert_eq!(first.verdict, PuzzleVerdict::Correct);
+    assert_eq!(first.reply.map( |reply| reply.uci() ), Some(String::from("d8e8")));
+    assert!(!first.solved);
+
+    let last = session.submit("Rxe8#").unwrap();
+    assert_eq!(last.verdict, PuzzleVerdict::Correct);
+    assert_eq!(last.reply, None);
+    assert!(last.solved && session.is_solved());
+
+    // Another mate solves the puzzle too
+    let mut session = Puzzle::new("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &["Re8#"]).unwrap().start();
+    assert_eq!(session.submit("Ra8#").unwrap().verdict, PuzzleVerdict::AlternateAccepted);
+    assert!(session.is_solved());
+
+    assert!(session.submit("Nf3").is_err());
+    assert!(Puzzle::new("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &["Re7"]).is_ok());
+    assert!(Puzzle::new("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &["Rf8"]).is_err());
+    assert!(Puzzle::new("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &[]).is_err());
+}
+
+#[test]
+fn test_blindfold_game() {
+    let mut game = BlindfoldGame::new(Game::new(Game::standard_position()));
+
+    assert!(game.submit("e4").legal);
+    assert!(game.submit("e7e5").legal);
+    assert!(!game.submit("Nf6").legal);
+    assert!(!game.submit("Ke3").legal);
+    assert_eq!(game.illegal_attempts(), 2);
+    assert_eq!(game.next_to_move(), Color::White);
+
+    for notation in ["Qh5", "Nc6", "Bc4", "Nf6"].iter() {
+        assert_eq!(game.submit(notation).events, Vec::new());
+    }
+
+    let reply = game.submit("Qxf7#");
+    assert!(reply.legal);
+    assert_eq!(reply.events, vec![BlindfoldEvent::Checkmate]);
+    assert!(game.is_over());
+    assert!(!game.submit("Kxf7").legal);
+
+    assert_eq!(game.reveal().ply_count(), 7);
+
+    let mut check = BlindfoldGame::new(Game::new_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap());
+    assert_eq!(check.submit("Ra8").events, vec![BlindfoldEvent::Check]);
+}
+
+#[test]
+fn test_anki_export() {
+    let tree = GameTree::from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 * 1. e4 c5 2. Nf3 *").unwrap();
+    let cards = repertoire_flashcards(&Repertoire::new(tree, Color::White, 0));
+
+    let mut questions: Vec<(String, String)> = cards.iter().map( |card| (card.prompt.clone(), card.answer.clone()) ).collect();
+    questions.sort();
+
+    assert_eq!(questions, vec![
+        (String::from("1. e4 c5"), String::from("Nf3")),
+        (String::from("1. e4 e5"), String::from("Nf3")),
+        (String::from("1. e4 e5 2. Nf3 Nc6"), String::from("Bb5")),
+        (String::from("Starting position"), String::from("e4"))
+    ]);
+
+    let puzzle = Puzzle::new("3r2k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1", &["Re8+", "d8e8", "Rxe8#"]).unwrap();
+    let puzzle_cards = puzzle_flashcards(&[puzzle]);
+
+    assert_eq!(puzzle_cards[0].prompt, "White to move");
+    assert_eq!(puzzle_cards[0].answer, "1. Re8+ Rxe8 2. Rxe8#");
+    assert_eq!(puzzle_cards[0].id, format!("puzzle-{:016x}-e2e8-d8e8-e1e8", puzzle_cards[0].game.position().stable_key()));
+
+    let options = AnkiOptions { deck: Some(String::from("Chess::Puzzles")), ..AnkiOptions::default() };
+    let text = to_anki_text(&puzzle_cards, &options);
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(&lines[..6], &[
+        "#separator:tab",
+        "#html:true",
+        "#guid column:1",
+        "#tags column:6",
+        "#deck:Chess::Puzzles",
+        "#columns:Id\tFEN\tBoard\tPrompt\tAnswer\tTags"
+    ]);
+
+    let fields: Vec<&str> = lines[6].split('\t').collect();
+
+    assert_eq!(lines.len(), 7);
+    assert_eq!(fields.len(), 6);
+    assert_eq!(fields[0], puzzle_cards[0].id);
+    assert_eq!(fields[1], "3r2k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1");
+    assert!(fields[2].starts_with("\"<svg ") && fields[2].ends_with("</svg>\""));
+    assert!(!fields[2][1..fields[2].len() - 1].replace("\"\"", "").contains('"'));
+    assert_eq!(fields[5], "puzzle");
+
+    let without_boards = to_anki_text(&puzzle_cards, &AnkiOptions { boards: false, ..AnkiOptions::default() });
+    assert!(!without_boards.contains("<svg"));
+}
@@ -0,0 +1,35 @@
+use super::*;
+use eval::EvalParams;
+use tuning::{TuningSample, TuningOptions};
+
+fn samples() -> Vec<TuningSample> {
+    vec![
+        // An extra knight wins
+        TuningSample::new("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", "1-0").unwrap(),
+        TuningSample::new("1n2k3/8/8/8/8/8/8/4K3 w - - 0 1", "0-1").unwrap(),
+        TuningSample::new("4k3/8/8/8/8/8/8/4KN2 b - - 0 1", "1-0").unwrap(),
+
+        // Equal material is drawn
+        TuningSample::new("1n2k3/8/8/8/8/8/8/1N2K3 w - - 0 1", "1/2-1/2").unwrap()
+    ]
+}
+
+#[test]
+fn test_tuning_sample_results() {
+    assert_eq!(TuningSample::new("4k3/8/8/8/8/8/8/4K3 w - - 0 1", "1/2-1/2").unwrap().result, 0.5);
+    assert!(TuningSample::new("4k3/8/8/8/8/8/8/4K3 w - - 0 1", "*").is_err());
+    assert!(TuningSample::new("not a fen", "1-0").is_err());
+}
+
+#[test]
+fn test_tuning_reduces_error() {
+    let samples = samples();
+    let mut params = EvalParams { knight_value: 0, ..Default::default() };
+
+    let options = TuningOptions { step: 20, max_iterations: 20, ..Default::default() };
+    let report = tuning::tune(&samples, &mut params, &options);
+
+    assert!(report.final_error < report.initial_error);
+    assert_eq!(report.final_error, tuning::error(&samples, &params, options.k));
+    assert!(params.knight_value > 0);
+}
@@ -0,0 +1,90 @@
+use super::*;
+
+#[test]
+fn test_incremental_hash_matches_full_hash() {
+    let steps = Game::replay_pgn("
+        1. e4 Nc6 2. e5 f5 3. exf6 Nxf6 4. Nf3 Ne4 5. d3 Nxf2 6. Kxf2 e5 1-0
+    ");
+
+    for step in steps {
+        let (_, game) = step.expect("Could not replay PGN");
+        let position = Position::from_fen(&game.position_to_fen()).expect("Cannot parse FEN");
+
+        assert_eq!(game.hash(), position.zobrist_hash());
+    }
+}
+
+#[test]
+fn test_transpositions_have_the_same_hash() {
+    let first = Game::replay_pgn("1. Nf3 Nf6 2. Nc3 Nc6 1-0").last().unwrap().unwrap().1;
+    let second = Game::replay_pgn("1. Nc3 Nc6 2. Nf3 Nf6 1-0").last().unwrap().unwrap().1;
+    let third = Game::replay_pgn("1. Nc3 Nf6 2. Nf3 Nc6 1-0").last().unwrap().unwrap().1;
+
+    assert_eq!(first.hash(), second.hash());
+    assert_eq!(first.hash(), third.hash());
+    assert_ne!(first.hash(), Game::new(Game::standard_position()).hash());
+}
+
+#[test]
+fn test_hash_depends_on_side_to_move_and_en_passant() {
+    let white = Position::from_fen("8/8/8/8/4P3/8/8/4K2k w - - 0 1").unwrap();
+    let black = Position::from_fen("8/8/8/8/4P3/8/8/4K2k b - - 0 1").unwrap();
+    let black_en_passant = Position::from_fen("8/8/8/8/4P3/8/8/4K2k b - e3 0 1").unwrap();
+
+    assert_ne!(white.zobrist_hash(), black.zobrist_hash());
+    assert_ne!(black.zobrist_hash(), black_en_passant.zobrist_hash());
+}
+
+#[test]
+fn test_position_set() {
+    let mut set = PositionSet::new();
+    let start = Game::standard_position();
+    let after_e4 = Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+
+    assert!(set.is_empty());
+    assert!(set.insert(&start));
+    assert!(!set.insert(&start));
+    assert!(set.insert(&after_e4));
+
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.count(&start), 2);
+    assert_eq!(set.count(&after_e4), 1);
+    assert!(set.contains(&after_e4));
+    assert!(!set.contains(&Position::from_fen("8/8/8/8/8/8/8/4K2k w - - 0 1").unwrap()));
+}
+
+#[test]
+fn test_stable_key() {
+    // These values are stored by users of the crate and must never change
+    let start = Game::standard_position();
+    let after_e4 = Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    let after_e4_without_ep = Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+
+    assert_eq!(start.stable_key(), 0x7FEE_443D_3F9E_A34E);
+    assert_eq!(after_e4.stable_key(), 0x5A92_12AE_D928_0BB1);
+
+    // Nothing can take on e3
+    assert_eq!(after_e4.stable_key(), after_e4_without_ep.stable_key());
+
+    let can_take = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    let cannot_take = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+
+    assert_ne!(can_take.stable_key(), cannot_take.stable_key());
+    assert_ne!(start.stable_key(), start.zobrist_hash());
+}
+
+#[test]
+fn test_stable_key_golden_values() {
+    // One position for each kind of key, so that a change to any part of the key table shows up here
+    let golden = [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1",         0x02D7_AF02_6DE1_CABF),
+        ("4k3/8/8/8/8/8/8/4K3 b - - 0 1",         0xCCDD_174F_89F6_C553),
+        ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",  0x314F_4497_EC31_3936),
+        ("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1",    0x4422_8541_D1DA_9F94),
+        ("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1",     0x7D30_3962_34B9_B230)
+    ];
+
+    for (fen, key) in golden.iter() {
+        assert_eq!(Position::from_fen(fen).unwrap().stable_key(), *key, "{}", fen);
+    }
+}
@@ -0,0 +1,175 @@
+use super::super::models::*;
+use super::super::game::{Game, ValidMove};
+use super::super::repertoire::Repertoire;
+use super::super::render::{render_svg, BoardView, SvgOptions};
+use super::Puzzle;
+
+// A question about a position, for spaced repetition outside of this crate
+#[derive(Debug, Clone)]
+pub struct Flashcard {
+    // Stays the same for the same position and answer, also between versions of the crate (it is built from
+    // `Position::stable_key`), so that importing an updated export again updates the notes instead of adding duplicates
+    pub id: String,
+
+    // The position of the question, shown from the side to move
+    pub game: Game,
+
+    pub prompt: String,
+    pub answer: String,
+    pub tags: Vec<String>
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnkiOptions {
+    // The deck the notes go into, Anki asks for one when missing
+    pub deck: Option<String>,
+
+    // Without boards, the cards only have the FEN
+    pub boards: bool,
+    pub board_size: f64
+}
+
+impl Default for AnkiOptions {
+    fn default() -> Self {
+        AnkiOptions { deck: None, boards: true, board_size: 300.0 }
+    }
+}
+
+// One card per position in which the repertoire has a move prepared. The prompt is the line leading to it
+// and the answer every prepared move.
+pub fn repertoire_flashcards(repertoire: &Repertoire) -> Vec<Flashcard> {
+    let tree = repertoire.tree();
+    let color = match repertoire.color() {
+        Color::White => "white",
+        Color::Black => "black"
+    };
+
+    tree.node_ids()
+        .filter( |node| repertoire.card(*node).is_some() )
+        .map( |node| {
+            let drill = repertoire.drill(node);
+
+            let answers: Vec<String> = tree.children(node).iter()
+                .filter_map( |child| tree.node(*child).valid_move.as_ref() )
+                .map( |valid_move| drill.game.san(valid_move) )
+                .collect();
+
+            let prompt = if drill.line.is_empty() {
+                String::from("Starting position")
+            } else {
+                numbered_line(tree.root_game(), &drill.line)
+            };
+
+            Flashcard {
+                id: format!("repertoire-{}-{:016x}", color, drill.game.position().stable_key()),
+                game: drill.game,
+                prompt,
+                answer: answers.join(", "),
+                tags: vec![String::from("repertoire"), format!("repertoire::{}", color)]
+            }
+        })
+        .collect()
+}
+
+// The prompt says who is to move, the answer is the whole solution
+pub fn puzzle_flashcards(puzzles: &[Puzzle]) -> Vec<Flashcard> {
+    puzzles.iter()
+        .map( |puzzle| {
+            let solution: Vec<String> = puzzle.solution.iter().map( |valid_move| valid_move.uci() ).collect();
+
+            let prompt = match puzzle.game.position().next_to_move {
+                Color::White => "White to move",
+                Color::Black => "Black to move"
+            };
+
+            Flashcard {
+                id: format!("puzzle-{:016x}-{}", puzzle.game.position().stable_key(), solution.join("-")),
+                game: puzzle.game.clone(),
+                prompt: String::from(prompt),
+                answer: numbered_line(&puzzle.game, &puzzle.solution),
+                tags: vec![String::from("puzzle")]
+            }
+        })
+        .collect()
+}
+
+// Anki's text import format: tab separated fields with a header saying which columns are the note's id and
+// tags. The fields are Id, FEN, Board (an inline SVG, empty without boards), Prompt, Answer and Tags. They map
+// onto any note type with enough fields when importing.
+pub fn to_anki_text(cards: &[Flashcard], options: &AnkiOptions) -> String {
+    let mut text = String::from("#separator:tab\n#html:true\n#guid column:1\n#tags column:6\n");
+
+    if let Some(deck) = &options.deck {
+        text.push_str(&format!("#deck:{}\n", deck.replace(['\n', '\t'], " ")));
+    }
+
+    text.push_str("#columns:Id\tFEN\tBoard\tPrompt\tAnswer\tTags\n");
+
+    for card in cards {
+        let position = card.game.position();
+
+        let board = if options.boards {
+            let view = BoardView {
+                orientation: position.next_to_move,
+                highlights: card.game.last_move().map( |valid_move| vec![valid_move.from, valid_move.to] ).unwrap_or_default(),
+                ..BoardView::default()
+            };
+
+            render_svg(&position.board, &view, &SvgOptions { size: options.board_size, ..SvgOptions::default() })
+        } else {
+            String::new()
+        };
+
+        let tags: Vec<String> = card.tags.iter().map( |tag| tag.replace(char::is_whitespace, "_") ).collect();
+
+        let fields = [
+            card.id.clone(),
+            position.to_fen(),
+            board,
+            escape_html(&card.prompt),
+            escape_html(&card.answer),
+            tags.join(" ")
+        ];
+
+        let fields: Vec<String> = fields.iter().map( |field| quote_field(field) ).collect();
+
+        text.push_str(&fields.join("\t"));
+        text.push('\n');
+    }
+
+    text
+}
+
+// e.g. "1. e4 e5 2. Nf3", or "3... Nc6" when black moves first
+fn numbered_line(game: &Game, moves: &[ValidMove]) -> String {
+    let mut game = game.clone();
+    let mut parts = Vec::new();
+
+    for (index, valid_move) in moves.iter().enumerate() {
+        let position = game.position();
+
+        match position.next_to_move {
+            Color::White                => parts.push(format!("{}.", position.full_move_counter)),
+            Color::Black if index == 0  => parts.push(format!("{}...", position.full_move_counter)),
+            Color::Black                => ()
+        }
+
+        parts.push(game.san(valid_move));
+        game = game.make_valid_move(valid_move);
+    }
+
+    parts.join(" ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// Fields with separators, line breaks or quotes are quoted, with the quotes inside doubled
+fn quote_field(field: &str) -> String {
+    if field.contains(['\t', '\n', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
@@ -0,0 +1,93 @@
+use super::super::models::*;
+use super::super::game::{Game, ValidMove, DrawReason};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BlindfoldEvent {
+    Check,
+    Checkmate,
+    Draw(DrawReason),
+
+    // Can be claimed as a draw, the game goes on otherwise
+    ThreefoldRepetition
+}
+
+// Whether the move was played and what it led to. Illegal moves come without a reason, as the reason would
+// tell what is on the board, e.g. that there is no piece on the square.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlindfoldReply {
+    pub legal: bool,
+    pub events: Vec<BlindfoldEvent>
+}
+
+// A game which only answers whether moves are legal, so that a blindfold training page can't show the
+// position by accident. The game itself is only given out by reveal(), e.g. once it is over.
+pub struct BlindfoldGame {
+    game: Game,
+    illegal_attempts: usize
+}
+
+impl BlindfoldGame {
+    pub fn new(game: Game) -> Self {
+        BlindfoldGame { game, illegal_attempts: 0 }
+    }
+
+    // Moves of both sides go through here, in SAN or UCI notation
+    pub fn submit(&mut self, notation: &str) -> BlindfoldReply {
+        let valid_move = if self.game.is_over() {
+            None
+        } else {
+            ValidMove::from_notation(&self.game, notation)
+                .or_else( |_| ValidMove::from_uci(&self.game, notation) )
+                .ok()
+        };
+
+        let valid_move = match valid_move {
+            Some(valid_move) => valid_move,
+            None => {
+                self.illegal_attempts += 1;
+                return BlindfoldReply { legal: false, events: Vec::new() };
+            }
+        };
+
+        self.game = self.game.make_valid_move(&valid_move);
+
+        BlindfoldReply { legal: true, events: self.events() }
+    }
+
+    pub fn next_to_move(&self) -> Color {
+        self.game.position().next_to_move
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game.is_over()
+    }
+
+    pub fn illegal_attempts(&self) -> usize {
+        self.illegal_attempts
+    }
+
+    pub fn reveal(self) -> Game {
+        self.game
+    }
+
+    fn events(&self) -> Vec<BlindfoldEvent> {
+        let mut events = Vec::new();
+
+        if self.game.in_mate() {
+            events.push(BlindfoldEvent::Checkmate);
+            return events;
+        }
+
+        if self.game.in_check(self.next_to_move()) {
+            events.push(BlindfoldEvent::Check);
+        }
+
+        if let Some(reason) = self.game.draw_reason() {
+            events.push(BlindfoldEvent::Draw(reason));
+        } else if self.game.is_threefold_repetition() {
+            events.push(BlindfoldEvent::ThreefoldRepetition);
+        }
+
+        events
+    }
+}
@@ -0,0 +1,143 @@
+use super::game::{Game, ValidMove, InvalidMoveError, ReplayError};
+use super::parser::ParsedGame;
+use super::search::{Searcher, SearchLimits};
+use super::endgame::EndgameType;
+
+mod anki;
+mod blindfold;
+mod puzzle;
+mod random;
+
+pub use anki::{Flashcard, AnkiOptions, repertoire_flashcards, puzzle_flashcards, to_anki_text};
+pub use blindfold::{BlindfoldGame, BlindfoldReply, BlindfoldEvent};
+pub use puzzle::{Puzzle, PuzzleSession, PuzzleVerdict, PuzzleAnswer};
+pub use random::random_position;
+
+// Guesses within this many centipawns of the game move count as equally good
+const EQUAL_MOVE_MARGIN: i32 = 15;
+
+// Guesses losing this much compared to the game move score nothing
+const ZERO_POINTS_LOSS: i32 = 200;
+
+// Mate scores are capped so that one missed mate does not dominate the scoring
+const MAX_SCORE: i32 = 2000;
+
+pub const MAX_POINTS: u32 = 10;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GuessOptions {
+    pub depth: u32
+}
+
+impl Default for GuessOptions {
+    fn default() -> Self {
+        GuessOptions { depth: 3 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GuessTheMove {
+    pub ply: usize,
+
+    // The position right before the move to guess
+    pub game: Game,
+    pub game_move: ValidMove
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GuessScore {
+    pub points: u32,
+    pub is_game_move: bool,
+
+    // Centipawns the guess is worse than the game move, negative if it is better
+    pub loss: i32,
+
+    pub guess: String,
+    pub game_move: String
+}
+
+// `ply` is the index of the half-move to guess
+pub fn guess_the_move(pgn_game: &ParsedGame, ply: usize) -> Result<GuessTheMove, ReplayError> {
+    let mut replay = Game::replay(pgn_game.clone());
+    let mut game = replay.game().clone();
+
+    for (current_ply, step) in (&mut replay).enumerate() {
+        let (valid_move, next) = step?;
+
+        if current_ply == ply {
+            return Ok(GuessTheMove { ply, game, game_move: valid_move });
+        }
+
+        game = next;
+    }
+
+    Err(ReplayError::InvalidPGN(format!("The game has no half-move #{}", ply + 1)))
+}
+
+impl GuessTheMove {
+    // The guess can be in SAN or UCI notation
+    pub fn score_guess(&self, guess: &str, options: &GuessOptions) -> Result<GuessScore, InvalidMoveError> {
+        let guessed_move = ValidMove::from_notation(&self.game, guess)
+            .or_else( |_| ValidMove::from_uci(&self.game, guess) )?;
+
+        let game_move = self.game.san(&self.game_move);
+
+        if guessed_move == self.game_move {
+            return Ok(GuessScore {
+                points: MAX_POINTS,
+                is_game_move: true,
+                loss: 0,
+                guess: game_move.clone(),
+                game_move
+            });
+        }
+
+        let mut searcher = Searcher::new();
+        let limits = SearchLimits::depth(options.depth);
+
+        let game_move_score = score_after(&mut searcher, &self.game, &self.game_move, &limits);
+        let guess_score = score_after(&mut searcher, &self.game, &guessed_move, &limits);
+        let loss = game_move_score - guess_score;
+
+        let points = if loss <= EQUAL_MOVE_MARGIN {
+            MAX_POINTS
+        } else if loss >= ZERO_POINTS_LOSS {
+            0
+        } else {
+            let remaining = (ZERO_POINTS_LOSS - loss) as f64 / (ZERO_POINTS_LOSS - EQUAL_MOVE_MARGIN) as f64;
+
+            (remaining * MAX_POINTS as f64).round() as u32
+        };
+
+        Ok(GuessScore {
+            points,
+            is_game_move: false,
+            loss,
+            guess: self.game.san(&guessed_move),
+            game_move
+        })
+    }
+}
+
+// Score for the side making the move
+fn score_after(searcher: &mut Searcher, game: &Game, valid_move: &ValidMove, limits: &SearchLimits) -> i32 {
+    let after = game.make_valid_move(valid_move);
+
+    if !after.has_legal_move() {
+        return if after.in_check(after.position().next_to_move) { MAX_SCORE } else { 0 };
+    }
+
+    -searcher.search(&after, limits).score.clamp(-MAX_SCORE, MAX_SCORE)
+}
+
+// The first position of each game which reached the endgame, e.g. to practice the rook endgames from your own games
+pub fn endgame_positions(games: &[Game], endgame: EndgameType) -> Vec<Game> {
+    games.iter()
+        .filter_map( |game| {
+            let mut positions: Vec<Game> = game.history().into_iter().map( |(before, _)| before ).collect();
+            positions.push(game.clone());
+
+            positions.into_iter().find( |position| position.position().endgame_type() == Some(endgame) )
+        })
+        .collect()
+}
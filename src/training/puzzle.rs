@@ -0,0 +1,122 @@
+use super::super::game::{Game, ValidMove, InvalidMoveError};
+
+// A position and the moves solving it. The solver moves first, the moves in between are the opponent's replies.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub game: Game,
+    pub solution: Vec<ValidMove>
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PuzzleVerdict {
+    Correct,
+    Incorrect,
+
+    // Not the move of the solution, but a mate, which solves the puzzle just as well
+    AlternateAccepted
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PuzzleAnswer {
+    pub verdict: PuzzleVerdict,
+
+    // The opponent's move to play after a correct one, already made in the session
+    pub reply: Option<ValidMove>,
+
+    pub solved: bool
+}
+
+// Someone solving a puzzle, one move at a time
+#[derive(Debug, Clone)]
+pub struct PuzzleSession {
+    puzzle: Puzzle,
+    game: Game,
+    ply: usize,
+    mistakes: usize
+}
+
+impl Puzzle {
+    // The solution moves can be in SAN or UCI notation
+    pub fn new(fen: &str, solution: &[&str]) -> Result<Self, String> {
+        let game = Game::new_from_fen(fen).map_err( |error| format!("{:?}", error) )?;
+
+        let mut position = game.clone();
+        let mut moves = Vec::new();
+
+        for notation in solution {
+            let valid_move = read_move(&position, notation)
+                .map_err( |_| format!("Invalid solution move '{}'", notation) )?;
+
+            position = position.make_valid_move(&valid_move);
+            moves.push(valid_move);
+        }
+
+        if moves.is_empty() {
+            return Err(String::from("The solution has no moves"));
+        }
+
+        Ok(Puzzle { game, solution: moves })
+    }
+
+    pub fn start(&self) -> PuzzleSession {
+        PuzzleSession { puzzle: self.clone(), game: self.game.clone(), ply: 0, mistakes: 0 }
+    }
+}
+
+impl PuzzleSession {
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    // The position the solver is looking at
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.ply >= self.puzzle.solution.len()
+    }
+
+    // The move can be in SAN or UCI notation. An incorrect move isn't made, so that the solver can try again.
+    pub fn submit(&mut self, notation: &str) -> Result<PuzzleAnswer, InvalidMoveError> {
+        let valid_move = read_move(&self.game, notation)?;
+
+        let expected = match self.puzzle.solution.get(self.ply) {
+            Some(expected) => expected.clone(),
+            None => return Ok(PuzzleAnswer { verdict: PuzzleVerdict::Incorrect, reply: None, solved: true })
+        };
+
+        if valid_move != expected {
+            if self.game.gives_mate(&valid_move) {
+                self.game = self.game.make_valid_move(&valid_move);
+                self.ply = self.puzzle.solution.len();
+
+                return Ok(PuzzleAnswer { verdict: PuzzleVerdict::AlternateAccepted, reply: None, solved: true });
+            }
+
+            self.mistakes += 1;
+
+            return Ok(PuzzleAnswer { verdict: PuzzleVerdict::Incorrect, reply: None, solved: false });
+        }
+
+        self.game = self.game.make_valid_move(&valid_move);
+        self.ply += 1;
+
+        let reply = self.puzzle.solution.get(self.ply).cloned();
+
+        if let Some(reply) = &reply {
+            self.game = self.game.make_valid_move(reply);
+            self.ply += 1;
+        }
+
+        Ok(PuzzleAnswer { verdict: PuzzleVerdict::Correct, reply, solved: self.is_solved() })
+    }
+}
+
+fn read_move(game: &Game, notation: &str) -> Result<ValidMove, InvalidMoveError> {
+    ValidMove::from_notation(game, notation).or_else( |_| ValidMove::from_uci(game, notation) )
+}
@@ -0,0 +1,78 @@
+use super::super::models::*;
+use super::super::game::Game;
+use super::super::rng::Rng;
+
+// Positions which keep failing, e.g. with too many bishops for one square color, are given up on after this
+// many attempts
+const MAX_ATTEMPTS: usize = 1000;
+
+// A legal position with the pieces on random squares, e.g. for board-vision drills or for endgames from a
+// piece list. Both kings are added and don't stand next to each other, pawns stay off the first and last
+// ranks and the side which just moved isn't in check. There are no castling rights or en passant square.
+// None if there are more than 62 pieces, or if no valid placement was found.
+pub fn random_position(pieces: &[OccupiedSquare], next_to_move: Color, rng: &mut dyn Rng) -> Option<Game> {
+    let pieces: Vec<&OccupiedSquare> = pieces.iter().filter( |piece| piece.piece != Piece::King ).collect();
+
+    if pieces.len() > 62 {
+        return None;
+    }
+
+    for _ in 0..MAX_ATTEMPTS {
+        if let Some(game) = try_placement(&pieces, next_to_move, rng) {
+            return Some(game);
+        }
+    }
+
+    None
+}
+
+fn try_placement(pieces: &[&OccupiedSquare], next_to_move: Color, rng: &mut dyn Rng) -> Option<Game> {
+    let mut squares: Vec<Option<OccupiedSquare>> = vec![None; 64];
+
+    let white_king = rng.below(64) as usize;
+    let black_king = rng.below(64) as usize;
+
+    let (white_square, black_square) = (Board::square(white_king), Board::square(black_king));
+
+    if (white_square.rank - black_square.rank).abs() <= 1 && (white_square.file - black_square.file).abs() <= 1 {
+        return None;
+    }
+
+    squares[white_king] = Some(OccupiedSquare { piece: Piece::King, color: Color::White });
+    squares[black_king] = Some(OccupiedSquare { piece: Piece::King, color: Color::Black });
+
+    for piece in pieces {
+        let free: Vec<usize> = (0..64)
+            .filter( |index| squares[*index].is_none() )
+            .filter( |index| piece.piece != Piece::Pawn || !matches!(Board::square(*index).rank, 0 | 7) )
+            .collect();
+
+        if free.is_empty() {
+            return None;
+        }
+
+        let index = free[rng.below(free.len() as u64) as usize];
+        squares[index] = Some((*piece).clone());
+    }
+
+    let game = Game::new(Position {
+        board: Board { squares },
+        next_to_move,
+
+        white_can_castle_king_side: false,
+        white_can_castle_queen_side: false,
+        black_can_castle_king_side: false,
+        black_can_castle_queen_side: false,
+
+        en_passant_square: None,
+
+        half_move_clock: 0,
+        full_move_counter: 1
+    });
+
+    if game.in_check(next_to_move.opposite()) {
+        return None;
+    }
+
+    Some(game)
+}
@@ -0,0 +1,187 @@
+use super::game::{Game, ValidMove};
+use super::render::{self, Overlay};
+
+pub type NodeId = usize;
+
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub game: Game,
+
+    // The move leading to this node, None for the root
+    pub valid_move: Option<ValidMove>,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+
+    pub comment: Option<String>,
+
+    // Arrows and marked squares for the position after the move
+    pub overlay: Overlay
+}
+
+// A tree of variations starting from a single position. Nodes are stored in a flat list and referenced by index.
+#[derive(Debug, Clone)]
+pub struct GameTree {
+    nodes: Vec<TreeNode>
+}
+
+impl GameTree {
+    pub fn new(root: Game) -> Self {
+        GameTree {
+            nodes: vec![TreeNode {
+                game: root,
+                valid_move: None,
+                parent: None,
+                children: Vec::new(),
+                comment: None,
+                overlay: Overlay::new()
+            }]
+        }
+    }
+
+    // All games inside the PGN become lines of the same tree, so they have to start from the same position
+    pub fn from_pgn(pgn: &str) -> Result<Self, String> {
+        let mut tree: Option<GameTree> = None;
+
+        for pgn_game in Game::parse_pgn(pgn)? {
+            let comments: Vec<Option<String>> = pgn_game.half_move_comments().into_iter()
+                .map( |comment| comment.map(String::from) )
+                .collect();
+
+            let mut replay = Game::replay(pgn_game);
+
+            let tree = tree.get_or_insert_with( || GameTree::new(replay.game().clone()) );
+
+            if tree.root_game().hash() != replay.game().hash() {
+                return Err(String::from("All games in the tree must start from the same position"));
+            }
+
+            let mut node = tree.root();
+
+            for (ply, step) in (&mut replay).enumerate() {
+                let (valid_move, _) = step?;
+
+                node = tree.add_move(node, &valid_move);
+
+                // The drawing commands of the comment become the overlay of the node
+                if let Some(Some(comment)) = comments.get(ply) {
+                    let overlay = Overlay::from_comment(comment);
+                    let text = render::strip_commands(comment);
+
+                    if !text.is_empty() {
+                        tree.set_comment(node, &text);
+                    }
+
+                    if !overlay.is_empty() {
+                        tree.set_overlay(node, overlay);
+                    }
+                }
+            }
+        }
+
+        tree.ok_or_else( || String::from("No games inside PGN") )
+    }
+
+    pub fn root(&self) -> NodeId {
+        0
+    }
+
+    pub fn root_game(&self) -> &Game {
+        &self.nodes[0].game
+    }
+
+    pub fn node(&self, id: NodeId) -> &TreeNode {
+        &self.nodes[id]
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id].children
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node_ids(&self) -> impl Iterator<Item=NodeId> {
+        0..self.nodes.len()
+    }
+
+    // Returns the existing child if the move was already added
+    pub fn add_move(&mut self, parent: NodeId, valid_move: &ValidMove) -> NodeId {
+        if let Some(child) = self.child_with_move(parent, valid_move) {
+            return child;
+        }
+
+        let game = self.nodes[parent].game.make_valid_move(valid_move);
+        let id = self.nodes.len();
+
+        self.nodes.push(TreeNode {
+            game,
+            valid_move: Some(valid_move.clone()),
+            parent: Some(parent),
+            children: Vec::new(),
+            comment: None,
+            overlay: Overlay::new()
+        });
+
+        self.nodes[parent].children.push(id);
+
+        id
+    }
+
+    pub fn add_line(&mut self, parent: NodeId, moves: &[ValidMove]) -> NodeId {
+        moves.iter().fold(parent, |node, valid_move| self.add_move(node, valid_move) )
+    }
+
+    pub fn child_with_move(&self, parent: NodeId, valid_move: &ValidMove) -> Option<NodeId> {
+        self.nodes[parent].children.iter()
+            .find( |child| self.nodes[**child].valid_move.as_ref() == Some(valid_move) )
+            .copied()
+    }
+
+    pub fn set_comment(&mut self, id: NodeId, comment: &str) {
+        self.nodes[id].comment = Some(String::from(comment));
+    }
+
+    pub fn set_overlay(&mut self, id: NodeId, overlay: Overlay) {
+        self.nodes[id].overlay = overlay;
+    }
+
+    // The comment of the node with the drawing commands of its overlay, as written into a PGN
+    pub fn pgn_comment(&self, id: NodeId) -> Option<String> {
+        let node = &self.nodes[id];
+        let commands = node.overlay.to_commands();
+
+        match (&node.comment, commands.is_empty()) {
+            (Some(comment), false) => Some(format!("{} {}", commands, comment)),
+            (Some(comment), true)  => Some(comment.clone()),
+            (None, false)          => Some(commands),
+            (None, true)           => None
+        }
+    }
+
+    // Moves from the root to the node
+    pub fn path(&self, id: NodeId) -> Vec<ValidMove> {
+        let mut moves = Vec::new();
+        let mut current = id;
+
+        while let Some(parent) = self.nodes[current].parent {
+            if let Some(valid_move) = &self.nodes[current].valid_move {
+                moves.push(valid_move.clone());
+            }
+
+            current = parent;
+        }
+
+        moves.reverse();
+        moves
+    }
+
+    // Nodes reaching the position with the given hash, through any move order
+    pub fn find(&self, hash: u64) -> Vec<NodeId> {
+        self.node_ids().filter( |id| self.nodes[*id].game.hash() == hash ).collect()
+    }
+}
@@ -0,0 +1,126 @@
+use super::models::*;
+use super::eval::{self, EvalParams};
+
+// Texel tuning: find the evaluation weights which best predict the game results of a set of positions.
+// The evaluation is mapped to an expected score with a logistic curve and the mean squared error is minimized
+// with a local search that nudges one weight at a time.
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TuningSample {
+    pub position: Position,
+
+    // From white's point of view: 1 for a win, 0.5 for a draw and 0 for a loss
+    pub result: f64
+}
+
+impl TuningSample {
+    pub fn new(fen: &str, result: &str) -> Result<Self, String> {
+        let position = Position::from_fen(fen).map_err( |error| error.message )?;
+        let result = match result {
+            "1-0"     => 1.0,
+            "0-1"     => 0.0,
+            "1/2-1/2" => 0.5,
+            _ => return Err(format!("Invalid game result '{}'", result))
+        };
+
+        Ok(TuningSample { position, result })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TuningOptions {
+    // Steepness of the logistic curve, 1.0 maps +400 centipawns to a 10:1 winning chance
+    pub k: f64,
+    pub step: i32,
+    pub max_iterations: usize
+}
+
+impl Default for TuningOptions {
+    fn default() -> Self {
+        TuningOptions {
+            k: 1.0,
+            step: 5,
+            max_iterations: 100
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TuningReport {
+    pub iterations: usize,
+    pub initial_error: f64,
+    pub final_error: f64
+}
+
+// The EvalParams fields which get tuned
+type Parameter = fn(&mut EvalParams) -> &mut i32;
+
+const PARAMETERS: &[Parameter] = &[
+    |params| &mut params.pawn_value,
+    |params| &mut params.knight_value,
+    |params| &mut params.bishop_value,
+    |params| &mut params.rook_value,
+    |params| &mut params.queen_value,
+    |params| &mut params.doubled_pawn_penalty,
+    |params| &mut params.isolated_pawn_penalty,
+    |params| &mut params.passed_pawn_bonus,
+    |params| &mut params.king_shield_bonus,
+    |params| &mut params.king_open_file_penalty,
+    |params| &mut params.king_zone_attacker_penalty
+];
+
+// Updates `params` in-place with the tuned weights
+pub fn tune(samples: &[TuningSample], params: &mut EvalParams, options: &TuningOptions) -> TuningReport {
+    let initial_error = error(samples, params, options.k);
+    let mut best_error = initial_error;
+    let mut iterations = 0;
+
+    while iterations < options.max_iterations {
+        iterations += 1;
+
+        let mut improved = false;
+
+        for parameter in PARAMETERS {
+            for delta in [options.step, -options.step].iter() {
+                let mut candidate = params.clone();
+                *parameter(&mut candidate) += delta;
+
+                let candidate_error = error(samples, &candidate, options.k);
+
+                if candidate_error < best_error {
+                    *params = candidate;
+                    best_error = candidate_error;
+                    improved = true;
+
+                    break;
+                }
+            }
+        }
+
+        if !improved {
+            break;
+        }
+    }
+
+    TuningReport { iterations, initial_error, final_error: best_error }
+}
+
+pub fn error(samples: &[TuningSample], params: &EvalParams, k: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let total: f64 = samples.iter()
+        .map( |sample| {
+            let expected = expected_score(eval::evaluate_with(&sample.position, params), k);
+
+            (sample.result - expected).powi(2)
+        })
+        .sum();
+
+    total / samples.len() as f64
+}
+
+pub fn expected_score(score: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
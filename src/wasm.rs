@@ -1,9 +1,9 @@
 #![allow(non_snake_case)]
 
 use wasm_bindgen::prelude::*;
-use js_sys::Array;
+use js_sys::{Array, Function};
 
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
 use super::*;
 
@@ -17,6 +17,95 @@ pub struct JsValidMove {
     valid_move: ValidMove
 }
 
+#[wasm_bindgen]
+pub struct JsBoardLayout {
+    layout: screen::BoardLayout
+}
+
+// A clock in the same wasm state as the game. Times are in milliseconds, `now` is e.g. performance.now().
+// The page calls tick(now) from a timer so that the flag callback fires when the time runs out.
+#[wasm_bindgen]
+pub struct JsClock {
+    clock: clock::Clock,
+
+    on_flag: Option<Function>,
+    flag_reported: bool
+}
+
+// An opening explorer over a tree built ahead of time with OpeningTree::to_bytes
+#[wasm_bindgen]
+pub struct JsOpeningTree {
+    tree: book::OpeningTree
+}
+
+#[wasm_bindgen]
+pub struct JsBookMove {
+    san: String,
+    valid_move: ValidMove,
+    weight: u32
+}
+
+// Imports a large PGN a few games at a time. The page calls parseChunk between frames until it returns true,
+// so that it stays responsive while the games are read.
+#[wasm_bindgen]
+pub struct JsPgnImporter {
+    pgn: String,
+    offset: usize,
+
+    games: Vec<JsGame>,
+    errors: Vec<String>,
+    games_parsed: usize
+}
+
+#[wasm_bindgen]
+pub struct JsPuzzle {
+    session: training::PuzzleSession
+}
+
+#[wasm_bindgen]
+pub struct JsPuzzleAnswer {
+    answer: training::PuzzleAnswer
+}
+
+// Has no way to get at the board, FEN or moves until reveal(), so a blindfold page can't show them by mistake
+#[wasm_bindgen]
+pub struct JsBlindfoldGame {
+    game: Option<training::BlindfoldGame>
+}
+
+#[wasm_bindgen]
+pub struct JsBlindfoldReply {
+    reply: training::BlindfoldReply
+}
+
+// The options of renderSVG, as JSON, e.g. {"size": 300, "flipped": true, "highlights": ["e4"],
+// "arrows": [{"from": "e2", "to": "e4", "color": "green"}], "circles": [{"square": "d5", "color": "red"}]}.
+// The colors are green, red, yellow or blue. Everything can be left out.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct JsSvgOptions {
+    size: Option<f64>,
+    flipped: bool,
+    coordinates: Option<bool>,
+    highlights: Vec<String>,
+    arrows: Vec<JsArrow>,
+    circles: Vec<JsMark>,
+    squares: Vec<JsMark>
+}
+
+#[derive(Deserialize)]
+struct JsArrow {
+    from: String,
+    to: String,
+    color: Option<String>
+}
+
+#[derive(Deserialize)]
+struct JsMark {
+    square: String,
+    color: Option<String>
+}
+
 #[derive(Serialize)]
 pub struct JsError {
     pub message: String
@@ -65,7 +154,506 @@ impl JsGame {
             .collect()
     }
 
+    // Squares in algebraic notation, e.g. "e4"
+    pub fn destinationsFrom(&self, square: &str) -> Result<Array, JsValue> {
+        let square = Self::parse_square(square)?;
+
+        Ok(self.game.destinations_from(square).into_iter()
+            .map( |to| JsValue::from_str(&to.to_notation(SquareNotationOptions::FileAndRank)) )
+            .collect())
+    }
+
+    pub fn isPlausiblePremove(&self, from: &str, to: &str) -> Result<bool, JsValue> {
+        Ok(self.game.is_plausible_premove(Self::parse_square(from)?, Self::parse_square(to)?))
+    }
+
+    // The promotion is a piece letter, e.g. "q". Fails with the reason the move is illegal, e.g. "NotYourTurn".
+    pub fn tryMove(&self, from: &str, to: &str, promotion: Option<String>) -> Result<JsValidMove, JsValue> {
+        let promotion = match promotion {
+            Some(letter) => Some(
+                locale::PieceLetters::English.parse(&letter.to_uppercase())
+                    .ok_or_else( || Self::js_error(format!("Invalid promotion piece '{}'", letter)) )?
+            ),
+            None => None
+        };
+
+        self.game.try_move(Self::parse_square(from)?, Self::parse_square(to)?, promotion)
+            .map( |valid_move| JsValidMove { valid_move } )
+            .map_err( |reason| Self::js_error(format!("{:?}", reason)) )
+    }
+
+    // Options: { depth }, { movetimeMs } or { elo } for a weaker bot which makes human-like mistakes. The bot
+    // limits its own search, so elo can't be combined with the others. Searches to depth 4 without any.
+    // Undefined when there are no legal moves.
+    pub fn bestMove(&self, options: &JsValue) -> Result<Option<JsValidMove>, JsValue> {
+        let option = |name: &str| -> Result<Option<f64>, JsValue> {
+            if options.is_undefined() || options.is_null() {
+                return Ok(None);
+            }
+
+            Ok(js_sys::Reflect::get(options, &JsValue::from_str(name))?.as_f64())
+        };
+
+        if let Some(elo) = option("elo")? {
+            if option("depth")?.is_some() || option("movetimeMs")?.is_some() {
+                return Err(Self::js_error(String::from("The elo option can't be combined with depth or movetimeMs")));
+            }
+
+            let mut bot = bot::Bot::new(elo.max(0.0) as u32).with_seed(js_sys::Date::now() as u64);
+
+            return Ok(bot.choose_move(&self.game).map( |bot_move| JsValidMove { valid_move: bot_move.valid_move } ));
+        }
+
+        let limits = match (option("depth")?, option("movetimeMs")?) {
+            (Some(depth), _)     => search::SearchLimits::depth(depth.max(1.0) as u32),
+            (None, Some(millis)) => search::SearchLimits::movetime(milliseconds(millis)),
+            (None, None)         => search::SearchLimits::depth(4)
+        };
+
+        Ok(search::search(&self.game, &limits).best_move.map( |valid_move| JsValidMove { valid_move } ))
+    }
+
+    // The static evaluation in centipawns, positive when white is better
+    pub fn evaluate(&self) -> i32 {
+        eval::evaluate(self.game.position())
+    }
+
+    pub fn makeMove(&self, validMove: &JsValidMove) -> JsGame {
+        JsGame { game: self.game.make_valid_move(&validMove.valid_move) }
+    }
+
+    fn parse_square(square: &str) -> Result<Square, JsValue> {
+        Square::from_notation(square).map_err( |_| Self::js_error(format!("Invalid square '{}'", square)) )
+    }
+
     fn js_error(message: String) -> JsValue {
         JsValue::from_serde(&JsError { message }).expect("Cannot serialize JS error to JSValue")
     }
 }
+
+#[wasm_bindgen]
+impl JsValidMove {
+    pub fn from(&self) -> String {
+        self.valid_move.from.to_notation(SquareNotationOptions::FileAndRank)
+    }
+
+    pub fn to(&self) -> String {
+        self.valid_move.to.to_notation(SquareNotationOptions::FileAndRank)
+    }
+
+    // e.g. "e7e8q"
+    pub fn uci(&self) -> String {
+        self.valid_move.uci()
+    }
+}
+
+#[wasm_bindgen]
+impl JsClock {
+    #[wasm_bindgen(constructor)]
+    pub fn new(baseMs: f64, incrementMs: f64) -> JsClock {
+        JsClock {
+            clock: clock::Clock::new(milliseconds(baseMs), milliseconds(incrementMs)),
+
+            on_flag: None,
+            flag_reported: false
+        }
+    }
+
+    // Called with "white" or "black" once, when that side runs out of time
+    pub fn onFlag(&mut self, callback: Function) {
+        self.on_flag = Some(callback);
+    }
+
+    pub fn start(&mut self, whiteToMove: bool, now: f64) {
+        self.clock.start(color_of(whiteToMove), milliseconds(now));
+    }
+
+    pub fn pause(&mut self, now: f64) {
+        self.clock.pause(milliseconds(now));
+    }
+
+    // Returns false if the side to move had already run out of time
+    pub fn press(&mut self, now: f64) -> Result<bool, JsValue> {
+        let flagged = self.clock.press(milliseconds(now));
+
+        self.report_flag()?;
+
+        Ok(flagged.is_none())
+    }
+
+    pub fn tick(&mut self, now: f64) -> Result<(), JsValue> {
+        self.clock.check_flag(milliseconds(now));
+
+        self.report_flag()
+    }
+
+    pub fn remainingMs(&self, white: bool, now: f64) -> f64 {
+        self.clock.remaining(color_of(white), milliseconds(now)).as_secs_f64() * 1000.0
+    }
+
+    // "white", "black" or undefined
+    pub fn running(&self) -> Option<String> {
+        self.clock.running().map( color_name )
+    }
+
+    pub fn flagged(&self) -> Option<String> {
+        self.clock.flagged().map( color_name )
+    }
+
+    fn report_flag(&mut self) -> Result<(), JsValue> {
+        if let (Some(color), Some(callback), false) = (self.clock.flagged(), &self.on_flag, self.flag_reported) {
+            self.flag_reported = true;
+
+            callback.call1(&JsValue::NULL, &JsValue::from_str(&color_name(color)))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn milliseconds(milliseconds: f64) -> std::time::Duration {
+    // from_secs_f64 panics on infinite values
+    let milliseconds = if milliseconds.is_finite() { milliseconds.max(0.0) } else { 0.0 };
+
+    std::time::Duration::from_secs_f64(milliseconds / 1000.0)
+}
+
+fn color_of(white: bool) -> Color {
+    if white { Color::White } else { Color::Black }
+}
+
+fn color_name(color: Color) -> String {
+    String::from(match color {
+        Color::White => "white",
+        Color::Black => "black"
+    })
+}
+
+#[wasm_bindgen]
+impl JsOpeningTree {
+    // The bytes as fetched, e.g. new Uint8Array(await response.arrayBuffer())
+    pub fn fromBytes(bytes: &[u8]) -> Result<JsOpeningTree, JsValue> {
+        book::OpeningTree::from_bytes(bytes)
+            .map( |tree| JsOpeningTree { tree } )
+            .map_err( JsGame::js_error )
+    }
+
+    // The moves played from the position, the most played first
+    pub fn moves(&self, game: &JsGame) -> Array {
+        use book::OpeningBook;
+
+        let mut moves = self.tree.moves(&game.game);
+        moves.sort_by_key( |book_move| std::cmp::Reverse(book_move.weight) );
+
+        moves.into_iter()
+            .map( |book_move| JsBookMove {
+                san: game.game.san(&book_move.valid_move),
+                valid_move: book_move.valid_move,
+                weight: book_move.weight
+            })
+            .map( JsValue::from )
+            .collect()
+    }
+
+    // How many games of the tree continue from the position
+    pub fn games(&self, game: &JsGame) -> u32 {
+        use book::OpeningBook;
+
+        self.tree.moves(&game.game).iter().map( |book_move| book_move.weight ).sum()
+    }
+
+    pub fn positions(&self) -> usize {
+        self.tree.len()
+    }
+}
+
+#[wasm_bindgen]
+impl JsBookMove {
+    pub fn san(&self) -> String {
+        self.san.clone()
+    }
+
+    pub fn uci(&self) -> String {
+        self.valid_move.uci()
+    }
+
+    // Number of games with the move
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    pub fn validMove(&self) -> JsValidMove {
+        JsValidMove { valid_move: self.valid_move.clone() }
+    }
+}
+
+#[wasm_bindgen]
+impl JsPuzzle {
+    // The solution is a list of moves separated by spaces, in SAN or UCI notation, e.g. "e1e8 d8e8 a1e1"
+    #[wasm_bindgen(constructor)]
+    pub fn new(fen: &str, solution: &str) -> Result<JsPuzzle, JsValue> {
+        let moves: Vec<&str> = solution.split_whitespace().collect();
+        let puzzle = training::Puzzle::new(fen, &moves).map_err( JsGame::js_error )?;
+
+        Ok(JsPuzzle { session: puzzle.start() })
+    }
+
+    // The opponent's reply to a correct move is already made, the page only has to animate it
+    pub fn submitMove(&mut self, notation: &str) -> Result<JsPuzzleAnswer, JsValue> {
+        self.session.submit(notation)
+            .map( |answer| JsPuzzleAnswer { answer } )
+            .map_err( |error| JsGame::js_error(format!("{:?}", error)) )
+    }
+
+    pub fn game(&self) -> JsGame {
+        JsGame { game: self.session.game().clone() }
+    }
+
+    pub fn fen(&self) -> String {
+        self.session.game().position_to_fen()
+    }
+
+    pub fn isSolved(&self) -> bool {
+        self.session.is_solved()
+    }
+
+    pub fn mistakes(&self) -> usize {
+        self.session.mistakes()
+    }
+}
+
+#[wasm_bindgen]
+impl JsPuzzleAnswer {
+    // "correct", "incorrect" or "alternate-accepted"
+    pub fn verdict(&self) -> String {
+        String::from(match self.answer.verdict {
+            training::PuzzleVerdict::Correct           => "correct",
+            training::PuzzleVerdict::Incorrect         => "incorrect",
+            training::PuzzleVerdict::AlternateAccepted => "alternate-accepted"
+        })
+    }
+
+    pub fn reply(&self) -> Option<JsValidMove> {
+        self.answer.reply.clone().map( |valid_move| JsValidMove { valid_move } )
+    }
+
+    pub fn solved(&self) -> bool {
+        self.answer.solved
+    }
+}
+
+#[wasm_bindgen]
+impl JsBlindfoldGame {
+    // The standard starting position without a FEN
+    #[wasm_bindgen(constructor)]
+    pub fn new(fen: Option<String>) -> Result<JsBlindfoldGame, JsValue> {
+        let game = match fen {
+            Some(fen) => Game::new_from_fen(&fen).map_err( |error| JsGame::js_error(error.message) )?,
+            None => Game::new(Game::standard_position())
+        };
+
+        Ok(JsBlindfoldGame { game: Some(training::BlindfoldGame::new(game)) })
+    }
+
+    pub fn submitMove(&mut self, notation: &str) -> Result<JsBlindfoldReply, JsValue> {
+        Ok(JsBlindfoldReply { reply: self.blindfold_game()?.submit(notation) })
+    }
+
+    // "white" or "black"
+    pub fn nextToMove(&mut self) -> Result<String, JsValue> {
+        Ok(color_name(self.blindfold_game()?.next_to_move()))
+    }
+
+    pub fn isOver(&mut self) -> Result<bool, JsValue> {
+        Ok(self.blindfold_game()?.is_over())
+    }
+
+    pub fn illegalAttempts(&mut self) -> Result<usize, JsValue> {
+        Ok(self.blindfold_game()?.illegal_attempts())
+    }
+
+    // Ends the blindfold mode, only the returned game can be used afterwards
+    pub fn reveal(&mut self) -> Result<JsGame, JsValue> {
+        let game = self.game.take().ok_or_else( || JsGame::js_error(String::from("The game was already revealed")) )?;
+
+        Ok(JsGame { game: game.reveal() })
+    }
+
+    fn blindfold_game(&mut self) -> Result<&mut training::BlindfoldGame, JsValue> {
+        self.game.as_mut().ok_or_else( || JsGame::js_error(String::from("The game was already revealed")) )
+    }
+}
+
+#[wasm_bindgen]
+impl JsBlindfoldReply {
+    pub fn legal(&self) -> bool {
+        self.reply.legal
+    }
+
+    // e.g. ["check"], ["checkmate"] or ["draw:stalemate"]. Repetitions are reported as "threefold-repetition".
+    pub fn events(&self) -> Array {
+        self.reply.events.iter()
+            .map( |event| JsValue::from_str(&match event {
+                training::BlindfoldEvent::Check               => String::from("check"),
+                training::BlindfoldEvent::Checkmate           => String::from("checkmate"),
+                training::BlindfoldEvent::ThreefoldRepetition => String::from("threefold-repetition"),
+                training::BlindfoldEvent::Draw(reason)        => format!("draw:{}", draw_reason_name(*reason))
+            }))
+            .collect()
+    }
+}
+
+fn draw_reason_name(reason: DrawReason) -> &'static str {
+    match reason {
+        DrawReason::Stalemate            => "stalemate",
+        DrawReason::FiftyMoveRule        => "fifty-move-rule",
+        DrawReason::InsufficientMaterial => "insufficient-material",
+        DrawReason::DeadPosition         => "dead-position"
+    }
+}
+
+#[wasm_bindgen]
+impl JsPgnImporter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(pgn: String) -> JsPgnImporter {
+        JsPgnImporter {
+            pgn,
+            offset: 0,
+
+            games: Vec::new(),
+            errors: Vec::new(),
+            games_parsed: 0
+        }
+    }
+
+    // Reads about maxGames games and calls onProgress(gamesParsed, bytesConsumed, totalBytes).
+    // Returns whether the whole PGN has been read.
+    pub fn parseChunk(&mut self, maxGames: usize, onProgress: &Function) -> Result<bool, JsValue> {
+        let mut chunks = pgn_file::PgnChunks::new(&self.pgn)
+            .with_limits(ParseLimits::untrusted())
+            .starting_at(self.offset);
+
+        for result in chunks.next_chunk(maxGames.max(1)) {
+            match result {
+                Ok((_, game)) => self.games.push(JsGame { game }),
+                Err(error) => self.errors.push(error)
+            }
+
+            self.games_parsed += 1;
+        }
+
+        self.offset = chunks.bytes_consumed();
+
+        onProgress.call3(
+            &JsValue::NULL,
+            &JsValue::from_f64(self.games_parsed as f64),
+            &JsValue::from_f64(self.offset as f64),
+            &JsValue::from_f64(self.pgn.len() as f64)
+        )?;
+
+        Ok(chunks.is_done())
+    }
+
+    // The games read since the last call
+    pub fn takeGames(&mut self) -> Array {
+        self.games.drain(..).map( JsValue::from ).collect()
+    }
+
+    // Messages for the games which couldn't be read
+    pub fn errors(&self) -> Array {
+        self.errors.iter().map( |error| JsValue::from_str(error) ).collect()
+    }
+}
+
+#[wasm_bindgen]
+pub fn renderSVG(fen: &str, options: Option<String>) -> Result<String, JsValue> {
+    let game = Game::new_from_fen(fen).map_err( |error| JsGame::js_error(format!("{:?}", error)) )?;
+
+    let options: JsSvgOptions = match options {
+        Some(options) => serde_json::from_str(&options).map_err( |error| JsGame::js_error(error.to_string()) )?,
+        None => JsSvgOptions::default()
+    };
+
+    let view = render::BoardView {
+        orientation: if options.flipped { Color::Black } else { Color::White },
+        coordinates: options.coordinates.unwrap_or(true),
+
+        highlights: options.highlights.iter()
+            .map( |square| JsGame::parse_square(square) )
+            .collect::<Result<_, _>>()?
+    };
+
+    let svg_options = render::SvgOptions {
+        size: options.size.unwrap_or(render::SvgOptions::default().size),
+
+        overlay: render::Overlay {
+            arrows: options.arrows.iter()
+                .map( |arrow| Ok(render::Arrow {
+                    from: JsGame::parse_square(&arrow.from)?,
+                    to: JsGame::parse_square(&arrow.to)?,
+                    color: mark_color(&arrow.color)?
+                }))
+                .collect::<Result<_, JsValue>>()?,
+
+            circles: marks(&options.circles)?,
+            squares: marks(&options.squares)?
+        }
+    };
+
+    Ok(render::render_svg(game.board(), &view, &svg_options))
+}
+
+fn marks(marks: &[JsMark]) -> Result<Vec<render::Mark>, JsValue> {
+    marks.iter()
+        .map( |mark| Ok(render::Mark {
+            square: JsGame::parse_square(&mark.square)?,
+            color: mark_color(&mark.color)?
+        }))
+        .collect()
+}
+
+fn mark_color(color: &Option<String>) -> Result<render::MarkColor, JsValue> {
+    match color.as_deref() {
+        None | Some("green") | Some("G") => Ok(render::MarkColor::Green),
+        Some("red") | Some("R")          => Ok(render::MarkColor::Red),
+        Some("yellow") | Some("Y")       => Ok(render::MarkColor::Yellow),
+        Some("blue") | Some("B")         => Ok(render::MarkColor::Blue),
+        Some(color)                      => Err(JsGame::js_error(format!("Unknown color '{}'", color)))
+    }
+}
+
+#[wasm_bindgen]
+impl JsBoardLayout {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f64, y: f64, size: f64, margin: f64, whiteAtBottom: bool) -> JsBoardLayout {
+        let orientation = if whiteAtBottom { Color::White } else { Color::Black };
+
+        JsBoardLayout {
+            layout: screen::BoardLayout { x, y, size, margin, orientation }
+        }
+    }
+
+    pub fn squareSize(&self) -> f64 {
+        self.layout.square_size()
+    }
+
+    // Square in algebraic notation, e.g. "e4"
+    pub fn squareAt(&self, x: f64, y: f64) -> Option<String> {
+        self.layout.square_at(x, y).map( |square| square.to_notation(SquareNotationOptions::FileAndRank) )
+    }
+
+    // [x, y] of the top left corner of the square
+    pub fn squareOrigin(&self, square: &str) -> Result<Array, JsValue> {
+        let square = JsGame::parse_square(square)?;
+        let (x, y) = self.layout.square_origin(square);
+
+        Ok(vec![JsValue::from_f64(x), JsValue::from_f64(y)].into_iter().collect())
+    }
+
+    pub fn squareCenter(&self, square: &str) -> Result<Array, JsValue> {
+        let square = JsGame::parse_square(square)?;
+        let (x, y) = self.layout.square_center(square);
+
+        Ok(vec![JsValue::from_f64(x), JsValue::from_f64(y)].into_iter().collect())
+    }
+}
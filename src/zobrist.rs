@@ -0,0 +1,215 @@
+use super::models::*;
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+
+use super::rng::{Rng, SplitMix64};
+
+struct ZobristKeys {
+    pieces: Vec<u64>,
+    castling: [u64; 4],
+    en_passant_files: [u64; 8],
+    black_to_move: u64
+}
+
+lazy_static! {
+    static ref KEYS: ZobristKeys = {
+        // Fixed seed so that hashes are reproducible between runs
+        let mut rng = SplitMix64::new(0x9E37_79B9_7F4A_7C15);
+        ZobristKeys::generate(|| rng.next_u64())
+    };
+
+    // Never change this seed, the generator below or the order in which the keys are drawn, stable keys are
+    // meant to be stored
+    static ref STABLE_KEYS: ZobristKeys = {
+        let mut state = 0x5354_4142_4C45_4B31;
+        ZobristKeys::generate(|| frozen_splitmix64(&mut state))
+    };
+}
+
+// A copy of splitmix64 which is only used for the stable keys, so that changes to `rng` can't change them
+fn frozen_splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl ZobristKeys {
+    // Keys are drawn in order: 768 piece keys, 4 castling keys, 8 en passant files and the side to move
+    fn generate(mut next: impl FnMut() -> u64) -> Self {
+        let pieces = (0..2 * 6 * 64).map( |_| next() ).collect();
+        let castling = [next(), next(), next(), next()];
+
+        let mut en_passant_files = [0; 8];
+        for key in en_passant_files.iter_mut() {
+            *key = next();
+        }
+
+        let black_to_move = next();
+
+        ZobristKeys { pieces, castling, en_passant_files, black_to_move }
+    }
+}
+
+pub fn piece_key(occupancy: &OccupiedSquare, square: Square) -> u64 {
+    let color_index = match occupancy.color {
+        Color::White => 0,
+        Color::Black => 1
+    };
+
+    let square_index = Board::index(square);
+
+    KEYS.pieces[(color_index * 6 + occupancy.piece as usize) * 64 + square_index]
+}
+
+pub fn en_passant_key(en_passant_square: Option<Square>) -> u64 {
+    match en_passant_square {
+        Some(square) => KEYS.en_passant_files[square.file as usize],
+        None => 0
+    }
+}
+
+pub fn castling_key(position: &Position) -> u64 {
+    let rights = [
+        position.white_can_castle_king_side,
+        position.white_can_castle_queen_side,
+        position.black_can_castle_king_side,
+        position.black_can_castle_queen_side
+    ];
+
+    rights.iter().zip(KEYS.castling.iter())
+        .filter( |(can_castle, _)| **can_castle )
+        .fold(0, |hash, (_, key)| hash ^ key)
+}
+
+pub fn side_to_move_key() -> u64 {
+    KEYS.black_to_move
+}
+
+// Piece keys are indexed by Piece::index and color, then by square from a1 = 0 to h8 = 63. Unlike the internal
+// keys this doesn't depend on the order of the Piece enum.
+fn stable_piece_key(occupancy: &OccupiedSquare, square: Square) -> u64 {
+    let color_index = match occupancy.color {
+        Color::White => 0,
+        Color::Black => 1
+    };
+
+    STABLE_KEYS.pieces[(occupancy.piece.index() * 2 + color_index) * 64 + square.to_a1_index()]
+}
+
+impl Position {
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for (i, occupancy) in self.board.squares.iter().enumerate() {
+            if let Some(occupancy) = occupancy {
+                hash ^= piece_key(occupancy, Board::square(i));
+            }
+        }
+
+        if self.next_to_move == Color::Black {
+            hash ^= side_to_move_key();
+        }
+
+        hash ^ castling_key(self) ^ en_passant_key(self.en_passant_square)
+    }
+
+    // A hash which is guaranteed to stay the same between versions of the crate, for keying stored data.
+    // The en passant square only counts if a pawn can actually take en passant, so FENs which always
+    // list it after a double push get the same key as ones which only list it when the capture is possible.
+    pub fn stable_key(&self) -> u64 {
+        let mut key = 0;
+
+        for (i, occupancy) in self.board.squares.iter().enumerate() {
+            if let Some(occupancy) = occupancy {
+                key ^= stable_piece_key(occupancy, Board::square(i));
+            }
+        }
+
+        let rights = [
+            self.white_can_castle_king_side,
+            self.white_can_castle_queen_side,
+            self.black_can_castle_king_side,
+            self.black_can_castle_queen_side
+        ];
+
+        for (can_castle, castling_key) in rights.iter().zip(STABLE_KEYS.castling.iter()) {
+            if *can_castle {
+                key ^= castling_key;
+            }
+        }
+
+        if let Some(square) = self.en_passant_square.filter( |square| self.can_take_en_passant(*square) ) {
+            key ^= STABLE_KEYS.en_passant_files[square.file as usize];
+        }
+
+        if self.next_to_move == Color::Black {
+            key ^= STABLE_KEYS.black_to_move;
+        }
+
+        key
+    }
+
+    pub(crate) fn can_take_en_passant(&self, square: Square) -> bool {
+        let pawn_rank = match self.next_to_move {
+            Color::White => square.rank - 1,
+            Color::Black => square.rank + 1
+        };
+
+        let pawn = Some(OccupiedSquare { piece: Piece::Pawn, color: self.next_to_move });
+
+        [square.file - 1, square.file + 1].iter()
+            .filter_map( |file| Square::new(pawn_rank, *file) )
+            .any( |from| self.board.squares[Board::index(from)] == pawn )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PositionSet {
+    counts: HashMap<u64, usize>
+}
+
+impl PositionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns true if the position was not seen before
+    pub fn insert(&mut self, position: &Position) -> bool {
+        self.insert_hash(position.zobrist_hash())
+    }
+
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let count = self.counts.entry(hash).or_insert(0);
+        *count += 1;
+
+        *count == 1
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        self.contains_hash(position.zobrist_hash())
+    }
+
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.counts.contains_key(&hash)
+    }
+
+    pub fn count(&self, position: &Position) -> usize {
+        self.count_hash(position.zobrist_hash())
+    }
+
+    pub fn count_hash(&self, hash: u64) -> usize {
+        self.counts.get(&hash).copied().unwrap_or(0)
+    }
+
+    // Number of distinct positions
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}